@@ -2,7 +2,33 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, ImplItem, ItemImpl};
+use syn::{parse_macro_input, Attribute, ImplItem, ItemImpl};
+
+/// Collect the doc comments of an item (if any)
+fn get_doc(attrs: &[Attribute]) -> proc_macro2::TokenStream {
+    let mut lines = Vec::new();
+
+    for attr in attrs {
+        if let syn::Meta::NameValue(meta) = &attr.meta {
+            if meta.path.is_ident("doc") {
+                if let syn::Expr::Lit(syn::ExprLit {
+                    lit: syn::Lit::Str(s),
+                    ..
+                }) = &meta.value
+                {
+                    lines.push(s.value().trim().to_string());
+                }
+            }
+        }
+    }
+
+    if lines.is_empty() {
+        quote! { None }
+    } else {
+        let doc = lines.join("\n");
+        quote! { Some(#doc.to_string()) }
+    }
+}
 
 #[proc_macro_attribute]
 pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -34,12 +60,16 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut method_return_conversions = Vec::new();
     let mut method_args = Vec::new();
     let mut method_attrs = Vec::new();
+    let mut method_cfgs = Vec::new();
+    let mut method_signatures = Vec::new();
+    let mut method_docs = Vec::new();
     let mut method_structs = Vec::new();
     let mut method_blocks = Vec::new();
 
     let mut constant_names = Vec::new();
     let mut constant_literals = Vec::new();
     let mut constant_expressions = Vec::new();
+    let mut constant_docs = Vec::new();
 
     for item in ast.items.iter() {
         match item {
@@ -63,6 +93,14 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 method_names.push(ident.to_string());
                 internal_method_names.push(format_ident!("_internal_{}", ident));
 
+                method_cfgs.push(
+                    attrs_out
+                        .iter()
+                        .filter(|attr| attr.path().is_ident("cfg"))
+                        .cloned()
+                        .collect::<Vec<_>>(),
+                );
+                method_docs.push(get_doc(&attrs_out));
                 method_attrs.push(attrs_out);
                 method_blocks.push(meth.block.clone());
                 method_outputs.push(meth.sig.output.clone());
@@ -94,6 +132,9 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
                     }
                 }
 
+                let arg_names: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                method_signatures.push(format!("{}({})", ident, arg_names.join(", ")));
+
                 method_args.push(args);
 
                 method_structs.push(format_ident!("MethodCall_{}_{}", name, ident));
@@ -101,6 +142,7 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
             ImplItem::Const(constant) => {
                 if let syn::Expr::Lit(lit) = &constant.expr {
                     constant_names.push(constant.ident.to_string());
+                    constant_docs.push(get_doc(&constant.attrs));
                     constant_literals.push(lit.lit.clone());
                 } else {
                     panic!("Unsupported expression: {:?}", constant.expr);
@@ -158,7 +200,8 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
         #( #method_attrs )*
         #[allow(non_camel_case_types)]
         struct #method_struct_defs {
-            self_ref: std::rc::Rc<dyn cowlang::Module>
+            // Weak to avoid reference cycles when modules store their own functions
+            self_ref: std::rc::Weak<dyn cowlang::Module>
         }
 
         #( #method_attrs )*
@@ -166,7 +209,7 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
             fn call(&self, mut _internal_args: Vec<cowlang::Value>) -> cowlang::interpreter::Handle {
                 //FIXME find a way to do this without raw pointers
 
-                let self_rc = self.self_ref.clone();
+                let self_rc = self.self_ref.upgrade().expect("Module was already dropped");
 
                 let self_ptr = std::rc::Rc::into_raw(self_rc);
                 let self_ref = unsafe{ &*(self_ptr as *const #name_iter) };
@@ -207,10 +250,10 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 #(
                 if member_name == #method_names {
 
-                    #( #method_attrs )*
+                    #( #method_cfgs )*
                     {
-                        return cowlang::interpreter::Handle::Callable( Box::new(
-                                #method_struct_defs2{ self_ref: self_ref.clone() }
+                        return cowlang::interpreter::Handle::Callable( std::rc::Rc::new(
+                                #method_struct_defs2{ self_ref: std::rc::Rc::downgrade(self_ref) }
                         ));
                     }
                 }
@@ -223,6 +266,46 @@ pub fn cow_module(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
                 panic!("No such member {}::{}", #name_string, member_name);
             }
+
+            fn has_member(&self, member_name: &str) -> bool {
+                #(
+                #( #method_cfgs )*
+                {
+                    if member_name == #method_names {
+                        return true;
+                    }
+                }
+                )*
+
+                #( #constant_names == member_name || )* false
+            }
+
+            fn members(&self) -> Vec<cowlang::interpreter::MemberInfo> {
+                #[allow(unused_mut)]
+                let mut members = Vec::new();
+
+                #(
+                #( #method_cfgs )*
+                {
+                    members.push(cowlang::interpreter::MemberInfo {
+                        name: #method_names.to_string(),
+                        kind: cowlang::interpreter::MemberKind::Function,
+                        signature: Some(#method_signatures.to_string()),
+                        doc: #method_docs,
+                    });
+                }
+                )*
+                #(
+                members.push(cowlang::interpreter::MemberInfo {
+                    name: #constant_names.to_string(),
+                    kind: cowlang::interpreter::MemberKind::Constant,
+                    signature: None,
+                    doc: #constant_docs,
+                });
+                )*
+
+                members
+            }
         }
     };
 
@@ -1,6 +1,7 @@
 use std::rc::Rc;
 
-use cowlang::{compile_string, Interpreter, Value};
+use cowlang::interpreter::MemberKind;
+use cowlang::{compile_string, Interpreter, Module, Value};
 use cowlang_derive::cow_module;
 
 use std::convert::TryInto;
@@ -53,6 +54,7 @@ impl TestModule {
         }
     }
 
+    /// Adds two to the given number
     fn add_two(&self, num: Value) -> i64 {
         let num: i64 = num.try_into().unwrap();
 
@@ -195,3 +197,20 @@ fn call_without_return() {
     let result = interpreter.run(&program);
     assert_eq!(result, Value::None);
 }
+
+#[test]
+fn list_members() {
+    let members = TestModule::new().members();
+
+    let add_two = members.iter().find(|m| m.name == "add_two").unwrap();
+    assert_eq!(add_two.kind, MemberKind::Function);
+    assert_eq!(add_two.signature.as_deref(), Some("add_two(num)"));
+    assert_eq!(add_two.doc.as_deref(), Some("Adds two to the given number"));
+
+    let constant = members.iter().find(|m| m.name == "MY_CONSTANT").unwrap();
+    assert_eq!(constant.kind, MemberKind::Constant);
+
+    // Only one version of get_cfg_val is compiled
+    let count = members.iter().filter(|m| m.name == "get_cfg_val").count();
+    assert_eq!(count, 1);
+}
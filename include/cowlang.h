@@ -0,0 +1,99 @@
+/*
+ * C interface of cowlang (see src/capi.rs)
+ *
+ * Build the library with
+ *   cargo rustc --release --features capi --crate-type staticlib
+ *
+ * Every pointer returned by these functions is owned by the caller and must be
+ * released with the matching cow_*_free function. Functions that can fail
+ * return NULL (or false) and, if `error` is not NULL, store an error in it.
+ * Strings are NUL-terminated UTF-8.
+ */
+
+#ifndef COWLANG_H
+#define COWLANG_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct CowProgram CowProgram;
+typedef struct CowInterpreter CowInterpreter;
+typedef struct CowValue CowValue;
+typedef struct CowError CowError;
+
+typedef enum CowValueType {
+    COW_VALUE_NONE = 0,
+    COW_VALUE_BOOL = 1,
+    COW_VALUE_STRING = 2,
+    COW_VALUE_I64 = 3,
+    COW_VALUE_U64 = 4,
+    COW_VALUE_U8 = 5,
+    COW_VALUE_F32 = 6,
+    COW_VALUE_F64 = 7,
+    COW_VALUE_MAP = 8,
+    COW_VALUE_LIST = 9,
+    COW_VALUE_BYTES = 10,
+    COW_VALUE_TUPLE = 11,
+    COW_VALUE_ARRAY = 12,
+    /* Only used if the library was built with the `decimal` feature */
+    COW_VALUE_DECIMAL = 13,
+} CowValueType;
+
+/* Programs */
+CowProgram *cow_compile(const char *source, CowError **error);
+void cow_program_free(CowProgram *program);
+
+/* Interpreters */
+CowInterpreter *cow_interpreter_new(void);
+void cow_interpreter_free(CowInterpreter *interpreter);
+/* Takes ownership of the value (also if the call fails) */
+bool cow_interpreter_set_value(CowInterpreter *interpreter, const char *name, CowValue *value,
+                               CowError **error);
+/* Does not change the interpreter's variables */
+CowValue *cow_interpreter_run(CowInterpreter *interpreter, const CowProgram *program,
+                              CowError **error);
+
+/* Errors */
+/* Valid until the error is freed */
+const char *cow_error_message(const CowError *error);
+void cow_error_free(CowError *error);
+
+/* Strings returned by cow_value_as_string and cow_value_to_string */
+void cow_string_free(char *s);
+
+/* Creating values */
+CowValue *cow_value_none(void);
+CowValue *cow_value_bool(bool b);
+CowValue *cow_value_i64(int64_t i);
+CowValue *cow_value_u64(uint64_t u);
+CowValue *cow_value_f64(double f);
+CowValue *cow_value_string(const char *s);
+CowValue *cow_value_list(void);
+CowValue *cow_value_map(void);
+/* Take ownership of the element or value (also if the call fails) */
+bool cow_value_list_append(CowValue *list, CowValue *element, CowError **error);
+bool cow_value_map_insert(CowValue *map, const char *key, CowValue *value, CowError **error);
+void cow_value_free(CowValue *value);
+
+/* Inspecting values */
+CowValueType cow_value_type(const CowValue *value);
+bool cow_value_as_bool(const CowValue *value, bool *result);
+bool cow_value_as_i64(const CowValue *value, int64_t *result);
+bool cow_value_as_f64(const CowValue *value, double *result);
+char *cow_value_as_string(const CowValue *value);
+char *cow_value_to_string(const CowValue *value);
+/* -1 if the value has no length */
+int64_t cow_value_length(const CowValue *value);
+/* Return copies, or NULL if there is no such element */
+CowValue *cow_value_get_index(const CowValue *value, uint64_t index);
+CowValue *cow_value_get_key(const CowValue *value, const char *key);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -1,7 +1,18 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+mod version;
+pub use version::{LegacyProgram, VersionedProgram, PROGRAM_VERSION};
+
+mod restrict;
+pub use restrict::{Policy, Violation};
+
+mod specialize;
+
+/// A location in the source code
+///
+/// Both positions are byte offsets into the source (not character offsets).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Span {
     pub hi: usize,
     pub lo: usize,
@@ -25,12 +36,79 @@ pub enum ValueType {
     Bytes,
 }
 
+/// A type written by the programmer, e.g., `let data: [u8; 5] = ...` (see `typecheck`)
+///
+/// Annotations do not change how a program runs, except that unsuffixed integer
+/// literals take the annotated type (e.g., `let x: u8 = 5` stores a `u8`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub enum TypeAnnotation {
+    /// A primitive type (e.g., `i64` or `str`), `bytes`, `list`, `map`, or `any`
+    Named(String),
+    /// A list with elements of the given type (`[i64]`)
+    List(Box<TypeAnnotation>),
+    /// A list with a fixed length (`[u8; 5]`)
+    Array(Box<TypeAnnotation>, usize),
+    /// `{str: i64}`
+    Map(Box<TypeAnnotation>, Box<TypeAnnotation>),
+    /// `(i64, str)`
+    Tuple(Vec<TypeAnnotation>),
+}
+
 pub type ParseNode = (Span, Expr);
 pub type Statements = Vec<ParseNode>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Program {
+    /// The name of the script this program was compiled from (if any)
+    pub name: Option<String>,
     pub stmts: Statements,
+    /// Comments of the source code (only kept if `CompilerOptions::keep_comments` is set)
+    ///
+    /// Comments are meant for tools like formatters and are not stored with the program.
+    #[serde(skip)]
+    pub comments: Vec<Comment>,
+}
+
+/// A `#`-comment together with the statement it belongs to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    pub span: Span,
+    /// The text after the `#` (without surrounding whitespace)
+    pub text: String,
+    /// The statement the comment belongs to (if any)
+    ///
+    /// This is the statement on the same line as the comment or, if the comment
+    /// is on a line of its own, the statement following it.
+    pub target: Option<Span>,
+}
+
+impl Program {
+    /// All comments that belong to the statement at the given location
+    pub fn comments_of(&self, span: Span) -> impl Iterator<Item = &Comment> {
+        self.comments
+            .iter()
+            .filter(move |comment| comment.target == Some(span))
+    }
+}
+
+// Compiled programs are meant to be cached and shared between threads,
+// even though each interpreter stays on a single thread
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Program>();
+    assert_send_sync::<Span>();
+    assert_send_sync::<Comment>();
+};
+
+/// Arithmetic operators that can be combined with an assignment (e.g., `-=`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArithmeticOp {
+    Add,
+    Subtract,
+    Multiply,
+    FloorDivide,
+    Remainder,
+    Divide,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,9 +116,16 @@ pub enum CompareType {
     Equals,
     Greater,
     Smaller,
+    /// Membership test (`lhs in rhs`)
+    In,
 }
 
+/// A node of the syntax tree
+///
+/// Serialized programs refer to variants by their position, so new variants
+/// must be added at the end (or `PROGRAM_VERSION` must be incremented).
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum Expr {
     Var(String),
     I64(i64),
@@ -84,6 +169,7 @@ pub enum Expr {
         rhs: Box<ParseNode>,
     },
     Assign(String, Box<ParseNode>),
+    /// Replaced by `OpAssign` (only found in programs stored by earlier releases)
     AddEquals {
         lhs: String,
         rhs: Box<ParseNode>,
@@ -108,4 +194,230 @@ pub enum Expr {
         rhs: Box<ParseNode>,
     },
     Return(Box<ParseNode>),
+    /// Assign to several variables at once, e.g., `x, y = y, x`
+    ///
+    /// All values are evaluated before any variable changes. A single value
+    /// must be a list or a tuple, which is then unpacked into the variables.
+    AssignMultiple {
+        targets: Vec<String>,
+        values: Vec<ParseNode>,
+    },
+    /// Run the body and then call `close()` on the resource, even if the body fails
+    With {
+        resource: Box<ParseNode>,
+        target_name: String,
+        body: Statements,
+    },
+    /// Logical and; `rhs` is only evaluated if `lhs` is true
+    And {
+        lhs: Box<ParseNode>,
+        rhs: Box<ParseNode>,
+    },
+    /// Logical or; `rhs` is only evaluated if `lhs` is false
+    Or {
+        lhs: Box<ParseNode>,
+        rhs: Box<ParseNode>,
+    },
+    /// Assign to an element of a list or an entry of a map (`l[0] = 1`)
+    SetElement {
+        container: Box<ParseNode>,
+        key: Box<ParseNode>,
+        value: Box<ParseNode>,
+    },
+    /// Compound assignment (e.g., `x -= 1`)
+    ///
+    /// The left-hand side is a variable, an element (`l[0]`), or a module member (`counter.value`).
+    OpAssign {
+        op: ArithmeticOp,
+        lhs: Box<ParseNode>,
+        rhs: Box<ParseNode>,
+    },
+    /// An access removed by `Program::restrict` (fails when evaluated)
+    Disallowed(String),
+    /// Run the body and, if it fails, run the handler instead of failing the program
+    ///
+    /// The handler can access the error (see `RuntimeError::to_value`) under `error_name`.
+    Try {
+        body: Statements,
+        error_name: Option<String>,
+        handler: Statements,
+    },
+    /// Fail with a custom error (the value is used as the message)
+    Raise(Box<ParseNode>),
+    /// Define a function and store it in a new variable (`def name(params):`)
+    Function {
+        name: String,
+        params: Vec<String>,
+        body: Statements,
+    },
+    /// A tuple literal, e.g., `(x, y)`
+    Tuple(Vec<ParseNode>),
+    /// Declare several variables at once, e.g., `let x, y = f()` (see `AssignMultiple`)
+    AssignNewMultiple {
+        targets: Vec<String>,
+        values: Vec<ParseNode>,
+    },
+    /// Like `AssignNew`, but with a type annotation (`let x: i64 = 1`)
+    AssignNewAnnotated {
+        name: String,
+        annotation: TypeAnnotation,
+        value: Box<ParseNode>,
+    },
+    /// A `Function` with type annotations (`def name(x: i64) -> str:`)
+    ///
+    /// Annotations are kept separately, so that the layout of `Function` stays the same.
+    AnnotatedFunction {
+        function: Box<ParseNode>,
+        /// The annotations of the parameters (in the same order as the parameters)
+        param_types: Vec<Option<TypeAnnotation>>,
+        return_type: Option<TypeAnnotation>,
+    },
+    /// An array that contains the same value `length` times (`[0u8; 16]`)
+    ///
+    /// `[u8; 16]` is short for an array of zeros of that type.
+    Array {
+        value: Box<ParseNode>,
+        length: Box<ParseNode>,
+    },
+    /// A decimal literal (`1.25d`), kept as it was written (requires the `decimal` feature to run)
+    Decimal(String),
+    /// Integer division that rounds towards negative infinity (`lhs // rhs`)
+    FloorDivide {
+        lhs: Box<ParseNode>,
+        rhs: Box<ParseNode>,
+    },
+    /// A module that was resolved ahead of time (see `Program::link`)
+    LinkedModule(usize),
+    /// Division without rounding (`lhs / rhs`), which turns integers into floats
+    Divide {
+        lhs: Box<ParseNode>,
+        rhs: Box<ParseNode>,
+    },
+    /// The remainder of a floor division (`lhs % rhs`)
+    Remainder {
+        lhs: Box<ParseNode>,
+        rhs: Box<ParseNode>,
+    },
+    /// A number with the opposite sign (`-x`)
+    Negative(Box<ParseNode>),
+}
+
+/// Generates the body of `Expr::children` and `Expr::children_mut`
+macro_rules! expr_children {
+    ($expr:expr, $iter:ident, $as_ref:ident, $values:ident) => {
+        match $expr {
+            Expr::Var(_)
+            | Expr::LinkedModule(_)
+            | Expr::Disallowed(_)
+            | Expr::I64(_)
+            | Expr::U64(_)
+            | Expr::U8(_)
+            | Expr::Decimal(_)
+            | Expr::Bool(_)
+            | Expr::String(_) => vec![],
+            Expr::List(elems)
+            | Expr::Tuple(elems)
+            | Expr::AssignMultiple { values: elems, .. }
+            | Expr::AssignNewMultiple { values: elems, .. } => elems.$iter().collect(),
+            Expr::Brackets(inner)
+            | Expr::ToStr(inner)
+            | Expr::Not(inner)
+            | Expr::Negative(inner)
+            | Expr::Cast { value: inner, .. }
+            | Expr::Assign(_, inner)
+            | Expr::AddEquals { rhs: inner, .. }
+            | Expr::AssignNew(_, inner)
+            | Expr::AssignNewAnnotated { value: inner, .. }
+            | Expr::AnnotatedFunction {
+                function: inner, ..
+            }
+            | Expr::GetMember(inner, _)
+            | Expr::Return(inner)
+            | Expr::Raise(inner) => vec![inner.$as_ref()],
+            Expr::Range { start, end, step } => {
+                let mut result = vec![start.$as_ref(), end.$as_ref()];
+
+                if let Some(step) = step {
+                    result.push(step.$as_ref());
+                }
+
+                result
+            }
+            Expr::Max { lhs, rhs }
+            | Expr::Min { lhs, rhs }
+            | Expr::Add { lhs, rhs }
+            | Expr::Multiply { lhs, rhs }
+            | Expr::FloorDivide { lhs, rhs }
+            | Expr::Divide { lhs, rhs }
+            | Expr::Remainder { lhs, rhs }
+            | Expr::Compare { lhs, rhs, .. }
+            | Expr::And { lhs, rhs }
+            | Expr::Or { lhs, rhs }
+            | Expr::OpAssign { lhs, rhs, .. }
+            | Expr::Array {
+                value: lhs,
+                length: rhs,
+            }
+            | Expr::GetElement(lhs, rhs) => vec![lhs.$as_ref(), rhs.$as_ref()],
+            Expr::SetElement {
+                container,
+                key,
+                value,
+            } => vec![container.$as_ref(), key.$as_ref(), value.$as_ref()],
+            Expr::Dictionary(kvs) => kvs.$values().collect(),
+            Expr::ForIn { iter, body, .. }
+            | Expr::With {
+                resource: iter,
+                body,
+                ..
+            } => {
+                let mut result = vec![iter.$as_ref()];
+                result.extend(body.$iter());
+                result
+            }
+            Expr::Call(callee, args) => {
+                let mut result = vec![callee.$as_ref()];
+                result.extend(args.$iter());
+                result
+            }
+            Expr::IfElse {
+                cond,
+                body,
+                else_branch,
+            } => {
+                let mut result = vec![cond.$as_ref()];
+                result.extend(body.$iter());
+
+                if let Some(branch) = else_branch {
+                    result.extend(branch.$iter());
+                }
+
+                result
+            }
+            Expr::IfElseRecursive {
+                cond,
+                body,
+                else_branch,
+            } => {
+                let mut result = vec![cond.$as_ref()];
+                result.extend(body.$iter());
+                result.push(else_branch.$as_ref());
+                result
+            }
+            Expr::Try { body, handler, .. } => body.$iter().chain(handler.$iter()).collect(),
+            Expr::Function { body, .. } => body.$iter().collect(),
+        }
+    };
+}
+
+impl Expr {
+    /// All nodes directly contained in this expression (including statements of nested blocks)
+    pub fn children(&self) -> Vec<&ParseNode> {
+        expr_children!(self, iter, as_ref, values)
+    }
+
+    /// Mutable version of `children`
+    pub fn children_mut(&mut self) -> Vec<&mut ParseNode> {
+        expr_children!(self, iter_mut, as_mut, values_mut)
+    }
 }
@@ -0,0 +1,258 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{Expr, ParseNode, Program};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::grammar;
+
+/// What `Program::restrict` does with accesses the policy does not allow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Violation {
+    /// Fail with `RuntimeErrorKind::NotAllowed`
+    #[default]
+    Reject,
+    /// Replace the access with a stub that fails when it is evaluated
+    ///
+    /// Branches of the program that do not touch forbidden modules keep working.
+    Stub,
+}
+
+/// The modules and variables a restricted program may use
+///
+/// Everything that is not allowed explicitly is forbidden. Any name
+/// the program uses without declaring it is considered to be a module
+/// or a variable provided by the host.
+///
+/// *Note:* Variables declared by the program cannot hide modules, as the
+/// interpreter looks up modules first. So make sure the policy knows about all
+/// modules of the interpreter (see `Policy::register_module`). Otherwise, a
+/// program could declare, e.g., `os` and then access the real module under that name.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    /// Allowed modules and their allowed members (`None` means all members)
+    modules: HashMap<String, Option<HashSet<String>>>,
+    /// All other modules the host registers
+    registered: HashSet<String>,
+    variables: HashSet<String>,
+    pub on_violation: Violation,
+}
+
+impl Policy {
+    /// Allow all members of a module
+    pub fn allow_module(&mut self, module: &str) {
+        self.modules.insert(module.to_string(), None);
+    }
+
+    /// Allow a single member of a module
+    pub fn allow_member(&mut self, module: &str, member: &str) {
+        let entry = self
+            .modules
+            .entry(module.to_string())
+            .or_insert_with(|| Some(HashSet::new()));
+
+        if let Some(members) = entry {
+            members.insert(member.to_string());
+        }
+    }
+
+    /// Make the policy aware of a module the host registers (see `Interpreter::register_module`)
+    ///
+    /// The module stays forbidden, but every access to it is checked, even where
+    /// the program declares a variable with the same name.
+    pub fn register_module(&mut self, module: &str) {
+        self.registered.insert(module.to_string());
+    }
+
+    /// Allow reading a variable set by the host (see `Interpreter::set_value`)
+    pub fn allow_variable(&mut self, name: &str) {
+        self.variables.insert(name.to_string());
+    }
+
+    fn is_module(&self, name: &str) -> bool {
+        self.modules.contains_key(name) || self.registered.contains(name)
+    }
+
+    fn allows_member(&self, module: &str, member: &str) -> bool {
+        match self.modules.get(module) {
+            Some(None) => true,
+            Some(Some(members)) => members.contains(member),
+            None => false,
+        }
+    }
+
+    /// Can the name be used on its own (e.g., to pass a module to a function)?
+    ///
+    /// Global functions are always allowed, as they have no access to the host
+    /// (output of `print()` goes wherever the host wants it to).
+    fn allows_name(&self, name: &str) -> bool {
+        if self.is_module(name) {
+            return matches!(self.modules.get(name), Some(None));
+        }
+
+        self.variables.contains(name) || grammar::is_global_function(name)
+    }
+}
+
+impl Program {
+    /// Remove access to modules, members, and variables the policy does not allow
+    ///
+    /// Unlike checks done by modules at runtime, this only needs to happen once,
+    /// so the restricted program can be cached and run many times.
+    ///
+    /// *Note:* This must be done before `Program::link`, as linked modules have no name.
+    pub fn restrict(&mut self, policy: &Policy) -> Result<(), RuntimeError> {
+        let mut restrictor = Restrictor {
+            policy,
+            scopes: vec![HashSet::new()],
+        };
+
+        for stmt in self.stmts.iter_mut() {
+            restrictor.restrict(stmt)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Restrictor<'a> {
+    policy: &'a Policy,
+    /// Names declared so far by the blocks that enclose the current node
+    scopes: Vec<HashSet<String>>,
+}
+
+impl Restrictor<'_> {
+    /// Does the name refer to a variable declared by the program?
+    ///
+    /// Modules are never hidden by declarations.
+    fn is_declared(&self, name: &str) -> bool {
+        !self.policy.is_module(name) && self.scopes.iter().any(|scope| scope.contains(name))
+    }
+
+    fn declare(&mut self, name: &str) {
+        self.scopes.last_mut().unwrap().insert(name.to_string());
+    }
+
+    /// Restrict the statements of a block that declares the given names
+    fn restrict_block<'n>(
+        &mut self,
+        names: impl IntoIterator<Item = &'n String>,
+        stmts: &mut [ParseNode],
+    ) -> Result<(), RuntimeError> {
+        self.scopes.push(names.into_iter().cloned().collect());
+
+        let result = stmts.iter_mut().try_for_each(|stmt| self.restrict(stmt));
+
+        self.scopes.pop();
+        result
+    }
+
+    fn restrict(&mut self, node: &mut ParseNode) -> Result<(), RuntimeError> {
+        let (span, expr) = node;
+
+        let forbidden = match &*expr {
+            Expr::GetMember(inner, member) => match inner.as_ref() {
+                (_, Expr::Var(module))
+                    if self.policy.is_module(module)
+                        || (!self.is_declared(module)
+                            && !self.policy.variables.contains(module)) =>
+                {
+                    if self.policy.allows_member(module, member) {
+                        // Do not look at the module name on its own
+                        return Ok(());
+                    }
+                    Some(format!("{module}.{member}"))
+                }
+                _ => None,
+            },
+            Expr::Var(name) if !self.is_declared(name) && !self.policy.allows_name(name) => {
+                Some(format!("'{name}'"))
+            }
+            _ => None,
+        };
+
+        if let Some(access) = forbidden {
+            match self.policy.on_violation {
+                Violation::Reject => {
+                    let err = RuntimeErrorKind::NotAllowed(access);
+                    return Err(RuntimeError::from(err).with_span(*span));
+                }
+                Violation::Stub => {
+                    *expr = Expr::Disallowed(access);
+                    return Ok(());
+                }
+            }
+        }
+
+        // Names are only declared after their value was evaluated, and
+        // only for the remainder of the block that declares them
+        match expr {
+            Expr::AssignNew(name, value) | Expr::AssignNewAnnotated { name, value, .. } => {
+                self.restrict(value)?;
+                self.declare(name);
+            }
+            Expr::AssignNewMultiple { targets, values } => {
+                for value in values.iter_mut() {
+                    self.restrict(value)?;
+                }
+
+                for target in targets.iter() {
+                    self.declare(target);
+                }
+            }
+            Expr::ForIn {
+                iter,
+                target_name,
+                body,
+            }
+            | Expr::With {
+                resource: iter,
+                target_name,
+                body,
+            } => {
+                self.restrict(iter)?;
+                self.restrict_block([&*target_name], body)?;
+            }
+            Expr::IfElse {
+                cond,
+                body,
+                else_branch,
+            } => {
+                self.restrict(cond)?;
+                self.restrict_block([], body)?;
+
+                if let Some(branch) = else_branch {
+                    self.restrict_block([], branch)?;
+                }
+            }
+            Expr::IfElseRecursive {
+                cond,
+                body,
+                else_branch,
+            } => {
+                self.restrict(cond)?;
+                self.restrict_block([], body)?;
+                self.restrict(else_branch)?;
+            }
+            Expr::Try {
+                body,
+                error_name,
+                handler,
+            } => {
+                self.restrict_block([], body)?;
+                self.restrict_block(error_name.iter(), handler)?;
+            }
+            Expr::Function { name, params, body } => {
+                // The body can call the function itself
+                let names = params.iter().chain([&*name]);
+                self.restrict_block(names, body)?;
+                self.declare(name);
+            }
+            _ => {
+                for child in expr.children_mut() {
+                    self.restrict(child)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+use super::{CompareType, Expr, ParseNode, Program, Span, Statements};
+use crate::values::Value;
+
+impl Program {
+    /// Create a version of this program for inputs that are known in advance
+    ///
+    /// Variables set by the host (see `Interpreter::set_value`) are replaced by
+    /// their value, and expressions and conditions that only depend on constants
+    /// are computed ahead of time. This is useful when the same program runs
+    /// many times with a few fixed parameters.
+    ///
+    /// *Note:* Only booleans, integers, decimals, and strings are substituted, as
+    /// the other types have no literal (or can be modified by the program). Variables
+    /// the program declares or assigns to are never substituted. Expressions that
+    /// would fail (e.g., because of a division by zero) are kept, so that they
+    /// still fail when the program runs.
+    pub fn specialize(&self, vars: HashMap<String, Value>) -> Program {
+        let mut written = HashSet::new();
+        for stmt in self.stmts.iter() {
+            collect_written(stmt, &mut written);
+        }
+
+        let constants = vars
+            .into_iter()
+            .filter(|(name, _)| !written.contains(name))
+            .filter_map(|(name, value)| Some((name, literal(&value)?)))
+            .collect();
+
+        let specializer = Specializer { constants };
+
+        let mut program = self.clone();
+        specializer.block(&mut program.stmts);
+        program
+    }
+}
+
+struct Specializer {
+    constants: HashMap<String, Expr>,
+}
+
+impl Specializer {
+    fn block(&self, stmts: &mut Statements) {
+        let mut result = Vec::with_capacity(stmts.len());
+
+        for mut stmt in stmts.drain(..) {
+            self.node(&mut stmt);
+
+            match stmt.1 {
+                // Take the branch that will run, if it is known
+                Expr::IfElse {
+                    cond,
+                    body,
+                    else_branch,
+                } => match cond.1 {
+                    Expr::Bool(true) => inline_block(stmt.0, body, &mut result),
+                    Expr::Bool(false) => {
+                        if let Some(branch) = else_branch {
+                            inline_block(stmt.0, branch, &mut result);
+                        }
+                    }
+                    _ => result.push((
+                        stmt.0,
+                        Expr::IfElse {
+                            cond,
+                            body,
+                            else_branch,
+                        },
+                    )),
+                },
+                expr => result.push((stmt.0, expr)),
+            }
+        }
+
+        *stmts = result;
+    }
+
+    fn node(&self, node: &mut ParseNode) {
+        let (_, expr) = node;
+
+        match expr {
+            Expr::IfElse {
+                cond,
+                body,
+                else_branch,
+            } => {
+                self.node(cond);
+                self.block(body);
+
+                if let Some(branch) = else_branch {
+                    self.block(branch);
+                }
+            }
+            Expr::IfElseRecursive {
+                cond,
+                body,
+                else_branch,
+            } => {
+                self.node(cond);
+                self.block(body);
+                self.node(else_branch);
+
+                // Turn `elif` chains into plain if-statements, so `block` can remove them
+                let replacement = match cond.1 {
+                    Expr::Bool(true) => Expr::IfElse {
+                        cond: cond.clone(),
+                        body: mem::take(body),
+                        else_branch: None,
+                    },
+                    Expr::Bool(false) => mem::replace(&mut else_branch.1, Expr::Bool(false)),
+                    _ => return,
+                };
+
+                *expr = replacement;
+            }
+            Expr::ForIn { iter, body, .. }
+            | Expr::With {
+                resource: iter,
+                body,
+                ..
+            } => {
+                self.node(iter);
+                self.block(body);
+            }
+            Expr::Try { body, handler, .. } => {
+                self.block(body);
+                self.block(handler);
+            }
+            Expr::Function { body, .. } => self.block(body),
+            Expr::Var(name) => {
+                if let Some(value) = self.constants.get(name) {
+                    *expr = value.clone();
+                }
+            }
+            _ => {
+                for child in expr.children_mut() {
+                    self.node(child);
+                }
+
+                if let Some(folded) = fold(expr) {
+                    *expr = folded;
+                }
+            }
+        }
+    }
+}
+
+/// Add the statements of a block that always runs to the surrounding block
+///
+/// Blocks that declare variables are kept as they are, because the variables
+/// must not be visible outside of them.
+fn inline_block(span: Span, body: Statements, result: &mut Statements) {
+    let declares = body.iter().any(|(_, expr)| {
+        matches!(
+            expr,
+            Expr::AssignNew(..)
+                | Expr::AssignNewAnnotated { .. }
+                | Expr::AssignNewMultiple { .. }
+                | Expr::Function { .. }
+                | Expr::AnnotatedFunction { .. }
+        )
+    });
+
+    if declares {
+        let cond = Box::new((span, Expr::Bool(true)));
+        result.push((
+            span,
+            Expr::IfElse {
+                cond,
+                body,
+                else_branch: None,
+            },
+        ));
+    } else {
+        result.extend(body);
+    }
+}
+
+/// Compute an expression whose operands are all constants
+fn fold(expr: &Expr) -> Option<Expr> {
+    let value = match expr {
+        Expr::Brackets(inner) => to_value(&inner.1)?,
+        Expr::Add { lhs, rhs } => to_value(&lhs.1)?.add(&to_value(&rhs.1)?).ok()?,
+        Expr::Multiply { lhs, rhs } => to_value(&lhs.1)?.multiply(&to_value(&rhs.1)?).ok()?,
+        Expr::FloorDivide { lhs, rhs } => {
+            to_value(&lhs.1)?.floor_divide(&to_value(&rhs.1)?).ok()?
+        }
+        Expr::Divide { lhs, rhs } => to_value(&lhs.1)?.divide(&to_value(&rhs.1)?).ok()?,
+        Expr::Remainder { lhs, rhs } => to_value(&lhs.1)?.remainder(&to_value(&rhs.1)?).ok()?,
+        Expr::Compare { ctype, lhs, rhs } => {
+            let lhs = to_value(&lhs.1)?;
+            let rhs = to_value(&rhs.1)?;
+
+            let result = match ctype {
+                CompareType::Greater => lhs.is_greater_than(&rhs),
+                CompareType::Smaller => lhs.is_smaller_than(&rhs),
+                CompareType::Equals => lhs.equals(&rhs),
+                CompareType::In => rhs.contains(&lhs),
+            };
+
+            result.ok()?.into()
+        }
+        Expr::Not(inner) => to_value(&inner.1)?.negate().ok()?,
+        Expr::Negative(inner) => to_value(&inner.1)?.negative().ok()?,
+        Expr::And { lhs, rhs } => {
+            let result = to_value(&lhs.1)?.as_bool().ok()? && to_value(&rhs.1)?.as_bool().ok()?;
+            result.into()
+        }
+        Expr::Or { lhs, rhs } => {
+            let result = to_value(&lhs.1)?.as_bool().ok()? || to_value(&rhs.1)?.as_bool().ok()?;
+            result.into()
+        }
+        _ => return None,
+    };
+
+    literal(&value)
+}
+
+fn to_value(expr: &Expr) -> Option<Value> {
+    let value = match expr {
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::I64(i) => Value::I64(*i),
+        Expr::U64(u) => Value::U64(*u),
+        Expr::U8(u) => Value::U8(*u),
+        Expr::String(s) => Value::Str(s.clone()),
+        #[cfg(feature = "decimal")]
+        Expr::Decimal(d) => Value::Decimal(d.parse().ok()?),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+fn literal(value: &Value) -> Option<Expr> {
+    let expr = match value.unfrozen() {
+        Value::Bool(b) => Expr::Bool(*b),
+        Value::I64(i) => Expr::I64(*i),
+        Value::U64(u) => Expr::U64(*u),
+        Value::U8(u) => Expr::U8(*u),
+        Value::Str(s) => Expr::String(s.clone()),
+        #[cfg(feature = "decimal")]
+        Value::Decimal(d) => Expr::Decimal(d.to_string()),
+        _ => return None,
+    };
+
+    Some(expr)
+}
+
+/// Collect all variables the program declares or assigns to
+fn collect_written(node: &ParseNode, written: &mut HashSet<String>) {
+    let (_, expr) = node;
+
+    match expr {
+        Expr::AssignNew(name, _)
+        | Expr::AssignNewAnnotated { name, .. }
+        | Expr::Assign(name, _)
+        | Expr::AddEquals { lhs: name, .. }
+        | Expr::ForIn {
+            target_name: name, ..
+        }
+        | Expr::With {
+            target_name: name, ..
+        }
+        | Expr::Try {
+            error_name: Some(name),
+            ..
+        } => {
+            written.insert(name.clone());
+        }
+        Expr::AssignNewMultiple { targets, .. } | Expr::AssignMultiple { targets, .. } => {
+            written.extend(targets.iter().cloned());
+        }
+        Expr::Function { name, params, .. } => {
+            written.insert(name.clone());
+            written.extend(params.iter().cloned());
+        }
+        Expr::OpAssign { lhs: target, .. }
+        | Expr::SetElement {
+            container: target, ..
+        } => {
+            if let (_, Expr::Var(name)) = target.as_ref() {
+                written.insert(name.clone());
+            }
+        }
+        _ => {}
+    }
+
+    for child in expr.children() {
+        collect_written(child, written);
+    }
+}
@@ -0,0 +1,113 @@
+use std::mem;
+
+use serde::{Deserialize, Serialize};
+
+use super::{ArithmeticOp, Expr, ParseNode, Program, Statements};
+use crate::error::MigrationError;
+
+/// The version of the program format produced by this version of the crate
+///
+/// Increment this whenever the layout or the meaning of the AST changes.
+pub const PROGRAM_VERSION: u32 = 1;
+
+/// A program in a form that can be stored persistently
+///
+/// Loading works even if the program was stored by an earlier version of this crate.
+/// When the layout of the AST changes, the old layout is kept around as a new variant
+/// and converted when loading the program.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum VersionedProgram {
+    V1(Program),
+}
+
+impl VersionedProgram {
+    /// The format version the program was stored with
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::V1(_) => 1,
+        }
+    }
+
+    /// Convert to a program that can be run by this version of the crate
+    pub fn into_program(self) -> Result<Program, MigrationError> {
+        let version = self.version();
+
+        let mut program = match self {
+            Self::V1(program) => program,
+        };
+
+        program.migrate(version)?;
+        Ok(program)
+    }
+}
+
+/// A program stored by a release of this crate that did not tag programs with a version yet
+///
+/// These programs were stored as a bare `Program` without a name. There is no reliable
+/// way to tell them apart from a `VersionedProgram`, so they have to be loaded as this
+/// type explicitly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegacyProgram {
+    pub stmts: Statements,
+}
+
+impl LegacyProgram {
+    /// Convert to a program that can be run by this version of the crate
+    pub fn into_program(self) -> Result<Program, MigrationError> {
+        let mut program = Program {
+            name: None,
+            stmts: self.stmts,
+            comments: vec![],
+        };
+
+        program.migrate(0)?;
+        Ok(program)
+    }
+}
+
+impl From<Program> for VersionedProgram {
+    fn from(program: Program) -> Self {
+        Self::V1(program)
+    }
+}
+
+impl Program {
+    /// Update a program that was compiled by an earlier version of this crate
+    ///
+    /// This is only needed when the meaning of the AST changed, not just its layout,
+    /// and is done automatically by `VersionedProgram::into_program`.
+    pub fn migrate(&mut self, from_version: u32) -> Result<(), MigrationError> {
+        match from_version {
+            v if v > PROGRAM_VERSION => Err(MigrationError::UnknownVersion(from_version)),
+            0 => {
+                for stmt in self.stmts.iter_mut() {
+                    replace_add_equals(stmt);
+                }
+                Ok(())
+            }
+            // Nothing changed yet
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Turn `AddEquals` into `OpAssign` (needed for version 0)
+fn replace_add_equals(node: &mut ParseNode) {
+    let (span, expr) = node;
+
+    if matches!(expr, Expr::AddEquals { .. }) {
+        let Expr::AddEquals { lhs, rhs } = mem::replace(expr, Expr::Bool(false)) else {
+            unreachable!();
+        };
+
+        *expr = Expr::OpAssign {
+            op: ArithmeticOp::Add,
+            lhs: Box::new((*span, Expr::Var(lhs))),
+            rhs,
+        };
+    }
+
+    for child in expr.children_mut() {
+        replace_add_equals(child);
+    }
+}
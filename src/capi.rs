@@ -0,0 +1,445 @@
+//! C interface for hosts that are not written in Rust (requires the `capi` feature)
+//!
+//! All objects are passed around as opaque pointers. Every pointer returned by
+//! a function of this module is owned by the caller and must be released with
+//! the matching `cow_*_free` function. Functions that can fail return NULL (or
+//! false) and, if `error` is not NULL, store an error in it.
+//!
+//! The declarations for C are in `include/cowlang.h`. Build a library for C
+//! hosts with `cargo rustc --release --features capi --crate-type staticlib`
+//! (or `cdylib`).
+//!
+//! *Note:* All pointers passed to these functions must either be NULL (where
+//! allowed) or have been returned by this module and not been freed yet.
+//! Strings must be valid, NUL-terminated UTF-8. Functions that take ownership
+//! of a value do so even if they fail.
+
+#![allow(clippy::missing_safety_doc)]
+
+use std::any::Any;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::ast::Program;
+use crate::{compile, Interpreter, Value};
+
+/// A compiled program
+pub struct CowProgram(Program);
+
+/// An interpreter, which keeps its variables and settings between runs
+pub struct CowInterpreter(Interpreter);
+
+/// A value passed to or returned from a program
+pub struct CowValue(Value);
+
+/// Describes why an operation failed
+pub struct CowError(CString);
+
+/// The type of a value (see `cow_value_type`)
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CowValueType {
+    None = 0,
+    Bool = 1,
+    String = 2,
+    I64 = 3,
+    U64 = 4,
+    U8 = 5,
+    F32 = 6,
+    F64 = 7,
+    Map = 8,
+    List = 9,
+    Bytes = 10,
+    Tuple = 11,
+    Array = 12,
+    /// Only used with the `decimal` feature
+    Decimal = 13,
+}
+
+fn into_ptr<T>(object: T) -> *mut T {
+    Box::into_raw(Box::new(object))
+}
+
+unsafe fn free<T>(object: *mut T) {
+    if !object.is_null() {
+        drop(Box::from_raw(object));
+    }
+}
+
+unsafe fn set_error(error: *mut *mut CowError, message: String) {
+    if error.is_null() {
+        return;
+    }
+
+    // Messages never contain NUL, but do not fail if one does
+    let message = CString::new(message.replace('\0', " ")).unwrap();
+    *error = into_ptr(CowError(message));
+}
+
+unsafe fn to_str<'a>(s: *const c_char, error: *mut *mut CowError) -> Option<&'a str> {
+    if s.is_null() {
+        set_error(error, "String must not be NULL".to_string());
+        return None;
+    }
+
+    match CStr::from_ptr(s).to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            set_error(error, "String is not valid UTF-8".to_string());
+            None
+        }
+    }
+}
+
+/// Take ownership of a value passed in by the caller
+unsafe fn take_value(value: *mut CowValue, error: *mut *mut CowError) -> Option<Value> {
+    if value.is_null() {
+        set_error(error, "Value must not be NULL".to_string());
+        None
+    } else {
+        Some(Box::from_raw(value).0)
+    }
+}
+
+/// Turn a panic into an error, as panics must not unwind into C code
+fn catch_panics<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(format!("Internal error: {}", panic_message(&*payload))))
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown cause".to_string()
+    }
+}
+
+fn to_c_string(s: &str) -> *mut c_char {
+    match CString::new(s) {
+        Ok(s) => s.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Compile a program (returns NULL if the source is invalid)
+#[no_mangle]
+pub unsafe extern "C" fn cow_compile(
+    source: *const c_char,
+    error: *mut *mut CowError,
+) -> *mut CowProgram {
+    let Some(source) = to_str(source, error) else {
+        return ptr::null_mut();
+    };
+
+    match catch_panics(|| compile(source).map_err(|err| err.to_string())) {
+        Ok(program) => into_ptr(CowProgram(program)),
+        Err(message) => {
+            set_error(error, message);
+            ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cow_program_free(program: *mut CowProgram) {
+    free(program);
+}
+
+#[no_mangle]
+pub extern "C" fn cow_interpreter_new() -> *mut CowInterpreter {
+    into_ptr(CowInterpreter(Interpreter::default()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cow_interpreter_free(interpreter: *mut CowInterpreter) {
+    free(interpreter);
+}
+
+/// Make a value available to programs under the given name
+///
+/// The interpreter takes ownership of the value. The value is freed if the call fails.
+#[no_mangle]
+pub unsafe extern "C" fn cow_interpreter_set_value(
+    interpreter: *mut CowInterpreter,
+    name: *const c_char,
+    value: *mut CowValue,
+    error: *mut *mut CowError,
+) -> bool {
+    let Some(value) = take_value(value, error) else {
+        return false;
+    };
+    let Some(name) = to_str(name, error) else {
+        return false;
+    };
+
+    (*interpreter).0.set_value(name.to_string(), value);
+    true
+}
+
+/// Run a program and return its result (returns NULL if the program fails)
+///
+/// Programs do not change the interpreter's variables (see `Interpreter::run_isolated`),
+/// so an interpreter can run any number of programs.
+#[no_mangle]
+pub unsafe extern "C" fn cow_interpreter_run(
+    interpreter: *mut CowInterpreter,
+    program: *const CowProgram,
+    error: *mut *mut CowError,
+) -> *mut CowValue {
+    let interpreter = &mut (*interpreter).0;
+
+    let result = catch_panics(|| {
+        interpreter
+            .run_isolated(&(*program).0, Default::default())
+            .map_err(|err| err.to_string())
+    });
+
+    match result {
+        Ok(value) => into_ptr(CowValue(value)),
+        Err(message) => {
+            set_error(error, message);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// The message of an error (valid until the error is freed)
+#[no_mangle]
+pub unsafe extern "C" fn cow_error_message(error: *const CowError) -> *const c_char {
+    (*error).0.as_ptr()
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cow_error_free(error: *mut CowError) {
+    free(error);
+}
+
+/// Free a string returned by this library
+#[no_mangle]
+pub unsafe extern "C" fn cow_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn cow_value_none() -> *mut CowValue {
+    into_ptr(CowValue(Value::None))
+}
+
+#[no_mangle]
+pub extern "C" fn cow_value_bool(b: bool) -> *mut CowValue {
+    into_ptr(CowValue(b.into()))
+}
+
+#[no_mangle]
+pub extern "C" fn cow_value_i64(i: i64) -> *mut CowValue {
+    into_ptr(CowValue(i.into()))
+}
+
+#[no_mangle]
+pub extern "C" fn cow_value_u64(u: u64) -> *mut CowValue {
+    into_ptr(CowValue(u.into()))
+}
+
+#[no_mangle]
+pub extern "C" fn cow_value_f64(f: f64) -> *mut CowValue {
+    into_ptr(CowValue(f.into()))
+}
+
+/// Create a string value (returns NULL if the string is not valid UTF-8)
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_string(s: *const c_char) -> *mut CowValue {
+    match to_str(s, ptr::null_mut()) {
+        Some(s) => into_ptr(CowValue(s.into())),
+        None => ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn cow_value_list() -> *mut CowValue {
+    into_ptr(CowValue(Value::make_list()))
+}
+
+#[no_mangle]
+pub extern "C" fn cow_value_map() -> *mut CowValue {
+    into_ptr(CowValue(Value::make_map()))
+}
+
+/// Append to a list
+///
+/// Takes ownership of the element, which is freed if the call fails.
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_list_append(
+    list: *mut CowValue,
+    element: *mut CowValue,
+    error: *mut *mut CowError,
+) -> bool {
+    let Some(element) = take_value(element, error) else {
+        return false;
+    };
+
+    match (*list).0.list_append(element) {
+        Ok(()) => true,
+        Err(err) => {
+            set_error(error, err.to_string());
+            false
+        }
+    }
+}
+
+/// Insert or replace an entry of a map
+///
+/// Takes ownership of the value, which is freed if the call fails.
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_map_insert(
+    map: *mut CowValue,
+    key: *const c_char,
+    value: *mut CowValue,
+    error: *mut *mut CowError,
+) -> bool {
+    let Some(value) = take_value(value, error) else {
+        return false;
+    };
+    let Some(key) = to_str(key, error) else {
+        return false;
+    };
+
+    match (*map).0.set(key.to_string(), value) {
+        Ok(()) => true,
+        Err(err) => {
+            set_error(error, err.to_string());
+            false
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_free(value: *mut CowValue) {
+    free(value);
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_type(value: *const CowValue) -> CowValueType {
+    match (*value).0.unfrozen() {
+        Value::None => CowValueType::None,
+        Value::Bool(_) => CowValueType::Bool,
+        Value::Str(_) => CowValueType::String,
+        Value::I64(_) => CowValueType::I64,
+        Value::U64(_) => CowValueType::U64,
+        Value::U8(_) => CowValueType::U8,
+        Value::F32(_) => CowValueType::F32,
+        Value::F64(_) => CowValueType::F64,
+        Value::Map(_) => CowValueType::Map,
+        Value::List(_) => CowValueType::List,
+        Value::Bytes(_) => CowValueType::Bytes,
+        Value::Tuple(_) => CowValueType::Tuple,
+        Value::Array(_) => CowValueType::Array,
+        #[cfg(feature = "decimal")]
+        Value::Decimal(_) => CowValueType::Decimal,
+        Value::Frozen(_) => unreachable!(),
+    }
+}
+
+/// Store the value in `result` if it is a boolean
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_as_bool(value: *const CowValue, result: *mut bool) -> bool {
+    match (*value).0.unfrozen() {
+        Value::Bool(b) => {
+            *result = *b;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Store the value in `result` if it is an integer that fits into an i64
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_as_i64(value: *const CowValue, result: *mut i64) -> bool {
+    let converted = match (*value).0.unfrozen() {
+        Value::U8(u) => Ok(*u as i64),
+        other => other.clone().try_into(),
+    };
+
+    match converted {
+        Ok(i) => {
+            *result = i;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Store the value in `result` if it is a number
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_as_f64(value: *const CowValue, result: *mut f64) -> bool {
+    let converted = match (*value).0.unfrozen() {
+        Value::U8(u) => Ok(*u as f64),
+        other => other.clone().try_into(),
+    };
+
+    match converted {
+        Ok(f) => {
+            *result = f;
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Get the content of a string value (returns NULL for other types)
+///
+/// Free the result with `cow_string_free`.
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_as_string(value: *const CowValue) -> *mut c_char {
+    match (*value).0.unfrozen() {
+        Value::Str(s) => to_c_string(s),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Describe the value as programs would print it (see `Display for Value`)
+///
+/// Free the result with `cow_string_free`.
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_to_string(value: *const CowValue) -> *mut c_char {
+    to_c_string(&(*value).0.to_string())
+}
+
+/// The number of elements of a list, tuple, array, map, string, or bytes (-1 for other types)
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_length(value: *const CowValue) -> i64 {
+    match (*value).0.length() {
+        Ok(length) => length as i64,
+        Err(_) => -1,
+    }
+}
+
+/// Get a copy of an element of a list, tuple, or array (returns NULL if there is none)
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_get_index(value: *const CowValue, index: u64) -> *mut CowValue {
+    match (*value).0.get_child(index.into()) {
+        Ok(element) => into_ptr(CowValue(element.clone())),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// Get a copy of an entry of a map (returns NULL if there is none)
+#[no_mangle]
+pub unsafe extern "C" fn cow_value_get_key(
+    value: *const CowValue,
+    key: *const c_char,
+) -> *mut CowValue {
+    let Some(key) = to_str(key, ptr::null_mut()) else {
+        return ptr::null_mut();
+    };
+
+    match (*value).0.get(key) {
+        Ok(entry) => into_ptr(CowValue(entry.clone())),
+        Err(_) => ptr::null_mut(),
+    }
+}
@@ -0,0 +1,227 @@
+use crate::ast::{Expr, ParseNode, Program, Span, Statements};
+use crate::format::{num_arguments, parse_format};
+
+/// A non-fatal issue found while compiling a program
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub span: Span,
+    /// The name of the script the warning refers to (if known)
+    pub source_name: Option<String>,
+    pub message: String,
+}
+
+/// Inspect a parsed program for likely bugs
+///
+/// This does not change the program; it only reports warnings.
+pub fn analyze(program: &Program) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    analyze_statements(&program.stmts, &mut warnings);
+
+    // `format` is not reserved, so the program might define its own
+    if !program.stmts.iter().any(|stmt| declares(stmt, "format")) {
+        for stmt in program.stmts.iter() {
+            check_format_strings(stmt, &mut warnings);
+        }
+    }
+
+    if let Some(name) = &program.name {
+        for warning in warnings.iter_mut() {
+            warning.source_name = Some(name.clone());
+        }
+    }
+
+    warnings
+}
+
+fn analyze_statements(stmts: &Statements, warnings: &mut Vec<Warning>) {
+    for stmt in stmts {
+        analyze_statement(stmt, warnings);
+    }
+}
+
+fn analyze_statement(stmt: &ParseNode, warnings: &mut Vec<Warning>) {
+    let (span, expr) = stmt;
+
+    match expr {
+        Expr::ForIn { iter, body, .. } => {
+            check_loop_length(iter, warnings);
+            analyze_statements(body, warnings);
+        }
+        Expr::With { body, .. } => analyze_statements(body, warnings),
+        Expr::Try { body, handler, .. } => {
+            analyze_statements(body, warnings);
+            analyze_statements(handler, warnings);
+        }
+        Expr::Function { body, .. } => analyze_statements(body, warnings),
+        Expr::AnnotatedFunction { function, .. } => analyze_statement(function, warnings),
+        Expr::IfElse {
+            body, else_branch, ..
+        } => {
+            analyze_statements(body, warnings);
+
+            if let Some(branch) = else_branch {
+                analyze_statements(branch, warnings);
+            }
+        }
+        Expr::IfElseRecursive {
+            body, else_branch, ..
+        } => {
+            analyze_statements(body, warnings);
+            analyze_statement(else_branch, warnings);
+        }
+        Expr::AssignMultiple { targets, values } | Expr::AssignNewMultiple { targets, values } => {
+            // A single value is a list that gets unpacked at runtime
+            if values.len() > 1 && values.len() != targets.len() {
+                warnings.push(Warning {
+                    span: *span,
+                    source_name: None,
+                    message: format!(
+                        "Cannot assign {} values to {} variables",
+                        values.len(),
+                        targets.len()
+                    ),
+                });
+            }
+        }
+        // Statements and calls (which might have side effects)
+        Expr::Assign(..)
+        | Expr::AssignNew(..)
+        | Expr::AssignNewAnnotated { .. }
+        | Expr::AddEquals { .. }
+        | Expr::OpAssign { .. }
+        | Expr::SetElement { .. }
+        | Expr::Return(_)
+        | Expr::Raise(_)
+        | Expr::Call(..) => {}
+        _ => {
+            warnings.push(Warning {
+                span: *span,
+                source_name: None,
+                message:
+                    "Result of expression is discarded. Did you forget `return` or an assignment?"
+                        .to_string(),
+            });
+        }
+    }
+}
+
+/// Loops with more iterations than this are very unlikely to finish
+const MAX_LOOP_ITERATIONS: i128 = 100_000_000;
+
+/// Warn about loops over literal ranges that are too large to ever finish
+fn check_loop_length(iter: &ParseNode, warnings: &mut Vec<Warning>) {
+    let (span, Expr::Range { start, end, step }) = iter else {
+        return;
+    };
+
+    let step = match step {
+        Some(step) => integer_literal(step),
+        None => Some(1),
+    };
+
+    let (Some(start), Some(end), Some(step)) = (integer_literal(start), integer_literal(end), step)
+    else {
+        return;
+    };
+
+    // Empty ranges are fine and a step of zero fails when the program runs
+    if start >= end || step <= 0 {
+        return;
+    }
+
+    let iterations = (end - start + step - 1) / step;
+
+    if iterations > MAX_LOOP_ITERATIONS {
+        warnings.push(Warning {
+            span: *span,
+            source_name: None,
+            message: format!("Loop runs {iterations} times and will probably not finish"),
+        });
+    }
+}
+
+fn integer_literal(node: &ParseNode) -> Option<i128> {
+    match &node.1 {
+        Expr::I64(i) => Some(*i as i128),
+        Expr::U64(u) => Some(*u as i128),
+        Expr::U8(u) => Some(*u as i128),
+        _ => None,
+    }
+}
+
+/// Make sure literal format strings match the number of arguments passed to `format()`
+fn check_format_strings(node: &ParseNode, warnings: &mut Vec<Warning>) {
+    let (span, expr) = node;
+
+    if let Some((format, num_args)) = literal_format_call(expr) {
+        let message = match parse_format(format) {
+            Ok(parts) if num_arguments(&parts) != num_args => Some(format!(
+                "Format string expects {} arguments, but got {}",
+                num_arguments(&parts),
+                num_args
+            )),
+            Ok(_) => None,
+            Err(err) => Some(err),
+        };
+
+        if let Some(message) = message {
+            warnings.push(Warning {
+                span: *span,
+                source_name: None,
+                message,
+            });
+        }
+    }
+
+    for child in expr.children() {
+        check_format_strings(child, warnings);
+    }
+}
+
+/// The format string and the number of arguments of a call like `format('%s', x)`
+fn literal_format_call(expr: &Expr) -> Option<(&str, usize)> {
+    let Expr::Call(callee, args) = expr else {
+        return None;
+    };
+
+    match (&callee.1, args.first()) {
+        (Expr::Var(name), Some((_, Expr::String(format)))) if name == "format" => {
+            Some((format, args.len() - 1))
+        }
+        _ => None,
+    }
+}
+
+/// Does the node (or any node it contains) declare a variable with the given name?
+fn declares(node: &ParseNode, name: &str) -> bool {
+    let declared = match &node.1 {
+        Expr::AssignNew(target, _)
+        | Expr::AssignNewAnnotated { name: target, .. }
+        | Expr::ForIn {
+            target_name: target,
+            ..
+        }
+        | Expr::With {
+            target_name: target,
+            ..
+        }
+        | Expr::Try {
+            error_name: Some(target),
+            ..
+        } => target == name,
+        Expr::AssignNewMultiple { targets, .. } => targets.iter().any(|t| t == name),
+        Expr::Function {
+            name: target,
+            params,
+            ..
+        } => target == name || params.iter().any(|p| p == name),
+        _ => false,
+    };
+
+    declared
+        || node
+            .1
+            .children()
+            .into_iter()
+            .any(|child| declares(child, name))
+}
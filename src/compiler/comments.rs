@@ -0,0 +1,46 @@
+use crate::ast::{Comment, ParseNode, Span, Statements};
+
+/// Find the statement each comment belongs to
+pub(super) fn attach_comments(
+    input: &str,
+    comments: Vec<(Span, String)>,
+    stmts: &Statements,
+) -> Vec<Comment> {
+    let mut spans = vec![];
+    for stmt in stmts.iter() {
+        collect_spans(stmt, &mut spans);
+    }
+
+    // Nodes starting at the same position are ordered from the outermost to the innermost
+    spans.sort_by(|a, b| a.lo.cmp(&b.lo).then(b.hi.cmp(&a.hi)));
+
+    comments
+        .into_iter()
+        .map(|(span, text)| {
+            let line_start = input[..span.lo].rfind('\n').map_or(0, |pos| pos + 1);
+
+            let target = if input[line_start..span.lo].trim().is_empty() {
+                // On a line of its own; describes the next statement
+                spans.iter().find(|s| s.lo >= span.hi)
+            } else {
+                // Trailing comment; describes the statement on the same line
+                spans.iter().find(|s| s.lo >= line_start && s.lo < span.lo)
+            };
+
+            Comment {
+                span,
+                text,
+                target: target.copied(),
+            }
+        })
+        .collect()
+}
+
+fn collect_spans(node: &ParseNode, spans: &mut Vec<Span>) {
+    let (span, expr) = node;
+    spans.push(*span);
+
+    for child in expr.children() {
+        collect_spans(child, spans);
+    }
+}
@@ -1,8 +1,10 @@
 use plex::lexer;
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::mem;
 
 use crate::ast::{Span, ValueType};
+use crate::error::CompileError;
 
 #[derive(Debug, Clone)]
 pub enum Token {
@@ -10,13 +12,17 @@ pub enum Token {
     I64Literal(i64),
     U64Literal(u64),
     U8Literal(u8),
+    /// A decimal literal without its suffix (e.g., `1.25` for `1.25d`)
+    DecimalLiteral(String),
     StringLiteral(String),
-    #[allow(dead_code)]
     Comment(String),
     Identifier(String),
     TypeName(ValueType),
     As,
     Star,
+    Slash,
+    DoubleSlash,
+    Percent,
     Let,
     ToStr,
     Max,
@@ -32,10 +38,19 @@ pub enum Token {
     Whitespace,
     Newline,
     Plus,
+    Minus,
     PlusEquals,
+    MinusEquals,
+    StarEquals,
+    SlashEquals,
+    DoubleSlashEquals,
+    PercentEquals,
     Not,
+    And,
+    Or,
     In,
     For,
+    With,
     Comma,
     Return,
     Assign,
@@ -45,98 +60,26 @@ pub enum Token {
     Indent,
     Dedent,
     Colon,
+    Semicolon,
+    Arrow,
     If,
     Else,
+    Try,
+    Except,
+    Raise,
+    Def,
+    /// A character that is not part of the language (the parser reports an error for it)
+    Unknown(String),
+    /// A string literal that is missing its closing quote (the lexer fails on it)
+    UnterminatedString,
+    /// An integer literal that does not fit into its type (the lexer fails on it)
+    IntegerOutOfRange,
 }
 
-pub fn parse_indents(s: &str) -> BTreeMap<usize, i32> {
-    let mut indents = BTreeMap::new();
-    let mut is_newline = false;
-    let mut current_icount = 0;
-    let mut last_icount = vec![0];
-
-    for (pos, c) in s.chars().enumerate() {
-        if c == ' ' && is_newline {
-            current_icount += 1;
-        } else if c != ' ' && is_newline {
-            loop {
-                let top = last_icount[last_icount.len() - 1];
-
-                match current_icount.cmp(&top) {
-                    Ordering::Less => {
-                        *indents.entry(pos).or_insert(0) -= 2;
-                        last_icount.pop();
-                    }
-                    Ordering::Greater => {
-                        *indents.entry(pos).or_insert(0) += 2;
-                        last_icount.push(current_icount);
-                        break;
-                    }
-                    _ => {
-                        break;
-                    }
-                }
-            }
-
-            is_newline = false;
-        }
-
-        if c == '\n' {
-            is_newline = true;
-            current_icount = 0;
-        }
-    }
-
-    // Add Dedent at end?
-    while last_icount.len() > 1 {
-        last_icount.pop();
-        *indents.entry(s.len()).or_insert(0) -= 2;
-    }
-
-    indents
-}
-
-pub enum IndentResult {
-    Indent,
-    Dedent,
-    Newline,
-}
-
-pub fn get_next_indent(
-    position: usize,
-    indents: &mut BTreeMap<usize, i32>,
-) -> Option<IndentResult> {
-    let mut entry = indents.first_entry()?;
-    let ipos = *entry.key();
-
-    if ipos != position {
-        return None;
-    }
-
-    let e = entry.get_mut();
-
-    let token = if *e < 0 {
-        let even = *e % 2 == 0;
-        *e += 1;
-
-        if even {
-            IndentResult::Newline
-        } else {
-            IndentResult::Dedent
-        }
-    } else if *e > 0 {
-        *e -= 2;
-        IndentResult::Indent
-    } else {
-        panic!("invalid state");
-    };
-
-    if *e == 0 {
-        entry.remove_entry();
-    }
-
-    Some(token)
-}
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Token>();
+};
 
 lexer! {
     fn take_token(tok: 'a) -> Token;
@@ -148,13 +91,24 @@ lexer! {
     "min" => Token::Min,
     "return" => Token::Return,
     "not" => Token::Not,
+    "and" => Token::And,
+    "or" => Token::Or,
     "as" => Token::As,
     "for" => Token::For,
+    "with" => Token::With,
     "in" => Token::In,
     "!" => Token::Not,
+    r"\&\&" => Token::And,
+    r"\|\|" => Token::Or,
     "if" => Token::If,
     "else" => Token::Else,
+    "try" => Token::Try,
+    "except" => Token::Except,
+    "raise" => Token::Raise,
+    "def" => Token::Def,
     ":" => Token::Colon,
+    ";" => Token::Semicolon,
+    "->" => Token::Arrow,
     "true" => Token::BoolLiteral(true),
     "false" => Token::BoolLiteral(false),
     "=" => Token::Assign,
@@ -170,64 +124,183 @@ lexer! {
     "}" => Token::CloseCurlyBracket,
     r"\+" => Token::Plus,
     r"\+=" => Token::PlusEquals,
+    "-" => Token::Minus,
+    "-=" => Token::MinusEquals,
+    r"\*=" => Token::StarEquals,
+    "/=" => Token::SlashEquals,
+    "//=" => Token::DoubleSlashEquals,
+    "%=" => Token::PercentEquals,
     "<" => Token::Smaller,
     ">" => Token::Greater,
     r"\*" => Token::Star,
-    "[0-9]+" => Token::I64Literal(tok.parse().unwrap()),
+    "/" => Token::Slash,
+    "//" => Token::DoubleSlash,
+    "%" => Token::Percent,
+    "[0-9]+" => match tok.parse() {
+        Ok(i) => Token::I64Literal(i),
+        Err(_) => Token::IntegerOutOfRange,
+    },
     "[0-9]+u" => {
         // cut off the u at the end
-        Token::U64Literal(tok[..tok.len()-1].parse().unwrap())
+        match tok[..tok.len()-1].parse() {
+            Ok(i) => Token::U64Literal(i),
+            Err(_) => Token::IntegerOutOfRange,
+        }
     },
     "[0-9]+u8" => {
         // cut off the u8 at the end
-        let i:i64 = tok[..tok.len()-2].parse().unwrap();
-
-        if !(0..=256).contains(&i) {
-            panic!("Invalid u8 value: {}", i);
+        match tok[..tok.len()-2].parse() {
+            Ok(i) => Token::U8Literal(i),
+            Err(_) => Token::IntegerOutOfRange,
         }
-
-        Token::U8Literal(i as u8)
+    },
+    r"[0-9]+(\.[0-9]+)?d" => {
+        // cut off the d at the end
+        Token::DecimalLiteral(tok[..tok.len()-1].into())
     },
     "u8" => Token::TypeName(ValueType::U8),
     "i64" => Token::TypeName(ValueType::I64),
     "u64" => Token::TypeName(ValueType::U64),
-    r#""[^"]*""# => Token::StringLiteral(tok[1..tok.len()-1].into()),
+    r#""[^"\n]*""# => Token::StringLiteral(tok[1..tok.len()-1].into()),
     // Allow string literal with delimited by ' as well
-    r#"'[^']*'"# => Token::StringLiteral(tok[1..tok.len()-1].into()),
+    r#"'[^'\n]*'"# => Token::StringLiteral(tok[1..tok.len()-1].into()),
+    // A string literal without a closing quote ends at the end of the line
+    r#""[^"\n]*"# => Token::UnterminatedString,
+    r#"'[^'\n]*"# => Token::UnterminatedString,
     r"\#[^\n]*" => Token::Comment(tok.into()),
     "let" => Token::Let,
     r#"[a-zA-Z_][a-zA-Z0-9_]*"# => Token::Identifier(tok.into()),
-    "." => panic!("Lexer got unexpected character: {}", tok),
+    // Escaped keywords (e.g., `\max`) can be used as identifiers
+    r#"\\[a-zA-Z_][a-zA-Z0-9_]*"# => Token::Identifier(tok[1..].into()),
+    "." => Token::Unknown(tok.into()),
+}
+
+pub const BYTE_ORDER_MARK: char = '\u{feff}';
+
+/// Split the input into tokens without dropping whitespace and comments
+///
+/// Unlike `Lexer`, this does not generate indentation tokens, and a byte order
+/// mark is returned as whitespace. Only meant for inputs that compiled successfully.
+pub fn tokenize_lossless(input: &str) -> Vec<(Token, Span)> {
+    let mut result = vec![];
+    let mut remaining = input;
+
+    if let Some(rest) = input.strip_prefix(BYTE_ORDER_MARK) {
+        let hi = input.len() - rest.len();
+        result.push((Token::Whitespace, Span { lo: 0, hi }));
+        remaining = rest;
+    }
+
+    while let Some((tok, new_remaining)) = take_token(remaining) {
+        let lo = input.len() - remaining.len();
+        let hi = input.len() - new_remaining.len();
+        result.push((tok, Span { lo, hi }));
+        remaining = new_remaining;
+    }
+
+    result
 }
 
 pub struct Lexer<'a> {
     original: &'a str,
     remaining: &'a str,
-    at_start: bool,
     at_end: bool,
     empty_line: bool,
 
-    indents: BTreeMap<usize, i32>,
-    position: usize,
+    /// Position where the current line starts
+    line_start: usize,
+
+    /// Indentation of all currently open blocks
+    /// (the first entry is the indentation of the first line)
+    indents: Vec<usize>,
+
+    /// Tokens that have been generated but not returned yet
+    pending: VecDeque<(Token, Span)>,
+
+    /// Set if lexing failed; no more tokens will be generated after that
+    error: Option<CompileError>,
+
+    /// All comments encountered so far
+    comments: Vec<(Span, String)>,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(s: &'a str) -> Lexer<'a> {
-        let indents = parse_indents(s);
-
-        let position = 0;
-        let at_start = true;
-        let at_end = false;
-        let empty_line = true;
+        // Skip the byte order mark, if any, but keep positions relative to the original input
+        let remaining = s.strip_prefix(BYTE_ORDER_MARK).unwrap_or(s);
+        let line_start = s.len() - remaining.len();
 
         Self {
             original: s,
-            remaining: s,
-            indents,
-            position,
-            at_start,
-            at_end,
-            empty_line,
+            remaining,
+            at_end: false,
+            empty_line: true,
+            line_start,
+            indents: vec![],
+            pending: VecDeque::new(),
+            error: None,
+            comments: vec![],
+        }
+    }
+
+    /// Returns the error that stopped the lexer, if any
+    pub fn take_error(&mut self) -> Option<CompileError> {
+        self.error.take()
+    }
+
+    /// Returns the comments of the input (without the leading `#`)
+    pub fn take_comments(&mut self) -> Vec<(Span, String)> {
+        mem::take(&mut self.comments)
+    }
+
+    fn fail(&mut self, span: Span, message: String) {
+        self.error = Some(CompileError::new(Some(span), message));
+        self.pending.clear();
+        self.at_end = true;
+    }
+
+    /// Called for the first token on every line to generate Indent and Dedent tokens
+    fn update_indentation(&mut self, next: &Token, position: usize) {
+        let indent = position - self.line_start;
+        let span = Span {
+            lo: position,
+            hi: position,
+        };
+
+        let Some(current) = self.indents.last().copied() else {
+            self.indents.push(indent);
+            return;
+        };
+
+        match indent.cmp(&current) {
+            Ordering::Greater => {
+                self.indents.push(indent);
+                self.pending.push_back((Token::Indent, span));
+            }
+            Ordering::Less => {
+                let mut num_dedents = 0;
+
+                while self.indents.len() > 1 && indent < *self.indents.last().unwrap() {
+                    self.indents.pop();
+                    num_dedents += 1;
+                }
+
+                if indent != *self.indents.last().unwrap() {
+                    self.fail(span, "Inconsistent indentation".to_string());
+                    return;
+                }
+
+                for i in 0..num_dedents {
+                    self.pending.push_back((Token::Dedent, span));
+
+                    // Blocks are statements too and need to be terminated by a newline.
+                    // The exceptions are else- and except-branches, which continue the current statement.
+                    if i + 1 < num_dedents || !matches!(next, Token::Else | Token::Except) {
+                        self.pending.push_back((Token::Newline, span));
+                    }
+                }
+            }
+            Ordering::Equal => {}
         }
     }
 }
@@ -235,74 +308,111 @@ impl<'a> Lexer<'a> {
 impl Iterator for Lexer<'_> {
     type Item = (Token, Span);
     fn next(&mut self) -> Option<(Token, Span)> {
-        // skip over whitespace and comments
         loop {
-            if let Some(res) = get_next_indent(self.position, &mut self.indents) {
-                let token = match res {
-                    IndentResult::Newline => Token::Newline,
-                    IndentResult::Indent => Token::Indent,
-                    IndentResult::Dedent => Token::Dedent,
-                };
-
-                let ipos = self.position;
-                let span = Span { lo: ipos, hi: ipos };
-
-                self.empty_line = false;
-                return Some((token, span));
+            if let Some(next) = self.pending.pop_front() {
+                return Some(next);
+            }
+
+            if self.at_end {
+                return None;
             }
 
-            let (tok, span) = if let Some((tok, new_remaining)) = take_token(self.remaining) {
-                let lo = self.original.len() - self.remaining.len();
-                let hi = self.original.len() - new_remaining.len();
-                self.remaining = new_remaining;
-                (tok, Span { lo, hi })
-            } else {
-                // Return EOF token exactly once
-                if self.at_end {
+            let Some((tok, new_remaining)) = take_token(self.remaining) else {
+                self.at_end = true;
+
+                // parser gets confused on empty file
+                // so do not insert a newline here
+                //
+                // TODO should an empty file be a vaild source?
+                if self.indents.is_empty() {
                     return None;
-                } else {
-                    self.at_end = true;
-
-                    if self.at_start {
-                        // parser gets confused on empty file
-                        // so do not insert a newline here
-                        //
-                        // TODO should an empty file be a vaild source?
-                        return None;
-                    } else {
-                        // Treat EOF as new line
-                        (
-                            Token::Newline,
-                            Span {
-                                lo: self.original.len(),
-                                hi: self.original.len(),
-                            },
-                        )
-                    }
                 }
+
+                let pos = self.original.len();
+                let span = Span { lo: pos, hi: pos };
+
+                // Treat EOF as new line
+                if !self.empty_line {
+                    self.pending.push_back((Token::Newline, span));
+                }
+
+                // Close all remaining blocks
+                while self.indents.len() > 1 {
+                    self.indents.pop();
+                    self.pending.push_back((Token::Dedent, span));
+                    self.pending.push_back((Token::Newline, span));
+                }
+
+                continue;
             };
 
-            self.position = span.hi;
+            let lo = self.original.len() - self.remaining.len();
+            let hi = self.original.len() - new_remaining.len();
+            let span = Span { lo, hi };
+            self.remaining = new_remaining;
+
+            if let Token::DecimalLiteral(literal) = &tok {
+                if let Err(message) = check_decimal(literal) {
+                    self.fail(span, message);
+                    return None;
+                }
+            }
 
             match tok {
-                Token::Whitespace | Token::Comment { 0: _ } => {
-                    continue;
+                Token::Whitespace => {}
+                Token::Comment(text) => {
+                    self.comments.push((span, text[1..].trim().to_string()));
                 }
-                // ignore empty lines
                 Token::Newline => {
-                    if self.empty_line {
-                        continue;
-                    } else {
+                    self.line_start = hi;
+
+                    // ignore empty lines
+                    if !self.empty_line {
                         self.empty_line = true;
                         return Some((tok, span));
                     }
                 }
+                Token::UnterminatedString => {
+                    let line = self.original[..lo].matches('\n').count() + 1;
+                    self.fail(
+                        span,
+                        format!("Unterminated string literal starting at line {line}"),
+                    );
+                    return None;
+                }
+                Token::IntegerOutOfRange => {
+                    let literal = &self.original[lo..hi];
+                    self.fail(span, format!("Integer literal `{literal}` is out of range"));
+                    return None;
+                }
                 tok => {
-                    self.at_start = false;
+                    if self.empty_line {
+                        self.update_indentation(&tok, lo);
+                    }
+
+                    if self.at_end {
+                        // lexing failed
+                        return None;
+                    }
+
                     self.empty_line = false;
-                    return Some((tok, span));
+                    self.pending.push_back((tok, span));
                 }
             }
         }
     }
 }
+
+/// Decimals have at most 28 significant digits
+#[cfg(feature = "decimal")]
+fn check_decimal(literal: &str) -> Result<(), String> {
+    match rust_decimal::Decimal::from_str_exact(literal) {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("Invalid decimal literal `{literal}d`")),
+    }
+}
+
+#[cfg(not(feature = "decimal"))]
+fn check_decimal(_literal: &str) -> Result<(), String> {
+    Err("Decimal literals require the `decimal` feature".to_string())
+}
@@ -0,0 +1,410 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::{Expr, ParseNode, Program, Span, Statements};
+
+/// A check run by `lint` (see `LintOptions` to configure them)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// A variable is declared but never read
+    UnusedVariable,
+    /// A variable has the same name as a variable of an outer scope
+    Shadowing,
+    /// The condition of an `if` does not depend on anything that can change
+    ConstantCondition,
+    /// A block does not contain any statements
+    EmptyBlock,
+    /// A function has more statements than `LintOptions::max_function_length`
+    LongFunction,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 5] = [
+        Lint::UnusedVariable,
+        Lint::Shadowing,
+        Lint::ConstantCondition,
+        Lint::EmptyBlock,
+        Lint::LongFunction,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused_variable",
+            Lint::Shadowing => "shadowing",
+            Lint::ConstantCondition => "constant_condition",
+            Lint::EmptyBlock => "empty_block",
+            Lint::LongFunction => "long_function",
+        }
+    }
+}
+
+/// How to treat the findings of a lint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LintLevel {
+    /// Do not run the lint
+    Allow,
+    /// Report findings as diagnostics
+    #[default]
+    Warn,
+    /// Fail to compile the program
+    Deny,
+}
+
+/// Which lints to run and how to report them (see `CompilerOptions::lints`)
+#[derive(Debug, Clone)]
+pub struct LintOptions {
+    levels: HashMap<Lint, LintLevel>,
+    /// The number of statements (including those of nested blocks) a function may have
+    pub max_function_length: usize,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            levels: HashMap::new(),
+            max_function_length: 50,
+        }
+    }
+}
+
+impl LintOptions {
+    pub fn set_level(&mut self, lint: Lint, level: LintLevel) {
+        self.levels.insert(lint, level);
+    }
+
+    /// The level of a lint (lints warn unless configured otherwise)
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels.get(&lint).copied().unwrap_or_default()
+    }
+
+    /// Is any lint set to `LintLevel::Deny`?
+    pub fn denies_any(&self) -> bool {
+        self.levels.values().any(|level| *level == LintLevel::Deny)
+    }
+}
+
+/// A finding of a lint
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub lint: Lint,
+    pub level: LintLevel,
+    pub span: Span,
+    /// The name of the script the diagnostic refers to (if known)
+    pub source_name: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.source_name {
+            write!(f, "{name}: ")?;
+        }
+
+        let level = match self.level {
+            LintLevel::Deny => "error",
+            _ => "warning",
+        };
+
+        write!(f, "{level}[{}]: {}", self.lint.name(), self.message)
+    }
+}
+
+/// Run all lints that are not allowed on a program
+///
+/// Unlike `analyze`, these report code that works but is likely to be
+/// confusing. Variables of the outermost scope are never reported as unused,
+/// as the host might read them after the program ran.
+/// Names starting with an underscore are never reported as unused either.
+pub fn lint(program: &Program, options: &LintOptions) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        options,
+        scopes: vec![HashMap::new()],
+        diagnostics: vec![],
+    };
+
+    linter.lint_statements(&program.stmts);
+
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.lo);
+
+    if let Some(name) = &program.name {
+        for diagnostic in diagnostics.iter_mut() {
+            diagnostic.source_name = Some(name.clone());
+        }
+    }
+
+    diagnostics
+}
+
+struct Variable {
+    span: Span,
+    used: bool,
+}
+
+struct Linter<'a> {
+    options: &'a LintOptions,
+    scopes: Vec<HashMap<String, Variable>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn report(&mut self, lint: Lint, span: Span, message: String) {
+        let level = self.options.level(lint);
+        if level == LintLevel::Allow {
+            return;
+        }
+
+        self.diagnostics.push(Diagnostic {
+            lint,
+            level,
+            span,
+            source_name: None,
+            message,
+        });
+    }
+
+    fn declare(&mut self, span: Span, name: &str) {
+        let outer = &self.scopes[..self.scopes.len() - 1];
+        if outer.iter().any(|scope| scope.contains_key(name)) {
+            self.report(
+                Lint::Shadowing,
+                span,
+                format!("`{name}` shadows a variable of an outer scope"),
+            );
+        }
+
+        let variable = Variable { span, used: false };
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), variable);
+    }
+
+    fn mark_used(&mut self, name: &str) {
+        let variable = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(name));
+
+        if let Some(variable) = variable {
+            variable.used = true;
+        }
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+
+        let mut unused: Vec<(String, Span)> = scope
+            .into_iter()
+            .filter(|(name, variable)| !variable.used && !name.starts_with('_'))
+            .map(|(name, variable)| (name, variable.span))
+            .collect();
+        unused.sort_by_key(|(_, span)| span.lo);
+
+        for (name, span) in unused {
+            self.report(
+                Lint::UnusedVariable,
+                span,
+                format!("Variable `{name}` is never used"),
+            );
+        }
+    }
+
+    /// Lint the statements of a block (in a new scope)
+    fn lint_block(&mut self, span: Span, stmts: &Statements) {
+        if stmts.is_empty() {
+            self.report(Lint::EmptyBlock, span, "Block is empty".to_string());
+        }
+
+        self.push_scope();
+        self.lint_statements(stmts);
+        self.pop_scope();
+    }
+
+    fn lint_statements(&mut self, stmts: &Statements) {
+        for stmt in stmts {
+            self.lint(stmt);
+        }
+    }
+
+    fn lint(&mut self, node: &ParseNode) {
+        let (span, expr) = node;
+
+        match expr {
+            Expr::Var(name) => self.mark_used(name),
+            Expr::AssignNew(name, value) | Expr::AssignNewAnnotated { name, value, .. } => {
+                self.lint(value);
+                self.declare(*span, name);
+            }
+            Expr::AssignNewMultiple { targets, values } => {
+                for value in values {
+                    self.lint(value);
+                }
+                for target in targets {
+                    self.declare(*span, target);
+                }
+            }
+            Expr::ForIn {
+                iter,
+                target_name,
+                body,
+            } => {
+                self.lint(iter);
+
+                if body.is_empty() {
+                    self.report(Lint::EmptyBlock, *span, "Loop body is empty".to_string());
+                }
+
+                // The target lives in the same scope as the body
+                self.push_scope();
+                self.declare(*span, target_name);
+                self.lint_statements(body);
+                self.pop_scope();
+            }
+            Expr::With {
+                resource,
+                target_name,
+                body,
+            } => {
+                self.lint(resource);
+
+                self.push_scope();
+                self.declare(*span, target_name);
+                self.lint_block(*span, body);
+                self.pop_scope();
+            }
+            Expr::Try {
+                body,
+                error_name,
+                handler,
+            } => {
+                self.lint_block(*span, body);
+
+                self.push_scope();
+                if let Some(name) = error_name {
+                    self.declare(*span, name);
+                }
+                self.lint_block(*span, handler);
+                self.pop_scope();
+            }
+            Expr::IfElse {
+                cond,
+                body,
+                else_branch,
+            } => {
+                self.lint_condition(cond);
+                self.lint_block(*span, body);
+
+                if let Some(branch) = else_branch {
+                    self.lint_block(*span, branch);
+                }
+            }
+            Expr::IfElseRecursive {
+                cond,
+                body,
+                else_branch,
+            } => {
+                self.lint_condition(cond);
+                self.lint_block(*span, body);
+                self.lint(else_branch);
+            }
+            Expr::Function { name, params, body } => self.lint_function(*span, name, params, body),
+            Expr::AnnotatedFunction { function, .. } => self.lint(function),
+            _ => {
+                for child in expr.children() {
+                    self.lint(child);
+                }
+            }
+        }
+    }
+
+    fn lint_condition(&mut self, cond: &ParseNode) {
+        if is_constant(cond) {
+            self.report(
+                Lint::ConstantCondition,
+                cond.0,
+                "Condition is constant, so only one of the branches can ever run".to_string(),
+            );
+        }
+
+        self.lint(cond);
+    }
+
+    fn lint_function(&mut self, span: Span, name: &str, params: &[String], body: &Statements) {
+        self.declare(span, name);
+        let scope = self.scopes.len() - 1;
+
+        let length = count_statements(body);
+        if length > self.options.max_function_length {
+            self.report(
+                Lint::LongFunction,
+                span,
+                format!(
+                    "Function `{name}` has {length} statements (at most {} are allowed)",
+                    self.options.max_function_length
+                ),
+            );
+        }
+
+        // Parameters live in their own scope, outside of the body
+        self.push_scope();
+        for param in params {
+            self.declare(span, param);
+            self.mark_used(param);
+        }
+        self.lint_block(span, body);
+        self.pop_scope();
+
+        // Recursive calls do not count as uses
+        if let Some(function) = self.scopes[scope].get_mut(name) {
+            function.used = false;
+        }
+    }
+}
+
+/// Is the value of the expression known before running the program?
+fn is_constant(node: &ParseNode) -> bool {
+    match &node.1 {
+        Expr::Bool(_)
+        | Expr::I64(_)
+        | Expr::U64(_)
+        | Expr::U8(_)
+        | Expr::Decimal(_)
+        | Expr::String(_) => true,
+        Expr::Brackets(inner) | Expr::Not(inner) | Expr::Negative(inner) => is_constant(inner),
+        Expr::And { lhs, rhs } | Expr::Or { lhs, rhs } | Expr::Compare { lhs, rhs, .. } => {
+            is_constant(lhs) && is_constant(rhs)
+        }
+        _ => false,
+    }
+}
+
+/// The number of statements of a block, including those of nested blocks
+fn count_statements(stmts: &Statements) -> usize {
+    stmts.iter().map(count_statement).sum()
+}
+
+fn count_statement(node: &ParseNode) -> usize {
+    let nested = match &node.1 {
+        Expr::ForIn { body, .. } | Expr::With { body, .. } | Expr::Function { body, .. } => {
+            count_statements(body)
+        }
+        Expr::Try { body, handler, .. } => count_statements(body) + count_statements(handler),
+        Expr::IfElse {
+            body, else_branch, ..
+        } => count_statements(body) + else_branch.as_ref().map_or(0, count_statements),
+        Expr::IfElseRecursive {
+            body, else_branch, ..
+        } => count_statements(body) + count_statement(else_branch),
+        // The wrapped function is not a statement of its own
+        Expr::AnnotatedFunction { function, .. } => return count_statement(function),
+        _ => 0,
+    };
+
+    1 + nested
+}
@@ -1,86 +1,291 @@
+mod analyzer;
+mod comments;
 mod lexer;
+mod lints;
 mod parser;
+mod syntax;
+mod typecheck;
 
-use crate::ast::{Program, Span};
+use crate::ast::{Expr, ParseNode, Program, Span};
+use crate::error::CompileError;
 
+use std::borrow::Cow;
 use std::fmt::Debug;
 
-use lexer::Lexer;
+use comments::attach_comments;
+use lexer::{tokenize_lossless, Lexer, Token, BYTE_ORDER_MARK};
 use parser::parse;
 
-pub use lexer::{get_next_indent, parse_indents, IndentResult};
+pub use analyzer::{analyze, Warning};
+pub use lints::{lint, Diagnostic, Lint, LintLevel, LintOptions};
+pub use syntax::{SyntaxNode, SyntaxToken, SyntaxTree, TokenKind};
+pub use typecheck::{typecheck, TypeError};
 
-pub fn generate_compile_error<T>(input: &str, info: Option<(T, Span)>, e: &str) -> String
-where
-    T: Debug,
-{
-    if let Some((token, span)) = info {
-        // Get interesting area
-        let mut pos = 0;
-        let mut start = 0;
-        let mut end = 0;
-        let mut it = input.chars();
+/// Settings for compiling a program
+#[derive(Debug, Clone, Default)]
+pub struct CompilerOptions {
+    /// Convert Windows line endings to `\n` and remove
+    /// a leading UTF-8 byte order mark before compiling.
+    ///
+    /// The lexer tolerates both without this option. Enabling it
+    /// makes spans and error messages refer to the normalized source.
+    pub normalize_source: bool,
+    /// Keep `#`-comments and attach them to statements (see `Program::comments`)
+    pub keep_comments: bool,
+    /// The type of integer literals without a suffix (e.g., `42`)
+    pub integer_literals: IntegerLiteralType,
+    /// Which lints to run (compiling fails if a denied lint reports anything)
+    pub lints: LintOptions,
+}
 
-        let mut msg = String::default();
+/// The type of integer literals without a suffix (see `CompilerOptions::integer_literals`)
+///
+/// Literals with a suffix (`42u` or `42u8`) always keep their type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntegerLiteralType {
+    #[default]
+    I64,
+    /// Useful if the host mostly provides unsigned values
+    U64,
+}
 
-        while pos < span.lo {
-            if it.next().unwrap() == '\n' {
-                start = pos + 1;
-            }
+/// Convert `\r\n` line endings to `\n` and strip a leading byte order mark
+pub fn normalize_source(input: &str) -> Cow<'_, str> {
+    let input = input.strip_prefix(BYTE_ORDER_MARK).unwrap_or(input);
 
-            pos += 1;
-        }
+    if input.contains("\r\n") {
+        Cow::Owned(input.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(input)
+    }
+}
 
-        for _i in start..span.lo {
-            msg += " ";
-        }
+/// Returns the line containing the span and a marker line pointing at the span
+///
+/// Spans are byte positions. The marker has one character per character of the
+/// line, so it lines up with non-ASCII text as well (as long as every character
+/// is displayed with the same width).
+fn highlight_span(input: &str, span: Span) -> (&str, String) {
+    let lo = floor_char_boundary(input, span.lo);
+    let hi = floor_char_boundary(input, span.hi.max(lo));
 
-        while pos < span.hi {
-            it.next().unwrap();
-            pos += 1;
-            msg += "^";
-        }
+    let start = input[..lo].rfind('\n').map_or(0, |pos| pos + 1);
+    let end = input[lo..].find('\n').map_or(input.len(), |pos| lo + pos);
 
-        msg += &format!("--- {:?}", e);
+    // Spans covering multiple lines are only marked up to the end of the first line
+    let marked = &input[lo..hi.min(end)];
 
-        for c in it {
-            if c == '\n' {
-                end = pos;
-                break;
-            }
-            pos += 1;
-        }
+    let mut marker = " ".repeat(input[start..lo].chars().count());
+    marker += &"^".repeat(marked.chars().count());
 
-        if end == 0 {
-            end = pos;
-        }
+    (&input[start..end], marker)
+}
+
+/// The closest position at or before `pos` that is at the start of a character
+fn floor_char_boundary(input: &str, pos: usize) -> usize {
+    let mut pos = pos.min(input.len());
+
+    while !input.is_char_boundary(pos) {
+        pos -= 1;
+    }
+
+    pos
+}
+
+pub fn generate_compile_error<T>(input: &str, info: Option<(T, Span)>, e: &str) -> String
+where
+    T: Debug,
+{
+    if let Some((token, span)) = info {
+        let (line, marker) = highlight_span(input, span);
 
         format!(
             "Got compile error at {:?} [{} to {}]:\n
                 |    {}\n\
-                |    {}",
-            token,
-            span.lo,
-            span.hi,
-            &input[start..end],
-            msg
+                |    {}--- {:?}",
+            token, span.lo, span.hi, line, marker, e
         )
     } else {
         format!("Got compile error: {:?}", e)
     }
 }
 
+impl CompileError {
+    /// Describe the error and point to its location in the source code
+    pub fn render(&self, input: &str) -> String {
+        if let Some(span) = self.span {
+            let (line, marker) = highlight_span(input, span);
+
+            format!(
+                "Got compile error at [{} to {}]:\n
+                |    {}\n\
+                |    {}--- {}",
+                span.lo, span.hi, line, marker, self.message
+            )
+        } else {
+            format!("Got compile error: {}", self.message)
+        }
+    }
+}
+
+/// Compile the input and run the analyzer pass on it
+///
+/// Returns the program together with all warnings that were found
+pub fn compile_with_warnings(input: &str) -> Result<(Program, Vec<Warning>), CompileError> {
+    let program = compile(input)?;
+    let warnings = analyze(&program);
+
+    Ok((program, warnings))
+}
+
+/// Compile the input and run all lints that are not allowed on it
+///
+/// Returns the program together with the diagnostics of lints set to warn.
+pub fn compile_with_diagnostics(
+    input: &str,
+    options: &CompilerOptions,
+) -> Result<(Program, Vec<Diagnostic>), CompileError> {
+    let program = compile_with_options(input, options)?;
+    let diagnostics = lint(&program, &options.lints);
+
+    Ok((program, diagnostics))
+}
+
+/// Compile the input
+///
+/// This will panic if the program is invalid. Use `compile` to handle errors instead.
 pub fn compile_string(input: &str) -> Program {
+    match compile(input) {
+        Ok(program) => program,
+        Err(err) => panic!("{}", err.render(input)),
+    }
+}
+
+/// Compile the input and remember the name of the script it came from
+///
+/// The name will be attached to compile errors, warnings, and runtime errors of the program.
+pub fn compile_named(name: &str, input: &str) -> Result<Program, CompileError> {
+    let mut program = compile(input).map_err(|err| err.with_source_name(name))?;
+    program.name = Some(name.to_string());
+
+    Ok(program)
+}
+
+pub fn compile(input: &str) -> Result<Program, CompileError> {
+    compile_with_options(input, &CompilerOptions::default())
+}
+
+pub fn compile_with_options(
+    input: &str,
+    options: &CompilerOptions,
+) -> Result<Program, CompileError> {
+    let input = if options.normalize_source {
+        normalize_source(input)
+    } else {
+        Cow::Borrowed(input)
+    };
+    let input = input.as_ref();
+
+    let mut lexer = Lexer::new(input);
+
     #[cfg(feature = "verbose")]
-    let lexer = Lexer::new(input).inspect(|elem| println!("{:?}", elem));
+    let tokens = lexer.by_ref().inspect(|elem| println!("{:?}", elem));
 
     #[cfg(not(feature = "verbose"))]
-    let lexer = Lexer::new(input);
+    let tokens = lexer.by_ref();
+
+    let result = parse(tokens);
+
+    // Errors in the lexer cause the parser to fail as well,
+    // but the lexer's error is more meaningful
+    if let Some(err) = lexer.take_error() {
+        return Err(err);
+    }
+
+    let mut program = result.map_err(|(info, e)| match info {
+        Some((Token::Unknown(c), span)) => {
+            CompileError::new(Some(span), format!("Unexpected character `{c}`"))
+        }
+        Some((token, span)) => CompileError::new(Some(span), format!("{} (got {:?})", e, token)),
+        None => CompileError::new(None, e.to_string()),
+    })?;
+
+    if options.keep_comments {
+        program.comments = attach_comments(input, lexer.take_comments(), &program.stmts);
+    }
+
+    if options.integer_literals == IntegerLiteralType::U64 {
+        for stmt in program.stmts.iter_mut() {
+            make_literals_unsigned(stmt);
+        }
+    }
+
+    // Only run the lints here if they can fail compilation
+    if options.lints.denies_any() {
+        let diagnostics = lint(&program, &options.lints);
 
-    match parse(lexer) {
-        Ok(p) => p,
-        Err((info, e)) => {
-            panic!("{}", generate_compile_error(input, info, e));
+        if let Some(denied) = diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.level == LintLevel::Deny)
+        {
+            return Err(CompileError::new(
+                Some(denied.span),
+                format!("{} (denied by `{}`)", denied.message, denied.lint.name()),
+            ));
         }
     }
+
+    Ok(program)
+}
+
+/// Turn all i64 literals into u64 literals
+///
+/// The lexer does not generate negative literals, so this never changes a value.
+fn make_literals_unsigned(node: &mut ParseNode) {
+    let (_, expr) = node;
+
+    if let Expr::I64(value) = expr {
+        *expr = Expr::U64(*value as u64);
+        return;
+    }
+
+    for child in expr.children_mut() {
+        make_literals_unsigned(child);
+    }
+}
+
+/// Compile the input and keep a lossless representation of its source code
+///
+/// Meant for tools that rewrite scripts (e.g., to rename a variable),
+/// as the syntax tree keeps all whitespace and comments.
+pub fn parse_lossless(input: &str) -> Result<(Program, SyntaxTree), CompileError> {
+    let program = compile(input)?;
+    let tree = SyntaxTree::new(input, tokenize_lossless(input), &program.stmts);
+
+    Ok((program, tree))
+}
+
+/// Rename all uses of a variable (or module) in the source code of a script
+///
+/// Everything else, including whitespace and comments, stays the same.
+/// Fails if the script, or the script after renaming, does not compile
+/// (e.g., because the new name is a keyword).
+pub fn rename_variable(
+    input: &str,
+    old_name: &str,
+    new_name: &str,
+) -> Result<String, CompileError> {
+    let (_, mut tree) = parse_lossless(input)?;
+    tree.rename(old_name, new_name);
+
+    let output = tree.to_source();
+    compile(&output)?;
+
+    Ok(output)
+}
+
+/// Find all uses of a variable (or module) in the source code of a script
+pub fn find_references(input: &str, name: &str) -> Result<Vec<Span>, CompileError> {
+    let (_, tree) = parse_lossless(input)?;
+    Ok(tree.find_references(name))
 }
@@ -18,8 +18,8 @@ parser! {
     }
 
     program: Program {
-        linebreak => Program{ stmts: vec!() },
-        statements[stmts] => Program{ stmts }
+        linebreak => Program{ name: None, stmts: vec!(), comments: vec!() },
+        statements[stmts] => Program{ name: None, stmts, comments: vec!() }
     }
 
     statements: Vec<ParseNode> {
@@ -44,20 +44,81 @@ parser! {
         Let Identifier(var) Assign assign[rhs] => {
             (span!(), Expr::AssignNew(var, Box::new(rhs)))
         }
-        Identifier(var) PlusEquals op[rhs] => {
-            (span!(),
-                Expr::AddEquals{lhs: var, rhs: Box::new(rhs)})
+        Let Identifier(name) Colon annotation[annotation] Assign assign[rhs] => {
+            let value = Box::new(coerce_literal(&annotation, rhs));
+            (span!(), Expr::AssignNewAnnotated{name, annotation, value})
+        }
+        Identifier(var) op_assign[op] op[rhs] => {
+            let lhs = (span!(), Expr::Var(var));
+            (span!(), Expr::OpAssign{op, lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        }
+        term[object] Period Identifier(member) op_assign[op] op[rhs] => {
+            let lhs = (span!(), Expr::GetMember(Box::new(object), member));
+            (span!(), Expr::OpAssign{op, lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        }
+        term[callee] OpenSquareBracket op[id] CloseSquareBracket op_assign[op] op[rhs] => {
+            let lhs = (span!(), Expr::GetElement(Box::new(callee), Box::new(id)));
+            (span!(), Expr::OpAssign{op, lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        }
+        term[container] OpenSquareBracket op[key] CloseSquareBracket Assign assign[value] => {
+            (span!(), Expr::SetElement{
+                container: Box::new(container), key: Box::new(key),
+                value: Box::new(value)
+            })
         }
         Identifier(var) Assign assign[rhs] => {
             (span!(), Expr::Assign(var, Box::new(rhs)))
         }
+        Identifier(first) Comma targets[rest] Assign exprs[values] => {
+            let mut targets = vec![first];
+            targets.extend(rest);
+
+            (span!(), Expr::AssignMultiple{targets, values})
+        }
+        Let Identifier(first) Comma targets[rest] Assign exprs[values] => {
+            let mut targets = vec![first];
+            targets.extend(rest);
+
+            (span!(), Expr::AssignNewMultiple{targets, values})
+        }
         For Identifier(target_name) In op[iter] Colon Newline Indent statements[body] Dedent => {
             (span!(), Expr::ForIn{iter: Box::new(iter), target_name, body})
         }
+        // This uses cast instead of op, so that `as` is not ambiguous
+        With cast[resource] As Identifier(target_name) Colon Newline Indent statements[body] Dedent => {
+            (span!(), Expr::With{resource: Box::new(resource), target_name, body})
+        }
+        Try Colon Newline Indent statements[body] Dedent Except Colon Newline Indent statements[handler] Dedent => {
+            (span!(), Expr::Try{body, error_name: None, handler})
+        }
+        Try Colon Newline Indent statements[body] Dedent Except As Identifier(name) Colon Newline Indent statements[handler] Dedent => {
+            (span!(), Expr::Try{body, error_name: Some(name), handler})
+        }
+        Def Identifier(name) OpenBracket CloseBracket return_type[return_type] Colon Newline Indent statements[body] Dedent => {
+            let function = (span!(), Expr::Function{name, params: vec![], body});
+            annotate_function(function, vec![], return_type)
+        }
+        Def Identifier(name) OpenBracket params[params] CloseBracket return_type[return_type] Colon Newline Indent statements[body] Dedent => {
+            let (params, param_types) = params.into_iter().unzip();
+            let function = (span!(), Expr::Function{name, params, body});
+            annotate_function(function, param_types, return_type)
+        }
+        Raise op[value] => {
+            (span!(), Expr::Raise(Box::new(value)))
+        }
         If if_stmt[ifs] => ifs,
         op[o] => o
     }
 
+    op_assign: ArithmeticOp {
+        PlusEquals => ArithmeticOp::Add,
+        MinusEquals => ArithmeticOp::Subtract,
+        StarEquals => ArithmeticOp::Multiply,
+        SlashEquals => ArithmeticOp::Divide,
+        DoubleSlashEquals => ArithmeticOp::FloorDivide,
+        PercentEquals => ArithmeticOp::Remainder,
+    }
+
     if_stmt: ParseNode {
         assign[cond] Colon Newline Indent statements[body] Dedent => {
             (span!(), Expr::IfElse{cond: Box::new(cond), body, else_branch: None})
@@ -70,75 +131,197 @@ parser! {
         }
     }
 
+    // Operators from lowest to highest precedence:
+    // `or`, `and`, comparisons, `+`, `*`, `/`, `//`, and `%`, `not` and unary `-`,
+    // `as`, and postfix expressions.
+    //
+    // Earlier releases parsed all binary operators and `as` at the same level
+    // and applied `not` to a single atom. Now `a + b as u8` means `a + (b as u8)`,
+    // `not a == b` means `(not a) == b`, and `not x.y` means `not (x.y)`.
     op: ParseNode {
-        op[lhs] Plus term[rhs] => {
-            (span!(),
-                Expr::Add{lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        op[lhs] Or conjunction[rhs] => {
+            (span!(), Expr::Or{lhs: Box::new(lhs), rhs: Box::new(rhs)})
         }
-        op[lhs] Star term[rhs] => {
-            (span!(),
-                Expr::Multiply{lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        conjunction[c] => c
+    }
+
+    conjunction: ParseNode {
+        conjunction[lhs] And comparison[rhs] => {
+            (span!(), Expr::And{lhs: Box::new(lhs), rhs: Box::new(rhs)})
         }
-        op[lhs] Equals term[rhs] => {
+        comparison[c] => c
+    }
+
+    comparison: ParseNode {
+        comparison[lhs] Equals sum[rhs] => {
             (span!(), Expr::Compare{
                 ctype: CompareType::Equals, lhs: Box::new(lhs),
                 rhs: Box::new(rhs)
             })
         }
-        op[lhs] As TypeName(t) => {
-            (span!(), Expr::Cast{
-                value: Box::new(lhs), typename: t
-            })
-        }
-        op[lhs] Greater term[rhs] => {
+        comparison[lhs] Greater sum[rhs] => {
             (span!(), Expr::Compare{
                 ctype: CompareType::Greater, lhs:Box::new(lhs),
                 rhs: Box::new(rhs)
             })
         }
-        op[lhs] Smaller term[rhs] => {
+        comparison[lhs] Smaller sum[rhs] => {
             (span!(), Expr::Compare{
                 ctype: CompareType::Smaller, lhs: Box::new(lhs),
                 rhs: Box::new(rhs)
             })
         }
-        ToStr OpenBracket op[inner] CloseBracket => {
-            (span!(), Expr::ToStr(Box::new(inner)))
+        comparison[lhs] In sum[rhs] => {
+            (span!(), Expr::Compare{
+                ctype: CompareType::In, lhs: Box::new(lhs),
+                rhs: Box::new(rhs)
+            })
         }
-        Max OpenBracket op[lhs] Comma op[rhs] CloseBracket => {
-            (span!(), Expr::Max{lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        sum[s] => s
+    }
+
+    sum: ParseNode {
+        sum[lhs] Plus product[rhs] => {
+            (span!(),
+                Expr::Add{lhs: Box::new(lhs), rhs: Box::new(rhs)})
         }
-        Min OpenBracket op[lhs] Comma op[rhs] CloseBracket => {
-            (span!(), Expr::Min{lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        product[p] => p
+    }
+
+    product: ParseNode {
+        product[lhs] Star unary[rhs] => {
+            (span!(),
+                Expr::Multiply{lhs: Box::new(lhs), rhs: Box::new(rhs)})
         }
-        Range OpenBracket op[start] Comma op[end] CloseBracket => {
-            (span!(), Expr::Range{start: Box::new(start), end: Box::new(end), step: None})
+        product[lhs] Slash unary[rhs] => {
+            (span!(),
+                Expr::Divide{lhs: Box::new(lhs), rhs: Box::new(rhs)})
         }
-        Range OpenBracket op[start] Comma op[end] Comma op[step] CloseBracket => {
-            (span!(), Expr::Range{start: Box::new(start), end: Box::new(end), step: Some(Box::new(step))})
+        product[lhs] DoubleSlash unary[rhs] => {
+            (span!(),
+                Expr::FloorDivide{lhs: Box::new(lhs), rhs: Box::new(rhs)})
         }
-        term[t] => t
+        product[lhs] Percent unary[rhs] => {
+            (span!(),
+                Expr::Remainder{lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        }
+        unary[u] => u
     }
 
-    term: ParseNode {
-        OpenBracket op[inner] CloseBracket => {
-            (span!(), Expr::Brackets( Box::new(inner) ))
-        }
-        Not atom[rhs] => {
+    unary: ParseNode {
+        Not unary[rhs] => {
             (span!(), Expr::Not(Box::new(rhs)))
         }
+        Minus unary[rhs] => {
+            (span!(), Expr::Negative(Box::new(rhs)))
+        }
+        cast[c] => c
+    }
+
+    cast: ParseNode {
+        cast[lhs] As TypeName(t) => {
+            (span!(), Expr::Cast{
+                value: Box::new(lhs), typename: t
+            })
+        }
+        term[t] => t
+    }
+
+    // Postfix expressions (member access, calls, and indexing)
+    // can be chained arbitrarily
+    term: ParseNode {
         term[lhs] Period Identifier(var) => {
             (span!(), Expr::GetMember(Box::new(lhs), var))
         }
+        // Allow builtins of lists that share their name with a keyword
+        term[lhs] Period Max => {
+            (span!(), Expr::GetMember(Box::new(lhs), "max".to_string()))
+        }
+        term[lhs] Period Min => {
+            (span!(), Expr::GetMember(Box::new(lhs), "min".to_string()))
+        }
         term[callee] OpenBracket args[a] CloseBracket => {
             (span!(), Expr::Call(Box::new(callee), a))
         }
-        term[callee] OpenSquareBracket atom[id] CloseSquareBracket => {
+        term[callee] OpenSquareBracket op[id] CloseSquareBracket => {
             (span!(), Expr::GetElement(Box::new(callee), Box::new(id)))
         }
         atom[x] => x
     }
 
+    targets: Vec<String> {
+        targets[mut targets] Comma Identifier(var) => {
+            targets.push(var);
+            targets
+        }
+        Identifier(var) => vec![var]
+    }
+
+    params: Vec<(String, Option<TypeAnnotation>)> {
+        params[mut params] Comma param[p] => {
+            params.push(p);
+            params
+        }
+        param[p] => vec![p]
+    }
+
+    param: (String, Option<TypeAnnotation>) {
+        Identifier(var) => (var, None),
+        Identifier(var) Colon annotation[t] => (var, Some(t)),
+    }
+
+    return_type: Option<TypeAnnotation> {
+        Arrow annotation[t] => Some(t),
+        => None
+    }
+
+    annotation: TypeAnnotation {
+        TypeName(t) => {
+            let name = match t {
+                ValueType::U8 => "u8",
+                ValueType::U64 => "u64",
+                _ => "i64",
+            };
+            TypeAnnotation::Named(name.to_string())
+        }
+        ToStr => TypeAnnotation::Named("str".to_string()),
+        Identifier(name) => TypeAnnotation::Named(name),
+        OpenSquareBracket annotation[t] CloseSquareBracket => {
+            TypeAnnotation::List(Box::new(t))
+        }
+        OpenSquareBracket annotation[t] Semicolon I64Literal(len) CloseSquareBracket => {
+            TypeAnnotation::Array(Box::new(t), len as usize)
+        }
+        OpenSquareBracket annotation[t] Semicolon U64Literal(len) CloseSquareBracket => {
+            TypeAnnotation::Array(Box::new(t), len as usize)
+        }
+        OpenCurlyBracket annotation[key] Colon annotation[value] CloseCurlyBracket => {
+            TypeAnnotation::Map(Box::new(key), Box::new(value))
+        }
+        OpenBracket annotation[first] Comma annotations[rest] CloseBracket => {
+            let mut elems = vec![first];
+            elems.extend(rest);
+            TypeAnnotation::Tuple(elems)
+        }
+    }
+
+    annotations: Vec<TypeAnnotation> {
+        annotations[mut elems] Comma annotation[t] => {
+            elems.push(t);
+            elems
+        }
+        annotation[t] => vec![t]
+    }
+
+    // Like args, but with at least one element
+    exprs: Vec<ParseNode> {
+        exprs[mut exprs] Comma op[e] => {
+            exprs.push(e);
+            exprs
+        }
+        op[e] => vec![e]
+    }
+
     args: Vec<ParseNode> {
         args[mut args] Comma op[t] => {
             args.push(t);
@@ -151,11 +334,11 @@ parser! {
     }
 
     kvs: HashMap<String, ParseNode> {
-        kvs[mut m] Comma StringLiteral(id) Colon atom[a] => {
+        kvs[mut m] Comma StringLiteral(id) Colon op[a] => {
             m.insert(id, a);
             m
         }
-        StringLiteral(id) Colon atom[a] => {
+        StringLiteral(id) Colon op[a] => {
             let mut m = HashMap::new();
             m.insert(id, a);
             m
@@ -164,6 +347,30 @@ parser! {
     }
 
     atom: ParseNode {
+        OpenBracket op[inner] CloseBracket => {
+            (span!(), Expr::Brackets( Box::new(inner) ))
+        }
+        OpenBracket op[first] Comma exprs[rest] CloseBracket => {
+            let mut elems = vec![first];
+            elems.extend(rest);
+
+            (span!(), Expr::Tuple(elems))
+        }
+        ToStr OpenBracket op[inner] CloseBracket => {
+            (span!(), Expr::ToStr(Box::new(inner)))
+        }
+        Max OpenBracket op[lhs] Comma op[rhs] CloseBracket => {
+            (span!(), Expr::Max{lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        }
+        Min OpenBracket op[lhs] Comma op[rhs] CloseBracket => {
+            (span!(), Expr::Min{lhs: Box::new(lhs), rhs: Box::new(rhs)})
+        }
+        Range OpenBracket op[start] Comma op[end] CloseBracket => {
+            (span!(), Expr::Range{start: Box::new(start), end: Box::new(end), step: None})
+        }
+        Range OpenBracket op[start] Comma op[end] Comma op[step] CloseBracket => {
+            (span!(), Expr::Range{start: Box::new(start), end: Box::new(end), step: Some(Box::new(step))})
+        }
         Identifier(var) => {
             (span!(), Expr::Var(var))
         },
@@ -176,6 +383,9 @@ parser! {
         U8Literal(i) => {
             (span!(), Expr::U8(i))
         }
+        DecimalLiteral(d) => {
+            (span!(), Expr::Decimal(d))
+        }
         BoolLiteral(b) => {
             (span!(), Expr::Bool(b))
         }
@@ -185,6 +395,17 @@ parser! {
         OpenSquareBracket list_vals[v] CloseSquareBracket => {
             (span!(), Expr::List(v))
         }
+        OpenSquareBracket op[value] Semicolon op[length] CloseSquareBracket => {
+            (span!(), Expr::Array{value: Box::new(value), length: Box::new(length)})
+        }
+        OpenSquareBracket TypeName(t) Semicolon op[length] CloseSquareBracket => {
+            let zero = match t {
+                ValueType::U8 => Expr::U8(0),
+                ValueType::U64 => Expr::U64(0),
+                _ => Expr::I64(0),
+            };
+            (span!(), Expr::Array{value: Box::new((span!(), zero)), length: Box::new(length)})
+        }
         OpenCurlyBracket kvs[m] CloseCurlyBracket => {
             (span!(), Expr::Dictionary(m))
         }
@@ -204,6 +425,47 @@ parser! {
 
 type ParseItem = (Token, Span);
 
+/// Functions without any annotations are stored as a plain `Expr::Function`
+fn annotate_function(
+    function: ParseNode,
+    param_types: Vec<Option<TypeAnnotation>>,
+    return_type: Option<TypeAnnotation>,
+) -> ParseNode {
+    if return_type.is_none() && param_types.iter().all(Option::is_none) {
+        return function;
+    }
+
+    let span = function.0;
+    let function = Box::new(function);
+
+    (
+        span,
+        Expr::AnnotatedFunction {
+            function,
+            param_types,
+            return_type,
+        },
+    )
+}
+
+/// Unsuffixed integer literals take the annotated type (e.g., `let x: u8 = 5`)
+///
+/// Literals that do not fit into the type are kept as they are, so the typecheck reports them.
+fn coerce_literal(annotation: &TypeAnnotation, value: ParseNode) -> ParseNode {
+    let (span, expr) = value;
+
+    let expr = match (annotation, expr) {
+        (TypeAnnotation::Named(name), Expr::I64(i)) => match name.as_str() {
+            "u8" => u8::try_from(i).map_or(Expr::I64(i), Expr::U8),
+            "u64" => u64::try_from(i).map_or(Expr::I64(i), Expr::U64),
+            _ => Expr::I64(i),
+        },
+        (_, expr) => expr,
+    };
+
+    (span, expr)
+}
+
 pub fn parse<I: Iterator<Item = ParseItem>>(
     i: I,
 ) -> Result<Program, (Option<ParseItem>, &'static str)> {
@@ -0,0 +1,196 @@
+use std::ops::Range;
+
+use super::lexer::Token;
+use crate::ast::{ParseNode, Span, Statements};
+
+/// The role of a token in the source code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Spaces, tabs, and byte order marks
+    Whitespace,
+    Newline,
+    Comment,
+    Identifier,
+    Keyword,
+    Literal,
+    /// Operators, brackets, and other punctuation
+    Punctuation,
+}
+
+impl TokenKind {
+    /// Trivia do not affect the meaning of the program
+    pub fn is_trivia(&self) -> bool {
+        matches!(self, Self::Whitespace | Self::Newline | Self::Comment)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    pub kind: TokenKind,
+    /// The location of the token in the original source
+    pub span: Span,
+    pub text: String,
+}
+
+impl SyntaxToken {
+    /// The name of an identifier (without the `\` of escaped keywords)
+    pub fn identifier(&self) -> Option<&str> {
+        if self.kind != TokenKind::Identifier {
+            return None;
+        }
+
+        Some(self.text.strip_prefix('\\').unwrap_or(&self.text))
+    }
+}
+
+/// A node of the syntax tree, which corresponds to a node of the AST
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    pub span: Span,
+    /// The tokens covered by this node (indices into `SyntaxTree::tokens`)
+    pub tokens: Range<usize>,
+    pub children: Vec<SyntaxNode>,
+}
+
+/// A lossless representation of the source code (see `parse_lossless`)
+///
+/// Every character of the source belongs to exactly one token, so the source
+/// can be recreated exactly, including whitespace and comments. Tools can
+/// change individual tokens and write the result back without reformatting
+/// the rest of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree {
+    pub tokens: Vec<SyntaxToken>,
+    /// The statements of the program (in the same order as `Program::stmts`)
+    pub statements: Vec<SyntaxNode>,
+}
+
+impl SyntaxTree {
+    pub(super) fn new(input: &str, tokens: Vec<(Token, Span)>, stmts: &Statements) -> Self {
+        let tokens: Vec<SyntaxToken> = tokens
+            .into_iter()
+            .map(|(token, span)| {
+                let text = &input[span.lo..span.hi];
+
+                SyntaxToken {
+                    kind: token_kind(&token, text),
+                    span,
+                    text: text.to_string(),
+                }
+            })
+            .collect();
+
+        let statements = stmts.iter().map(|stmt| build_node(&tokens, stmt)).collect();
+
+        Self { tokens, statements }
+    }
+
+    /// Recreate the source code (including all changes made to tokens)
+    pub fn to_source(&self) -> String {
+        self.tokens
+            .iter()
+            .map(|token| token.text.as_str())
+            .collect()
+    }
+
+    /// Find all uses of a variable (or module)
+    ///
+    /// Member names (e.g., `name` in `person.name`) are not references.
+    /// This does not consider scopes, so all variables with that name are found.
+    pub fn find_references(&self, name: &str) -> Vec<Span> {
+        self.reference_indices(name)
+            .into_iter()
+            .map(|index| self.tokens[index].span)
+            .collect()
+    }
+
+    /// Rename all uses of a variable (or module) found by `find_references`
+    ///
+    /// Returns the number of renamed tokens.
+    pub fn rename(&mut self, from: &str, to: &str) -> usize {
+        let indices = self.reference_indices(from);
+
+        for index in indices.iter() {
+            self.tokens[*index].text = to.to_string();
+        }
+
+        indices.len()
+    }
+
+    fn reference_indices(&self, name: &str) -> Vec<usize> {
+        let mut result = vec![];
+        let mut after_period = false;
+
+        for (index, token) in self.tokens.iter().enumerate() {
+            if token.kind.is_trivia() {
+                continue;
+            }
+
+            if !after_period && token.identifier() == Some(name) {
+                result.push(index);
+            }
+
+            after_period = token.kind == TokenKind::Punctuation && token.text == ".";
+        }
+
+        result
+    }
+}
+
+fn build_node(tokens: &[SyntaxToken], node: &ParseNode) -> SyntaxNode {
+    let (span, expr) = node;
+
+    let start = tokens.partition_point(|token| token.span.hi <= span.lo);
+    let end = tokens.partition_point(|token| token.span.lo < span.hi);
+
+    SyntaxNode {
+        span: *span,
+        tokens: start..end.max(start),
+        children: expr
+            .children()
+            .into_iter()
+            .map(|child| build_node(tokens, child))
+            .collect(),
+    }
+}
+
+fn token_kind(token: &Token, text: &str) -> TokenKind {
+    match token {
+        Token::Whitespace | Token::Indent | Token::Dedent => TokenKind::Whitespace,
+        Token::Newline => TokenKind::Newline,
+        Token::Comment(_) => TokenKind::Comment,
+        Token::Identifier(_) => TokenKind::Identifier,
+        Token::BoolLiteral(_)
+        | Token::I64Literal(_)
+        | Token::U64Literal(_)
+        | Token::U8Literal(_)
+        | Token::DecimalLiteral(_)
+        | Token::StringLiteral(_) => TokenKind::Literal,
+        Token::TypeName(_)
+        | Token::As
+        | Token::Let
+        | Token::ToStr
+        | Token::Max
+        | Token::Min
+        | Token::Range
+        | Token::Return
+        | Token::For
+        | Token::With
+        | Token::In
+        | Token::If
+        | Token::Else
+        | Token::Try
+        | Token::Except
+        | Token::Raise
+        | Token::Def => TokenKind::Keyword,
+        // `and`, `or`, and `not` can also be spelled as symbols
+        Token::And | Token::Or | Token::Not => {
+            if text.starts_with(|c: char| c.is_ascii_alphabetic()) {
+                TokenKind::Keyword
+            } else {
+                TokenKind::Punctuation
+            }
+        }
+        _ => TokenKind::Punctuation,
+    }
+}
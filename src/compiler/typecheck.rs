@@ -0,0 +1,467 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::ast::{Expr, ParseNode, Program, Span, Statements, TypeAnnotation, ValueType};
+use crate::values::{PrimitiveType, TypeDefinition};
+
+/// A mismatch between a type annotation and how the annotated value is used
+#[derive(Debug, Clone)]
+pub struct TypeError {
+    pub span: Span,
+    /// The name of the script the error refers to (if known)
+    pub source_name: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source_name {
+            Some(name) => write!(f, "{name}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Check that a program agrees with its type annotations
+///
+/// Annotations are optional, so only annotated variables, parameters,
+/// and return values are checked. Values whose type cannot be known before
+/// running the program (e.g., results of calls to modules) are always accepted.
+/// Annotations barely change how a program runs (see `TypeAnnotation`), so this
+/// should be called after compiling and before executing a program.
+pub fn typecheck(program: &Program) -> Result<(), Vec<TypeError>> {
+    let mut checker = TypeChecker {
+        scopes: vec![HashMap::new()],
+        return_types: vec![],
+        errors: vec![],
+    };
+
+    checker.check_statements(&program.stmts);
+
+    let mut errors = checker.errors;
+    if errors.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(name) = &program.name {
+        for err in errors.iter_mut() {
+            err.source_name = Some(name.clone());
+        }
+    }
+
+    Err(errors)
+}
+
+#[derive(Clone)]
+enum Binding {
+    Variable(TypeDefinition),
+    Function {
+        params: Vec<(String, TypeDefinition)>,
+        result: TypeDefinition,
+    },
+}
+
+struct TypeChecker {
+    scopes: Vec<HashMap<String, Binding>>,
+    /// The annotated result of the functions currently being checked
+    return_types: Vec<TypeDefinition>,
+    errors: Vec<TypeError>,
+}
+
+const ANY: TypeDefinition = TypeDefinition::Primitive(PrimitiveType::Any);
+
+impl TypeChecker {
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(TypeError {
+            span,
+            source_name: None,
+            message,
+        });
+    }
+
+    fn declare(&mut self, name: &str, binding: Binding) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), binding);
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    fn variable_type(&self, name: &str) -> TypeDefinition {
+        match self.lookup(name) {
+            Some(Binding::Variable(t)) => t.clone(),
+            _ => ANY,
+        }
+    }
+
+    /// Convert an annotation (reports unknown types and uses `any` for them)
+    fn resolve(&mut self, span: Span, annotation: &TypeAnnotation) -> TypeDefinition {
+        match annotation {
+            TypeAnnotation::Named(name) => {
+                let primitive = match name.as_str() {
+                    "any" => PrimitiveType::Any,
+                    "none" => PrimitiveType::None,
+                    "bool" => PrimitiveType::Bool,
+                    "str" | "string" => PrimitiveType::String,
+                    "i64" => PrimitiveType::I64,
+                    "u64" => PrimitiveType::U64,
+                    "u8" => PrimitiveType::U8,
+                    "f32" => PrimitiveType::F32,
+                    "f64" => PrimitiveType::F64,
+                    "decimal" => PrimitiveType::Decimal,
+                    "bytes" => return TypeDefinition::Bytes,
+                    "list" => return TypeDefinition::List(Box::new(ANY)),
+                    "map" => return TypeDefinition::make_map(string_type(), ANY),
+                    _ => {
+                        self.error(span, format!("Unknown type `{name}`"));
+                        PrimitiveType::Any
+                    }
+                };
+                TypeDefinition::Primitive(primitive)
+            }
+            TypeAnnotation::List(elem) => TypeDefinition::List(Box::new(self.resolve(span, elem))),
+            TypeAnnotation::Array(elem, len) => {
+                TypeDefinition::Array(Box::new(self.resolve(span, elem)), *len)
+            }
+            TypeAnnotation::Map(key, value) => {
+                let key = self.resolve(span, key);
+                if !is_assignable(&string_type(), &key) {
+                    self.error(
+                        span,
+                        format!("Map keys must be strings, not `{}`", describe(&key)),
+                    );
+                }
+                TypeDefinition::make_map(string_type(), self.resolve(span, value))
+            }
+            TypeAnnotation::Tuple(elems) => {
+                TypeDefinition::Tuple(elems.iter().map(|elem| self.resolve(span, elem)).collect())
+            }
+        }
+    }
+
+    fn check_statements(&mut self, stmts: &Statements) {
+        for stmt in stmts {
+            self.check(stmt);
+        }
+    }
+
+    fn check(&mut self, node: &ParseNode) {
+        let (span, expr) = node;
+
+        match expr {
+            Expr::AssignNewAnnotated {
+                name,
+                annotation,
+                value,
+            } => {
+                self.check(value);
+
+                let expected = self.resolve(*span, annotation);
+                let actual = self.infer(value);
+                if !is_assignable(&expected, &actual) {
+                    self.error(
+                        *span,
+                        format!(
+                            "Cannot assign a value of type `{}` to `{name}` of type `{}`",
+                            describe(&actual),
+                            describe(&expected)
+                        ),
+                    );
+                }
+
+                self.declare(name, Binding::Variable(expected));
+                return;
+            }
+            Expr::AssignNew(name, value) => {
+                self.check(value);
+                self.declare(name, Binding::Variable(ANY));
+                return;
+            }
+            Expr::AssignNewMultiple { targets, values } => {
+                for value in values {
+                    self.check(value);
+                }
+                for target in targets {
+                    self.declare(target, Binding::Variable(ANY));
+                }
+                return;
+            }
+            Expr::Assign(name, value) => {
+                let expected = self.variable_type(name);
+                let actual = self.infer(value);
+                if !is_assignable(&expected, &actual) {
+                    self.error(
+                        *span,
+                        format!(
+                            "Cannot assign a value of type `{}` to `{name}` of type `{}`",
+                            describe(&actual),
+                            describe(&expected)
+                        ),
+                    );
+                }
+            }
+            Expr::Function { name, params, body } => {
+                self.check_function(*span, name, params, body, &[], &None);
+                return;
+            }
+            Expr::AnnotatedFunction {
+                function,
+                param_types,
+                return_type,
+            } => {
+                if let (_, Expr::Function { name, params, body }) = function.as_ref() {
+                    self.check_function(*span, name, params, body, param_types, return_type);
+                }
+                return;
+            }
+            Expr::Return(value) => {
+                if let Some(expected) = self.return_types.last().cloned() {
+                    let actual = self.infer(value);
+                    if !is_assignable(&expected, &actual) {
+                        self.error(
+                            *span,
+                            format!(
+                                "Expected a return value of type `{}`, but got `{}`",
+                                describe(&expected),
+                                describe(&actual)
+                            ),
+                        );
+                    }
+                }
+            }
+            Expr::Call(callee, args) => self.check_call(*span, callee, args),
+            Expr::ForIn { target_name, .. } | Expr::With { target_name, .. } => {
+                self.declare(target_name, Binding::Variable(ANY));
+            }
+            Expr::Try {
+                error_name: Some(name),
+                ..
+            } => {
+                self.declare(name, Binding::Variable(ANY));
+            }
+            _ => {}
+        }
+
+        for child in expr.children() {
+            self.check(child);
+        }
+    }
+
+    fn check_function(
+        &mut self,
+        span: Span,
+        name: &str,
+        params: &[String],
+        body: &Statements,
+        param_types: &[Option<TypeAnnotation>],
+        return_type: &Option<TypeAnnotation>,
+    ) {
+        let params: Vec<(String, TypeDefinition)> = params
+            .iter()
+            .enumerate()
+            .map(|(pos, param)| {
+                let param_type = match param_types.get(pos) {
+                    Some(Some(annotation)) => self.resolve(span, annotation),
+                    _ => ANY,
+                };
+                (param.clone(), param_type)
+            })
+            .collect();
+
+        let result = match return_type {
+            Some(annotation) => self.resolve(span, annotation),
+            None => ANY,
+        };
+
+        // Declare first, so that the function can call itself
+        self.declare(
+            name,
+            Binding::Function {
+                params: params.clone(),
+                result: result.clone(),
+            },
+        );
+
+        let scope = params
+            .into_iter()
+            .map(|(name, param_type)| (name, Binding::Variable(param_type)))
+            .collect();
+
+        self.scopes.push(scope);
+        self.return_types.push(result);
+        self.check_statements(body);
+        self.return_types.pop();
+        self.scopes.pop();
+    }
+
+    fn check_call(&mut self, span: Span, callee: &ParseNode, args: &[ParseNode]) {
+        let (_, Expr::Var(name)) = callee else {
+            return;
+        };
+
+        let Some(Binding::Function { params, .. }) = self.lookup(name).cloned() else {
+            return;
+        };
+
+        if params.len() != args.len() {
+            self.error(
+                span,
+                format!(
+                    "{name}() expects {} argument(s), but got {}",
+                    params.len(),
+                    args.len()
+                ),
+            );
+            return;
+        }
+
+        for ((param, expected), arg) in params.iter().zip(args.iter()) {
+            let actual = self.infer(arg);
+            if !is_assignable(expected, &actual) {
+                self.error(
+                    arg.0,
+                    format!(
+                        "Argument `{param}` of {name}() must be of type `{}`, but got `{}`",
+                        describe(expected),
+                        describe(&actual)
+                    ),
+                );
+            }
+        }
+    }
+
+    /// The type of an expression (or `any` if it cannot be known)
+    fn infer(&self, node: &ParseNode) -> TypeDefinition {
+        let (_, expr) = node;
+
+        match expr {
+            Expr::I64(_) => TypeDefinition::Primitive(PrimitiveType::I64),
+            Expr::U64(_) => TypeDefinition::Primitive(PrimitiveType::U64),
+            Expr::U8(_) => TypeDefinition::Primitive(PrimitiveType::U8),
+            Expr::Decimal(_) => TypeDefinition::Primitive(PrimitiveType::Decimal),
+            Expr::Bool(_)
+            | Expr::Compare { .. }
+            | Expr::Not(_)
+            | Expr::And { .. }
+            | Expr::Or { .. } => TypeDefinition::Primitive(PrimitiveType::Bool),
+            Expr::String(_) | Expr::ToStr(_) => string_type(),
+            Expr::Brackets(inner) | Expr::Negative(inner) => self.infer(inner),
+            Expr::Var(name) => self.variable_type(name),
+            Expr::Cast { typename, .. } => match typename {
+                ValueType::U8 => TypeDefinition::Primitive(PrimitiveType::U8),
+                ValueType::U64 => TypeDefinition::Primitive(PrimitiveType::U64),
+                ValueType::I64 => TypeDefinition::Primitive(PrimitiveType::I64),
+                _ => ANY,
+            },
+            Expr::List(elems) => {
+                let elem_type = self.common_type(elems.iter());
+                TypeDefinition::Array(Box::new(elem_type), elems.len())
+            }
+            Expr::Dictionary(entries) => {
+                TypeDefinition::make_map(string_type(), self.common_type(entries.values()))
+            }
+            Expr::Tuple(elems) => {
+                TypeDefinition::Tuple(elems.iter().map(|elem| self.infer(elem)).collect())
+            }
+            Expr::Array { value, length } => {
+                let elem_type = Box::new(self.infer(value));
+                match length.1 {
+                    Expr::I64(len) if len >= 0 => TypeDefinition::Array(elem_type, len as usize),
+                    Expr::U64(len) => TypeDefinition::Array(elem_type, len as usize),
+                    _ => TypeDefinition::List(elem_type),
+                }
+            }
+            Expr::Add { lhs, rhs } | Expr::Multiply { lhs, rhs } | Expr::Remainder { lhs, rhs } => {
+                let lhs = self.infer(lhs);
+                match lhs {
+                    TypeDefinition::Primitive(_) if lhs == self.infer(rhs) => lhs,
+                    _ => ANY,
+                }
+            }
+            Expr::Call(callee, _) => match callee.as_ref() {
+                (_, Expr::Var(name)) => match self.lookup(name) {
+                    Some(Binding::Function { result, .. }) => result.clone(),
+                    None if name == "format" => string_type(),
+                    _ => ANY,
+                },
+                _ => ANY,
+            },
+            _ => ANY,
+        }
+    }
+
+    /// The type all values have in common (or `any` if they differ)
+    fn common_type<'a>(&self, mut nodes: impl Iterator<Item = &'a ParseNode>) -> TypeDefinition {
+        let Some(first) = nodes.next() else {
+            return ANY;
+        };
+
+        let result = self.infer(first);
+        if nodes.all(|node| self.infer(node) == result) {
+            result
+        } else {
+            ANY
+        }
+    }
+}
+
+fn string_type() -> TypeDefinition {
+    TypeDefinition::Primitive(PrimitiveType::String)
+}
+
+/// Can a value of type `actual` be stored where `expected` is required?
+///
+/// Lists of unknown length (e.g., parameters) are accepted as arrays of any length.
+fn is_assignable(expected: &TypeDefinition, actual: &TypeDefinition) -> bool {
+    use TypeDefinition as T;
+
+    match (expected, actual) {
+        (T::Primitive(PrimitiveType::Any), _) | (_, T::Primitive(PrimitiveType::Any)) => true,
+        (T::Primitive(expected), T::Primitive(actual)) => expected == actual,
+        (T::List(expected), T::List(actual) | T::Array(actual, _))
+        | (T::Array(expected, _), T::List(actual)) => is_assignable(expected, actual),
+        (T::Array(expected, expected_len), T::Array(actual, actual_len)) => {
+            expected_len == actual_len && is_assignable(expected, actual)
+        }
+        (T::Map(_, expected), T::Map(_, actual)) => is_assignable(expected, actual),
+        (T::Tuple(expected), T::Tuple(actual)) => {
+            expected.len() == actual.len()
+                && expected
+                    .iter()
+                    .zip(actual.iter())
+                    .all(|(expected, actual)| is_assignable(expected, actual))
+        }
+        (T::Bytes, T::Bytes) => true,
+        _ => false,
+    }
+}
+
+/// Write a type the same way it would be annotated
+fn describe(type_def: &TypeDefinition) -> String {
+    match type_def {
+        TypeDefinition::Primitive(primitive) => match primitive {
+            PrimitiveType::None => "none",
+            PrimitiveType::Any => "any",
+            PrimitiveType::Bool => "bool",
+            PrimitiveType::String => "str",
+            PrimitiveType::I64 => "i64",
+            PrimitiveType::U64 => "u64",
+            PrimitiveType::U8 => "u8",
+            PrimitiveType::F32 => "f32",
+            PrimitiveType::F64 => "f64",
+            PrimitiveType::Decimal => "decimal",
+        }
+        .to_string(),
+        TypeDefinition::Array(elem, len) => format!("[{}; {len}]", describe(elem)),
+        TypeDefinition::List(elem) => format!("[{}]", describe(elem)),
+        TypeDefinition::Map(key, value) => format!("{{{}: {}}}", describe(key), describe(value)),
+        TypeDefinition::Bytes => "bytes".to_string(),
+        TypeDefinition::Tuple(elems) => {
+            let elems: Vec<String> = elems.iter().map(describe).collect();
+            format!("({})", elems.join(", "))
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use crate::ast::Span;
+use crate::source::NamedSource;
+use crate::values::{Value, ValueError};
+
+/// Any error that can occur when compiling or running a program
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error("Compile error: {0}")]
+    Compile(#[from] CompileError),
+    #[error("Runtime error: {0}")]
+    Runtime(#[from] RuntimeError),
+    #[error("Value error: {0}")]
+    Value(#[from] ValueError),
+}
+
+/// The program could not be parsed
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{message}{}", fmt_location(.span, .source_name))]
+pub struct CompileError {
+    pub span: Option<Span>,
+    /// The name of the script that failed to compile (if known)
+    pub source_name: Option<String>,
+    pub message: String,
+}
+
+/// A stored program could not be converted to the current format
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum MigrationError {
+    #[error("Unknown program version {0}")]
+    UnknownVersion(u32),
+}
+
+/// Serialized data could not be loaded (see `Program::from_bytes` and `Value::from_msgpack`)
+#[cfg(feature = "msgpack")]
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum DecodeError {
+    #[error("Data does not start with the expected header")]
+    InvalidHeader,
+    /// The data was written by a newer version of this crate
+    #[error("Unknown format version {0}")]
+    UnknownVersion(u32),
+    #[error(transparent)]
+    Migration(#[from] MigrationError),
+    #[error("Malformed data: {0}")]
+    Malformed(String),
+}
+
+/// Executing the program failed
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{kind}{}", fmt_location(.span, .source_name))]
+pub struct RuntimeError {
+    /// The location of the expression that failed (if known)
+    pub span: Option<Span>,
+    /// The name of the script that failed (if known)
+    pub source_name: Option<String>,
+    pub kind: RuntimeErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RuntimeErrorKind {
+    /// An operation on a value failed
+    #[error(transparent)]
+    Value(#[from] ValueError),
+    #[error("No such value or module '{0}'")]
+    NoSuchVariable(String),
+    #[error("Variable '{0}' already exists")]
+    VariableAlreadyExists(String),
+    #[error("Module '{module}' has no member '{member}'")]
+    NoSuchMember { module: String, member: String },
+    #[error("Cannot use {0} in a with block (expected a module with a close() member)")]
+    NotAResource(String),
+    #[error("No such builtin: '{0}'")]
+    NoSuchBuiltin(String),
+    #[error("No such child {0}")]
+    NoSuchChild(String),
+    #[error("Expected a value, but got a module or callable")]
+    NotAValue,
+    #[error("Expression is not callable")]
+    NotCallable,
+    #[error("Cannot iterate over {0}")]
+    NotIterable(String),
+    #[error("Cannot modify {0}")]
+    NotAssignable(String),
+    /// Iterators can only be used once, so they cannot be read from a variable
+    #[error("Variable '{0}' holds an iterator (use it directly, e.g., in a for loop)")]
+    StoredIterator(String),
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Assertion failed: {0}")]
+    AssertionFailed(String),
+    #[error("Cannot assign {values} values to {targets} variables")]
+    AssignmentMismatch { targets: usize, values: usize },
+    /// Only raised if the interpreter forbids non-finite floats
+    #[error("Floating point operation resulted in {0}")]
+    NonFiniteFloat(String),
+    /// Only raised if the interpreter has output limits
+    #[error("Value has a nesting depth of {depth} (at most {max_depth} allowed)")]
+    ValueTooDeep { depth: usize, max_depth: usize },
+    /// Only raised if the interpreter has output limits
+    #[error("Value has a size of {size} (at most {max_size} allowed)")]
+    ValueTooLarge { size: usize, max_size: usize },
+    /// Raised if functions call each other too deeply (see `Interpreter::set_max_call_depth`)
+    #[error("Too many nested function calls (at most {max_depth} allowed)")]
+    CallTooDeep { max_depth: usize },
+    /// Only raised if the interpreter catches panics of modules
+    #[error("Module function panicked: {0}")]
+    ModulePanicked(String),
+    /// Only raised by restricted programs (see `Program::restrict`)
+    #[error("Access to {0} is not allowed")]
+    NotAllowed(String),
+    /// Only raised by `Interpreter::leak_check`
+    #[error("Module '{0}' is still referenced after the program finished")]
+    ModuleLeaked(String),
+    /// Only raised by `SharedInterpreter`
+    #[error("Cannot run a program while the interpreter is already running")]
+    ReentrantRun,
+    /// Only raised if the interpreter has a cancel token (see `Interpreter::set_cancel_token`)
+    #[error("Program was cancelled")]
+    Cancelled,
+    /// Raised by the program itself (using `raise`)
+    #[error("{0}")]
+    Raised(String),
+    /// An async function was called outside of `cowlang::tokio::run_blocking` (see `crate::tokio`)
+    #[cfg(feature = "async")]
+    #[error("Async functions need cowlang::tokio::run_blocking with a multi-threaded runtime")]
+    NotAsync,
+}
+
+fn fmt_location(span: &Option<Span>, source_name: &Option<String>) -> String {
+    let mut result = String::new();
+
+    if let Some(span) = span {
+        result += &format!(" at [{} to {}]", span.lo, span.hi);
+    }
+
+    if let Some(name) = source_name {
+        result += &format!(" in '{}'", name);
+    }
+
+    result
+}
+
+/// Describe an error as `name:line:column: message`
+fn render_location(span: Option<Span>, source: &NamedSource, message: &str) -> String {
+    if let Some(span) = span {
+        let (line, column) = source.line_col(span.lo);
+        format!("{}:{}:{}: {}", source.name(), line, column, message)
+    } else {
+        format!("{}: {}", source.name(), message)
+    }
+}
+
+impl Error {
+    /// The location of the error in the source code (if known)
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Compile(err) => err.span,
+            Self::Runtime(err) => err.span,
+            Self::Value(_) => None,
+        }
+    }
+
+    /// The name of the script the error occurred in (if known)
+    pub fn source_name(&self) -> Option<&str> {
+        match self {
+            Self::Compile(err) => err.source_name.as_deref(),
+            Self::Runtime(err) => err.source_name.as_deref(),
+            Self::Value(_) => None,
+        }
+    }
+
+    /// Attach the location of the error
+    ///
+    /// Value errors are turned into runtime errors, as they have no location otherwise.
+    #[must_use]
+    pub fn with_span(self, span: Span) -> Self {
+        match self {
+            Self::Compile(err) => Self::Compile(err.with_span(span)),
+            Self::Runtime(err) => Self::Runtime(err.with_span(span)),
+            Self::Value(err) => Self::Runtime(RuntimeError::from(err).with_span(span)),
+        }
+    }
+
+    /// Attach the name of the script the error occurred in
+    #[must_use]
+    pub fn with_source_name<S: Into<String>>(self, name: S) -> Self {
+        match self {
+            Self::Compile(err) => Self::Compile(err.with_source_name(name)),
+            Self::Runtime(err) => Self::Runtime(err.with_source_name(name)),
+            Self::Value(err) => Self::Runtime(RuntimeError::from(err).with_source_name(name)),
+        }
+    }
+
+    /// Describe the error as `name:line:column: message` using the script's source code
+    pub fn render(&self, source: &NamedSource) -> String {
+        match self {
+            Self::Compile(err) => render_location(err.span, source, &err.message),
+            Self::Runtime(err) => render_location(err.span, source, &err.kind.to_string()),
+            Self::Value(err) => render_location(None, source, &err.to_string()),
+        }
+    }
+}
+
+impl CompileError {
+    pub fn new(span: Option<Span>, message: String) -> Self {
+        Self {
+            span,
+            message,
+            source_name: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    #[must_use]
+    pub fn with_source_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.source_name = Some(name.into());
+        self
+    }
+}
+
+impl RuntimeError {
+    pub fn new(kind: RuntimeErrorKind) -> Self {
+        Self {
+            span: None,
+            source_name: None,
+            kind,
+        }
+    }
+
+    #[must_use]
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    #[must_use]
+    pub fn with_source_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.source_name = Some(name.into());
+        self
+    }
+
+    /// Set the location of the error, unless a more precise one is already known
+    #[cfg(feature = "interpreter")]
+    pub(crate) fn or_span(mut self, span: Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span);
+        }
+
+        self
+    }
+}
+
+impl RuntimeErrorKind {
+    /// The name of the error as seen by programs (e.g., `NoSuchChild`)
+    ///
+    /// Value errors use the name of the `ValueError` instead.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Value(err) => err.name(),
+            Self::NoSuchVariable(_) => "NoSuchVariable",
+            Self::VariableAlreadyExists(_) => "VariableAlreadyExists",
+            Self::NoSuchMember { .. } => "NoSuchMember",
+            Self::NotAResource(_) => "NotAResource",
+            Self::NoSuchBuiltin(_) => "NoSuchBuiltin",
+            Self::NoSuchChild(_) => "NoSuchChild",
+            Self::NotAValue => "NotAValue",
+            Self::NotCallable => "NotCallable",
+            Self::NotIterable(_) => "NotIterable",
+            Self::NotAssignable(_) => "NotAssignable",
+            Self::StoredIterator(_) => "StoredIterator",
+            Self::InvalidArgument(_) => "InvalidArgument",
+            Self::AssertionFailed(_) => "AssertionFailed",
+            Self::AssignmentMismatch { .. } => "AssignmentMismatch",
+            Self::NonFiniteFloat(_) => "NonFiniteFloat",
+            Self::ValueTooDeep { .. } => "ValueTooDeep",
+            Self::ValueTooLarge { .. } => "ValueTooLarge",
+            Self::CallTooDeep { .. } => "CallTooDeep",
+            Self::ModulePanicked(_) => "ModulePanicked",
+            Self::NotAllowed(_) => "NotAllowed",
+            Self::ModuleLeaked(_) => "ModuleLeaked",
+            Self::ReentrantRun => "ReentrantRun",
+            Self::Cancelled => "Cancelled",
+            Self::Raised(_) => "Raised",
+            #[cfg(feature = "async")]
+            Self::NotAsync => "NotAsync",
+        }
+    }
+
+    /// Can programs recover from this error using `try`?
+    ///
+    /// Errors that enforce limits set by the host cannot be caught.
+    pub fn is_catchable(&self) -> bool {
+        !matches!(
+            self,
+            Self::ValueTooDeep { .. }
+                | Self::ValueTooLarge { .. }
+                | Self::ModulePanicked(_)
+                | Self::NotAllowed(_)
+                | Self::ModuleLeaked(_)
+                | Self::ReentrantRun
+                | Self::Cancelled
+        )
+    }
+}
+
+impl RuntimeError {
+    /// Describe the error as a map with a `kind` and a `message` entry
+    ///
+    /// This is what the handler of a `try` block sees.
+    pub fn to_value(&self) -> Value {
+        let mut result = Value::make_map();
+        result
+            .map_insert("kind".to_string(), self.kind.name().into())
+            .unwrap();
+        result
+            .map_insert("message".to_string(), self.kind.to_string().into())
+            .unwrap();
+        result
+    }
+}
+
+impl From<RuntimeErrorKind> for RuntimeError {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl From<ValueError> for RuntimeError {
+    fn from(err: ValueError) -> Self {
+        Self::new(RuntimeErrorKind::Value(err))
+    }
+}
@@ -0,0 +1,60 @@
+//! Small programs together with the value they return
+//!
+//! Embedders can run these to check their integration (e.g., that programs
+//! still produce the same results after being serialized and loaded again).
+//! None of the examples require modules or variables set by the host.
+
+use crate::values::Value;
+
+/// A program and the value it is expected to return
+#[derive(Debug, Clone)]
+pub struct Example {
+    pub name: &'static str,
+    pub source: &'static str,
+    pub expected: Value,
+}
+
+/// All examples, in alphabetical order of their names
+pub fn examples() -> Vec<Example> {
+    vec![
+        Example {
+            name: "arithmetic",
+            source: include_str!("arithmetic.cow"),
+            expected: vec![20i64, -3, 34, 3, 3, 2].into(),
+        },
+        Example {
+            name: "errors",
+            source: include_str!("errors.cow"),
+            expected: false.into(),
+        },
+        Example {
+            name: "functions",
+            source: include_str!("functions.cow"),
+            expected: vec![10i64, 110].into(),
+        },
+        Example {
+            name: "loops",
+            source: include_str!("loops.cow"),
+            expected: 30i64.into(),
+        },
+        Example {
+            name: "maps",
+            source: include_str!("maps.cow"),
+            expected: Value::List(vec![vec!["apples", "pears", "plums"].into(), 5i64.into()]),
+        },
+        Example {
+            name: "strings",
+            source: include_str!("strings.cow"),
+            expected: Value::List(vec![
+                "the, quick, brown, fox".into(),
+                "COW".into(),
+                4u64.into(),
+            ]),
+        },
+    ]
+}
+
+/// Look up an example by its name
+pub fn example(name: &str) -> Option<Example> {
+    examples().into_iter().find(|example| example.name == name)
+}
@@ -0,0 +1,109 @@
+#[cfg(feature = "interpreter")]
+use crate::error::{RuntimeError, RuntimeErrorKind};
+#[cfg(feature = "interpreter")]
+use crate::values::{Value, ValueError};
+
+/// A piece of a format string used by the `format()` builtin
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum FormatPart {
+    Literal(String),
+    /// `%s`: any value that can be converted to a string
+    String,
+    /// `%d`: an integer
+    Integer,
+    /// `%f`: a floating point number
+    Float,
+}
+
+/// Split a format string into literals and placeholders
+///
+/// `%%` is an escaped percent sign.
+pub(crate) fn parse_format(format: &str) -> Result<Vec<FormatPart>, String> {
+    let mut parts = Vec::new();
+    let mut literal = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            literal.push(c);
+            continue;
+        }
+
+        let part = match chars.next() {
+            Some('%') => {
+                literal.push('%');
+                continue;
+            }
+            Some('s') => FormatPart::String,
+            Some('d') => FormatPart::Integer,
+            Some('f') => FormatPart::Float,
+            Some(other) => return Err(format!("Unknown format specifier '%{}'", other)),
+            None => return Err("Format string ends with '%'".to_string()),
+        };
+
+        if !literal.is_empty() {
+            parts.push(FormatPart::Literal(std::mem::take(&mut literal)));
+        }
+
+        parts.push(part);
+    }
+
+    if !literal.is_empty() {
+        parts.push(FormatPart::Literal(literal));
+    }
+
+    Ok(parts)
+}
+
+/// The number of arguments a parsed format string expects
+pub(crate) fn num_arguments(parts: &[FormatPart]) -> usize {
+    parts
+        .iter()
+        .filter(|part| !matches!(part, FormatPart::Literal(_)))
+        .count()
+}
+
+/// Fill the placeholders of a format string with the given arguments
+#[cfg(feature = "interpreter")]
+pub(crate) fn format_values(format: &str, args: Vec<Value>) -> Result<String, RuntimeError> {
+    let parts = parse_format(format).map_err(RuntimeErrorKind::InvalidArgument)?;
+    let expected = num_arguments(&parts);
+
+    if expected != args.len() {
+        return Err(RuntimeErrorKind::InvalidArgument(format!(
+            "format string expects {} arguments, but got {}",
+            expected,
+            args.len()
+        ))
+        .into());
+    }
+
+    let mut result = String::new();
+    let mut args = args.into_iter();
+
+    for part in parts {
+        let arg = match part {
+            FormatPart::Literal(literal) => {
+                result += &literal;
+                continue;
+            }
+            _ => args.next().unwrap(),
+        };
+
+        match (&part, &arg) {
+            (FormatPart::Integer, Value::I64(_) | Value::U64(_) | Value::U8(_)) => {}
+            (FormatPart::Integer, _) => {
+                return Err(ValueError::type_mismatch("integer", &arg).into());
+            }
+            (FormatPart::Float, Value::F32(_) | Value::F64(_)) => {}
+            (FormatPart::Float, _) => {
+                return Err(ValueError::type_mismatch("float", &arg).into());
+            }
+            _ => {}
+        }
+
+        result += &arg.to_canonical_string()?;
+    }
+
+    Ok(result)
+}
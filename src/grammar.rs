@@ -0,0 +1,336 @@
+//! A machine-readable description of the language
+//!
+//! Meant for editors and documentation, so they do not have to hard-code
+//! the keywords and builtins of a specific version of cowlang.
+
+use serde::Serialize;
+
+/// Words that cannot be used as identifiers (unless escaped with `\`)
+pub const KEYWORDS: &[&str] = &[
+    "let", "return", "if", "else", "for", "in", "with", "not", "and", "or", "as", "true", "false",
+    "try", "except", "raise", "def",
+];
+
+/// Names of types that values can be cast to (using `as`)
+pub const TYPE_NAMES: &[&str] = &["u8", "i64", "u64"];
+
+/// Operators and punctuation, together with a short description
+pub const OPERATORS: &[(&str, &str)] = &[
+    ("=", "Assignment"),
+    ("+=", "Add to a variable, element, or module member"),
+    ("-=", "Subtract from a variable, element, or module member"),
+    ("*=", "Multiply a variable, element, or module member"),
+    ("/=", "Divide a variable, element, or module member"),
+    ("//=", "Floor divide a variable, element, or module member"),
+    (
+        "%=",
+        "Replace a variable, element, or module member with its remainder",
+    ),
+    ("==", "Equality"),
+    ("<", "Smaller than"),
+    (">", "Greater than"),
+    ("+", "Addition"),
+    ("-", "Negation of a number"),
+    ("*", "Multiplication"),
+    ("/", "Division (integers are divided as floats)"),
+    ("//", "Floor division"),
+    ("%", "Remainder of a floor division"),
+    ("!", "Logical not"),
+    ("&&", "Logical and (short-circuiting)"),
+    ("||", "Logical or (short-circuiting)"),
+    (".", "Member access"),
+    (",", "Separates arguments, elements, and assignment targets"),
+    (
+        ":",
+        "Starts a block, separates a key from its value, or starts a type annotation",
+    ),
+    (
+        ";",
+        "Separates the value (or element type) of an array from its length, e.g., `[u8; 4]`",
+    ),
+    (
+        "->",
+        "Separates the parameters of a function from its return type",
+    ),
+    ("(", "Opens a call or a parenthesized expression"),
+    (")", "Closes a call or a parenthesized expression"),
+    ("[", "Opens a list, an array, or an index"),
+    ("]", "Closes a list, an array, or an index"),
+    ("{", "Opens a dictionary"),
+    ("}", "Closes a dictionary"),
+    ("#", "Starts a comment"),
+    (
+        "\\",
+        "Escapes a reserved word so it can be used as an identifier, e.g., `\\max`",
+    ),
+];
+
+/// Builtin functions (these are keywords as well)
+pub const FUNCTIONS: &[(&str, &str)] = &[
+    ("str(value)", "Convert a value into a string"),
+    ("max(a, b)", "Get the larger of two numbers"),
+    ("min(a, b)", "Get the smaller of two numbers"),
+    (
+        "range(start, end[, step])",
+        "Iterate over a range of integers (a negative step counts down)",
+    ),
+];
+
+/// Functions every program can use (these are not reserved and can be shadowed by variables)
+pub const GLOBAL_FUNCTIONS: &[(&str, &str)] = &[
+    (
+        "len(value)",
+        "Get the number of elements, entries, characters, or bytes",
+    ),
+    ("print(values...)", "Write a line to the host's output"),
+    ("type(value)", "Get the name of the value's type"),
+    ("abs(number)", "Get the absolute value of a number"),
+    (
+        "assert(condition[, message])",
+        "Fail with the message if the condition is false",
+    ),
+    (
+        "bind(function, args...)",
+        "Fix the first arguments of a function",
+    ),
+    ("divmod(a, b)", "Get the floored quotient and the remainder"),
+    (
+        "format(format, args...)",
+        "Insert values into a string (%s, %d, %f)",
+    ),
+    ("help(object)", "Describe a value or module"),
+    ("sum(list)", "Add up all elements of a list"),
+    (
+        "map(function, list)",
+        "Call the function for every element and collect the results",
+    ),
+    (
+        "filter(function, list)",
+        "Keep the elements for which the function returns true",
+    ),
+    (
+        "reduce(function, list, init)",
+        "Combine all elements, starting with init (the function gets the result so far and an element)",
+    ),
+    (
+        "enumerate(iterable)",
+        "Iterate over [index, item] pairs",
+    ),
+    (
+        "zip(lhs, rhs)",
+        "Iterate over pairs of items until one of the two ends",
+    ),
+    (
+        "sleep(milliseconds)",
+        "Pause the program (for at most the time the host allows)",
+    ),
+];
+
+/// Builtin functions of lists
+pub const LIST_BUILTINS: &[(&str, &str)] = &[
+    ("len()", "Get the number of elements"),
+    ("append(value)", "Add a value to the end of the list"),
+    (
+        "extend(other)",
+        "Add all elements of another list to the end",
+    ),
+    ("clear()", "Remove all elements"),
+    (
+        "get(index, default)",
+        "Get an element, or the default if the index is out of bounds",
+    ),
+    ("count(value)", "Count the elements equal to the value"),
+    ("min()", "Get the smallest element"),
+    ("max()", "Get the largest element"),
+    ("sum()", "Add up all elements (0 for an empty list)"),
+    (
+        "pop(index)",
+        "Remove and return the element at the index (the last element if no index is given)",
+    ),
+    ("insert(index, value)", "Insert a value before the index"),
+    (
+        "remove(value)",
+        "Remove the first element that is equal to the value",
+    ),
+    (
+        "index(value)",
+        "Get the position of the first element that is equal to the value",
+    ),
+    ("sort()", "Sort numbers or strings in ascending order"),
+    ("reverse()", "Reverse the order of the elements"),
+];
+
+/// Builtin functions of maps
+pub const MAP_BUILTINS: &[(&str, &str)] = &[
+    ("len()", "Get the number of entries"),
+    ("keys()", "Get a list of all keys in sorted order"),
+    ("values()", "Get a list of all values, sorted by their key"),
+    (
+        "items()",
+        "Get a list of all entries as [key, value] pairs, sorted by key",
+    ),
+    (
+        "contains_key(key)",
+        "Check if there is an entry with the key",
+    ),
+    (
+        "get(key, default)",
+        "Get an entry, or the default if there is no such key",
+    ),
+    (
+        "update(other)",
+        "Insert all entries of another map (overwriting existing ones)",
+    ),
+    (
+        "pop(key, default)",
+        "Remove an entry and return it, or the default if there is no such key",
+    ),
+];
+
+/// Builtin functions of strings
+pub const STR_BUILTINS: &[(&str, &str)] = &[
+    ("encode()", "Convert into UTF-8 bytes"),
+    (
+        "split(separator)",
+        "Split into a list of strings (at whitespace if no separator is given)",
+    ),
+    (
+        "join(list)",
+        "Concatenate a list of strings, separated by this string",
+    ),
+    ("upper()", "Convert to upper case"),
+    ("lower()", "Convert to lower case"),
+    ("strip()", "Remove leading and trailing whitespace"),
+    (
+        "startswith(prefix)",
+        "Check if the string starts with a prefix",
+    ),
+    ("endswith(suffix)", "Check if the string ends with a suffix"),
+    (
+        "replace(old, new)",
+        "Replace all occurrences of a substring",
+    ),
+    (
+        "find(substring)",
+        "Get the position of the first occurrence of a substring (or -1)",
+    ),
+];
+
+/// Builtin functions of bytes
+pub const BYTES_BUILTINS: &[(&str, &str)] = &[(
+    "decode()",
+    "Convert UTF-8 bytes into a string (fails if they are not valid UTF-8)",
+)];
+
+/// Builtin functions of floats
+pub const FLOAT_BUILTINS: &[(&str, &str)] = &[
+    (
+        "trunc()",
+        "Convert into an integer by rounding towards zero",
+    ),
+    ("floor()", "Convert into an integer by rounding down"),
+    ("ceil()", "Convert into an integer by rounding up"),
+    (
+        "round()",
+        "Convert into the nearest integer (rounding away from zero at .5)",
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum BuiltinKind {
+    Function,
+    GlobalFunction,
+    ListMethod,
+    MapMethod,
+    StrMethod,
+    BytesMethod,
+    FloatMethod,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Builtin {
+    pub kind: BuiltinKind,
+    /// How to call the builtin, e.g., `max(a, b)`
+    pub signature: &'static str,
+    pub doc: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Operator {
+    pub symbol: &'static str,
+    pub doc: &'static str,
+}
+
+/// Describes the syntax of the language (see `describe`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Grammar {
+    pub keywords: Vec<&'static str>,
+    pub type_names: Vec<&'static str>,
+    pub operators: Vec<Operator>,
+    pub builtins: Vec<Builtin>,
+}
+
+impl Grammar {
+    /// All words that cannot be used as identifiers
+    pub fn reserved_words(&self) -> Vec<&'static str> {
+        let functions = self
+            .builtins
+            .iter()
+            .filter(|b| b.kind == BuiltinKind::Function)
+            .map(|b| builtin_name(b.signature));
+
+        self.keywords
+            .iter()
+            .chain(self.type_names.iter())
+            .copied()
+            .chain(functions)
+            .collect()
+    }
+}
+
+/// Get the keywords, operators, and builtins of this version of the language
+pub fn describe() -> Grammar {
+    let builtins = [
+        (BuiltinKind::Function, FUNCTIONS),
+        (BuiltinKind::GlobalFunction, GLOBAL_FUNCTIONS),
+        (BuiltinKind::ListMethod, LIST_BUILTINS),
+        (BuiltinKind::MapMethod, MAP_BUILTINS),
+        (BuiltinKind::StrMethod, STR_BUILTINS),
+        (BuiltinKind::BytesMethod, BYTES_BUILTINS),
+        (BuiltinKind::FloatMethod, FLOAT_BUILTINS),
+    ]
+    .into_iter()
+    .flat_map(|(kind, entries)| {
+        entries.iter().map(move |(signature, doc)| Builtin {
+            kind: kind.clone(),
+            signature,
+            doc,
+        })
+    })
+    .collect();
+
+    Grammar {
+        keywords: KEYWORDS.to_vec(),
+        type_names: TYPE_NAMES.to_vec(),
+        operators: OPERATORS
+            .iter()
+            .map(|(symbol, doc)| Operator { symbol, doc })
+            .collect(),
+        builtins,
+    }
+}
+
+/// Is there a global function with this name (see `GLOBAL_FUNCTIONS`)?
+pub(crate) fn is_global_function(name: &str) -> bool {
+    GLOBAL_FUNCTIONS
+        .iter()
+        .any(|(signature, _)| builtin_name(signature) == name)
+}
+
+/// The name of a builtin without its arguments
+fn builtin_name(signature: &'static str) -> &'static str {
+    match signature.find('(') {
+        Some(pos) => &signature[..pos],
+        None => signature,
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Callable, Module};
+use crate::ast::Expr;
+
+struct CacheEntry {
+    member: String,
+    module: Rc<dyn Module>,
+    callable: Rc<dyn Callable>,
+}
+
+/// Remembers which callable a member lookup resolved to
+///
+/// Entries are keyed by the address of the `GetMember` node in the program.
+/// The module and member name are checked on every lookup, so an address that
+/// is reused by another node never returns the wrong callable.
+#[derive(Default)]
+pub struct MemberCache {
+    entries: HashMap<*const Expr, CacheEntry>,
+}
+
+impl MemberCache {
+    /// Get the callable previously resolved at this site, if it is the same member of the same module
+    pub fn get(
+        &self,
+        site: &Expr,
+        module: &Rc<dyn Module>,
+        member: &str,
+    ) -> Option<Rc<dyn Callable>> {
+        let entry = self.entries.get(&(site as *const Expr))?;
+
+        if entry.member == member && Rc::ptr_eq(&entry.module, module) {
+            Some(entry.callable.clone())
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        site: &Expr,
+        module: Rc<dyn Module>,
+        member: &str,
+        callable: Rc<dyn Callable>,
+    ) {
+        let entry = CacheEntry {
+            member: member.to_string(),
+            module,
+            callable,
+        };
+
+        self.entries.insert(site as *const Expr, entry);
+    }
+}
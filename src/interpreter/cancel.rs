@@ -0,0 +1,33 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::error::{RuntimeError, RuntimeErrorKind};
+
+/// Stops a running program from another thread or task (see `Interpreter::set_cancel_token`)
+///
+/// The program is stopped before it executes its next statement, so a call
+/// to a module function that blocks is not interrupted. Async functions
+/// awaited by `cowlang::tokio::run_blocking` are interrupted, however.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    /// Make the program fail with `RuntimeErrorKind::Cancelled`
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn check(&self) -> Result<(), RuntimeError> {
+        if self.is_cancelled() {
+            Err(RuntimeErrorKind::Cancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Handle, MemberInfo, MemberKind, Module};
+use crate::values::Value;
+
+/// A named set of constants, e.g., `Color.RED` (see `Interpreter::register_constants`)
+///
+/// Every access returns a copy, so scripts cannot change the constants.
+pub(super) struct Constants {
+    values: HashMap<String, Value>,
+}
+
+impl Constants {
+    pub fn new(values: HashMap<String, Value>) -> Self {
+        Self { values }
+    }
+}
+
+impl Module for Constants {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match self.values.get(name) {
+            Some(value) => Handle::wrap_value(value.clone()),
+            // Using this results in an error (and linking catches it earlier)
+            None => Handle::None,
+        }
+    }
+
+    fn has_member(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        let mut names: Vec<&String> = self.values.keys().collect();
+        names.sort();
+
+        names
+            .into_iter()
+            .map(|name| MemberInfo {
+                name: name.clone(),
+                kind: MemberKind::Constant,
+                signature: None,
+                doc: None,
+            })
+            .collect()
+    }
+}
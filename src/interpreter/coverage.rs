@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use crate::ast::{Expr, ParseNode, Program, Span, Statements};
+use crate::source::NamedSource;
+
+/// Counts how often each statement of a program was executed
+///
+/// Counts accumulate over all runs of the interpreter that collected them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Coverage {
+    hits: HashMap<Span, usize>,
+}
+
+/// Per-line execution counts of a script
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageReport {
+    /// How often each line was executed (starting at the first line)
+    ///
+    /// `None` for lines that do not contain the start of a statement.
+    pub lines: Vec<Option<usize>>,
+}
+
+impl Coverage {
+    /// How often the statement at the given location was executed
+    pub fn hits(&self, span: Span) -> usize {
+        self.hits.get(&span).copied().unwrap_or(0)
+    }
+
+    pub(super) fn record(&mut self, span: Span) {
+        *self.hits.entry(span).or_insert(0) += 1;
+    }
+
+    /// Generate per-line hit counts for a program and the source code it was compiled from
+    pub fn report(&self, program: &Program, source: &NamedSource) -> CoverageReport {
+        let num_lines = source.source().lines().count();
+        let mut lines = vec![None; num_lines];
+
+        let mut statements = Vec::new();
+        collect_statements(&program.stmts, &mut statements);
+
+        for span in statements {
+            let (line, _) = source.line_col(span.lo);
+
+            if line > lines.len() {
+                lines.resize(line, None);
+            }
+
+            // Multiple statements on the same line count as executed once per run of that line
+            let entry = &mut lines[line - 1];
+            *entry = Some(entry.unwrap_or(0).max(self.hits(span)));
+        }
+
+        CoverageReport { lines }
+    }
+}
+
+impl CoverageReport {
+    /// The number of lines containing statements
+    pub fn num_coverable(&self) -> usize {
+        self.lines.iter().filter(|l| l.is_some()).count()
+    }
+
+    /// The number of lines containing statements that were executed at least once
+    pub fn num_covered(&self) -> usize {
+        self.lines
+            .iter()
+            .filter(|l| matches!(l, Some(n) if *n > 0))
+            .count()
+    }
+}
+
+fn collect_statements(stmts: &Statements, out: &mut Vec<Span>) {
+    for stmt in stmts {
+        collect_statement(stmt, out);
+    }
+}
+
+fn collect_statement(stmt: &ParseNode, out: &mut Vec<Span>) {
+    let (span, expr) = stmt;
+
+    out.push(*span);
+    collect_nested_statements(expr, out);
+}
+
+fn collect_nested_statements(expr: &Expr, out: &mut Vec<Span>) {
+    match expr {
+        Expr::ForIn { body, .. } | Expr::With { body, .. } | Expr::Function { body, .. } => {
+            collect_statements(body, out)
+        }
+        // The inner function has the same span as the annotated one
+        Expr::AnnotatedFunction { function, .. } => collect_nested_statements(&function.1, out),
+        Expr::Try { body, handler, .. } => {
+            collect_statements(body, out);
+            collect_statements(handler, out);
+        }
+        Expr::IfElse {
+            body, else_branch, ..
+        } => {
+            collect_statements(body, out);
+
+            if let Some(branch) = else_branch {
+                collect_statements(branch, out);
+            }
+        }
+        Expr::IfElseRecursive {
+            body, else_branch, ..
+        } => {
+            collect_statements(body, out);
+
+            // The `else if` itself is not a statement
+            collect_nested_statements(&else_branch.1, out);
+        }
+        _ => {}
+    }
+}
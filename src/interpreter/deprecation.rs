@@ -0,0 +1,9 @@
+/// A deprecated module member that was used by a program (see `Interpreter::deprecations`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deprecation {
+    /// The name the module was registered with (if known)
+    pub module: Option<String>,
+    pub member: String,
+    /// Explains what to use instead
+    pub message: String,
+}
@@ -0,0 +1,95 @@
+use std::rc::Rc;
+
+use super::scopes::{Context, Scope, Scopes};
+use super::{ControlFlow, Handle, Interpreter};
+use crate::ast::Statements;
+use crate::error::{RuntimeError, RuntimeErrorKind};
+
+/// A function defined by a program (using `def`)
+///
+/// Functions capture the variables and modules visible where they were
+/// defined. Captured values are shared, not copied, so changes made to
+/// their content are visible to the function.
+pub struct ScriptFunction {
+    name: String,
+    params: Vec<String>,
+    body: Statements,
+    captured: Scope,
+    /// Used when the function is called from outside of the program (e.g., by a module)
+    context: Context,
+}
+
+impl ScriptFunction {
+    pub(super) fn new(
+        name: String,
+        params: Vec<String>,
+        body: Statements,
+        captured: Scope,
+        context: Context,
+    ) -> Self {
+        Self {
+            name,
+            params,
+            body,
+            captured,
+            context,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The names of the function's parameters
+    pub fn params(&self) -> &[String] {
+        &self.params
+    }
+
+    /// Create the scope the body runs in
+    fn frame(self: &Rc<Self>, args: Vec<Handle>) -> Result<Scope, RuntimeError> {
+        if args.len() != self.params.len() {
+            return Err(RuntimeErrorKind::InvalidArgument(format!(
+                "{}() expects {} argument(s), but got {}",
+                self.name,
+                self.params.len(),
+                args.len()
+            ))
+            .into());
+        }
+
+        let mut frame = self.captured.try_clone();
+
+        // Allow recursion
+        frame.define(self.name.clone(), Handle::Function(self.clone()));
+
+        for (param, arg) in self.params.iter().zip(args) {
+            frame.define(param.clone(), arg);
+        }
+
+        Ok(frame)
+    }
+
+    /// Call the function from the program that is currently running
+    pub(super) fn call_in(
+        self: &Rc<Self>,
+        scopes: &mut Scopes,
+        args: Vec<Handle>,
+    ) -> Result<Handle, RuntimeError> {
+        let frame = self.frame(args)?;
+
+        let caller = scopes.enter_function(frame)?;
+        let result = Interpreter::step_block(scopes, &self.body);
+        scopes.leave_function(caller);
+
+        match result? {
+            (ControlFlow::Return, result) => Ok(result),
+            (ControlFlow::Continue, _) => Ok(Handle::None),
+        }
+    }
+
+    /// Call the function from outside of the program (see `Handle::call`)
+    pub(super) fn call(self: &Rc<Self>, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let mut scopes = Scopes::from_context(&self.context);
+        self.call_in(&mut scopes, args)
+    }
+}
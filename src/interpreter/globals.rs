@@ -0,0 +1,303 @@
+use std::rc::Rc;
+use std::thread;
+use std::time::Duration;
+
+use super::{help, BoundCallable, Callable, EnumerateIterable, Handle, ZipIterable};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::format;
+use crate::values::Value;
+
+/// Receives the output of `print()` (see `Interpreter::set_stdout`)
+pub trait StdoutSink {
+    /// Called once for every call to `print()` (the line has no trailing newline)
+    fn print(&self, line: &str);
+}
+
+/// Writes to the standard output of the process (used by default)
+#[derive(Debug, Default)]
+pub struct Stdout {}
+
+impl StdoutSink for Stdout {
+    fn print(&self, line: &str) {
+        println!("{line}");
+    }
+}
+
+/// A function that can be used without importing a module (see `grammar::GLOBAL_FUNCTIONS`)
+enum GlobalFunction {
+    Len,
+    Print(Rc<dyn StdoutSink>),
+    Type,
+    Abs,
+    Assert,
+    Bind,
+    DivMod,
+    Format,
+    Help,
+    Sum,
+    Map,
+    Filter,
+    Reduce,
+    Enumerate,
+    Zip,
+    /// Blocks for at most the given duration (see `Interpreter::set_max_sleep`),
+    /// unless the program was started by `cowlang::tokio::run_blocking`
+    Sleep(Duration),
+}
+
+/// Get the global function with the given name (if any)
+pub(super) fn get_global(
+    name: &str,
+    stdout: &Rc<dyn StdoutSink>,
+    max_sleep: Duration,
+) -> Option<Handle> {
+    let function = match name {
+        "len" => GlobalFunction::Len,
+        "print" => GlobalFunction::Print(stdout.clone()),
+        "type" => GlobalFunction::Type,
+        "abs" => GlobalFunction::Abs,
+        "assert" => GlobalFunction::Assert,
+        "bind" => GlobalFunction::Bind,
+        "divmod" => GlobalFunction::DivMod,
+        "format" => GlobalFunction::Format,
+        "help" => GlobalFunction::Help,
+        "sum" => GlobalFunction::Sum,
+        "map" => GlobalFunction::Map,
+        "filter" => GlobalFunction::Filter,
+        "reduce" => GlobalFunction::Reduce,
+        "enumerate" => GlobalFunction::Enumerate,
+        "zip" => GlobalFunction::Zip,
+        "sleep" => GlobalFunction::Sleep(max_sleep),
+        _ => return None,
+    };
+
+    Some(Handle::Callable(Rc::new(function)))
+}
+
+impl GlobalFunction {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Len => "len",
+            Self::Print(_) => "print",
+            Self::Type => "type",
+            Self::Abs => "abs",
+            Self::Assert => "assert",
+            Self::Bind => "bind",
+            Self::DivMod => "divmod",
+            Self::Format => "format",
+            Self::Help => "help",
+            Self::Sum => "sum",
+            Self::Map => "map",
+            Self::Filter => "filter",
+            Self::Reduce => "reduce",
+            Self::Enumerate => "enumerate",
+            Self::Zip => "zip",
+            Self::Sleep(_) => "sleep",
+        }
+    }
+
+    fn single_argument(&self, args: Vec<Handle>) -> Result<Value, RuntimeError> {
+        let [arg] = self.arguments(args)?;
+        arg.into_value()
+    }
+
+    fn arguments<const N: usize>(&self, args: Vec<Handle>) -> Result<[Handle; N], RuntimeError> {
+        let count = args.len();
+
+        args.try_into().map_err(|_| {
+            RuntimeErrorKind::InvalidArgument(format!(
+                "{}() expects {N} argument(s), but got {count}",
+                self.name()
+            ))
+            .into()
+        })
+    }
+
+    /// Check that the function can be called and get the elements of the list
+    fn elements(function: &Handle, list: Handle) -> Result<Vec<Value>, RuntimeError> {
+        if !matches!(
+            function,
+            Handle::Callable(_) | Handle::Function(_) | Handle::BuiltinCallable(..)
+        ) {
+            return Err(RuntimeErrorKind::NotCallable.into());
+        }
+
+        Ok(list.into_value()?.into_vec()?)
+    }
+}
+
+impl Callable for GlobalFunction {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        let args = args.into_iter().map(Handle::wrap_value).collect();
+
+        match self.call_with_handles(args) {
+            Ok(result) => result,
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let result = match self {
+            Self::Print(stdout) => {
+                let args = Handle::into_values(args)?;
+                let mut parts = Vec::with_capacity(args.len());
+
+                for arg in args.iter() {
+                    parts.push(arg.to_canonical_string()?);
+                }
+
+                stdout.print(&parts.join(" "));
+                Value::None
+            }
+            Self::Len => self.single_argument(args)?.length()?.into(),
+            Self::Type => self.single_argument(args)?.type_name().into(),
+            Self::Abs => self.single_argument(args)?.abs()?,
+            Self::Assert => {
+                let mut args = Handle::into_values(args)?.into_iter();
+
+                let (Some(cond), message, None) = (args.next(), args.next(), args.next()) else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "assert() expects a condition and an optional message".to_string(),
+                    )
+                    .into());
+                };
+
+                if !cond.as_bool()? {
+                    let message = match message {
+                        Some(message) => message.to_canonical_string()?,
+                        None => "condition is false".to_string(),
+                    };
+
+                    return Err(RuntimeErrorKind::AssertionFailed(message).into());
+                }
+
+                Value::None
+            }
+            Self::Bind => {
+                let mut args = args.into_iter();
+
+                let callee = match args.next() {
+                    Some(
+                        callee @ (Handle::Callable(_)
+                        | Handle::Function(_)
+                        | Handle::BuiltinCallable(..)),
+                    ) => callee,
+                    Some(_) => return Err(RuntimeErrorKind::NotCallable.into()),
+                    None => {
+                        return Err(RuntimeErrorKind::InvalidArgument(
+                            "bind() expects a function".to_string(),
+                        )
+                        .into());
+                    }
+                };
+
+                let args: Vec<Handle> = args.collect();
+
+                if args.iter().any(|arg| matches!(arg, Handle::Iter(_))) {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "cannot bind an iterator".to_string(),
+                    )
+                    .into());
+                }
+
+                return Ok(Handle::Callable(Rc::new(BoundCallable { callee, args })));
+            }
+            Self::DivMod => {
+                let [lhs, rhs] = self.arguments(args)?;
+                let (quotient, remainder) = lhs.into_value()?.divmod(&rhs.into_value()?)?;
+
+                vec![quotient, remainder].into()
+            }
+            Self::Format => {
+                let mut args = Handle::into_values(args)?.into_iter();
+
+                let Some(format) = args.next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "format() expects a format string".to_string(),
+                    )
+                    .into());
+                };
+
+                let format: String = format.try_into()?;
+                format::format_values(&format, args.collect())?.into()
+            }
+            Self::Help => {
+                let [object] = self.arguments(args)?;
+                help::describe(&object).into()
+            }
+            Self::Sum => self.single_argument(args)?.list_sum()?,
+            Self::Map => {
+                let [function, list] = self.arguments(args)?;
+                let mut result = vec![];
+
+                for elem in Self::elements(&function, list)? {
+                    let mapped = function.call(vec![Handle::wrap_value(elem)])?;
+                    result.push(mapped.into_value()?);
+                }
+
+                Value::List(result)
+            }
+            Self::Filter => {
+                let [function, list] = self.arguments(args)?;
+                let mut result = vec![];
+
+                for elem in Self::elements(&function, list)? {
+                    let keep = function.call(vec![Handle::wrap_value(elem.clone())])?;
+                    if keep.into_value()?.as_bool()? {
+                        result.push(elem);
+                    }
+                }
+
+                Value::List(result)
+            }
+            Self::Reduce => {
+                let [function, list, init] = self.arguments(args)?;
+                let mut result = init;
+
+                for elem in Self::elements(&function, list)? {
+                    result = function.call(vec![result, Handle::wrap_value(elem)])?;
+                }
+
+                result.into_value()?
+            }
+            Self::Enumerate => {
+                let [iterable] = self.arguments(args)?;
+
+                return Ok(Handle::Iter(Box::new(EnumerateIterable {
+                    inner: iterable.into_iterable()?,
+                    index: 0,
+                })));
+            }
+            Self::Zip => {
+                let [lhs, rhs] = self.arguments(args)?;
+
+                return Ok(Handle::Iter(Box::new(ZipIterable {
+                    lhs: lhs.into_iterable()?,
+                    rhs: rhs.into_iterable()?,
+                })));
+            }
+            Self::Sleep(max_sleep) => {
+                let millis: i64 = self.single_argument(args)?.try_into()?;
+                let millis = u64::try_from(millis).map_err(|_| {
+                    RuntimeErrorKind::InvalidArgument(
+                        "cannot sleep for a negative time".to_string(),
+                    )
+                })?;
+
+                let duration = Duration::from_millis(millis);
+
+                // Programs run by `cowlang::tokio::run_blocking` wait on the runtime instead
+                #[cfg(feature = "async")]
+                if let Some(result) = crate::tokio::sleep(duration) {
+                    result?;
+                    return Ok(Handle::wrap_value(Value::None));
+                }
+
+                thread::sleep(duration.min(*max_sleep));
+                Value::None
+            }
+        };
+
+        Ok(Handle::wrap_value(result))
+    }
+}
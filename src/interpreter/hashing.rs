@@ -0,0 +1,37 @@
+use std::collections::hash_map::{DefaultHasher, RandomState};
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+
+/// A hash map whose iteration order only depends on the interpreter's seed
+pub type SeededHashMap<K, V> = HashMap<K, V, SeededState>;
+
+/// Builds hashers that are deterministic for a given seed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeededState {
+    seed: u64,
+}
+
+impl SeededState {
+    pub fn new(seed: u64) -> Self {
+        Self { seed }
+    }
+
+    /// Pick a new seed at random
+    pub fn random() -> Self {
+        Self::new(RandomState::new().build_hasher().finish())
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = DefaultHasher;
+
+    fn build_hasher(&self) -> DefaultHasher {
+        let mut hasher = DefaultHasher::new();
+        hasher.write_u64(self.seed);
+        hasher
+    }
+}
@@ -0,0 +1,79 @@
+use super::{Handle, MemberInfo};
+use crate::grammar::{BYTES_BUILTINS, FLOAT_BUILTINS, LIST_BUILTINS, MAP_BUILTINS, STR_BUILTINS};
+use crate::values::Value;
+
+/// Generate the text returned by `help(obj)`
+pub fn describe(hdl: &Handle) -> String {
+    match hdl {
+        Handle::None => "none".to_string(),
+        Handle::Object(module) => {
+            let members = module.members();
+
+            if members.is_empty() {
+                "module (no documentation available)".to_string()
+            } else {
+                let lines: Vec<String> = members.iter().map(describe_member).collect();
+                format!("module with members:\n{}", lines.join("\n"))
+            }
+        }
+        Handle::Value(value) => describe_value(&Handle::read_value(value)),
+        Handle::BuiltinCallable(_, name) => format!("builtin function '{}'", name),
+        Handle::Callable(_) => "function".to_string(),
+        Handle::Function(function) => {
+            format!(
+                "function {}({})",
+                function.name(),
+                function.params().join(", ")
+            )
+        }
+        Handle::Iter(_) => "iterator".to_string(),
+        Handle::Range(range) => format!(
+            "range({}, {}, {})",
+            range.start(),
+            range.end(),
+            range.step()
+        ),
+        Handle::Deprecated { inner, message } => {
+            format!("{} (deprecated: {})", describe(inner), message)
+        }
+        Handle::Error(err) => format!("error: {err}"),
+    }
+}
+
+fn describe_member(member: &MemberInfo) -> String {
+    let mut result = format!("  {}", member.signature.as_ref().unwrap_or(&member.name));
+
+    if let Some(doc) = &member.doc {
+        for line in doc.lines() {
+            result += &format!("\n      {}", line);
+        }
+    }
+
+    result
+}
+
+fn describe_value(value: &Value) -> String {
+    let builtins = match value.unfrozen() {
+        Value::List(_) => LIST_BUILTINS,
+        Value::Map(_) => MAP_BUILTINS,
+        Value::Str(_) => STR_BUILTINS,
+        Value::Bytes(_) => BYTES_BUILTINS,
+        Value::F32(_) | Value::F64(_) => FLOAT_BUILTINS,
+        _ => &[],
+    };
+
+    if builtins.is_empty() {
+        return format!("value of type {}", value.type_name());
+    }
+
+    let lines: Vec<String> = builtins
+        .iter()
+        .map(|(signature, doc)| format!("  {}\n      {}", signature, doc))
+        .collect();
+
+    format!(
+        "value of type {} with builtins:\n{}",
+        value.type_name(),
+        lines.join("\n")
+    )
+}
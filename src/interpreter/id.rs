@@ -0,0 +1,310 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::random::Rng;
+use super::{Callable, Handle, MemberInfo, MemberKind, Module};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::Value;
+
+/// Generates and parses unique identifiers (UUIDs and ULIDs)
+///
+/// By default, identifiers are random and ULIDs contain the current time.
+/// A module created with `IdModule::with_seed` is deterministic instead:
+/// it draws from a seeded generator, and its ULIDs use a counter
+/// (starting at zero) instead of the clock, so they still sort in the
+/// order they were created.
+///
+/// ```ignore
+/// let mut interpreter = Interpreter::with_seed(seed);
+/// let ids = IdModule::with_seed(interpreter.seed());
+/// interpreter.register_module("id".to_string(), Rc::new(ids));
+/// ```
+pub struct IdModule {
+    state: Rc<IdState>,
+}
+
+struct IdState {
+    rng: RefCell<Rng>,
+    /// The next ULID timestamp (only used in deterministic mode)
+    counter: Option<Cell<u64>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum IdFunction {
+    Uuid4,
+    Ulid,
+    IsUuid,
+    IsUlid,
+    ParseUuid,
+    UlidTimestamp,
+}
+
+const FUNCTIONS: &[(&str, IdFunction, &str, &str)] = &[
+    (
+        "uuid4",
+        IdFunction::Uuid4,
+        "uuid4()",
+        "Creates a random (version 4) UUID",
+    ),
+    (
+        "ulid",
+        IdFunction::Ulid,
+        "ulid()",
+        "Creates a ULID, which sorts by creation time",
+    ),
+    (
+        "is_uuid",
+        IdFunction::IsUuid,
+        "is_uuid(text)",
+        "Checks whether a string is a valid UUID (of any version)",
+    ),
+    (
+        "is_ulid",
+        IdFunction::IsUlid,
+        "is_ulid(text)",
+        "Checks whether a string is a valid ULID",
+    ),
+    (
+        "parse_uuid",
+        IdFunction::ParseUuid,
+        "parse_uuid(text)",
+        "Converts a UUID into its canonical (lowercase and hyphenated) form",
+    ),
+    (
+        "ulid_timestamp",
+        IdFunction::UlidTimestamp,
+        "ulid_timestamp(text)",
+        "Gets the time (in milliseconds since the epoch) a ULID was created",
+    ),
+];
+
+/// Crockford's base32 alphabet (used by ULIDs)
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+const ULID_LENGTH: usize = 26;
+
+impl Default for IdModule {
+    fn default() -> Self {
+        Self {
+            state: Rc::new(IdState {
+                rng: RefCell::new(Rng::random()),
+                counter: None,
+            }),
+        }
+    }
+}
+
+impl IdModule {
+    /// Create a deterministic module (e.g., using `Interpreter::seed`)
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            state: Rc::new(IdState {
+                rng: RefCell::new(Rng::new(seed)),
+                counter: Some(Cell::new(0)),
+            }),
+        }
+    }
+}
+
+impl Module for IdModule {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match FUNCTIONS.iter().find(|(n, ..)| *n == name) {
+            Some((_, function, ..)) => Handle::Callable(Rc::new(IdCallable {
+                function: *function,
+                state: self.state.clone(),
+            })),
+            None => Handle::Error(
+                RuntimeErrorKind::NoSuchMember {
+                    module: "id".to_string(),
+                    member: name.to_string(),
+                }
+                .into(),
+            ),
+        }
+    }
+
+    fn has_member(&self, name: &str) -> bool {
+        FUNCTIONS.iter().any(|(n, ..)| *n == name)
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        FUNCTIONS
+            .iter()
+            .map(|(name, _, signature, doc)| MemberInfo {
+                name: name.to_string(),
+                kind: MemberKind::Function,
+                signature: Some(signature.to_string()),
+                doc: Some(doc.to_string()),
+            })
+            .collect()
+    }
+}
+
+impl IdState {
+    fn uuid4(&self) -> String {
+        let mut bytes = [0u8; 16];
+        self.rng.borrow_mut().fill(&mut bytes);
+
+        // Set the version (4) and the variant (RFC 4122)
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+        format_uuid(&bytes)
+    }
+
+    fn ulid(&self) -> String {
+        let timestamp = match &self.counter {
+            Some(counter) => {
+                let timestamp = counter.get();
+                counter.set(timestamp + 1);
+                timestamp
+            }
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_millis() as u64)
+                .unwrap_or(0),
+        };
+
+        let mut random = [0u8; 10];
+        self.rng.borrow_mut().fill(&mut random);
+
+        // 48 bits of timestamp followed by 80 random bits
+        let mut value = random
+            .iter()
+            .fold(0u128, |value, byte| (value << 8) | *byte as u128);
+        value |= (timestamp as u128 & 0xffff_ffff_ffff) << 80;
+
+        (0..ULID_LENGTH)
+            .map(|pos| {
+                let shift = 5 * (ULID_LENGTH - 1 - pos);
+                ULID_ALPHABET[((value >> shift) & 0x1f) as usize] as char
+            })
+            .collect()
+    }
+}
+
+fn format_uuid(bytes: &[u8; 16]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Accepts UUIDs with or without hyphens and in upper or lower case
+fn parse_uuid(text: &str) -> Option<[u8; 16]> {
+    let digits: String = match text.len() {
+        32 => text.to_string(),
+        36 => {
+            let hyphens = text
+                .char_indices()
+                .filter(|(_, c)| *c == '-')
+                .map(|(pos, _)| pos);
+
+            if !hyphens.eq([8, 13, 18, 23]) {
+                return None;
+            }
+
+            text.replace('-', "")
+        }
+        _ => return None,
+    };
+
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut bytes = [0u8; 16];
+    for (pos, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&digits[2 * pos..2 * pos + 2], 16).ok()?;
+    }
+
+    Some(bytes)
+}
+
+/// Get the 128-bit value of a ULID (in upper or lower case)
+fn parse_ulid(text: &str) -> Option<u128> {
+    if text.len() != ULID_LENGTH {
+        return None;
+    }
+
+    let mut value: u128 = 0;
+
+    for (pos, c) in text.bytes().enumerate() {
+        let digit = ULID_ALPHABET
+            .iter()
+            .position(|d| *d == c.to_ascii_uppercase())? as u128;
+
+        // The first character only holds three bits
+        if pos == 0 && digit > 7 {
+            return None;
+        }
+
+        value = (value << 5) | digit;
+    }
+
+    Some(value)
+}
+
+fn invalid_argument(message: String) -> RuntimeError {
+    RuntimeErrorKind::InvalidArgument(message).into()
+}
+
+struct IdCallable {
+    function: IdFunction,
+    state: Rc<IdState>,
+}
+
+impl IdCallable {
+    fn run(&self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let result = match (self.function, args.as_slice()) {
+            (IdFunction::Uuid4, []) => self.state.uuid4().into(),
+            (IdFunction::Ulid, []) => self.state.ulid().into(),
+            (IdFunction::IsUuid, [text]) => {
+                let text: String = text.clone().try_into()?;
+                parse_uuid(&text).is_some().into()
+            }
+            (IdFunction::IsUlid, [text]) => {
+                let text: String = text.clone().try_into()?;
+                parse_ulid(&text).is_some().into()
+            }
+            (IdFunction::ParseUuid, [text]) => {
+                let text: String = text.clone().try_into()?;
+                match parse_uuid(&text) {
+                    Some(bytes) => format_uuid(&bytes).into(),
+                    None => return Err(invalid_argument(format!("not a valid UUID: {text}"))),
+                }
+            }
+            (IdFunction::UlidTimestamp, [text]) => {
+                let text: String = text.clone().try_into()?;
+                match parse_ulid(&text) {
+                    Some(value) => ((value >> 80) as u64).into(),
+                    None => return Err(invalid_argument(format!("not a valid ULID: {text}"))),
+                }
+            }
+            _ => return Err(invalid_argument("wrong number of arguments".to_string())),
+        };
+
+        Ok(result)
+    }
+}
+
+impl Callable for IdCallable {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        match self.run(args) {
+            Ok(result) => Handle::wrap_value(result),
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let args = Handle::into_values(args)?;
+        Ok(Handle::wrap_value(self.run(args)?))
+    }
+}
@@ -0,0 +1,37 @@
+use std::rc::{Rc, Weak};
+
+use super::Module;
+use crate::error::{RuntimeError, RuntimeErrorKind};
+
+/// Finds modules that are kept alive by references created while running a program
+///
+/// Such references are usually caused by reference cycles, e.g., a module storing
+/// a callable that holds a strong reference to the module itself.
+#[derive(Default)]
+pub(super) struct LeakDetector {
+    /// The modules of the last run and how often they were referenced outside the interpreter
+    modules: Vec<(String, Weak<dyn Module>, usize)>,
+}
+
+impl LeakDetector {
+    pub fn begin_run(&mut self, modules: &[(String, Rc<dyn Module>)]) {
+        self.modules = modules
+            .iter()
+            .map(|(name, module)| {
+                // Do not count the reference held by the interpreter
+                let external = Rc::strong_count(module) - 1;
+                (name.clone(), Rc::downgrade(module), external)
+            })
+            .collect();
+    }
+
+    pub fn check(&self) -> Result<(), RuntimeError> {
+        for (name, module, external) in &self.modules {
+            if module.strong_count() > *external {
+                return Err(RuntimeErrorKind::ModuleLeaked(name.clone()).into());
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,33 @@
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::Value;
+
+/// Bounds for values that leave the interpreter (see `Interpreter::set_output_limits`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutputLimits {
+    /// How deeply lists and maps can be nested (see `Value::depth`)
+    pub max_depth: Option<usize>,
+    /// The largest allowed size (see `Value::size`)
+    pub max_size: Option<usize>,
+}
+
+impl OutputLimits {
+    pub(super) fn check(&self, value: &Value) -> Result<(), RuntimeError> {
+        if let Some(max_depth) = self.max_depth {
+            let depth = value.depth();
+
+            if depth > max_depth {
+                return Err(RuntimeErrorKind::ValueTooDeep { depth, max_depth }.into());
+            }
+        }
+
+        if let Some(max_size) = self.max_size {
+            let size = value.size();
+
+            if size > max_size {
+                return Err(RuntimeErrorKind::ValueTooLarge { size, max_size }.into());
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+
+use super::Interpreter;
+use crate::ast::{Expr, ParseNode, Program};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::grammar;
+
+impl Program {
+    /// Resolve references to the interpreter's modules ahead of time
+    ///
+    /// Unknown modules, module members, and variables are reported
+    /// here instead of when the program runs.
+    ///
+    /// *Note:* The linked program must be run by an interpreter that has the
+    /// same modules registered in the same order.
+    pub fn link(&mut self, interpreter: &Interpreter) -> Result<(), RuntimeError> {
+        // Names declared by the program itself might shadow modules
+        let mut declared = HashSet::new();
+        for stmt in self.stmts.iter_mut() {
+            collect_declarations(stmt, &mut declared);
+        }
+
+        let linker = Linker {
+            interpreter,
+            declared,
+        };
+
+        for stmt in self.stmts.iter_mut() {
+            linker.link(stmt)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct Linker<'a> {
+    interpreter: &'a Interpreter,
+    declared: HashSet<String>,
+}
+
+impl Linker<'_> {
+    fn link(&self, node: &mut ParseNode) -> Result<(), RuntimeError> {
+        stacker::maybe_grow(super::STACK_RED_ZONE, super::STACK_GROWTH, || {
+            self.link_node(node)
+        })
+    }
+
+    fn link_node(&self, node: &mut ParseNode) -> Result<(), RuntimeError> {
+        let (span, expr) = node;
+
+        for child in expr.children_mut() {
+            self.link(child)?;
+        }
+
+        match expr {
+            Expr::Var(name) if !self.declared.contains(name.as_str()) => {
+                if let Some(index) = self.interpreter.modules.iter().position(|(n, _)| n == name) {
+                    *expr = Expr::LinkedModule(index);
+                } else if !self.interpreter.variables.contains_key(name.as_str())
+                    && !grammar::is_global_function(name)
+                {
+                    let err = RuntimeErrorKind::NoSuchVariable(name.clone());
+                    return Err(RuntimeError::from(err).with_span(*span));
+                }
+            }
+            Expr::GetMember(inner, member) => {
+                if let (_, Expr::LinkedModule(index)) = inner.as_ref() {
+                    let (name, module) = &self.interpreter.modules[*index];
+
+                    if !module.has_member(member) {
+                        let err = RuntimeErrorKind::NoSuchMember {
+                            module: name.clone(),
+                            member: member.clone(),
+                        };
+                        return Err(RuntimeError::from(err).with_span(*span));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+fn collect_declarations(node: &mut ParseNode, declared: &mut HashSet<String>) {
+    let (_, expr) = node;
+
+    match expr {
+        Expr::AssignNew(name, _) | Expr::AssignNewAnnotated { name, .. } => {
+            declared.insert(name.clone());
+        }
+        Expr::AssignNewMultiple { targets, .. } => {
+            declared.extend(targets.iter().cloned());
+        }
+        Expr::ForIn { target_name, .. } | Expr::With { target_name, .. } => {
+            declared.insert(target_name.clone());
+        }
+        Expr::Try {
+            error_name: Some(name),
+            ..
+        } => {
+            declared.insert(name.clone());
+        }
+        Expr::Function { name, params, .. } => {
+            declared.insert(name.clone());
+            declared.extend(params.iter().cloned());
+        }
+        _ => {}
+    }
+
+    for child in expr.children_mut() {
+        stacker::maybe_grow(super::STACK_RED_ZONE, super::STACK_GROWTH, || {
+            collect_declarations(child, declared)
+        });
+    }
+}
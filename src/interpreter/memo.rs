@@ -0,0 +1,71 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Callable, Handle};
+use crate::error::RuntimeError;
+use crate::values::Value;
+
+/// Results of pure module functions, keyed by module, member, and the encoded arguments
+pub(super) type MemoTable = Rc<RefCell<HashMap<(usize, String, Vec<u8>), Value>>>;
+
+/// A pure module function whose results are reused (see `Interpreter::enable_memoization`)
+pub(super) struct MemoizedCallable {
+    /// Identifies the module the function belongs to
+    pub module: usize,
+    pub member: String,
+    pub inner: Rc<dyn Callable>,
+    pub table: MemoTable,
+}
+
+impl MemoizedCallable {
+    /// Only calls with plain values as arguments can be memoized
+    fn key(&self, args: &[Handle]) -> Option<(usize, String, Vec<u8>)> {
+        let mut values = Vec::with_capacity(args.len());
+
+        for arg in args {
+            match arg {
+                Handle::Value(value) => values.push(Handle::read_value(value)),
+                _ => return None,
+            }
+        }
+
+        let encoded = bincode::serialize(&values).ok()?;
+        Some((self.module, self.member.clone(), encoded))
+    }
+}
+
+impl Callable for MemoizedCallable {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        let args = args.into_iter().map(Handle::wrap_value).collect();
+
+        match self.call_with_handles(args) {
+            Ok(result) => result,
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let Some(key) = self.key(&args) else {
+            return self.inner.call_with_handles(args);
+        };
+
+        if let Some(result) = self.table.borrow().get(&key) {
+            return Ok(Handle::wrap_value(result.clone()));
+        }
+
+        let result = self.inner.call_with_handles(args)?;
+
+        if let Handle::Value(value) = &result {
+            self.table
+                .borrow_mut()
+                .insert(key, Handle::read_value(value));
+        }
+
+        Ok(result)
+    }
+
+    fn is_pure(&self) -> bool {
+        true
+    }
+}
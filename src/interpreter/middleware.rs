@@ -0,0 +1,42 @@
+use std::rc::Rc;
+
+use super::{Callable, Handle};
+use crate::error::RuntimeError;
+use crate::values::Value;
+
+/// Describes a call to a module function (see `Interpreter::wrap_all_callables`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallInfo {
+    /// The name the module was registered with
+    ///
+    /// `None` for modules that were not registered, e.g., ones returned by other functions.
+    pub module: Option<String>,
+    pub member: String,
+}
+
+/// Runs around every call to a module function
+///
+/// Receives the arguments and the wrapped function, and decides if and how to call it.
+pub type Middleware = dyn Fn(&CallInfo, Vec<Handle>, &dyn Callable) -> Result<Handle, RuntimeError>;
+
+/// A module function with a middleware installed around it
+pub(super) struct WrappedCallable {
+    pub info: CallInfo,
+    pub inner: Rc<dyn Callable>,
+    pub middleware: Rc<Middleware>,
+}
+
+impl Callable for WrappedCallable {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        let args = args.into_iter().map(Handle::wrap_value).collect();
+
+        match self.call_with_handles(args) {
+            Ok(result) => result,
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        (self.middleware)(&self.info, args, self.inner.as_ref())
+    }
+}
@@ -1,28 +1,199 @@
-use crate::ast::{CompareType, Expr, ParseNode, Program, ValueType};
-use crate::values::{Value, ValueError};
+use crate::ast::{ArithmeticOp, CompareType, Expr, ParseNode, Program, ValueType};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::{ConversionMode, PrimitiveType, Rounding, Value, ValueError};
 
+use std::any::Any;
 use std::cell::Cell;
 use std::collections::{hash_map, HashMap};
 use std::convert::TryInto;
 use std::fmt::Debug;
 use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+mod cache;
+mod help;
+mod link;
+
+mod trace;
+pub use trace::{Trace, TraceEntry, TraceEvent};
+
+mod coverage;
+pub use coverage::{Coverage, CoverageReport};
+
+mod leaks;
+use leaks::LeakDetector;
+
+mod limits;
+pub use limits::OutputLimits;
+
+mod memo;
+use memo::{MemoTable, MemoizedCallable};
+
+mod middleware;
+use middleware::WrappedCallable;
+pub use middleware::{CallInfo, Middleware};
+
+mod hashing;
+use hashing::{SeededHashMap, SeededState};
+
+mod stats;
+pub use stats::Stats;
+
+mod globals;
+pub use globals::{Stdout, StdoutSink};
+
+mod deprecation;
+pub use deprecation::Deprecation;
+
+mod constants;
+use constants::Constants;
+
+mod function;
+pub use function::ScriptFunction;
+
+mod range;
+pub use range::Range;
+
+mod random;
+
+mod id;
+pub use id::IdModule;
+
+mod validate;
+pub use validate::ValidateModule;
+
+mod parallel;
+pub use parallel::{
+    Executor, Job, ParallelModule, SendFunction, ThreadExecutor, ThreadSafeFunction,
+};
+
+mod watch;
+use watch::Watcher;
+
+mod shared;
+pub use shared::SharedInterpreter;
+
+mod cancel;
+pub use cancel::CancelToken;
+
+#[cfg(feature = "ndarray")]
+mod tensor;
+#[cfg(feature = "ndarray")]
+pub use tensor::TensorModule;
 
 mod scopes;
 use scopes::Scopes;
 
 pub trait Module {
+    /// Look up a function or constant of this module
+    ///
+    /// Wrap the member with `Handle::deprecated` to warn hosts about
+    /// programs that still use it (see `Interpreter::deprecations`).
+    ///
+    /// *Note:* The interpreter caches callables returned by this function
+    /// for each call site, so the same member should always resolve to an
+    /// equivalent callable.
+    ///
+    /// Callables should only keep a `Weak` reference to the module (see `Rc::downgrade`).
+    /// Otherwise, storing them inside a module creates a reference cycle.
     fn get_member(&self, self_ptr: &Rc<dyn Module>, name: &str) -> Handle;
+
+    /// Does this module have a member with the given name?
+    ///
+    /// Used to detect unknown members before running a program.
+    /// The default implementation assumes every member exists.
+    fn has_member(&self, name: &str) -> bool {
+        let _ = name;
+        true
+    }
+
+    /// Describe the members of this module (e.g., for autocompletion)
+    ///
+    /// Modules can only be used in `with` blocks if they list a `close` function here.
+    /// The default implementation does not list any members.
+    fn members(&self) -> Vec<MemberInfo> {
+        vec![]
+    }
+}
+
+/// The kind of a module member
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemberKind {
+    Function,
+    Constant,
+}
+
+/// Describes a member of a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberInfo {
+    pub name: String,
+    pub kind: MemberKind,
+    /// How to call the member, e.g., `add_two(num)` (only set for functions)
+    pub signature: Option<String>,
+    pub doc: Option<String>,
 }
 
-#[derive(Default)]
 pub struct Interpreter {
-    modules: HashMap<String, Rc<dyn Module>>,
-    variables: HashMap<String, Handle>,
+    /// Modules in the order they were registered
+    modules: Vec<(String, Rc<dyn Module>)>,
+    variables: SeededHashMap<String, Handle>,
+    hash_state: SeededState,
+    tracing: bool,
+    trace: Option<Trace>,
+    coverage: Option<Coverage>,
+    leak_detector: Option<LeakDetector>,
+    middleware: Vec<Rc<Middleware>>,
+    watcher: Option<Watcher>,
+    forbid_non_finite_floats: bool,
+    cast_mode: ConversionMode,
+    cast_rounding: Rounding,
+    stats: Stats,
+    deprecations: Vec<Deprecation>,
+    memoization: bool,
+    output_limits: OutputLimits,
+    catch_panics: bool,
+    stdout: Rc<dyn StdoutSink>,
+    max_sleep: Duration,
+    max_call_depth: usize,
+    cancel_token: Option<CancelToken>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::with_hash_state(SeededState::random())
+    }
 }
 
 pub trait Callable {
+    /// Call the function (return `Handle::Error` if the call fails)
     fn call(&self, args: Vec<Value>) -> Handle;
+
+    /// Call with arguments that are not necessarily values (e.g., functions)
+    ///
+    /// Override this to implement higher-order functions. The default
+    /// implementation only accepts values and forwards them to `call`.
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let argv = Handle::into_values(args)?;
+        self.call(argv).into_result()
+    }
+
+    /// Does this function always return the same result for the same arguments
+    /// (and has no side effects)?
+    ///
+    /// Results of pure functions are reused if memoization is enabled
+    /// (see `Interpreter::enable_memoization`).
+    fn is_pure(&self) -> bool {
+        false
+    }
+
+    /// A version of this function that can be called from other threads
+    ///
+    /// Only such functions can be used with `ParallelModule` (see `ThreadSafeFunction`).
+    fn thread_safe(&self) -> Option<SendFunction> {
+        None
+    }
 }
 
 pub trait Iterable {
@@ -30,42 +201,87 @@ pub trait Iterable {
 }
 
 struct ListIterable {
-    list: Vec<Value>,
+    list: std::vec::IntoIter<Value>,
 }
 
 impl ListIterable {
     pub fn new(list: Vec<Value>) -> Self {
-        Self { list }
+        Self {
+            list: list.into_iter(),
+        }
     }
 }
 
 impl Iterable for ListIterable {
     fn next(&mut self) -> Option<Value> {
-        if self.list.is_empty() {
-            None
-        } else {
-            Some(self.list.remove(0))
-        }
+        self.list.next()
     }
 }
 
-struct RangeIterable {
-    end: i64,
-    step: i64,
-    pos: i64,
+/// Yields `[index, item]` pairs (see `enumerate()`)
+struct EnumerateIterable {
+    inner: Box<dyn Iterable>,
+    index: i64,
 }
 
-impl Iterable for RangeIterable {
+impl Iterable for EnumerateIterable {
     fn next(&mut self) -> Option<Value> {
-        let val = self.pos;
+        let item = self.inner.next()?;
+        let index = self.index;
 
-        if val < self.end {
-            self.pos = val + self.step;
-            Some(val.into())
-        } else {
-            None
+        self.index += 1;
+        Some(Value::List(vec![index.into(), item]))
+    }
+}
+
+/// Yields `[lhs_item, rhs_item]` pairs until one of the two ends (see `zip()`)
+struct ZipIterable {
+    lhs: Box<dyn Iterable>,
+    rhs: Box<dyn Iterable>,
+}
+
+impl Iterable for ZipIterable {
+    fn next(&mut self) -> Option<Value> {
+        let lhs = self.lhs.next()?;
+        let rhs = self.rhs.next()?;
+
+        Some(Value::List(vec![lhs, rhs]))
+    }
+}
+
+/// A callable with some of its arguments already set (see `bind`)
+struct BoundCallable {
+    callee: Handle,
+    args: Vec<Handle>,
+}
+
+impl Callable for BoundCallable {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        let args = args.into_iter().map(Handle::wrap_value).collect();
+
+        match self.call_with_handles(args) {
+            Ok(result) => result,
+            Err(err) => Handle::Error(err),
         }
     }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let mut argv: Vec<Handle> = self.args.iter().map(Handle::try_clone).collect();
+        argv.extend(args);
+
+        self.callee.call(argv)
+    }
+}
+
+/// Describe the cause of a panic caught by `panic::catch_unwind`
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        msg.to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown cause".to_string()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -73,43 +289,103 @@ enum ControlFlow {
     Continue,
     Return,
 }
-
 pub enum Handle {
     None,
     Value(Rc<Cell<Value>>),
     BuiltinCallable(Rc<Cell<Value>>, String),
     Object(Rc<dyn Module>),
-    Callable(Box<dyn Callable>),
+    Callable(Rc<dyn Callable>),
     Iter(Box<dyn Iterable>),
+    /// Created by `range()` (unlike iterators, ranges can be used more than once)
+    Range(Range),
+    /// A function defined by the program (see `ScriptFunction`)
+    Function(Rc<ScriptFunction>),
+    /// A module member that should no longer be used (see `Handle::deprecated`)
+    Deprecated {
+        inner: Box<Handle>,
+        message: String,
+    },
+    /// Returned by `Callable::call` if the call failed
+    Error(RuntimeError),
 }
 
 impl Handle {
+    /// Mark a module member as deprecated
+    ///
+    /// The member keeps working, but each use is reported to the host.
+    pub fn deprecated(inner: Handle, message: &str) -> Self {
+        Self::Deprecated {
+            inner: Box::new(inner),
+            message: message.to_string(),
+        }
+    }
+
     #[must_use]
     pub fn try_clone(&self) -> Self {
         match &self {
             Self::None => Self::None,
             Self::Value(inner) => Self::Value(inner.clone()),
             Self::Object(inner) => Self::Object(inner.clone()),
+            Self::Callable(inner) => Self::Callable(inner.clone()),
+            Self::Function(inner) => Self::Function(inner.clone()),
+            Self::Range(range) => Self::Range(*range),
             Self::BuiltinCallable(inner, name) => {
                 Self::BuiltinCallable(inner.clone(), name.clone())
             }
-            _ => {
-                panic!("Cannot clone this handle");
+            Self::Iter(_) => {
+                panic!("Cannot clone an iterator");
             }
+            Self::Deprecated { inner, message } => Self::Deprecated {
+                inner: Box::new(inner.try_clone()),
+                message: message.clone(),
+            },
+            Self::Error(err) => Self::Error(err.clone()),
+        }
+    }
+
+    /// Turn `Handle::Error` into an error
+    pub fn into_result(self) -> Result<Handle, RuntimeError> {
+        match self {
+            Handle::Error(err) => Err(err),
+            other => Ok(other),
         }
     }
 
     pub fn unwrap_value(self) -> Value {
-        if let Handle::Value(value) = self {
-            let mut val_cpy = Cell::new(Value::None);
+        match self.into_value() {
+            Ok(value) => value,
+            Err(_) => panic!("Handle is not a value!"),
+        }
+    }
+
+    /// Get a copy of the value this handle points to
+    ///
+    /// Ranges are turned into a list.
+    pub fn into_value(self) -> Result<Value, RuntimeError> {
+        match self {
+            Handle::Value(value) => Ok(Self::read_value(&value)),
+            Handle::Range(range) => Ok(range.to_value()),
+            Handle::Error(err) => Err(err),
+            _ => Err(RuntimeErrorKind::NotAValue.into()),
+        }
+    }
 
-            val_cpy.swap(&*value);
-            let result = val_cpy.get_mut().clone();
-            val_cpy.swap(&*value);
+    /// Get copies of the values the handles point to
+    pub fn into_values(handles: Vec<Handle>) -> Result<Vec<Value>, RuntimeError> {
+        handles.into_iter().map(Handle::into_value).collect()
+    }
 
-            result
-        } else {
-            panic!("Handle is not a value!");
+    /// Call the function this handle points to
+    ///
+    /// Modules can use this to invoke functions that were passed to them as arguments.
+    pub fn call(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        match self {
+            Handle::Callable(c) => c.call_with_handles(args)?.into_result(),
+            Handle::Function(f) => f.call(args),
+            Handle::BuiltinCallable(val, name) => {
+                Interpreter::call_builtin(val, name, Self::into_values(args)?)
+            }
+            _ => Err(RuntimeErrorKind::NotCallable.into()),
         }
     }
 
@@ -124,20 +400,310 @@ impl Handle {
     pub fn wrap_value(val: Value) -> Self {
         Handle::Value(Rc::new(Cell::new(val)))
     }
+
+    fn read_value(value: &Cell<Value>) -> Value {
+        let mut val_cpy = Cell::new(Value::None);
+
+        val_cpy.swap(value);
+        let result = val_cpy.get_mut().clone();
+        val_cpy.swap(value);
+
+        result
+    }
+
+    fn modify_value<F, R>(value: &Cell<Value>, func: F) -> R
+    where
+        F: FnOnce(&mut Value) -> R,
+    {
+        let mut val_cpy = Cell::new(Value::None);
+
+        val_cpy.swap(value);
+        let result = func(val_cpy.get_mut());
+        val_cpy.swap(value);
+
+        result
+    }
+
+    /// Convert this handle into something that can be iterated over
+    ///
+    /// Lists (and arrays) yield their elements, maps yield their keys (in sorted order), strings yield
+    /// their characters, and bytes yield u8 values.
+    /// Iterators (e.g., returned by modules) are passed through and ranges start from the beginning.
+    pub fn into_iterable(self) -> Result<Box<dyn Iterable>, RuntimeError> {
+        match self {
+            Handle::Iter(i) => Ok(i),
+            Handle::Range(range) => Ok(range.iter()),
+            Handle::Value(_) => {
+                let elems: Vec<Value> = match self.into_value()?.thaw() {
+                    Value::List(list) | Value::Tuple(list) | Value::Array(list) => list,
+                    Value::Map(map) => {
+                        let mut keys: Vec<String> = map.into_keys().collect();
+                        keys.sort();
+                        keys.into_iter().map(Value::from).collect()
+                    }
+                    Value::Str(s) => s.chars().map(|c| Value::from(c.to_string())).collect(),
+                    Value::Bytes(b) => b.into_vec().into_iter().map(Value::from).collect(),
+                    other => {
+                        return Err(RuntimeErrorKind::NotIterable(format!("{:?}", other)).into());
+                    }
+                };
+
+                Ok(Box::new(ListIterable::new(elems)))
+            }
+            Handle::Object(_) => Err(RuntimeErrorKind::NotIterable("a module".to_string()).into()),
+            _ => Err(RuntimeErrorKind::NotIterable("a callable".to_string()).into()),
+        }
+    }
 }
 
+type StepResult = Result<(ControlFlow, Handle), RuntimeError>;
+
+/// How long `sleep()` may block unless the host changes it (see `Interpreter::set_max_sleep`)
+const DEFAULT_MAX_SLEEP: Duration = Duration::from_secs(1);
+
+/// How deeply functions may call each other unless the host changes it
+/// (see `Interpreter::set_max_call_depth`)
+const DEFAULT_MAX_CALL_DEPTH: usize = 1000;
+
+/// Allocate more stack once less than this many bytes are left
+const STACK_RED_ZONE: usize = 64 * 1024;
+
+/// How many bytes of stack to allocate at a time
+const STACK_GROWTH: usize = 1024 * 1024;
+
 impl Interpreter {
+    /// Create an interpreter that behaves the same way on every run
+    ///
+    /// Internal hash maps derive their state from the seed. Use `seed()` to capture
+    /// the seed of an existing interpreter (e.g., to create an `IdModule` with it).
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_hash_state(SeededState::new(seed))
+    }
+
+    fn with_hash_state(hash_state: SeededState) -> Self {
+        Self {
+            modules: Vec::new(),
+            variables: SeededHashMap::with_hasher(hash_state),
+            hash_state,
+            tracing: false,
+            trace: None,
+            coverage: None,
+            leak_detector: None,
+            middleware: Vec::new(),
+            watcher: None,
+            forbid_non_finite_floats: false,
+            cast_mode: ConversionMode::default(),
+            cast_rounding: Rounding::default(),
+            stats: Stats::default(),
+            deprecations: Vec::new(),
+            memoization: false,
+            output_limits: OutputLimits::default(),
+            catch_panics: false,
+            stdout: Rc::new(Stdout::default()),
+            max_sleep: DEFAULT_MAX_SLEEP,
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            cancel_token: None,
+        }
+    }
+
+    /// The seed used by this interpreter
+    ///
+    /// Passing it to `with_seed` allows replaying a run.
+    pub fn seed(&self) -> u64 {
+        self.hash_state.seed()
+    }
+
+    /// Record all changes to variables when running a program
+    ///
+    /// Use `take_trace` afterwards to inspect them.
+    pub fn enable_tracing(&mut self) {
+        self.tracing = true;
+    }
+
+    /// Raise an error whenever a computation results in NaN or infinity
+    ///
+    /// Useful when the results of a program must be the same on every machine,
+    /// e.g., for replicated execution. Only the results of expressions are checked,
+    /// not values nested inside of lists or maps.
+    pub fn forbid_non_finite_floats(&mut self) {
+        self.forbid_non_finite_floats = true;
+    }
+
+    /// Choose what `as` does with integers that do not fit into the target type
+    ///
+    /// By default, such casts fail with an error.
+    pub fn set_cast_mode(&mut self, mode: ConversionMode) {
+        self.cast_mode = mode;
+    }
+
+    /// Choose how `as` turns floats into integers
+    ///
+    /// By default, casting a float with a fractional part fails with an error.
+    pub fn set_cast_rounding(&mut self, rounding: Rounding) {
+        self.cast_rounding = rounding;
+    }
+
+    /// Call pure module functions only once per run for each set of arguments
+    ///
+    /// Only functions that declare themselves as pure (see `Callable::is_pure`)
+    /// and calls with plain values as arguments are affected.
+    pub fn enable_memoization(&mut self) {
+        self.memoization = true;
+    }
+
+    /// Restrict the size and nesting depth of values that leave the interpreter
+    ///
+    /// This applies to the result of a run and to arguments passed to module functions.
+    /// Values exceeding the limits result in a `ValueTooLarge` or `ValueTooDeep` error.
+    pub fn set_output_limits(&mut self, limits: OutputLimits) {
+        self.output_limits = limits;
+    }
+
+    /// Turn panics of module functions into errors instead of unwinding through the interpreter
+    ///
+    /// Panics still invoke the panic hook (which prints them by default), and the module
+    /// might be left in an inconsistent state. This is meant to keep a host alive when
+    /// a buggy module panics, not as a replacement for returning errors.
+    pub fn catch_module_panics(&mut self) {
+        self.catch_panics = true;
+    }
+
+    /// Send the output of `print()` somewhere other than the standard output
+    pub fn set_stdout(&mut self, stdout: Rc<dyn StdoutSink>) {
+        self.stdout = stdout;
+    }
+
+    /// The longest a single call to `sleep()` may block (one second by default)
+    ///
+    /// Longer sleeps are cut short, so that scripts cannot stall the host.
+    /// Use `Duration::ZERO` to turn `sleep()` into a no-op.
+    ///
+    /// Programs run by `cowlang::tokio::run_blocking` (with the `async` feature) sleep on the
+    /// runtime's timer and can be cancelled, so their sleeps are not limited.
+    pub fn set_max_sleep(&mut self, max_sleep: Duration) {
+        self.max_sleep = max_sleep;
+    }
+
+    /// How many calls of functions defined by the program may be active at once (1000 by default)
+    ///
+    /// Deeper calls fail with `RuntimeErrorKind::CallTooDeep`, so that endless
+    /// recursion does not exhaust the host's memory.
+    pub fn set_max_call_depth(&mut self, max_call_depth: usize) {
+        self.max_call_depth = max_call_depth;
+    }
+
+    /// Stop running programs once the token is cancelled
+    ///
+    /// This allows hosts to abort programs that run on a worker thread,
+    /// e.g., when an async request that started them is dropped.
+    pub fn set_cancel_token(&mut self, token: CancelToken) {
+        self.cancel_token = Some(token);
+    }
+
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel_token.as_ref()
+    }
+
+    /// Deprecated module members used by the most recent run
+    ///
+    /// Each member is only listed once, no matter how often it was used.
+    pub fn deprecations(&self) -> &[Deprecation] {
+        &self.deprecations
+    }
+
+    /// Counters of the most recent run (e.g., the number of executed statements)
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Count how often each statement is executed
+    ///
+    /// Counts accumulate over all following runs. Use `coverage` to get them.
+    pub fn enable_coverage(&mut self) {
+        if self.coverage.is_none() {
+            self.coverage = Some(Coverage::default());
+        }
+    }
+
+    /// The statements executed so far (if coverage was enabled)
+    pub fn coverage(&self) -> Option<&Coverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Install a middleware around every call to a module function
+    ///
+    /// The middleware receives the arguments and the function being called, and can,
+    /// for example, rewrite arguments, retry failed calls, or collect metrics.
+    /// Middleware installed later runs first.
+    pub fn wrap_all_callables<F>(&mut self, middleware: F)
+    where
+        F: Fn(&CallInfo, Vec<Handle>, &dyn Callable) -> Result<Handle, RuntimeError> + 'static,
+    {
+        self.middleware.push(Rc::new(middleware));
+    }
+
+    /// Call `callback` whenever a program changes one of the given variables
+    ///
+    /// The callback receives the name, the previous value (`None` if the variable
+    /// was just created), and the new value, e.g., to keep a user interface in sync
+    /// with the state of a script. Writes that do not change the value are not
+    /// reported, and neither are the initial values of variables set by `set_value`.
+    /// Variables are matched by name only, so local variables of functions
+    /// with the same name are watched as well.
+    ///
+    /// Calling this again replaces the previous callback and variables.
+    pub fn watch_variables<F>(&mut self, names: &[&str], callback: F)
+    where
+        F: Fn(&str, Option<&Value>, &Value) + 'static,
+    {
+        self.watcher = Some(Watcher::new(names, Rc::new(callback)));
+    }
+
+    /// Keep track of the modules used by each run (for debugging)
+    ///
+    /// Use `leak_check` after a run to find out whether it leaked any of them.
+    pub fn enable_leak_detection(&mut self) {
+        self.leak_detector = Some(LeakDetector::default());
+    }
+
+    /// Make sure the last run did not create any new references to its modules
+    ///
+    /// A module that is still referenced by something created during the run,
+    /// e.g., a callable stored in another module, might never be freed if
+    /// the references form a cycle. Always succeeds if leak detection is not enabled.
+    pub fn leak_check(&self) -> Result<(), RuntimeError> {
+        match &self.leak_detector {
+            Some(detector) => detector.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the trace of the last run (if tracing was enabled)
+    ///
+    /// The trace is available even if the program failed.
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
     pub fn register_module(&mut self, name: String, module: Rc<dyn Module>) {
         if name.is_empty() {
             //TODO check for other invalid identifiers (e.g. one containing spaces)
             panic!("Cannot register module with invalid name: {}", name);
         }
 
-        let result = self.modules.insert(name, module);
-
-        if result.is_some() {
+        if self.modules.iter().any(|(other, _)| *other == name) {
             panic!("Module with the same name already existed");
         }
+
+        self.modules.push((name, module));
+    }
+
+    /// Expose a set of constants to scripts, e.g., `Color.RED`
+    ///
+    /// This behaves like a module whose members are the given values,
+    /// so scripts can compare against them using `==`.
+    pub fn register_constants(&mut self, name: &str, constants: HashMap<String, Value>) {
+        self.register_module(name.to_string(), Rc::new(Constants::new(constants)));
     }
 
     pub fn set_value(&mut self, name: String, value: Value) {
@@ -145,28 +711,223 @@ impl Interpreter {
         self.variables.insert(name, hdl);
     }
 
+    /// Run the program and return its result
+    ///
+    /// This will panic if the program fails. Use `try_run` to handle errors instead.
     pub fn run(&mut self, program: &Program) -> Value {
-        let mut result = Value::None;
+        match self.try_run(program) {
+            Ok(result) => result,
+            Err(err) => panic!("{}", err),
+        }
+    }
 
+    /// Run the program and return its result or the first error that occurred
+    ///
+    /// Errors are tagged with the program's name, if it has one.
+    pub fn try_run(&mut self, program: &Program) -> Result<Value, RuntimeError> {
         let modules = mem::take(&mut self.modules);
-        let variables = mem::take(&mut self.variables);
+        let variables = mem::replace(
+            &mut self.variables,
+            SeededHashMap::with_hasher(self.hash_state),
+        );
+
+        let result = self.run_statements(program, modules, variables);
+        Self::tag_error(program, result)
+    }
+
+    /// Run the program without consuming or changing the interpreter's variables
+    ///
+    /// The program works on copies of all variables, with `inputs` taking
+    /// precedence over variables of the same name. This allows reusing an
+    /// interpreter (and its modules) for independent runs. Note that modules
+    /// are shared between runs, so any state they keep persists.
+    pub fn run_isolated(
+        &mut self,
+        program: &Program,
+        inputs: HashMap<String, Value>,
+    ) -> Result<Value, RuntimeError> {
+        let mut variables = SeededHashMap::with_hasher(self.hash_state);
+
+        for (name, hdl) in self.variables.iter() {
+            let copy = match hdl {
+                Handle::Value(value) => Handle::wrap_value(Handle::read_value(value)),
+                other => other.try_clone(),
+            };
+
+            variables.insert(name.clone(), copy);
+        }
+
+        for (name, value) in inputs {
+            variables.insert(name, Handle::wrap_value(value));
+        }
+
+        let result = self.run_statements(program, self.modules.clone(), variables);
+        Self::tag_error(program, result)
+    }
+
+    /// Tag errors with the name of the program, if it has one
+    fn tag_error(
+        program: &Program,
+        result: Result<Value, RuntimeError>,
+    ) -> Result<Value, RuntimeError> {
+        match &program.name {
+            Some(name) => result.map_err(|err| err.with_source_name(name.as_str())),
+            None => result,
+        }
+    }
+
+    fn run_statements(
+        &mut self,
+        program: &Program,
+        modules: Vec<(String, Rc<dyn Module>)>,
+        variables: SeededHashMap<String, Handle>,
+    ) -> Result<Value, RuntimeError> {
+        if let Some(detector) = &mut self.leak_detector {
+            detector.begin_run(&modules);
+        }
+
+        let trace = self.tracing.then(Trace::default);
 
-        let mut root_scopes = Scopes::new(modules, variables);
+        let mut watcher = self.watcher.clone();
+        if let Some(watcher) = &mut watcher {
+            watcher.begin_run(&variables);
+        }
+
+        let mut root_scopes = Scopes::new(modules, variables, self.hash_state, trace);
+        root_scopes.forbid_non_finite_floats = self.forbid_non_finite_floats;
+        root_scopes.cast_mode = self.cast_mode;
+        root_scopes.cast_rounding = self.cast_rounding;
+        root_scopes.middleware = self.middleware.clone();
+        root_scopes.memo_table = self.memoization.then(MemoTable::default);
+        root_scopes.output_limits = self.output_limits;
+        root_scopes.catch_panics = self.catch_panics;
+        root_scopes.stdout = self.stdout.clone();
+        root_scopes.max_sleep = self.max_sleep;
+        root_scopes.max_call_depth = self.max_call_depth;
+        root_scopes.cancel_token = self.cancel_token.clone();
+        root_scopes.watcher = watcher;
+
+        root_scopes.coverage = self.coverage.take();
+
+        let start = Instant::now();
+        let result = Self::run_root(&mut root_scopes, program);
+
+        self.stats = mem::take(&mut root_scopes.stats);
+        self.stats.wall_time = start.elapsed();
+        self.deprecations = mem::take(&mut root_scopes.deprecations);
+        self.trace = root_scopes.take_trace();
+        self.coverage = root_scopes.coverage.take();
+
+        result
+    }
 
+    fn run_root(root_scopes: &mut Scopes, program: &Program) -> Result<Value, RuntimeError> {
         for stmt in &program.stmts {
-            let (cflw, res) = Self::step(&mut root_scopes, stmt);
+            let (cflw, res) = Self::step_statement(root_scopes, stmt)?;
 
             if ControlFlow::Return == cflw {
-                result = res.unwrap_value();
-                break;
+                let result = res.into_value().map_err(|err| err.or_span(stmt.0))?;
+                root_scopes.output_limits.check(&result)?;
+
+                return Ok(result);
             }
         }
 
-        result
+        Ok(Value::None)
+    }
+
+    /// Evaluate the right-hand side of an assignment
+    ///
+    /// For chained assignments (`a = b = 0`) this is the value assigned to the last variable.
+    fn step_assigned_value(scopes: &mut Scopes, rhs: &ParseNode) -> Result<Handle, RuntimeError> {
+        match &rhs.1 {
+            Expr::Assign(var, _) => {
+                Self::step(scopes, rhs)?;
+                scopes.get(var)
+            }
+            _ => Ok(Self::step(scopes, rhs)?.1),
+        }
     }
 
-    fn step(scopes: &mut Scopes, stmt: &ParseNode) -> (ControlFlow, Handle) {
-        let (_span, expr) = stmt;
+    /// Assign to (or, if `declare` is set, create) several variables at once
+    fn assign_multiple(
+        scopes: &mut Scopes,
+        targets: &[String],
+        values: &[ParseNode],
+        declare: bool,
+    ) -> Result<(), RuntimeError> {
+        let mut handles = Vec::with_capacity(values.len());
+
+        for value in values {
+            handles.push(Self::step(scopes, value)?.1);
+        }
+
+        // Unpack a single list or tuple
+        if handles.len() == 1 && targets.len() > 1 {
+            let list = handles.remove(0).into_value()?.into_vec()?;
+            handles = list.into_iter().map(|v| scopes.wrap_value(v)).collect();
+        }
+
+        if handles.len() != targets.len() {
+            return Err(RuntimeErrorKind::AssignmentMismatch {
+                targets: targets.len(),
+                values: handles.len(),
+            }
+            .into());
+        }
+
+        for (target, handle) in targets.iter().zip(handles) {
+            if declare {
+                scopes.create_variable(target.clone(), handle)?;
+            } else {
+                scopes.update_variable(target, handle)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn step_statement(scopes: &mut Scopes, stmt: &ParseNode) -> StepResult {
+        scopes.begin_statement(stmt.0)?;
+        Self::step(scopes, stmt)
+    }
+
+    /// Execute a list of statements in a new scope
+    fn step_block(scopes: &mut Scopes, stmts: &[ParseNode]) -> StepResult {
+        scopes.push();
+
+        for stmt in stmts {
+            let (cflw, res) = Self::step_statement(scopes, stmt)?;
+
+            if cflw == ControlFlow::Return {
+                scopes.pop();
+                return Ok((cflw, res));
+            }
+        }
+
+        scopes.pop();
+        Ok((ControlFlow::Continue, Handle::None))
+    }
+
+    /// Evaluate an expression that must result in a value
+    fn step_value(scopes: &mut Scopes, stmt: &ParseNode) -> Result<Value, RuntimeError> {
+        let (span, expr) = stmt;
+
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+            Self::evaluate_value(scopes, expr).map_err(|err| err.or_span(*span))
+        })
+    }
+
+    fn step(scopes: &mut Scopes, stmt: &ParseNode) -> StepResult {
+        let (span, expr) = stmt;
+
+        // Deeply nested programs would overflow the stack otherwise
+        stacker::maybe_grow(STACK_RED_ZONE, STACK_GROWTH, || {
+            Self::evaluate(scopes, expr).map_err(|err| err.or_span(*span))
+        })
+    }
+
+    fn evaluate(scopes: &mut Scopes, expr: &Expr) -> StepResult {
         let mut control_flow = ControlFlow::Continue;
 
         let hdl = match expr {
@@ -175,73 +936,65 @@ impl Interpreter {
                 body,
                 else_branch,
             } => {
-                if Self::step(scopes, cond).1.unwrap_value().as_bool().unwrap() {
-                    scopes.push();
-
-                    for stmt in body {
-                        let (cflw, res) = Self::step(scopes, stmt);
-
-                        if cflw == ControlFlow::Return {
-                            return (cflw, res);
-                        }
-                    }
-
-                    scopes.pop();
+                if Self::step_value(scopes, cond)?.as_bool()? {
+                    return Self::step_block(scopes, body);
                 } else {
                     return Self::step(scopes, else_branch);
                 }
-                Handle::None
             }
             Expr::IfElse {
                 cond,
                 body,
                 else_branch,
             } => {
-                if Self::step(scopes, cond).1.unwrap_value().as_bool().unwrap() {
-                    scopes.push();
-
-                    for stmt in body {
-                        let (cflw, res) = Self::step(scopes, stmt);
-
-                        if cflw == ControlFlow::Return {
-                            scopes.pop();
-                            return (cflw, res);
-                        }
-                    }
-
-                    scopes.pop();
+                if Self::step_value(scopes, cond)?.as_bool()? {
+                    return Self::step_block(scopes, body);
                 } else if let Some(branch) = else_branch {
-                    scopes.push();
-
-                    for stmt in branch {
-                        let (cflw, res) = Self::step(scopes, stmt);
-
-                        if cflw == ControlFlow::Return {
-                            scopes.pop();
-                            return (cflw, res);
-                        }
-                    }
-
-                    scopes.pop();
+                    return Self::step_block(scopes, branch);
                 }
                 Handle::None
             }
+            Expr::OpAssign { op, lhs, rhs } => {
+                Self::op_assign(scopes, *op, lhs, rhs)?;
+                Handle::None
+            }
+            // Programs are migrated to OpAssign when loading, but might have been created manually
             Expr::AddEquals { lhs, rhs } => {
-                let var = scopes.get(lhs).unwrap_value();
-                let right = Self::step(scopes, rhs).1.unwrap_value();
-                let result = var.add(&right).unwrap();
+                let target = (rhs.0, Expr::Var(lhs.clone()));
+                Self::op_assign(scopes, ArithmeticOp::Add, &target, rhs)?;
+                Handle::None
+            }
+            Expr::SetElement {
+                container,
+                key,
+                value,
+            } => {
+                let value = Self::step_value(scopes, value)?;
+                let key = Self::step_value(scopes, key)?;
 
-                scopes.update_variable(lhs, Handle::wrap_value(result));
+                let (cell, path) = Self::step_place(scopes, container)?;
+
+                Self::ensure_not_temporary(&cell)?;
+                Handle::modify_value(&cell, |v| Self::follow_path(v, path)?.set_child(key, value))?;
+
+                if let Some(name) = Self::place_variable(container) {
+                    scopes.record_modification(name);
+                }
 
                 Handle::None
             }
-            Expr::AssignNew(var, rhs) => {
-                let val = Self::step(scopes, rhs).1;
+            Expr::AssignNew(var, rhs)
+            | Expr::AssignNewAnnotated {
+                name: var,
+                value: rhs,
+                ..
+            } => {
+                let val = Self::step_assigned_value(scopes, rhs)?;
 
                 #[cfg(feature = "verbose")]
                 println!("let {} = {:?}", var, val);
 
-                scopes.create_variable(var.clone(), val);
+                scopes.create_variable(var.clone(), val)?;
 
                 Handle::None
             }
@@ -250,40 +1003,20 @@ impl Interpreter {
                 target_name,
                 body,
             } => {
-                let hdl = Self::step(scopes, iter).1;
-
-                let mut iter: Box<dyn Iterable> = match hdl {
-                    Handle::Value(val) => {
-                        let mut val_cpy = Cell::new(Value::None);
-                        val_cpy.swap(&*val);
-
-                        let res = if let Value::List(list) = val_cpy.get_mut() {
-                            Box::new(ListIterable::new(list.clone()))
-                        } else {
-                            let mut val_cpy = Cell::new(Value::None);
-                            val_cpy.swap(&*val);
-                            panic!("Cannot iterate {:?}", val_cpy.get_mut());
-                        };
-
-                        val_cpy.swap(&*val);
-                        res
-                    }
-                    Handle::Iter(i) => i,
-                    _ => {
-                        panic!("Cannot iterate!");
-                    }
-                };
+                let hdl = Self::step(scopes, iter)?.1;
+                let mut iter = hdl.into_iterable()?;
 
                 while let Some(val) = iter.next() {
                     scopes.push();
-                    scopes.create_variable(target_name.clone(), Handle::wrap_value(val));
+                    let hdl = scopes.wrap_value(val);
+                    scopes.create_variable(target_name.clone(), hdl)?;
 
                     for stmt in body {
-                        let (cflw, res) = Self::step(scopes, stmt);
+                        let (cflw, res) = Self::step_statement(scopes, stmt)?;
 
                         if cflw == ControlFlow::Return {
                             scopes.pop();
-                            return (cflw, res);
+                            return Ok((cflw, res));
                         }
                     }
                     scopes.pop();
@@ -291,124 +1024,324 @@ impl Interpreter {
 
                 Handle::None
             }
-            Expr::Var(var) => scopes.get(var),
-            Expr::Brackets(inner) => Self::step(scopes, inner).1,
-            Expr::Add { lhs, rhs } => {
-                let left = Self::step(scopes, lhs).1.unwrap_value();
-                let right = Self::step(scopes, rhs).1.unwrap_value();
+            Expr::With {
+                resource,
+                target_name,
+                body,
+            } => {
+                let hdl = Self::step(scopes, resource)?.1;
+
+                let module = match &hdl {
+                    Handle::Object(module)
+                        if module.members().iter().any(|member| {
+                            member.name == "close" && member.kind == MemberKind::Function
+                        }) =>
+                    {
+                        module.clone()
+                    }
+                    _ => {
+                        return Err(RuntimeErrorKind::NotAResource(target_name.clone()).into());
+                    }
+                };
 
-                Handle::wrap_value(left.add(&right).unwrap())
-            }
-            Expr::Multiply { lhs, rhs } => {
-                let left = Self::step(scopes, lhs).1.unwrap_value();
-                let right = Self::step(scopes, rhs).1.unwrap_value();
+                scopes.push();
+                let result = scopes
+                    .create_variable(target_name.clone(), hdl)
+                    .and_then(|_| Self::step_block(scopes, body));
+                scopes.pop();
 
-                Handle::wrap_value(left.multiply(&right).unwrap())
+                // Always close the resource, but report errors of the body first
+                let closed = Self::get_module_member(scopes, expr, module, "close")
+                    .into_result()
+                    .and_then(|close| {
+                        scopes.stats.module_calls += 1;
+
+                        if scopes.catch_panics {
+                            Self::call_catching_panics(&close, Vec::new())
+                        } else {
+                            close.call(Vec::new())
+                        }
+                    });
+                let (cflw, res) = result?;
+                closed?;
+
+                if cflw == ControlFlow::Return {
+                    return Ok((cflw, res));
+                }
+
+                Handle::None
             }
-            Expr::Compare { ctype, lhs, rhs } => {
-                let left = Self::step(scopes, lhs).1.unwrap_value();
-                let right = Self::step(scopes, rhs).1.unwrap_value();
+            Expr::Try {
+                body,
+                error_name,
+                handler,
+            } => {
+                let depth = scopes.depth();
+
+                let result = match Self::step_block(scopes, body) {
+                    Err(err) if err.kind.is_catchable() => {
+                        // Close the scopes of the block that failed
+                        scopes.unwind(depth);
+                        scopes.push();
+
+                        let result = match error_name {
+                            Some(name) => {
+                                let error = scopes.wrap_value(err.to_value());
+                                scopes
+                                    .create_variable(name.clone(), error)
+                                    .and_then(|_| Self::step_block(scopes, handler))
+                            }
+                            None => Self::step_block(scopes, handler),
+                        };
 
-                let result = match ctype {
-                    CompareType::Greater => left.is_greater_than(&right).unwrap(),
-                    CompareType::Smaller => left.is_smaller_than(&right).unwrap(),
-                    CompareType::Equals => left.equals(&right).unwrap(),
+                        scopes.pop();
+                        result
+                    }
+                    other => other,
                 };
 
-                Handle::wrap_value(result.into())
+                let (cflw, res) = result?;
+
+                if cflw == ControlFlow::Return {
+                    return Ok((cflw, res));
+                }
+
+                Handle::None
             }
-            Expr::Not(rhs) => {
-                let right = Self::step(scopes, rhs).1.unwrap_value();
-                Handle::wrap_value(right.negate().unwrap())
+            Expr::Raise(value) => {
+                let message = Self::step_value(scopes, value)?.to_canonical_string()?;
+
+                return Err(RuntimeErrorKind::Raised(message).into());
+            }
+            Expr::Function { name, params, body } => {
+                let function = ScriptFunction::new(
+                    name.clone(),
+                    params.clone(),
+                    body.clone(),
+                    scopes.capture(),
+                    scopes.context(),
+                );
+
+                scopes.create_variable(name.clone(), Handle::Function(Rc::new(function)))?;
+                Handle::None
+            }
+            Expr::Var(var) => scopes.get(var)?,
+            Expr::LinkedModule(index) => Handle::Object(scopes.get_linked_module(*index)?),
+            Expr::Disallowed(access) => {
+                return Err(RuntimeErrorKind::NotAllowed(access.clone()).into());
             }
+            Expr::Brackets(inner) => Self::step(scopes, inner)?.1,
+            Expr::AnnotatedFunction { function, .. } => Self::step(scopes, function)?.1,
             Expr::Assign(var, rhs) => {
-                let val = Self::step(scopes, rhs).1;
+                let val = Self::step_assigned_value(scopes, rhs)?;
 
                 #[cfg(feature = "verbose")]
                 println!("{} = {:?}", var, val);
 
-                scopes.update_variable(var, val);
+                scopes.update_variable(var, val)?;
+                Handle::None
+            }
+            Expr::AssignMultiple { targets, values } => {
+                Self::assign_multiple(scopes, targets, values, false)?;
+                Handle::None
+            }
+            Expr::AssignNewMultiple { targets, values } => {
+                Self::assign_multiple(scopes, targets, values, true)?;
                 Handle::None
             }
             Expr::GetMember(rhs, name) => {
-                let res = Self::step(scopes, rhs).1;
+                let res = Self::step(scopes, rhs)?.1;
 
                 match res {
-                    Handle::Object(m) => m.get_member(&m, name),
+                    Handle::Object(m) => {
+                        Self::get_module_member(scopes, expr, m, name).into_result()?
+                    }
                     Handle::Value(val) => Handle::BuiltinCallable(val, name.clone()),
                     _ => {
-                        panic!("GetMember got unexpected Handle");
+                        return Err(RuntimeErrorKind::NotAValue.into());
                     }
                 }
             }
             Expr::Call(callee, args) => {
-                let res = Self::step(scopes, callee).1;
+                let res = Self::step(scopes, callee)?.1;
                 let mut argv = Vec::new();
 
                 for arg in args {
-                    if let Handle::Value(v) = Self::step(scopes, arg).1 {
-                        let mut val_cpy = Cell::new(Value::None);
-                        val_cpy.swap(&*v);
-
-                        argv.push(val_cpy.get_mut().clone());
-                        val_cpy.swap(&*v);
-                    } else {
-                        panic!("Argument is not a value!");
-                    }
+                    argv.push(Self::step(scopes, arg)?.1);
                 }
 
-                if let Handle::Callable(c) = res {
-                    c.call(argv)
-                } else if let Handle::BuiltinCallable(val, name) = res {
-                    if name == "len" {
-                        let mut val_cpy = Cell::new(Value::None);
-                        val_cpy.swap(&*val);
-                        let len = val_cpy.get_mut().num_children();
-                        val_cpy.swap(&*val);
-
-                        Handle::wrap_value(len.into())
-                    } else if name == "values" {
-                        let mut val_cpy = Cell::new(Value::None);
-                        val_cpy.swap(&*val);
-                        let mut map = val_cpy.get_mut().clone().into_map().unwrap();
-                        val_cpy.swap(&*val);
-
-                        let mut vals = Value::make_list();
-
-                        for (_, v) in map.drain() {
-                            vals.list_append(v).unwrap();
-                        }
+                if let Handle::Callable(_) = &res {
+                    let global =
+                        matches!(&callee.1, Expr::Var(name) if scopes.is_global_function(name));
+                    if !global {
+                        scopes.stats.module_calls += 1;
+                    }
 
-                        Handle::wrap_value(vals)
-                    } else if name == "append" {
-                        let arg = argv.drain(..).next().unwrap();
-                        let mut val_cpy = Cell::new(Value::None);
+                    for arg in argv.iter() {
+                        if let Handle::Value(value) = arg {
+                            let limits = scopes.output_limits;
+                            Handle::modify_value(value, |v| limits.check(v))?;
+                        }
+                    }
+                }
 
-                        val_cpy.swap(&*val);
-                        val_cpy.get_mut().list_append(arg).unwrap();
+                let result = if let Handle::Function(function) = &res {
+                    function.call_in(scopes, argv)?
+                } else if scopes.catch_panics && matches!(res, Handle::Callable(_)) {
+                    Self::call_catching_panics(&res, argv)?
+                } else {
+                    res.call(argv)?
+                };
 
-                        val_cpy.swap(&*val);
+                if let Handle::Value(value) = &result {
+                    Handle::modify_value(value, |v| Self::check_float(scopes, v))?;
+                }
 
-                        Handle::wrap_value(Value::None)
-                    } else {
-                        panic!("No such builtin: {}", name);
+                // Builtins like append() modify their variable in place
+                if let (Handle::BuiltinCallable(..), (_, Expr::GetMember(target, _))) =
+                    (&res, callee.as_ref())
+                {
+                    if let (_, Expr::Var(name)) = target.as_ref() {
+                        scopes.record_modification(name);
                     }
-                } else {
-                    panic!("Not a callable!");
                 }
+
+                result
+            }
+            Expr::Range { start, end, step } => {
+                let start: i64 = Self::step_value(scopes, start)?.try_into()?;
+                let end: i64 = Self::step_value(scopes, end)?.try_into()?;
+
+                let step: i64 = if let Some(s) = step {
+                    Self::step_value(scopes, s)?.try_into()?
+                } else {
+                    1
+                };
+
+                Handle::Range(Range::new(start, end, step)?)
+            }
+            Expr::Return(rhs) => {
+                control_flow = ControlFlow::Return;
+                Self::step(scopes, rhs)?.1
+            }
+            // Expressions that always result in a plain value
+            Expr::Add { .. }
+            | Expr::Multiply { .. }
+            | Expr::FloorDivide { .. }
+            | Expr::Divide { .. }
+            | Expr::Remainder { .. }
+            | Expr::Compare { .. }
+            | Expr::Not(_)
+            | Expr::Negative(_)
+            | Expr::And { .. }
+            | Expr::Or { .. }
+            | Expr::GetElement(..)
+            | Expr::Dictionary(_)
+            | Expr::String(_)
+            | Expr::Max { .. }
+            | Expr::Min { .. }
+            | Expr::ToStr(_)
+            | Expr::Cast { .. }
+            | Expr::List(_)
+            | Expr::Tuple(_)
+            | Expr::Array { .. }
+            | Expr::Bool(_)
+            | Expr::I64(_)
+            | Expr::U64(_)
+            | Expr::U8(_)
+            | Expr::Decimal(_) => {
+                let value = Self::evaluate_value(scopes, expr)?;
+                scopes.wrap_value(value)
+            }
+        };
+
+        Ok((control_flow, hdl))
+    }
+
+    /// Evaluate an expression that must result in a value
+    ///
+    /// Unlike `evaluate`, this does not wrap intermediate results in handles,
+    /// which avoids an allocation for every step of arithmetic-heavy code.
+    fn evaluate_value(scopes: &mut Scopes, expr: &Expr) -> Result<Value, RuntimeError> {
+        let value = match expr {
+            Expr::Var(var) => scopes.get(var)?.into_value()?,
+            Expr::Brackets(inner) => Self::step_value(scopes, inner)?,
+            Expr::Add { lhs, rhs } => {
+                let left = Self::step_value(scopes, lhs)?;
+                let right = Self::step_value(scopes, rhs)?;
+
+                left.add(&right)?
+            }
+            Expr::Multiply { lhs, rhs } => {
+                let left = Self::step_value(scopes, lhs)?;
+                let right = Self::step_value(scopes, rhs)?;
+
+                left.multiply(&right)?
+            }
+            Expr::FloorDivide { lhs, rhs } => {
+                let left = Self::step_value(scopes, lhs)?;
+                let right = Self::step_value(scopes, rhs)?;
+
+                left.floor_divide(&right)?
+            }
+            Expr::Divide { lhs, rhs } => {
+                let left = Self::step_value(scopes, lhs)?;
+                let right = Self::step_value(scopes, rhs)?;
+
+                left.divide(&right)?
+            }
+            Expr::Remainder { lhs, rhs } => {
+                let left = Self::step_value(scopes, lhs)?;
+                let right = Self::step_value(scopes, rhs)?;
+
+                left.remainder(&right)?
+            }
+            Expr::Compare { ctype, lhs, rhs } => {
+                let left = Self::step_value(scopes, lhs)?;
+                let right = Self::step_value(scopes, rhs)?;
+
+                let result = match ctype {
+                    CompareType::Greater => left.is_greater_than(&right)?,
+                    CompareType::Smaller => left.is_smaller_than(&right)?,
+                    CompareType::Equals => left.equals(&right)?,
+                    CompareType::In => right.contains(&left)?,
+                };
+
+                result.into()
+            }
+            Expr::Not(rhs) => {
+                let right = Self::step_value(scopes, rhs)?;
+                right.negate()?
+            }
+            Expr::Negative(rhs) => {
+                let right = Self::step_value(scopes, rhs)?;
+                right.negative()?
+            }
+            Expr::And { lhs, rhs } => {
+                let result = Self::step_value(scopes, lhs)?.as_bool()?
+                    && Self::step_value(scopes, rhs)?.as_bool()?;
+                result.into()
+            }
+            Expr::Or { lhs, rhs } => {
+                let result = Self::step_value(scopes, lhs)?.as_bool()?
+                    || Self::step_value(scopes, rhs)?.as_bool()?;
+                result.into()
             }
             Expr::GetElement(callee, k) => {
-                let res = Self::step(scopes, callee).1.unwrap_value();
-                let key = Self::step(scopes, k).1.unwrap_value();
+                let res = Self::step_value(scopes, callee)?;
+                let key = Self::step_value(scopes, k)?;
 
-                match res.get_child(key) {
-                    Ok(c) => Handle::wrap_value(c.clone()),
+                match res.get_child(key.clone()) {
+                    Ok(c) => c.clone(),
                     Err(ValueError::NoSuchChild) => {
-                        let key = Self::step(scopes, k).1.unwrap_value();
-                        panic!("No such child '{:?}' in '{:?}'", key, res);
+                        return Err(RuntimeErrorKind::NoSuchChild(format!(
+                            "'{:?}' in '{:?}'",
+                            key, res
+                        ))
+                        .into());
                     }
                     Err(e) => {
-                        panic!("Got unexpected error: {:?}", e);
+                        return Err(e.into());
                     }
                 }
             }
@@ -416,138 +1349,575 @@ impl Interpreter {
                 let mut res = Value::make_map();
 
                 for (k, v) in kvs {
-                    let elem = Self::step(scopes, v).1.unwrap_value();
-                    res.map_insert(k.clone(), elem).unwrap();
+                    let elem = Self::step_value(scopes, v)?;
+                    res.map_insert(k.clone(), elem)?;
                 }
 
-                Handle::wrap_value(res)
+                res
             }
-            Expr::String(s) => Handle::wrap_value(s.clone().into()),
-            Expr::Range { start, end, step } => {
-                let start = Self::step(scopes, start).1.unwrap_value();
-                let end = Self::step(scopes, end).1.unwrap_value();
+            Expr::String(s) => s.clone().into(),
+            Expr::Max { lhs, rhs } => {
+                let lhs: i64 = Self::step_value(scopes, lhs)?.try_into()?;
+                let rhs: i64 = Self::step_value(scopes, rhs)?.try_into()?;
 
-                let start: i64 = start.try_into().unwrap();
-                let end: i64 = end.try_into().unwrap();
+                let result = std::cmp::max(lhs, rhs);
+                result.into()
+            }
+            Expr::Min { lhs, rhs } => {
+                let lhs: i64 = Self::step_value(scopes, lhs)?.try_into()?;
+                let rhs: i64 = Self::step_value(scopes, rhs)?.try_into()?;
 
-                let step: i64 = if let Some(s) = step {
-                    let step = Self::step(scopes, s).1.unwrap_value();
-                    step.try_into().unwrap()
-                } else {
-                    1
+                let result = std::cmp::min(lhs, rhs);
+                result.into()
+            }
+            Expr::ToStr(inner) => {
+                let value = Self::step_value(scopes, inner)?;
+
+                let s = match value.unfrozen() {
+                    Value::List(_) | Value::Tuple(_) | Value::Array(_) | Value::Map(_) => {
+                        value.to_string()
+                    }
+                    _ => value.to_canonical_string()?,
+                };
+                s.into()
+            }
+            Expr::Cast { value, typename } => {
+                let inner = Self::step_value(scopes, value)?;
+
+                let target = match typename {
+                    ValueType::U8 => PrimitiveType::U8,
+                    ValueType::I64 => PrimitiveType::I64,
+                    ValueType::U64 => PrimitiveType::U64,
+                    _ => {
+                        return Err(ValueError::OperationNotSupported.into());
+                    }
                 };
 
-                if start > end {
-                    panic!("invalid range: {} to {}", start, end);
-                } else if step <= 0 {
-                    panic!("invalid step size: {}", step);
+                let inner = match inner.unfrozen() {
+                    Value::F32(_) | Value::F64(_) => inner.to_integer(scopes.cast_rounding)?,
+                    _ => inner,
+                };
+                inner.convert_to(target, scopes.cast_mode)?
+            }
+            Expr::List(elems) => {
+                let mut result = Value::make_list();
+
+                for e in elems {
+                    let elem = Self::step_value(scopes, e)?;
+                    result.list_append(elem)?;
                 }
 
-                Handle::Iter(Box::new(RangeIterable {
-                    end,
-                    step,
-                    pos: start,
-                }))
+                result
             }
-            Expr::Max { lhs, rhs } => {
-                let lhs = Self::step(scopes, lhs).1.unwrap_value();
-                let rhs = Self::step(scopes, rhs).1.unwrap_value();
+            Expr::Tuple(elems) => {
+                let mut result = Vec::with_capacity(elems.len());
 
-                let lhs: i64 = match lhs.try_into() {
-                    Ok(i) => i,
-                    Err(val) => {
-                        panic!("max() failed. '{:?}' is not an integer!", val);
-                    }
-                };
+                for e in elems {
+                    result.push(Self::step_value(scopes, e)?);
+                }
 
-                let rhs: i64 = match rhs.try_into() {
-                    Ok(i) => i,
-                    Err(val) => {
-                        panic!("max() failed. '{:?}' is not an integer!", val);
-                    }
+                Value::Tuple(result)
+            }
+            Expr::Array { value, length } => {
+                let value = Self::step_value(scopes, value)?;
+                let length: i64 = Self::step_value(scopes, length)?.try_into()?;
+
+                let Ok(length) = usize::try_from(length) else {
+                    return Err(RuntimeErrorKind::InvalidArgument(format!(
+                        "array length cannot be negative: {length}"
+                    ))
+                    .into());
                 };
 
-                let result = std::cmp::max(lhs, rhs);
-                Handle::wrap_value(result.into())
+                Value::Array(vec![value; length])
             }
-            Expr::Min { lhs, rhs } => {
-                let lhs = Self::step(scopes, lhs).1.unwrap_value();
-                let rhs = Self::step(scopes, rhs).1.unwrap_value();
+            Expr::Bool(b) => b.into(),
+            Expr::I64(i) => i.into(),
+            Expr::U64(i) => i.into(),
+            Expr::U8(i) => (*i).into(),
+            Expr::Decimal(literal) => Value::from(literal.as_str())
+                .convert_to(PrimitiveType::Decimal, ConversionMode::Checked)?,
+            _ => Self::evaluate(scopes, expr)?.1.into_value()?,
+        };
 
-                let lhs: i64 = match lhs.try_into() {
-                    Ok(i) => i,
-                    Err(val) => {
-                        panic!("min() failed. '{:?}' is not an integer!", val);
-                    }
+        Self::check_float(scopes, &value)?;
+        Ok(value)
+    }
+
+    fn check_float(scopes: &Scopes, value: &Value) -> Result<(), RuntimeError> {
+        if !scopes.forbid_non_finite_floats {
+            return Ok(());
+        }
+
+        let float = match value {
+            Value::F32(f) => *f as f64,
+            Value::F64(f) => *f,
+            _ => return Ok(()),
+        };
+
+        if float.is_finite() {
+            Ok(())
+        } else {
+            Err(RuntimeErrorKind::NonFiniteFloat(float.to_string()).into())
+        }
+    }
+
+    /// Update a variable, element, or module member in place (e.g., `x -= 1`)
+    fn op_assign(
+        scopes: &mut Scopes,
+        op: ArithmeticOp,
+        target: &ParseNode,
+        rhs: &ParseNode,
+    ) -> Result<(), RuntimeError> {
+        let right = Self::step_value(scopes, rhs)?;
+        let (_, expr) = target;
+
+        let (cell, path) = match expr {
+            Expr::Var(name) => {
+                let var = scopes.get(name)?.into_value()?;
+                let result = apply_arithmetic(op, &var, &right)?;
+
+                let hdl = scopes.wrap_value(result);
+                scopes.update_variable(name, hdl)?;
+
+                return Ok(());
+            }
+            Expr::GetMember(object, name) => {
+                let Handle::Object(module) = Self::step(scopes, object)?.1 else {
+                    return Err(RuntimeErrorKind::NotAssignable(format!(
+                        "'{}' (only module members can be modified)",
+                        name
+                    ))
+                    .into());
                 };
 
-                let rhs: i64 = match rhs.try_into() {
-                    Ok(i) => i,
-                    Err(val) => {
-                        panic!("min() failed. '{:?}' is not an integer!", val);
+                match Self::get_module_member(scopes, expr, module, name).into_result()? {
+                    Handle::Value(cell) => (cell, vec![]),
+                    _ => {
+                        return Err(RuntimeErrorKind::NotAssignable(format!(
+                            "member '{}' (not a value)",
+                            name
+                        ))
+                        .into());
                     }
+                }
+            }
+            Expr::GetElement(..) => Self::step_place(scopes, target)?,
+            _ => {
+                return Err(RuntimeErrorKind::NotAssignable("this expression".to_string()).into());
+            }
+        };
+
+        Self::ensure_not_temporary(&cell)?;
+
+        Handle::modify_value(&cell, |value| {
+            let value = Self::follow_path(value, path)?;
+            *value = apply_arithmetic(op, value, &right)?;
+            Ok::<(), ValueError>(())
+        })?;
+
+        if let Some(name) = Self::place_variable(target) {
+            scopes.record_modification(name);
+        }
+
+        Ok(())
+    }
+
+    /// Find the value that an element access refers to, without copying it
+    ///
+    /// Returns the cell holding the outermost container and the keys leading
+    /// from it to the element, e.g., `m` and `["a", 0]` for `m["a"][0]`.
+    fn step_place(
+        scopes: &mut Scopes,
+        node: &ParseNode,
+    ) -> Result<(Rc<Cell<Value>>, Vec<Value>), RuntimeError> {
+        if let (_, Expr::GetElement(container, key)) = node {
+            let (cell, mut path) = Self::step_place(scopes, container)?;
+            path.push(Self::step_value(scopes, key)?);
+            return Ok((cell, path));
+        }
+
+        match Self::step(scopes, node)?.1 {
+            Handle::Value(cell) => Ok((cell, vec![])),
+            _ => Err(RuntimeErrorKind::NotAValue.into()),
+        }
+    }
+
+    fn follow_path(mut value: &mut Value, path: Vec<Value>) -> Result<&mut Value, ValueError> {
+        for key in path {
+            value = value.get_child_mut(key)?;
+        }
+
+        Ok(value)
+    }
+
+    /// The variable that contains the place, if any (e.g., `m` for `m["a"][0]`)
+    fn place_variable(node: &ParseNode) -> Option<&str> {
+        match &node.1 {
+            Expr::Var(name) => Some(name),
+            Expr::GetElement(container, _) => Self::place_variable(container),
+            _ => None,
+        }
+    }
+
+    /// Call a module function and turn a panic into an error
+    fn call_catching_panics(callee: &Handle, argv: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        match panic::catch_unwind(AssertUnwindSafe(|| callee.call(argv))) {
+            Ok(result) => result,
+            Err(payload) => Err(RuntimeErrorKind::ModulePanicked(panic_message(&*payload)).into()),
+        }
+    }
+
+    /// Fail if nobody else holds the value (e.g., a module that returns a copy),
+    /// as changing it would have no effect
+    fn ensure_not_temporary(cell: &Rc<Cell<Value>>) -> Result<(), RuntimeError> {
+        if Rc::strong_count(cell) < 2 {
+            Err(RuntimeErrorKind::NotAssignable("a temporary value".to_string()).into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Resolve a member of a module, reusing the callable found at this site before
+    fn get_module_member(
+        scopes: &mut Scopes,
+        site: &Expr,
+        module: Rc<dyn Module>,
+        name: &str,
+    ) -> Handle {
+        if let Some(callable) = scopes.member_cache.get(site, &module, name) {
+            return Handle::Callable(callable);
+        }
+
+        let mut member = module.get_member(&module, name);
+
+        if let Handle::Deprecated { inner, message } = member {
+            let module = scopes.module_name(&module).map(str::to_string);
+            scopes.record_deprecation(module, name, message);
+            member = *inner;
+        }
+
+        if let Handle::Callable(mut callable) = member {
+            if let Some(table) = &scopes.memo_table {
+                if callable.is_pure() {
+                    callable = Rc::new(MemoizedCallable {
+                        module: Rc::as_ptr(&module) as *const () as usize,
+                        member: name.to_string(),
+                        inner: callable,
+                        table: table.clone(),
+                    });
+                }
+            }
+
+            for middleware in scopes.middleware.iter() {
+                let info = CallInfo {
+                    module: scopes.module_name(&module).map(str::to_string),
+                    member: name.to_string(),
                 };
 
-                let result = std::cmp::min(lhs, rhs);
-                Handle::wrap_value(result.into())
+                callable = Rc::new(WrappedCallable {
+                    info,
+                    inner: callable,
+                    middleware: middleware.clone(),
+                });
             }
-            Expr::ToStr(inner) => {
-                let val = Self::step(scopes, inner).1.unwrap_value();
 
-                #[allow(clippy::match_wild_err_arm)]
-                let s: String = match val.try_into() {
-                    Ok(s) => s,
-                    Err(_) => {
-                        panic!("Failed to convert to string");
-                    }
+            scopes
+                .member_cache
+                .insert(site, module.clone(), name, callable.clone());
+            member = Handle::Callable(callable);
+        }
+
+        member
+    }
+
+    fn call_builtin(
+        val: &Cell<Value>,
+        name: &str,
+        mut argv: Vec<Value>,
+    ) -> Result<Handle, RuntimeError> {
+        let result = match name {
+            "len" => Handle::modify_value(val, |v| v.num_children()).into(),
+            "values" => {
+                let map = match Handle::read_value(val).into_map() {
+                    Ok(map) => map,
+                    Err(other) => return Err(ValueError::type_mismatch("map", &other).into()),
                 };
 
-                Handle::wrap_value(s.into())
+                // Sort by key so that scripts behave the same on every run
+                let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+                entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+
+                let mut vals = Value::make_list();
+
+                for (_, v) in entries {
+                    vals.list_append(v)?;
+                }
+
+                vals
             }
-            Expr::Cast { value, typename } => match typename {
-                ValueType::U8 => {
-                    let inner = Self::step(scopes, value).1.unwrap_value();
+            "keys" => {
+                let map = match Handle::read_value(val).into_map() {
+                    Ok(map) => map,
+                    Err(other) => return Err(ValueError::type_mismatch("map", &other).into()),
+                };
 
-                    let val: u8 = inner.try_into().unwrap();
-                    Handle::wrap_value(val.into())
+                let mut keys: Vec<String> = map.into_keys().collect();
+                keys.sort();
+
+                let mut result = Value::make_list();
+
+                for k in keys {
+                    result.list_append(k.into())?;
                 }
-                ValueType::I64 => {
-                    let inner = Self::step(scopes, value).1.unwrap_value();
 
-                    let val: i64 = inner.try_into().unwrap();
-                    Handle::wrap_value(val.into())
+                result
+            }
+            "items" => Handle::modify_value(val, |v| v.map_items())?,
+            "contains_key" => {
+                let [key] = Self::string_arguments(name, argv)?;
+                Handle::modify_value(val, |v| v.map_contains_key(&key))?.into()
+            }
+            "append" => {
+                let Some(arg) = argv.drain(..).next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "append() expects one argument".to_string(),
+                    )
+                    .into());
+                };
+
+                Handle::modify_value(val, |v| v.list_append(arg))?;
+                Value::None
+            }
+            "trunc" => Handle::modify_value(val, |v| v.to_integer(Rounding::Truncate))?,
+            "floor" => Handle::modify_value(val, |v| v.to_integer(Rounding::Floor))?,
+            "ceil" => Handle::modify_value(val, |v| v.to_integer(Rounding::Ceil))?,
+            "round" => Handle::modify_value(val, |v| v.to_integer(Rounding::Round))?,
+            "encode" => Handle::modify_value(val, |v| v.encode())?,
+            "decode" => Handle::modify_value(val, |v| v.decode())?,
+            "update" => {
+                let Some(arg) = argv.drain(..).next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "update() expects one argument".to_string(),
+                    )
+                    .into());
+                };
+
+                Handle::modify_value(val, |v| v.map_update(arg))?;
+                Value::None
+            }
+            "pop" if Handle::modify_value(val, |v| matches!(v.unfrozen(), Value::List(_))) => {
+                let position = match argv.len() {
+                    0 => None,
+                    1 => Some(Self::position_argument(argv.remove(0))?),
+                    _ => {
+                        return Err(RuntimeErrorKind::InvalidArgument(
+                            "pop() expects an optional index".to_string(),
+                        )
+                        .into());
+                    }
+                };
+
+                Handle::modify_value(val, |v| v.list_pop(position))?
+            }
+            "pop" => {
+                if argv.is_empty() || argv.len() > 2 {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "pop() expects a key and an optional default".to_string(),
+                    )
+                    .into());
                 }
-                ValueType::U64 => {
-                    let inner = Self::step(scopes, value).1.unwrap_value();
 
-                    let val: u64 = inner.try_into().unwrap();
-                    Handle::wrap_value(val.into())
+                let mut args = argv.drain(..);
+                let key: String = args.next().unwrap().try_into()?;
+                let default = args.next();
+
+                match Handle::modify_value(val, |v| v.remove(&key)) {
+                    Ok(value) => value,
+                    Err(ValueError::NoSuchChild) if default.is_some() => default.unwrap(),
+                    Err(err) => return Err(err.into()),
                 }
-                _ => {
-                    todo!();
+            }
+            "extend" => {
+                let Some(arg) = argv.drain(..).next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "extend() expects one argument".to_string(),
+                    )
+                    .into());
+                };
+
+                Handle::modify_value(val, |v| v.list_extend(arg))?;
+                Value::None
+            }
+            "clear" => {
+                Handle::modify_value(val, |v| v.list_clear())?;
+                Value::None
+            }
+            "count" => {
+                let Some(arg) = argv.drain(..).next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "count() expects one argument".to_string(),
+                    )
+                    .into());
+                };
+
+                Handle::modify_value(val, |v| v.list_count(&arg))?.into()
+            }
+            "min" => Handle::modify_value(val, |v| v.list_min())?,
+            "max" => Handle::modify_value(val, |v| v.list_max())?,
+            "sum" => Handle::modify_value(val, |v| v.list_sum())?,
+            "get" => {
+                if argv.is_empty() || argv.len() > 2 {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "get() expects a key and an optional default".to_string(),
+                    )
+                    .into());
                 }
-            },
-            Expr::List(elems) => {
-                let mut result = Value::make_list();
 
-                for e in elems {
-                    let elem = Self::step(scopes, e).1.unwrap_value();
+                let mut args = argv.drain(..);
+                let key = args.next().unwrap();
+                let default = args.next().unwrap_or(Value::None);
+
+                let child = Handle::modify_value(val, |v| match v.get_child(key) {
+                    Ok(child) => Ok(Some(child.clone())),
+                    Err(ValueError::NoSuchChild | ValueError::IndexOutOfBounds) => Ok(None),
+                    Err(err) => Err(err),
+                })?;
 
-                    result.list_append(elem).unwrap();
+                child.unwrap_or(default)
+            }
+            "insert" => {
+                if argv.len() != 2 {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "insert() expects an index and a value".to_string(),
+                    )
+                    .into());
                 }
 
-                Handle::wrap_value(result)
+                let mut args = argv.drain(..);
+                let position = Self::position_argument(args.next().unwrap())?;
+                let value = args.next().unwrap();
+
+                Handle::modify_value(val, |v| v.list_insert(position, value))?;
+                Value::None
             }
-            Expr::Bool(b) => Handle::wrap_value(b.into()),
-            Expr::I64(i) => Handle::wrap_value(i.into()),
-            Expr::U64(i) => Handle::wrap_value(i.into()),
-            Expr::U8(i) => Handle::wrap_value((*i).into()),
-            Expr::Return(rhs) => {
-                control_flow = ControlFlow::Return;
-                Self::step(scopes, rhs).1
+            "remove" => {
+                let Some(arg) = argv.drain(..).next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "remove() expects one argument".to_string(),
+                    )
+                    .into());
+                };
+
+                Handle::modify_value(val, |v| v.list_remove(&arg))?;
+                Value::None
+            }
+            "index" => {
+                let Some(arg) = argv.drain(..).next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "index() expects one argument".to_string(),
+                    )
+                    .into());
+                };
+
+                Handle::modify_value(val, |v| v.list_index(&arg))?.into()
+            }
+            "sort" => {
+                Handle::modify_value(val, |v| v.list_sort())?;
+                Value::None
+            }
+            "reverse" => {
+                Handle::modify_value(val, |v| v.list_reverse())?;
+                Value::None
+            }
+            "split" => {
+                let separator = match argv.len() {
+                    0 => None,
+                    1 => {
+                        let separator: String = argv.remove(0).try_into()?;
+                        Some(separator)
+                    }
+                    _ => {
+                        return Err(RuntimeErrorKind::InvalidArgument(
+                            "split() expects an optional separator".to_string(),
+                        )
+                        .into());
+                    }
+                };
+
+                Handle::modify_value(val, |v| v.str_split(separator.as_deref()))?
+            }
+            "join" => {
+                let Some(arg) = argv.drain(..).next() else {
+                    return Err(RuntimeErrorKind::InvalidArgument(
+                        "join() expects one argument".to_string(),
+                    )
+                    .into());
+                };
+
+                Handle::modify_value(val, |v| v.str_join(&arg))?
+            }
+            "upper" => Handle::modify_value(val, |v| v.str_upper())?,
+            "lower" => Handle::modify_value(val, |v| v.str_lower())?,
+            "strip" => Handle::modify_value(val, |v| v.str_strip())?,
+            "startswith" => {
+                let [prefix] = Self::string_arguments(name, argv)?;
+                Handle::modify_value(val, |v| v.str_starts_with(&prefix))?.into()
+            }
+            "endswith" => {
+                let [suffix] = Self::string_arguments(name, argv)?;
+                Handle::modify_value(val, |v| v.str_ends_with(&suffix))?.into()
+            }
+            "replace" => {
+                let [from, to] = Self::string_arguments(name, argv)?;
+                Handle::modify_value(val, |v| v.str_replace(&from, &to))?
+            }
+            "find" => {
+                let [needle] = Self::string_arguments(name, argv)?;
+                Handle::modify_value(val, |v| v.str_find(&needle))?
+            }
+            _ => {
+                return Err(RuntimeErrorKind::NoSuchBuiltin(name.to_string()).into());
             }
         };
 
-        (control_flow, hdl)
+        Ok(Handle::wrap_value(result))
+    }
+
+    /// Convert the index argument of a list builtin
+    fn position_argument(value: Value) -> Result<usize, ValueError> {
+        let position: i64 = value.try_into()?;
+        usize::try_from(position).map_err(|_| ValueError::IndexOutOfBounds)
+    }
+
+    /// Get the arguments of a builtin that expects exactly `N` strings
+    fn string_arguments<const N: usize>(
+        name: &str,
+        argv: Vec<Value>,
+    ) -> Result<[String; N], RuntimeError> {
+        let count = argv.len();
+        let args = argv
+            .into_iter()
+            .map(TryInto::<String>::try_into)
+            .collect::<Result<Vec<String>, ValueError>>()?;
+
+        args.try_into().map_err(|_| {
+            RuntimeErrorKind::InvalidArgument(format!(
+                "{name}() expects {N} string argument(s), but got {count}"
+            ))
+            .into()
+        })
+    }
+}
+
+/// Evaluate the operator of a compound assignment
+fn apply_arithmetic(op: ArithmeticOp, lhs: &Value, rhs: &Value) -> Result<Value, ValueError> {
+    match op {
+        ArithmeticOp::Add => lhs.add(rhs),
+        ArithmeticOp::Subtract => lhs.subtract(rhs),
+        ArithmeticOp::Multiply => lhs.multiply(rhs),
+        ArithmeticOp::FloorDivide => lhs.floor_divide(rhs),
+        ArithmeticOp::Remainder => lhs.remainder(rhs),
+        ArithmeticOp::Divide => lhs.divide(rhs),
     }
 }
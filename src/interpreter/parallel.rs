@@ -0,0 +1,254 @@
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use super::{panic_message, Callable, Handle, MemberInfo, MemberKind, Module};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::Value;
+
+/// A function that can be called from any thread (see `Callable::thread_safe`)
+pub type SendFunction = Arc<dyn Fn(Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync>;
+
+/// A single call made by `parallel.map`
+pub type Job = Box<dyn FnOnce() -> Result<Value, RuntimeError> + Send>;
+
+// Results are handed back from other threads
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<RuntimeError>();
+};
+
+/// Runs the calls of the `parallel` module, e.g., on a thread pool of the host
+pub trait Executor {
+    /// Run all jobs and return their results in the same order
+    ///
+    /// Jobs may run concurrently on other threads, but this must only
+    /// return once all of them are done.
+    fn execute(&self, jobs: Vec<Job>) -> Vec<Result<Value, RuntimeError>>;
+}
+
+/// Runs every batch of jobs on (at most) a fixed number of new threads
+///
+/// Jobs that panic fail with `RuntimeErrorKind::ModulePanicked`.
+#[derive(Debug)]
+pub struct ThreadExecutor {
+    num_threads: usize,
+}
+
+impl ThreadExecutor {
+    pub fn new(num_threads: usize) -> Self {
+        Self {
+            num_threads: num_threads.max(1),
+        }
+    }
+}
+
+impl Default for ThreadExecutor {
+    /// Uses one thread per available core
+    fn default() -> Self {
+        Self::new(thread::available_parallelism().map_or(1, usize::from))
+    }
+}
+
+impl Executor for ThreadExecutor {
+    fn execute(&self, jobs: Vec<Job>) -> Vec<Result<Value, RuntimeError>> {
+        let num_jobs = jobs.len();
+        let queue: Mutex<VecDeque<(usize, Job)>> =
+            Mutex::new(jobs.into_iter().enumerate().collect());
+        let results = Mutex::new(vec![None; num_jobs]);
+
+        thread::scope(|scope| {
+            for _ in 0..self.num_threads.min(num_jobs) {
+                scope.spawn(|| loop {
+                    let Some((index, job)) = queue.lock().unwrap().pop_front() else {
+                        break;
+                    };
+
+                    // Panics would otherwise take down the interpreter's thread
+                    let result =
+                        panic::catch_unwind(AssertUnwindSafe(job)).unwrap_or_else(|payload| {
+                            Err(RuntimeErrorKind::ModulePanicked(panic_message(&*payload)).into())
+                        });
+                    results.lock().unwrap()[index] = Some(result);
+                });
+            }
+        });
+
+        results
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .map(|result| result.expect("Job did not run"))
+            .collect()
+    }
+}
+
+/// A module function that can also be called by `parallel.map`
+pub struct ThreadSafeFunction {
+    function: SendFunction,
+}
+
+impl ThreadSafeFunction {
+    pub fn new<F>(function: F) -> Self
+    where
+        F: Fn(Vec<Value>) -> Result<Value, RuntimeError> + Send + Sync + 'static,
+    {
+        Self {
+            function: Arc::new(function),
+        }
+    }
+}
+
+impl Callable for ThreadSafeFunction {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        match (self.function)(args) {
+            Ok(result) => Handle::wrap_value(result),
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let args = Handle::into_values(args)?;
+        Ok(Handle::wrap_value((self.function)(args)?))
+    }
+
+    fn thread_safe(&self) -> Option<SendFunction> {
+        Some(self.function.clone())
+    }
+}
+
+/// Lets scripts spread calls over many threads, e.g., `parallel.map(urls, http.get)`
+///
+/// Only functions that can run on other threads can be used (see `ThreadSafeFunction`).
+/// The interpreter itself stays single-threaded and waits until all calls of a batch
+/// are done. Middleware and memoization do not apply to these calls.
+///
+/// ```ignore
+/// let parallel = ParallelModule::new(Rc::new(ThreadExecutor::new(8)));
+/// interpreter.register_module("parallel".to_string(), Rc::new(parallel));
+/// ```
+pub struct ParallelModule {
+    executor: Rc<dyn Executor>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ParallelFunction {
+    Map,
+}
+
+const FUNCTIONS: &[(&str, ParallelFunction, &str, &str)] = &[(
+    "map",
+    ParallelFunction::Map,
+    "map(list, function)",
+    "Calls a function for every element (possibly at the same time) and returns the results",
+)];
+
+impl ParallelModule {
+    pub fn new(executor: Rc<dyn Executor>) -> Self {
+        Self { executor }
+    }
+}
+
+impl Default for ParallelModule {
+    fn default() -> Self {
+        Self::new(Rc::new(ThreadExecutor::default()))
+    }
+}
+
+impl Module for ParallelModule {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match FUNCTIONS.iter().find(|(n, ..)| *n == name) {
+            Some((_, function, ..)) => Handle::Callable(Rc::new(ParallelCallable {
+                function: *function,
+                executor: self.executor.clone(),
+            })),
+            None => Handle::Error(
+                RuntimeErrorKind::NoSuchMember {
+                    module: "parallel".to_string(),
+                    member: name.to_string(),
+                }
+                .into(),
+            ),
+        }
+    }
+
+    fn has_member(&self, name: &str) -> bool {
+        FUNCTIONS.iter().any(|(n, ..)| *n == name)
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        FUNCTIONS
+            .iter()
+            .map(|(name, _, signature, doc)| MemberInfo {
+                name: name.to_string(),
+                kind: MemberKind::Function,
+                signature: Some(signature.to_string()),
+                doc: Some(doc.to_string()),
+            })
+            .collect()
+    }
+}
+
+fn invalid_argument(message: &str) -> RuntimeError {
+    RuntimeErrorKind::InvalidArgument(message.to_string()).into()
+}
+
+struct ParallelCallable {
+    function: ParallelFunction,
+    executor: Rc<dyn Executor>,
+}
+
+impl ParallelCallable {
+    fn map(&self, list: Handle, function: Handle) -> Result<Value, RuntimeError> {
+        let function = match function {
+            Handle::Callable(callable) => callable.thread_safe(),
+            _ => None,
+        };
+
+        let Some(function) = function else {
+            return Err(invalid_argument(
+                "map() expects a function that can run on other threads",
+            ));
+        };
+
+        let jobs: Vec<Job> = list
+            .into_value()?
+            .into_vec()?
+            .into_iter()
+            .map(|elem| {
+                let function = function.clone();
+                Box::new(move || function(vec![elem])) as Job
+            })
+            .collect();
+
+        let results = self
+            .executor
+            .execute(jobs)
+            .into_iter()
+            .collect::<Result<Vec<Value>, RuntimeError>>()?;
+
+        Ok(Value::List(results))
+    }
+}
+
+impl Callable for ParallelCallable {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        let args = args.into_iter().map(Handle::wrap_value).collect();
+
+        match self.call_with_handles(args) {
+            Ok(result) => result,
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let result = match (self.function, <[Handle; 2]>::try_from(args)) {
+            (ParallelFunction::Map, Ok([list, function])) => self.map(list, function)?,
+            _ => return Err(invalid_argument("wrong number of arguments")),
+        };
+
+        Ok(Handle::wrap_value(result))
+    }
+}
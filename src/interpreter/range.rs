@@ -0,0 +1,103 @@
+use super::Iterable;
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::Value;
+
+/// A range of integers created by `range(start, end[, step])`
+///
+/// Ranges are lazy: they can be stored in a variable and each loop
+/// over them starts from the beginning. A range only turns into a list
+/// when it is used as a value (e.g., when it is returned).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    start: i64,
+    end: i64,
+    step: i64,
+}
+
+impl Range {
+    /// A negative step counts down from `start` to (but excluding) `end`
+    pub fn new(start: i64, end: i64, step: i64) -> Result<Self, RuntimeError> {
+        if step == 0 {
+            return Err(
+                RuntimeErrorKind::InvalidArgument("invalid step size: 0".to_string()).into(),
+            );
+        }
+
+        Ok(Self { start, end, step })
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+
+    pub fn step(&self) -> i64 {
+        self.step
+    }
+
+    /// The number of integers in the range
+    pub fn len(&self) -> u64 {
+        let (distance, step) = if self.step > 0 {
+            (self.end as i128 - self.start as i128, self.step as i128)
+        } else {
+            (self.start as i128 - self.end as i128, -(self.step as i128))
+        };
+
+        if distance <= 0 {
+            0
+        } else {
+            ((distance + step - 1) / step) as u64
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(super) fn iter(&self) -> Box<dyn Iterable> {
+        Box::new(RangeIterable {
+            range: *self,
+            pos: Some(self.start),
+        })
+    }
+
+    /// Create a list with all integers of the range
+    pub fn to_value(&self) -> Value {
+        let mut iter = self.iter();
+        let mut result = Vec::with_capacity(self.len().min(1024) as usize);
+
+        while let Some(value) = iter.next() {
+            result.push(value);
+        }
+
+        Value::List(result)
+    }
+}
+
+struct RangeIterable {
+    range: Range,
+    /// `None` once the next position would overflow
+    pos: Option<i64>,
+}
+
+impl Iterable for RangeIterable {
+    fn next(&mut self) -> Option<Value> {
+        let val = self.pos?;
+
+        let in_range = if self.range.step > 0 {
+            val < self.range.end
+        } else {
+            val > self.range.end
+        };
+
+        if in_range {
+            self.pos = val.checked_add(self.range.step);
+            Some(val.into())
+        } else {
+            None
+        }
+    }
+}
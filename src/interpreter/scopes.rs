@@ -1,76 +1,447 @@
 use std::rc::Rc;
 
-use std::collections::HashMap;
+use crate::ast::Span;
+use crate::grammar;
 
+use super::cache::MemberCache;
+use super::cancel::CancelToken;
+use super::coverage::Coverage;
+use super::deprecation::Deprecation;
+use super::globals::{self, Stdout, StdoutSink};
+use super::hashing::{SeededHashMap, SeededState};
+use super::limits::OutputLimits;
+use super::memo::MemoTable;
+use super::middleware::Middleware;
+use super::stats::Stats;
+use super::trace::{Trace, TraceEvent};
+use super::watch::Watcher;
 use super::*;
 
-#[derive(Default)]
-struct Scope {
-    modules: HashMap<String, Rc<dyn Module>>,
-    variables: HashMap<String, Handle>,
+pub struct Scope {
+    modules: SeededHashMap<String, Rc<dyn Module>>,
+    variables: SeededHashMap<String, Handle>,
+}
+
+impl Scope {
+    fn new(hash_state: SeededState) -> Self {
+        Self {
+            modules: SeededHashMap::with_hasher(hash_state),
+            variables: SeededHashMap::with_hasher(hash_state),
+        }
+    }
+
+    /// Set a variable, replacing modules and variables with the same name
+    pub fn define(&mut self, name: String, hdl: Handle) {
+        self.modules.remove(&name);
+        self.variables.insert(name, hdl);
+    }
+
+    #[must_use]
+    pub fn try_clone(&self) -> Self {
+        let mut variables = SeededHashMap::with_hasher(*self.variables.hasher());
+        for (name, hdl) in self.variables.iter() {
+            variables.insert(name.clone(), hdl.try_clone());
+        }
+
+        Self {
+            modules: self.modules.clone(),
+            variables,
+        }
+    }
+}
+
+/// The settings of a run, so that functions can be called after it finished
+#[derive(Clone)]
+pub struct Context {
+    hash_state: SeededState,
+    linked_modules: Vec<(String, Rc<dyn Module>)>,
+    forbid_non_finite_floats: bool,
+    cast_mode: ConversionMode,
+    cast_rounding: Rounding,
+    output_limits: OutputLimits,
+    catch_panics: bool,
+    stdout: Rc<dyn StdoutSink>,
+    max_sleep: Duration,
+    max_call_depth: usize,
+    call_depth: Rc<Cell<usize>>,
+    cancel_token: Option<CancelToken>,
+    memo_table: Option<MemoTable>,
+    middleware: Vec<Rc<Middleware>>,
 }
 
 pub struct Scopes {
     scopes: Vec<Scope>,
+    hash_state: SeededState,
+    /// All modules in the order they were registered
+    linked_modules: Vec<(String, Rc<dyn Module>)>,
+    /// Resolved module members of the program that is currently running
+    pub member_cache: MemberCache,
+    /// Records all changes to variables (if tracing is enabled)
+    trace: Option<Trace>,
+    /// Counts executed statements (if coverage is enabled)
+    pub coverage: Option<Coverage>,
+    /// Raise an error when a float operation results in NaN or infinity
+    pub forbid_non_finite_floats: bool,
+    /// What `as` does with integers that do not fit into the target type
+    pub cast_mode: ConversionMode,
+    /// How `as` turns floats into integers
+    pub cast_rounding: Rounding,
+    /// Bounds for the result and for arguments of module functions
+    pub output_limits: OutputLimits,
+    /// Turn panics of module functions into errors
+    pub catch_panics: bool,
+    /// Receives the output of `print()`
+    pub stdout: Rc<dyn StdoutSink>,
+    /// The longest a single call to `sleep()` may block
+    pub max_sleep: Duration,
+    /// How many function calls may be active at once
+    pub max_call_depth: usize,
+    /// The number of active function calls (shared with functions called by modules)
+    call_depth: Rc<Cell<usize>>,
+    /// Stops the program before the next statement once cancelled
+    pub cancel_token: Option<CancelToken>,
+    /// Results of pure module functions (if memoization is enabled)
+    pub memo_table: Option<MemoTable>,
+    /// Counters for `Interpreter::stats`
+    pub stats: Stats,
+    /// Deprecated module members that were used
+    pub deprecations: Vec<Deprecation>,
+    /// Installed around all module functions (innermost first)
+    pub middleware: Vec<Rc<Middleware>>,
+    /// Reports changes of watched variables to the host
+    pub watcher: Option<Watcher>,
 }
 
 impl Scopes {
     pub fn new(
-        modules: HashMap<String, Rc<dyn Module>>,
-        variables: HashMap<String, Handle>,
+        modules: Vec<(String, Rc<dyn Module>)>,
+        variables: SeededHashMap<String, Handle>,
+        hash_state: SeededState,
+        mut trace: Option<Trace>,
     ) -> Self {
-        let scope = Scope { modules, variables };
+        let linked_modules = modules.clone();
+
+        let mut scope = Scope::new(hash_state);
+        scope.modules.extend(modules);
+        scope.variables = variables;
+
+        if let Some(trace) = &mut trace {
+            for (name, hdl) in scope.variables.iter() {
+                let value = Trace::value_of(hdl);
+                trace.record(TraceEvent::Create {
+                    name: name.clone(),
+                    value,
+                });
+            }
+        }
 
         Self {
             scopes: vec![scope],
+            hash_state,
+            linked_modules,
+            member_cache: MemberCache::default(),
+            trace,
+            coverage: None,
+            forbid_non_finite_floats: false,
+            cast_mode: ConversionMode::default(),
+            cast_rounding: Rounding::default(),
+            catch_panics: false,
+            stdout: Rc::new(Stdout::default()),
+            max_sleep: Duration::ZERO,
+            max_call_depth: usize::MAX,
+            call_depth: Rc::new(Cell::new(0)),
+            cancel_token: None,
+            memo_table: None,
+            output_limits: OutputLimits::default(),
+            stats: Stats {
+                peak_scope_depth: 1,
+                ..Default::default()
+            },
+            deprecations: Vec::new(),
+            middleware: Vec::new(),
+            watcher: None,
+        }
+    }
+
+    /// Create scopes for calling a function outside of the run that defined it
+    pub fn from_context(context: &Context) -> Self {
+        let mut scopes = Self::new(
+            context.linked_modules.clone(),
+            SeededHashMap::with_hasher(context.hash_state),
+            context.hash_state,
+            None,
+        );
+
+        scopes.forbid_non_finite_floats = context.forbid_non_finite_floats;
+        scopes.cast_mode = context.cast_mode;
+        scopes.cast_rounding = context.cast_rounding;
+        scopes.output_limits = context.output_limits;
+        scopes.catch_panics = context.catch_panics;
+        scopes.stdout = context.stdout.clone();
+        scopes.max_sleep = context.max_sleep;
+        scopes.max_call_depth = context.max_call_depth;
+        scopes.call_depth = context.call_depth.clone();
+        scopes.cancel_token = context.cancel_token.clone();
+        scopes.memo_table = context.memo_table.clone();
+        scopes.middleware = context.middleware.clone();
+        scopes
+    }
+
+    /// The settings of this run (see `Scopes::from_context`)
+    pub fn context(&self) -> Context {
+        Context {
+            hash_state: self.hash_state,
+            linked_modules: self.linked_modules.clone(),
+            forbid_non_finite_floats: self.forbid_non_finite_floats,
+            cast_mode: self.cast_mode,
+            cast_rounding: self.cast_rounding,
+            output_limits: self.output_limits,
+            catch_panics: self.catch_panics,
+            stdout: self.stdout.clone(),
+            max_sleep: self.max_sleep,
+            max_call_depth: self.max_call_depth,
+            call_depth: self.call_depth.clone(),
+            cancel_token: self.cancel_token.clone(),
+            memo_table: self.memo_table.clone(),
+            middleware: self.middleware.clone(),
+        }
+    }
+
+    /// All variables and modules that are currently visible
+    ///
+    /// Values are shared with the original variables, not copied.
+    /// Iterators cannot be shared and are left out.
+    pub fn capture(&self) -> Scope {
+        let mut captured = Scope::new(self.hash_state);
+
+        for scope in self.scopes.iter() {
+            for (name, module) in scope.modules.iter() {
+                captured.variables.remove(name);
+                captured.modules.insert(name.clone(), module.clone());
+            }
+
+            for (name, hdl) in scope.variables.iter() {
+                if !matches!(hdl, Handle::Iter(_)) {
+                    captured.define(name.clone(), hdl.try_clone());
+                }
+            }
+        }
+
+        captured
+    }
+
+    /// Replace all scopes with the scope of a function that is called
+    ///
+    /// Returns the scopes of the caller, which must be restored using `leave_function`.
+    /// Fails if too many calls are active already (see `Interpreter::set_max_call_depth`).
+    pub fn enter_function(&mut self, frame: Scope) -> Result<Vec<Scope>, RuntimeError> {
+        let depth = self.call_depth.get();
+        if depth >= self.max_call_depth {
+            return Err(RuntimeErrorKind::CallTooDeep {
+                max_depth: self.max_call_depth,
+            }
+            .into());
+        }
+
+        self.call_depth.set(depth + 1);
+        self.record(TraceEvent::EnterScope);
+        Ok(mem::replace(&mut self.scopes, vec![frame]))
+    }
+
+    pub fn leave_function(&mut self, caller: Vec<Scope>) {
+        self.call_depth.set(self.call_depth.get() - 1);
+        self.scopes = caller;
+        self.record(TraceEvent::ExitScope);
+    }
+
+    /// Stop tracing and return everything recorded so far
+    pub fn take_trace(&mut self) -> Option<Trace> {
+        self.trace.take()
+    }
+
+    /// Mark the start of a new statement
+    pub fn begin_statement(&mut self, span: Span) -> Result<(), RuntimeError> {
+        if let Some(token) = &self.cancel_token {
+            token.check()?;
+        }
+
+        self.stats.statements += 1;
+
+        if let Some(trace) = &mut self.trace {
+            trace.begin_statement(span);
+        }
+
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(span);
+        }
+
+        Ok(())
+    }
+
+    /// Record that the value of a variable was changed in place
+    pub fn record_modification(&mut self, name: &str) {
+        let watched = matches!(&self.watcher, Some(watcher) if watcher.is_watched(name));
+        if self.trace.is_none() && !watched {
+            return;
+        }
+
+        if let Ok(hdl) = self.get(name) {
+            if let Some(watcher) = &mut self.watcher {
+                watcher.update(name, &hdl);
+            }
+
+            self.record(TraceEvent::Update {
+                name: name.to_string(),
+                value: Trace::value_of(&hdl),
+            });
+        }
+    }
+
+    fn record(&mut self, event: TraceEvent) {
+        if let Some(trace) = &mut self.trace {
+            trace.record(event);
         }
     }
 
     pub fn push(&mut self) {
-        self.scopes.push(Scope::default());
+        self.scopes.push(Scope::new(self.hash_state));
+        self.stats.peak_scope_depth = self.stats.peak_scope_depth.max(self.scopes.len());
+        self.record(TraceEvent::EnterScope);
+    }
+
+    /// Create a handle for a value (counted in `Stats::allocations`)
+    pub fn wrap_value(&mut self, value: Value) -> Handle {
+        self.stats.allocations += 1;
+        Handle::wrap_value(value)
+    }
+
+    /// The number of currently open scopes
+    pub fn depth(&self) -> usize {
+        self.scopes.len()
+    }
+
+    /// Close all scopes opened after `depth` (e.g., by a block that failed)
+    pub fn unwind(&mut self, depth: usize) {
+        while self.scopes.len() > depth {
+            self.pop();
+        }
     }
 
     pub fn pop(&mut self) {
         if self.scopes.len() > 1 {
             self.scopes.pop();
+            self.record(TraceEvent::ExitScope);
         } else {
             panic!("Cannot pop scope. Only one left");
         }
     }
 
-    pub fn get(&self, name: &str) -> Handle {
+    pub fn get(&self, name: &str) -> Result<Handle, RuntimeError> {
         for scope in self.scopes.iter().rev() {
             if let Some(m) = scope.modules.get(name) {
-                return Handle::Object(m.clone());
+                return Ok(Handle::Object(m.clone()));
             } else if let Some(v) = scope.variables.get(name) {
-                return v.try_clone();
+                if let Handle::Iter(_) = v {
+                    return Err(RuntimeErrorKind::StoredIterator(name.to_string()).into());
+                }
+
+                return Ok(v.try_clone());
             }
         }
 
-        panic!("No such value or module '{}'!", name);
+        // Variables and modules can shadow global functions
+        if let Some(function) = globals::get_global(name, &self.stdout, self.max_sleep) {
+            return Ok(function);
+        }
+
+        Err(RuntimeErrorKind::NoSuchVariable(name.to_string()).into())
+    }
+
+    /// Does the name refer to a global function (i.e., it is not shadowed)?
+    pub fn is_global_function(&self, name: &str) -> bool {
+        grammar::is_global_function(name)
+            && !self
+                .scopes
+                .iter()
+                .any(|scope| scope.modules.contains_key(name) || scope.variables.contains_key(name))
+    }
+
+    /// Get a module that was resolved by `Program::link`
+    pub fn get_linked_module(&self, index: usize) -> Result<Rc<dyn Module>, RuntimeError> {
+        match self.linked_modules.get(index) {
+            Some((_, module)) => Ok(module.clone()),
+            None => Err(RuntimeErrorKind::NoSuchVariable(format!("#{}", index)).into()),
+        }
     }
 
-    pub fn create_variable(&mut self, name: String, val: Handle) {
+    /// The name a module was registered with (if any)
+    pub fn module_name(&self, module: &Rc<dyn Module>) -> Option<&str> {
+        self.linked_modules
+            .iter()
+            .find(|(_, m)| Rc::ptr_eq(m, module))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Remember that a deprecated module member was used (once per member)
+    pub fn record_deprecation(&mut self, module: Option<String>, member: &str, message: String) {
+        let known = self
+            .deprecations
+            .iter()
+            .any(|entry| entry.module == module && entry.member == member);
+
+        if !known {
+            self.deprecations.push(Deprecation {
+                module,
+                member: member.to_string(),
+                message,
+            });
+        }
+    }
+
+    pub fn create_variable(&mut self, name: String, val: Handle) -> Result<(), RuntimeError> {
         let scope = self.scopes.last_mut().unwrap();
 
         match scope.variables.entry(name) {
             hash_map::Entry::Vacant(o) => {
+                if let Some(trace) = &mut self.trace {
+                    trace.record(TraceEvent::Create {
+                        name: o.key().clone(),
+                        value: Trace::value_of(&val),
+                    });
+                }
+
+                if let Some(watcher) = &mut self.watcher {
+                    watcher.update(o.key(), &val);
+                }
+
                 o.insert(val);
+                Ok(())
             }
             hash_map::Entry::Occupied(o) => {
-                panic!("Variable {} already existed!", o.key());
+                Err(RuntimeErrorKind::VariableAlreadyExists(o.key().clone()).into())
             }
         }
     }
 
-    pub fn update_variable(&mut self, name: &str, val: Handle) {
+    pub fn update_variable(&mut self, name: &str, val: Handle) -> Result<(), RuntimeError> {
         for scope in self.scopes.iter_mut().rev() {
             if let Some(var) = scope.variables.get_mut(name) {
+                if let Some(trace) = &mut self.trace {
+                    trace.record(TraceEvent::Update {
+                        name: name.to_string(),
+                        value: Trace::value_of(&val),
+                    });
+                }
+
+                if let Some(watcher) = &mut self.watcher {
+                    watcher.update(name, &val);
+                }
+
                 *var = val;
-                return;
+                return Ok(());
             }
         }
 
-        panic!("Cannot update variable '{}': did not exist", name);
+        Err(RuntimeErrorKind::NoSuchVariable(name.to_string()).into())
     }
 }
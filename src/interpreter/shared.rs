@@ -0,0 +1,53 @@
+use std::cell::{RefCell, RefMut};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::Interpreter;
+use crate::ast::Program;
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::Value;
+
+/// An interpreter that host functions can hold on to (see `Interpreter::into_shared`)
+///
+/// Running a program while the interpreter is already running (e.g., from a
+/// module function called by the program) fails with
+/// `RuntimeErrorKind::ReentrantRun` instead of seeing the interpreter in the
+/// middle of a run.
+#[derive(Clone)]
+pub struct SharedInterpreter {
+    inner: Rc<RefCell<Interpreter>>,
+}
+
+impl SharedInterpreter {
+    /// Access the interpreter, e.g., to register modules
+    ///
+    /// Fails if the interpreter is in use.
+    pub fn get(&self) -> Result<RefMut<'_, Interpreter>, RuntimeError> {
+        self.inner
+            .try_borrow_mut()
+            .map_err(|_| RuntimeErrorKind::ReentrantRun.into())
+    }
+
+    /// See `Interpreter::try_run`
+    pub fn try_run(&self, program: &Program) -> Result<Value, RuntimeError> {
+        self.get()?.try_run(program)
+    }
+
+    /// See `Interpreter::run_isolated`
+    pub fn run_isolated(
+        &self,
+        program: &Program,
+        inputs: HashMap<String, Value>,
+    ) -> Result<Value, RuntimeError> {
+        self.get()?.run_isolated(program, inputs)
+    }
+}
+
+impl Interpreter {
+    /// Share the interpreter, so that host functions can run programs on it
+    pub fn into_shared(self) -> SharedInterpreter {
+        SharedInterpreter {
+            inner: Rc::new(RefCell::new(self)),
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use std::time::Duration;
+
+/// Counters collected during the most recent run (see `Interpreter::stats`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Number of statements executed, including those inside of loops and blocks
+    pub statements: u64,
+    /// Number of handles created for intermediate results and variables
+    pub allocations: u64,
+    /// Number of calls to module functions (builtins are not counted)
+    pub module_calls: u64,
+    /// The largest number of nested scopes at any point
+    pub peak_scope_depth: usize,
+    /// How long the run took
+    pub wall_time: Duration,
+}
@@ -0,0 +1,258 @@
+use std::rc::Rc;
+
+use ndarray::{ArrayD, Ix1, Ix2, IxDyn};
+
+use super::{Callable, Handle, MemberInfo, MemberKind, Module};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::{Value, ValueError};
+
+/// Vector and matrix operations for scripts (requires the `ndarray` feature)
+///
+/// Vectors are lists of numbers and matrices are lists of rows (see `Value::to_array`).
+/// All results contain f64 values.
+///
+/// ```ignore
+/// interpreter.register_module("tensor".to_string(), Rc::new(TensorModule::default()));
+/// ```
+#[derive(Debug, Default)]
+pub struct TensorModule {}
+
+#[derive(Debug, Clone, Copy)]
+enum TensorFunction {
+    Zeros,
+    Ones,
+    Identity,
+    Shape,
+    Add,
+    Subtract,
+    Multiply,
+    Scale,
+    Dot,
+    Transpose,
+    Sum,
+}
+
+const FUNCTIONS: &[(&str, TensorFunction, &str, &str)] = &[
+    (
+        "zeros",
+        TensorFunction::Zeros,
+        "zeros(rows, [columns])",
+        "Creates a vector (or matrix) of zeros",
+    ),
+    (
+        "ones",
+        TensorFunction::Ones,
+        "ones(rows, [columns])",
+        "Creates a vector (or matrix) of ones",
+    ),
+    (
+        "identity",
+        TensorFunction::Identity,
+        "identity(size)",
+        "Creates an identity matrix",
+    ),
+    (
+        "shape",
+        TensorFunction::Shape,
+        "shape(tensor)",
+        "Returns the length of every dimension",
+    ),
+    (
+        "add",
+        TensorFunction::Add,
+        "add(lhs, rhs)",
+        "Adds two tensors of the same shape elementwise",
+    ),
+    (
+        "subtract",
+        TensorFunction::Subtract,
+        "subtract(lhs, rhs)",
+        "Subtracts two tensors of the same shape elementwise",
+    ),
+    (
+        "multiply",
+        TensorFunction::Multiply,
+        "multiply(lhs, rhs)",
+        "Multiplies two tensors of the same shape elementwise",
+    ),
+    (
+        "scale",
+        TensorFunction::Scale,
+        "scale(tensor, factor)",
+        "Multiplies every element with a number",
+    ),
+    (
+        "dot",
+        TensorFunction::Dot,
+        "dot(lhs, rhs)",
+        "Dot product of two vectors or matrix product",
+    ),
+    (
+        "transpose",
+        TensorFunction::Transpose,
+        "transpose(matrix)",
+        "Swaps rows and columns",
+    ),
+    (
+        "sum",
+        TensorFunction::Sum,
+        "sum(tensor)",
+        "Adds up all elements",
+    ),
+];
+
+impl Module for TensorModule {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match FUNCTIONS.iter().find(|(n, ..)| *n == name) {
+            Some((_, function, ..)) => Handle::Callable(Rc::new(*function)),
+            None => Handle::Error(
+                RuntimeErrorKind::NoSuchMember {
+                    module: "tensor".to_string(),
+                    member: name.to_string(),
+                }
+                .into(),
+            ),
+        }
+    }
+
+    fn has_member(&self, name: &str) -> bool {
+        FUNCTIONS.iter().any(|(n, ..)| *n == name)
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        FUNCTIONS
+            .iter()
+            .map(|(name, _, signature, doc)| MemberInfo {
+                name: name.to_string(),
+                kind: MemberKind::Function,
+                signature: Some(signature.to_string()),
+                doc: Some(doc.to_string()),
+            })
+            .collect()
+    }
+}
+
+fn invalid_argument(message: &str) -> RuntimeError {
+    RuntimeErrorKind::InvalidArgument(message.to_string()).into()
+}
+
+/// Convert a value into a length (or another non-negative integer)
+fn dimension(value: &Value) -> Result<usize, RuntimeError> {
+    let length: i64 = value.clone().try_into()?;
+    usize::try_from(length).map_err(|_| invalid_argument("dimensions cannot be negative"))
+}
+
+fn same_shape(lhs: &ArrayD<f64>, rhs: &ArrayD<f64>) -> Result<(), RuntimeError> {
+    if lhs.shape() == rhs.shape() {
+        Ok(())
+    } else {
+        Err(ValueError::ShapeMismatch.into())
+    }
+}
+
+fn dot(lhs: ArrayD<f64>, rhs: ArrayD<f64>) -> Result<Value, RuntimeError> {
+    let shape_error = |_| RuntimeError::from(ValueError::ShapeMismatch);
+
+    let result = match (lhs.ndim(), rhs.ndim()) {
+        (1, 1) => {
+            let lhs = lhs.into_dimensionality::<Ix1>().map_err(shape_error)?;
+            let rhs = rhs.into_dimensionality::<Ix1>().map_err(shape_error)?;
+            if lhs.len() != rhs.len() {
+                return Err(ValueError::ShapeMismatch.into());
+            }
+            Value::F64(lhs.dot(&rhs))
+        }
+        (2, 1) => {
+            let lhs = lhs.into_dimensionality::<Ix2>().map_err(shape_error)?;
+            let rhs = rhs.into_dimensionality::<Ix1>().map_err(shape_error)?;
+            if lhs.ncols() != rhs.len() {
+                return Err(ValueError::ShapeMismatch.into());
+            }
+            lhs.dot(&rhs).into()
+        }
+        (2, 2) => {
+            let lhs = lhs.into_dimensionality::<Ix2>().map_err(shape_error)?;
+            let rhs = rhs.into_dimensionality::<Ix2>().map_err(shape_error)?;
+            if lhs.ncols() != rhs.nrows() {
+                return Err(ValueError::ShapeMismatch.into());
+            }
+            lhs.dot(&rhs).into()
+        }
+        _ => return Err(ValueError::ShapeMismatch.into()),
+    };
+
+    Ok(result)
+}
+
+impl TensorFunction {
+    fn filled(args: &[Value], value: f64) -> Result<Value, RuntimeError> {
+        let shape = match args {
+            [rows] => vec![dimension(rows)?],
+            [rows, columns] => vec![dimension(rows)?, dimension(columns)?],
+            _ => return Err(invalid_argument("expected one or two dimensions")),
+        };
+
+        Ok(ArrayD::from_elem(IxDyn(&shape), value).into())
+    }
+
+    fn run(self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let result = match (self, args.as_slice()) {
+            (Self::Zeros, args) => Self::filled(args, 0.0)?,
+            (Self::Ones, args) => Self::filled(args, 1.0)?,
+            (Self::Identity, [size]) => ndarray::Array2::<f64>::eye(dimension(size)?).into(),
+            (Self::Shape, [tensor]) => {
+                let shape: Vec<Value> = tensor
+                    .to_array()?
+                    .shape()
+                    .iter()
+                    .map(|len| Value::from(*len))
+                    .collect();
+                Value::List(shape)
+            }
+            (Self::Add | Self::Subtract | Self::Multiply, [lhs, rhs]) => {
+                let (lhs, rhs) = (lhs.to_array()?, rhs.to_array()?);
+                same_shape(&lhs, &rhs)?;
+
+                match self {
+                    Self::Add => (lhs + rhs).into(),
+                    Self::Subtract => (lhs - rhs).into(),
+                    _ => (lhs * rhs).into(),
+                }
+            }
+            (Self::Scale, [tensor, factor]) => {
+                let factor: f64 = factor.clone().try_into()?;
+                (tensor.to_array()? * factor).into()
+            }
+            (Self::Dot, [lhs, rhs]) => dot(lhs.to_array()?, rhs.to_array()?)?,
+            (Self::Transpose, [matrix]) => {
+                let matrix = matrix.to_array()?;
+                if matrix.ndim() != 2 {
+                    return Err(invalid_argument("transpose() expects a matrix"));
+                }
+                matrix.reversed_axes().into()
+            }
+            (Self::Sum, [tensor]) => Value::F64(tensor.to_array()?.sum()),
+            _ => return Err(invalid_argument("wrong number of arguments")),
+        };
+
+        Ok(result)
+    }
+}
+
+impl Callable for TensorFunction {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        match self.run(args) {
+            Ok(result) => Handle::wrap_value(result),
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let args = Handle::into_values(args)?;
+        Ok(Handle::wrap_value(self.run(args)?))
+    }
+
+    fn is_pure(&self) -> bool {
+        true
+    }
+}
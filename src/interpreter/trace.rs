@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::Handle;
+use crate::ast::Span;
+use crate::values::Value;
+
+/// A change to the variables of a running program
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TraceEvent {
+    EnterScope,
+    ExitScope,
+    /// A variable was declared
+    ///
+    /// The value is `None` if the variable does not hold a value (e.g., a function).
+    Create {
+        name: String,
+        value: Option<Value>,
+    },
+    /// A variable was assigned to or modified in place
+    Update {
+        name: String,
+        value: Option<Value>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TraceEntry {
+    /// How many statements had started executing when the event happened
+    pub statement: usize,
+    /// The location of the statement that caused the event
+    pub span: Option<Span>,
+    pub event: TraceEvent,
+}
+
+/// A log of all variable changes during a run of the interpreter
+///
+/// Traces can be stored (they implement serde's traits) and later be
+/// used to inspect the state of a program at any point of its execution.
+///
+/// *Note:* Values that are shared between variables (e.g., `let b = a` for a list)
+/// are only recorded as modified for the variable they were modified through.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Trace {
+    entries: Vec<TraceEntry>,
+    num_statements: usize,
+    #[serde(skip)]
+    current_span: Option<Span>,
+}
+
+impl Trace {
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    /// The number of statements that were executed
+    pub fn num_statements(&self) -> usize {
+        self.num_statements
+    }
+
+    /// Reconstruct all variables (and their values) right before the
+    /// statement with the given index (in order of execution) started
+    pub fn replay(&self, statement: usize) -> HashMap<String, Value> {
+        let mut scopes: Vec<HashMap<String, Option<Value>>> = vec![HashMap::new()];
+
+        for entry in self.entries.iter() {
+            if entry.statement > statement {
+                break;
+            }
+
+            match &entry.event {
+                TraceEvent::EnterScope => scopes.push(HashMap::new()),
+                TraceEvent::ExitScope => {
+                    scopes.pop();
+                }
+                TraceEvent::Create { name, value } => {
+                    if let Some(scope) = scopes.last_mut() {
+                        scope.insert(name.clone(), value.clone());
+                    }
+                }
+                TraceEvent::Update { name, value } => {
+                    if let Some(var) = scopes.iter_mut().rev().find_map(|s| s.get_mut(name)) {
+                        *var = value.clone();
+                    }
+                }
+            }
+        }
+
+        let mut result = HashMap::new();
+
+        // Inner scopes shadow outer ones
+        for scope in scopes {
+            for (name, value) in scope {
+                match value {
+                    Some(value) => {
+                        result.insert(name, value);
+                    }
+                    None => {
+                        result.remove(&name);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    pub(super) fn begin_statement(&mut self, span: Span) {
+        self.num_statements += 1;
+        self.current_span = Some(span);
+    }
+
+    pub(super) fn record(&mut self, event: TraceEvent) {
+        self.entries.push(TraceEntry {
+            statement: self.num_statements,
+            span: self.current_span,
+            event,
+        });
+    }
+
+    /// The value a handle points to (if any) for recording it
+    pub(super) fn value_of(hdl: &Handle) -> Option<Value> {
+        match hdl {
+            Handle::Value(value) => Some(Handle::read_value(value)),
+            _ => None,
+        }
+    }
+}
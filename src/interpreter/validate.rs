@@ -0,0 +1,304 @@
+use std::net::IpAddr;
+use std::rc::Rc;
+
+use super::{Callable, Handle, MemberInfo, MemberKind, Module};
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::values::{MapEntries, Value};
+
+/// Checks user input, such as email addresses, URLs, and IP addresses
+///
+/// `email`, `url`, and `ip` return whether the input is valid. The `parse_`
+/// variants split valid input into a map of its parts and fail otherwise.
+///
+/// The checks are meant for form validation and deliberately stricter
+/// than the corresponding RFCs (e.g., quoted local parts are not allowed).
+#[derive(Debug, Default)]
+pub struct ValidateModule {}
+
+#[derive(Debug, Clone, Copy)]
+enum ValidateFunction {
+    Email,
+    Url,
+    Ip,
+    ParseEmail,
+    ParseUrl,
+    ParseIp,
+}
+
+const FUNCTIONS: &[(&str, ValidateFunction, &str, &str)] = &[
+    (
+        "email",
+        ValidateFunction::Email,
+        "email(text)",
+        "Checks whether a string is an email address",
+    ),
+    (
+        "url",
+        ValidateFunction::Url,
+        "url(text)",
+        "Checks whether a string is an absolute URL (e.g., https://example.com/path)",
+    ),
+    (
+        "ip",
+        ValidateFunction::Ip,
+        "ip(text)",
+        "Checks whether a string is an IPv4 or IPv6 address",
+    ),
+    (
+        "parse_email",
+        ValidateFunction::ParseEmail,
+        "parse_email(text)",
+        "Splits an email address into 'local' and 'domain'",
+    ),
+    (
+        "parse_url",
+        ValidateFunction::ParseUrl,
+        "parse_url(text)",
+        "Splits a URL into 'scheme', 'host', 'port', 'path', 'query', and 'fragment'",
+    ),
+    (
+        "parse_ip",
+        ValidateFunction::ParseIp,
+        "parse_ip(text)",
+        "Gets the 'version' (4 or 6) and the canonical form ('address') of an IP address",
+    ),
+];
+
+/// Characters allowed in the local part of an email address (besides letters and digits)
+const EMAIL_SPECIAL_CHARS: &str = "!#$%&'*+/=?^_`{|}~.-";
+
+impl Module for ValidateModule {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match FUNCTIONS.iter().find(|(n, ..)| *n == name) {
+            Some((_, function, ..)) => Handle::Callable(Rc::new(*function)),
+            None => Handle::Error(
+                RuntimeErrorKind::NoSuchMember {
+                    module: "validate".to_string(),
+                    member: name.to_string(),
+                }
+                .into(),
+            ),
+        }
+    }
+
+    fn has_member(&self, name: &str) -> bool {
+        FUNCTIONS.iter().any(|(n, ..)| *n == name)
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        FUNCTIONS
+            .iter()
+            .map(|(name, _, signature, doc)| MemberInfo {
+                name: name.to_string(),
+                kind: MemberKind::Function,
+                signature: Some(signature.to_string()),
+                doc: Some(doc.to_string()),
+            })
+            .collect()
+    }
+}
+
+/// A part of a domain name
+fn is_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= 63
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// A domain name with at least two labels and an alphabetic top-level domain
+fn is_domain(domain: &str) -> bool {
+    let labels: Vec<&str> = domain.split('.').collect();
+    let tld = labels[labels.len() - 1];
+
+    domain.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| is_label(label))
+        && tld.len() >= 2
+        && tld.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// A host name (e.g., "localhost"), an IPv4 address, or an IPv6 address in brackets
+fn is_host(host: &str) -> bool {
+    if let Some(address) = host.strip_prefix('[').and_then(|h| h.strip_suffix(']')) {
+        return matches!(address.parse(), Ok(IpAddr::V6(_)));
+    }
+
+    // Names that only consist of digits must be IPv4 addresses
+    if host.chars().all(|c| c.is_ascii_digit() || c == '.') {
+        return matches!(host.parse(), Ok(IpAddr::V4(_)));
+    }
+
+    host.len() <= 253 && host.split('.').all(is_label)
+}
+
+fn parse_email(text: &str) -> Option<(&str, &str)> {
+    let (local, domain) = text.rsplit_once('@')?;
+
+    let valid_local = !local.is_empty()
+        && local.len() <= 64
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || EMAIL_SPECIAL_CHARS.contains(c));
+
+    if valid_local && is_domain(domain) {
+        Some((local, domain))
+    } else {
+        None
+    }
+}
+
+struct Url<'a> {
+    scheme: &'a str,
+    host: &'a str,
+    port: Option<u16>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+fn parse_url(text: &str) -> Option<Url<'_>> {
+    if text.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return None;
+    }
+
+    let (scheme, rest) = text.split_once("://")?;
+
+    let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+
+    if !valid_scheme {
+        return None;
+    }
+
+    let (rest, fragment) = match rest.split_once('#') {
+        Some((rest, fragment)) => (rest, Some(fragment)),
+        None => (rest, None),
+    };
+
+    let (rest, query) = match rest.split_once('?') {
+        Some((rest, query)) => (rest, Some(query)),
+        None => (rest, None),
+    };
+
+    let (authority, path) = match rest.find('/') {
+        Some(pos) => rest.split_at(pos),
+        None => (rest, ""),
+    };
+
+    // Ignore user information (e.g., "user:password@")
+    let authority = match authority.rsplit_once('@') {
+        Some((_, authority)) => authority,
+        None => authority,
+    };
+
+    // The colon of the port comes after the closing bracket of IPv6 addresses
+    let (host, port) = match authority.rfind(':') {
+        Some(pos) if !authority[pos..].contains(']') => {
+            let port = authority[pos + 1..].parse().ok()?;
+            (&authority[..pos], Some(port))
+        }
+        _ => (authority, None),
+    };
+
+    if !is_host(host) {
+        return None;
+    }
+
+    Some(Url {
+        scheme,
+        host,
+        port,
+        path,
+        query,
+        fragment,
+    })
+}
+
+fn make_map(entries: Vec<(&str, Value)>) -> Value {
+    let entries: MapEntries = entries
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+
+    Value::Map(Box::new(entries))
+}
+
+fn invalid(kind: &str, text: &str) -> RuntimeError {
+    RuntimeErrorKind::InvalidArgument(format!("not a valid {kind}: {text}")).into()
+}
+
+impl ValidateFunction {
+    fn run(self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let mut args = args.into_iter();
+
+        let text: String = match (args.next(), args.next()) {
+            (Some(text), None) => text.try_into()?,
+            _ => {
+                return Err(RuntimeErrorKind::InvalidArgument(
+                    "expected exactly one argument".to_string(),
+                )
+                .into())
+            }
+        };
+
+        let result = match self {
+            Self::Email => parse_email(&text).is_some().into(),
+            Self::Url => parse_url(&text).is_some().into(),
+            Self::Ip => text.parse::<IpAddr>().is_ok().into(),
+            Self::ParseEmail => {
+                let (local, domain) = parse_email(&text).ok_or_else(|| invalid("email", &text))?;
+                make_map(vec![("local", local.into()), ("domain", domain.into())])
+            }
+            Self::ParseUrl => {
+                let url = parse_url(&text).ok_or_else(|| invalid("URL", &text))?;
+                let optional = |part: Option<&str>| part.map(Value::from).unwrap_or(Value::None);
+                let port = url.port.map(|port| Value::I64(port.into()));
+
+                make_map(vec![
+                    ("scheme", url.scheme.to_ascii_lowercase().into()),
+                    ("host", url.host.into()),
+                    ("port", port.unwrap_or(Value::None)),
+                    ("path", url.path.into()),
+                    ("query", optional(url.query)),
+                    ("fragment", optional(url.fragment)),
+                ])
+            }
+            Self::ParseIp => {
+                let address: IpAddr = text.parse().map_err(|_| invalid("IP address", &text))?;
+                let version: i64 = if address.is_ipv4() { 4 } else { 6 };
+
+                make_map(vec![
+                    ("version", version.into()),
+                    ("address", address.to_string().into()),
+                ])
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+impl Callable for ValidateFunction {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        match self.run(args) {
+            Ok(result) => Handle::wrap_value(result),
+            Err(err) => Handle::Error(err),
+        }
+    }
+
+    fn call_with_handles(&self, args: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let args = Handle::into_values(args)?;
+        Ok(Handle::wrap_value(self.run(args)?))
+    }
+
+    fn is_pure(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::hashing::SeededHashMap;
+use super::trace::Trace;
+use super::Handle;
+use crate::values::Value;
+
+type WatchCallback = dyn Fn(&str, Option<&Value>, &Value);
+
+/// Reports changes of watched variables to the host (see `Interpreter::watch_variables`)
+#[derive(Clone)]
+pub(super) struct Watcher {
+    callback: Rc<WatchCallback>,
+    /// The last value reported for each watched variable (`None` if it does not exist yet)
+    values: HashMap<String, Option<Value>>,
+}
+
+impl Watcher {
+    pub fn new(names: &[&str], callback: Rc<WatchCallback>) -> Self {
+        let values = names.iter().map(|name| (name.to_string(), None)).collect();
+        Self { callback, values }
+    }
+
+    /// Remember the variables that exist before the program starts (these are not reported)
+    pub fn begin_run(&mut self, variables: &SeededHashMap<String, Handle>) {
+        for (name, last) in self.values.iter_mut() {
+            *last = variables.get(name).and_then(Trace::value_of);
+        }
+    }
+
+    pub fn is_watched(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// Call the host if the variable is watched and its value changed
+    pub fn update(&mut self, name: &str, hdl: &Handle) {
+        let Some(last) = self.values.get_mut(name) else {
+            return;
+        };
+
+        // Functions and modules are not values
+        let Some(value) = Trace::value_of(hdl) else {
+            return;
+        };
+
+        if last.as_ref() != Some(&value) {
+            (self.callback)(name, last.as_ref(), &value);
+            *last = Some(value);
+        }
+    }
+}
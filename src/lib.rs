@@ -13,9 +13,29 @@
 
 pub mod ast;
 
+pub mod grammar;
+
+mod error;
+pub use error::{CompileError, Error, MigrationError, RuntimeError, RuntimeErrorKind};
+
+#[cfg(feature = "msgpack")]
+pub use error::DecodeError;
+
+#[cfg(feature = "msgpack")]
+mod msgpack;
+
+mod source;
+pub use source::NamedSource;
+
+mod examples;
+pub use examples::{example, examples, Example};
+
 mod values;
 pub use values::*;
 
+#[cfg(any(feature = "compiler", feature = "interpreter"))]
+mod format;
+
 #[cfg(feature = "compiler")]
 mod compiler;
 #[cfg(feature = "compiler")]
@@ -25,3 +45,12 @@ pub use compiler::*;
 pub mod interpreter;
 #[cfg(feature = "interpreter")]
 pub use interpreter::{Interpreter, Module};
+
+#[cfg(all(feature = "compiler", feature = "interpreter"))]
+pub mod testing;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+#[cfg(feature = "async")]
+pub mod tokio;
@@ -0,0 +1,96 @@
+//! MessagePack encoding of programs and values (requires the `msgpack` feature)
+//!
+//! Encoded data starts with a magic number and the format version it was
+//! written with, so that data of other (or newer) crate versions is detected
+//! before decoding it.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::ast::{Program, VersionedProgram, PROGRAM_VERSION};
+use crate::error::DecodeError;
+use crate::values::Value;
+
+const PROGRAM_MAGIC: &[u8; 4] = b"COWP";
+const VALUE_MAGIC: &[u8; 4] = b"COWV";
+
+/// The version of the value format produced by this version of the crate
+///
+/// Increment this whenever the layout of `Value` changes.
+const VALUE_VERSION: u32 = 1;
+
+const HEADER_LENGTH: usize = 8;
+
+fn encode<T: Serialize>(magic: &[u8; 4], version: u32, data: &T) -> Vec<u8> {
+    let mut result = Vec::with_capacity(HEADER_LENGTH);
+    result.extend_from_slice(magic);
+    result.extend_from_slice(&version.to_le_bytes());
+
+    // Writing to a vector only fails for types serde cannot represent
+    rmp_serde::encode::write(&mut result, data).expect("Failed to encode data");
+    result
+}
+
+/// Check the header and return the version the data was written with
+fn decode<T: DeserializeOwned>(
+    magic: &[u8; 4],
+    current_version: u32,
+    data: &[u8],
+) -> Result<(u32, T), DecodeError> {
+    if data.len() < HEADER_LENGTH || &data[..4] != magic {
+        return Err(DecodeError::InvalidHeader);
+    }
+
+    let version = u32::from_le_bytes(data[4..HEADER_LENGTH].try_into().unwrap());
+    if version == 0 || version > current_version {
+        return Err(DecodeError::UnknownVersion(version));
+    }
+
+    let result = rmp_serde::from_slice(&data[HEADER_LENGTH..])
+        .map_err(|err| DecodeError::Malformed(err.to_string()))?;
+
+    Ok((version, result))
+}
+
+impl Program {
+    /// Serialize the program, e.g., to run it on another machine
+    ///
+    /// Comments are not stored (see `Program::comments`).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        encode(
+            PROGRAM_MAGIC,
+            PROGRAM_VERSION,
+            &VersionedProgram::from(self.clone()),
+        )
+    }
+
+    /// Load a program serialized by `Program::to_bytes`
+    ///
+    /// Programs stored by earlier versions of this crate are migrated.
+    pub fn from_bytes(data: &[u8]) -> Result<Program, DecodeError> {
+        let (version, program): (u32, VersionedProgram) =
+            decode(PROGRAM_MAGIC, PROGRAM_VERSION, data)?;
+
+        if program.version() != version {
+            return Err(DecodeError::Malformed(format!(
+                "header has version {version}, but the program has version {}",
+                program.version()
+            )));
+        }
+
+        Ok(program.into_program()?)
+    }
+}
+
+impl Value {
+    /// Serialize the value using MessagePack
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        encode(VALUE_MAGIC, VALUE_VERSION, self)
+    }
+
+    /// Load a value serialized by `Value::to_msgpack`
+    pub fn from_msgpack(data: &[u8]) -> Result<Value, DecodeError> {
+        let (_, value) = decode(VALUE_MAGIC, VALUE_VERSION, data)?;
+        Ok(value)
+    }
+}
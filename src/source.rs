@@ -0,0 +1,46 @@
+/// Source code of a script together with a name identifying it (e.g., its file name)
+///
+/// Hosts that run many scripts can use this to report where an error occurred.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedSource {
+    name: String,
+    source: String,
+}
+
+impl NamedSource {
+    pub fn new<N: Into<String>, S: Into<String>>(name: N, source: S) -> Self {
+        Self {
+            name: name.into(),
+            source: source.into(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the line and column (both starting at 1) of a position in the source
+    pub fn line_col(&self, position: usize) -> (usize, usize) {
+        let mut line = 1;
+        let mut column = 1;
+
+        for (pos, c) in self.source.char_indices() {
+            if pos >= position {
+                break;
+            }
+
+            if c == '\n' {
+                line += 1;
+                column = 1;
+            } else {
+                column += 1;
+            }
+        }
+
+        (line, column)
+    }
+}
@@ -0,0 +1,84 @@
+//! Run tests written in cowlang
+//!
+//! Test scripts use `assert()` to check their results, e.g.,
+//! `assert(1 + 1 == 2, "math is broken")`. For now, every file is a single test;
+//! discovering individual `test_*` functions requires function definitions.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{compile_named, Error, Interpreter};
+
+/// The outcome of a single test
+#[derive(Debug)]
+pub struct TestResult {
+    pub name: String,
+    /// Why the test failed (`None` if it passed)
+    pub error: Option<Error>,
+}
+
+impl TestResult {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The outcome of all tests that were run
+#[derive(Debug, Default)]
+pub struct TestReport {
+    pub results: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn num_passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed()).count()
+    }
+
+    pub fn num_failed(&self) -> usize {
+        self.results.len() - self.num_passed()
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.num_failed() == 0
+    }
+
+    /// A human-readable summary, listing every failed test
+    pub fn summary(&self) -> String {
+        let mut result = format!("{} passed, {} failed", self.num_passed(), self.num_failed());
+
+        for test in self.results.iter() {
+            if let Some(err) = &test.error {
+                result += &format!("\n{}: {}", test.name, err);
+            }
+        }
+
+        result
+    }
+}
+
+/// Run a test script with the given interpreter
+///
+/// Use this if the script requires modules or values set up by the host.
+pub fn run_test(name: &str, source: &str, mut interpreter: Interpreter) -> TestResult {
+    let result = compile_named(name, source)
+        .map_err(Error::from)
+        .and_then(|program| interpreter.try_run(&program).map_err(Error::from));
+
+    TestResult {
+        name: name.to_string(),
+        error: result.err(),
+    }
+}
+
+/// Load and run the test script at the given path
+pub fn run_test_file<P: AsRef<Path>>(path: P) -> io::Result<TestReport> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+
+    let result = run_test(&path.display().to_string(), &source, Interpreter::default());
+
+    Ok(TestReport {
+        results: vec![result],
+    })
+}
@@ -0,0 +1,152 @@
+//! Running programs inside a tokio runtime (requires the `async` feature)
+//!
+//! Programs started with `run_blocking` can call async functions of modules (see
+//! `AsyncFunction`), which are awaited on the runtime. Their calls to `sleep()`
+//! wait on the runtime's timer, so they can be cancelled.
+//!
+//! The interpreter itself is synchronous, so this is a blocking bridge: the
+//! program never yields to the calling task. `run_blocking` occupies the current
+//! worker thread until the program finished, after handing the thread's other
+//! tasks to the rest of the runtime (see `tokio::task::block_in_place`), so they
+//! keep making progress while the program runs or waits for an async function.
+//! This requires the multi-threaded runtime.
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use ::tokio::runtime::{Handle as RuntimeHandle, RuntimeFlavor};
+use ::tokio::{task, time};
+
+use crate::ast::Program;
+use crate::interpreter::{Callable, CancelToken, Handle};
+use crate::{Interpreter, RuntimeError, RuntimeErrorKind, Value};
+
+/// How often a pending async call checks whether the program was cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The future returned by `AsyncCallable::call`
+pub type AsyncResult = Pin<Box<dyn Future<Output = Result<Value, RuntimeError>>>>;
+
+/// A module function that is awaited instead of blocking the thread
+pub trait AsyncCallable {
+    fn call(&self, args: Vec<Value>) -> AsyncResult;
+}
+
+/// Turns an `AsyncCallable` into a module member
+///
+/// Calls fail with `RuntimeErrorKind::NotAsync` unless the program was started by `run_blocking`.
+pub struct AsyncFunction<F: AsyncCallable>(pub F);
+
+impl<F: AsyncCallable> Callable for AsyncFunction<F> {
+    fn call(&self, args: Vec<Value>) -> Handle {
+        match block_on(self.0.call(args)) {
+            Ok(value) => Handle::wrap_value(value),
+            Err(err) => Handle::Error(err),
+        }
+    }
+}
+
+struct Context {
+    runtime: RuntimeHandle,
+    cancel_token: Option<CancelToken>,
+}
+
+thread_local! {
+    static CONTEXT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+/// Restores the context of an enclosing run (if any) once the program finished
+struct ContextGuard {
+    previous: Option<Context>,
+}
+
+impl ContextGuard {
+    fn enter(context: Context) -> Self {
+        let previous = CONTEXT.with(|current| current.replace(Some(context)));
+        Self { previous }
+    }
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        CONTEXT.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+/// Run the program on the current worker thread and block until it finished
+///
+/// Async functions and `sleep()` are awaited on the runtime, while other tasks move to
+/// the remaining worker threads. If the interpreter has a cancel token (see
+/// `Interpreter::set_cancel_token`), cancelling it also stops pending async calls.
+///
+/// Must be called from a task of a multi-threaded runtime and fails with
+/// `RuntimeErrorKind::NotAsync` otherwise.
+pub fn run_blocking(
+    program: &Program,
+    interpreter: &mut Interpreter,
+) -> Result<Value, RuntimeError> {
+    let Ok(runtime) = RuntimeHandle::try_current() else {
+        return Err(RuntimeErrorKind::NotAsync.into());
+    };
+
+    if runtime.runtime_flavor() != RuntimeFlavor::MultiThread {
+        return Err(RuntimeErrorKind::NotAsync.into());
+    }
+
+    let context = Context {
+        runtime,
+        cancel_token: interpreter.cancel_token().cloned(),
+    };
+
+    task::block_in_place(|| {
+        let _guard = ContextGuard::enter(context);
+        interpreter.try_run(program)
+    })
+}
+
+/// Wait for the future on the runtime of the current run
+fn block_on<T>(future: impl Future<Output = Result<T, RuntimeError>>) -> Result<T, RuntimeError> {
+    let context = CONTEXT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map(|context| (context.runtime.clone(), context.cancel_token.clone()))
+    });
+
+    let Some((runtime, cancel_token)) = context else {
+        return Err(RuntimeErrorKind::NotAsync.into());
+    };
+
+    runtime.block_on(async move {
+        ::tokio::select! {
+            result = future => result,
+            _ = cancelled(cancel_token) => Err(RuntimeErrorKind::Cancelled.into()),
+        }
+    })
+}
+
+async fn cancelled(cancel_token: Option<CancelToken>) {
+    let Some(cancel_token) = cancel_token else {
+        return std::future::pending().await;
+    };
+
+    while !cancel_token.is_cancelled() {
+        time::sleep(CANCEL_POLL_INTERVAL).await;
+    }
+}
+
+/// Wait on the runtime's timer (returns `None` if the program was not started by `run_blocking`)
+pub(crate) fn sleep(duration: Duration) -> Option<Result<(), RuntimeError>> {
+    let running = CONTEXT.with(|current| current.borrow().is_some());
+
+    if running {
+        Some(block_on(async move {
+            time::sleep(duration).await;
+            Ok(())
+        }))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,87 @@
+use ndarray::{ArrayD, Dimension, IxDyn};
+
+use super::numeric::Number;
+use super::{Value, ValueError};
+
+/// Conversions between (nested) lists of numbers and arrays (requires the `ndarray` feature)
+///
+/// Vectors are lists of numbers and matrices are lists of rows. All elements
+/// are converted to f64, so each number is copied exactly once.
+impl Value {
+    /// Convert a number or a (nested) list of numbers into an array
+    ///
+    /// All lists at the same depth must have the same length.
+    pub fn to_array(&self) -> Result<ArrayD<f64>, ValueError> {
+        let shape = self.array_shape();
+        let mut elements = Vec::with_capacity(shape.iter().product());
+
+        self.flatten_into(&shape, &mut elements)?;
+
+        // The number of elements always matches the shape
+        Ok(ArrayD::from_shape_vec(IxDyn(&shape), elements).unwrap())
+    }
+
+    /// The shape of a nested list, based on the first element at every depth
+    fn array_shape(&self) -> Vec<usize> {
+        let mut shape = vec![];
+        let mut current = self.unfrozen();
+
+        while let Value::List(elements) = current {
+            shape.push(elements.len());
+
+            match elements.first() {
+                Some(first) => current = first.unfrozen(),
+                None => break,
+            }
+        }
+
+        shape
+    }
+
+    fn flatten_into(&self, shape: &[usize], out: &mut Vec<f64>) -> Result<(), ValueError> {
+        match (self.unfrozen(), shape.split_first()) {
+            (Value::List(elements), Some((len, rest))) => {
+                if elements.len() != *len {
+                    return Err(ValueError::ShapeMismatch);
+                }
+
+                for element in elements.iter() {
+                    element.flatten_into(rest, out)?;
+                }
+
+                Ok(())
+            }
+            (Value::List(_), None) => Err(ValueError::ShapeMismatch),
+            (other, None) => match Number::from_value(other) {
+                Some(number) => {
+                    out.push(number.as_float());
+                    Ok(())
+                }
+                None => Err(ValueError::type_mismatch("a number", other)),
+            },
+            (_, Some(_)) => Err(ValueError::ShapeMismatch),
+        }
+    }
+}
+
+/// Arrays become (nested) lists of f64 values, and arrays without dimensions become a single f64
+impl<D: Dimension> From<ndarray::Array<f64, D>> for Value {
+    fn from(array: ndarray::Array<f64, D>) -> Self {
+        let array = array.into_dyn();
+
+        if array.ndim() == 0 {
+            return Value::F64(array.into_iter().next().unwrap_or_default());
+        }
+
+        if array.ndim() == 1 {
+            return Value::List(array.into_iter().map(Value::F64).collect());
+        }
+
+        Value::List(
+            array
+                .outer_iter()
+                .map(|row| Value::from(row.to_owned()))
+                .collect(),
+        )
+    }
+}
@@ -0,0 +1,233 @@
+use serde::{Deserialize, Serialize};
+
+use super::{PrimitiveType, Value, ValueError};
+
+/// How to handle integers that do not fit into the target type
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum ConversionMode {
+    /// Fail with `ValueError::IntegerOverflow`
+    #[default]
+    Checked,
+    /// Use the closest value the target type can hold
+    Saturating,
+    /// Keep the lowest bits (like `as` in Rust)
+    Wrapping,
+}
+
+/// How to turn a float into an integer
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Fail with `ValueError::NotAnInteger` if the float has a fractional part
+    #[default]
+    Exact,
+    /// Round towards zero
+    Truncate,
+    /// Round towards negative infinity
+    Floor,
+    /// Round towards positive infinity
+    Ceil,
+    /// Round to the nearest integer (and away from zero at .5)
+    Round,
+}
+
+impl Value {
+    /// Convert a float into an i64 (integers are returned as they are)
+    pub fn to_integer(&self, rounding: Rounding) -> Result<Value, ValueError> {
+        let float = match self.unfrozen() {
+            Value::F64(f) => *f,
+            Value::F32(f) => *f as f64,
+            Value::U8(_) | Value::I64(_) | Value::U64(_) => return Ok(self.unfrozen().clone()),
+            other => return Err(ValueError::type_mismatch("f64", other)),
+        };
+
+        let integer = round_float(float, rounding)?;
+        Ok(Value::I64(convert_integer(
+            integer,
+            ConversionMode::Checked,
+        )?))
+    }
+
+    /// Convert this value into another primitive type
+    ///
+    /// Integers can be converted into any other numeric type. The mode
+    /// decides what happens if an integer does not fit into the target type.
+    ///
+    /// Decimals can also be created from floats and strings (e.g., "1.25"),
+    /// and converted into floats, strings, and (if they are whole) integers.
+    pub fn convert_to(
+        &self,
+        target: PrimitiveType,
+        mode: ConversionMode,
+    ) -> Result<Value, ValueError> {
+        let value = self.unfrozen();
+
+        #[cfg(feature = "decimal")]
+        if let Some(result) = decimal::convert(value, target) {
+            return result;
+        }
+
+        let integer = match (target, value) {
+            (_, Value::U8(i)) => *i as i128,
+            (_, Value::I64(i)) => *i as i128,
+            (_, Value::U64(i)) => *i as i128,
+            (PrimitiveType::U8 | PrimitiveType::I64 | PrimitiveType::U64, Value::F64(f)) => {
+                round_float(*f, Rounding::Exact)?
+            }
+            (PrimitiveType::U8 | PrimitiveType::I64 | PrimitiveType::U64, Value::F32(f)) => {
+                round_float(*f as f64, Rounding::Exact)?
+            }
+            #[cfg(feature = "decimal")]
+            (PrimitiveType::U8 | PrimitiveType::I64 | PrimitiveType::U64, Value::Decimal(d)) => {
+                decimal::to_integer(*d)?
+            }
+            _ => {
+                return match (target, value) {
+                    (PrimitiveType::Any, _)
+                    | (PrimitiveType::F64, Value::F64(_))
+                    | (PrimitiveType::F32, Value::F32(_))
+                    | (PrimitiveType::Bool, Value::Bool(_))
+                    | (PrimitiveType::String, Value::Str(_)) => Ok(value.clone()),
+                    (PrimitiveType::F64, Value::F32(f)) => Ok((*f as f64).into()),
+                    (PrimitiveType::F32, Value::F64(f)) => Ok((*f as f32).into()),
+                    (PrimitiveType::String, _) => Ok(value.to_canonical_string()?.into()),
+                    _ => Err(ValueError::OperationNotSupported),
+                };
+            }
+        };
+
+        let result = match target {
+            PrimitiveType::Any => value.clone(),
+            PrimitiveType::U8 => Value::U8(convert_integer(integer, mode)?),
+            PrimitiveType::I64 => Value::I64(convert_integer(integer, mode)?),
+            PrimitiveType::U64 => Value::U64(convert_integer(integer, mode)?),
+            PrimitiveType::F64 => Value::F64(integer as f64),
+            PrimitiveType::F32 => Value::F32(integer as f32),
+            PrimitiveType::String => value.to_canonical_string()?.into(),
+            #[cfg(feature = "decimal")]
+            PrimitiveType::Decimal => {
+                Value::Decimal(rust_decimal::Decimal::from_i128_with_scale(integer, 0))
+            }
+            #[cfg(not(feature = "decimal"))]
+            PrimitiveType::Decimal => return Err(ValueError::OperationNotSupported),
+            PrimitiveType::None | PrimitiveType::Bool => {
+                return Err(ValueError::OperationNotSupported);
+            }
+        };
+
+        Ok(result)
+    }
+}
+
+#[cfg(feature = "decimal")]
+mod decimal {
+    use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+    use rust_decimal::Decimal;
+
+    use super::{PrimitiveType, Value, ValueError};
+
+    /// Conversions from and to decimals that do not go through an integer
+    pub(super) fn convert(
+        value: &Value,
+        target: PrimitiveType,
+    ) -> Option<Result<Value, ValueError>> {
+        let result = match (target, value) {
+            (PrimitiveType::Decimal, Value::Decimal(_)) => Ok(value.clone()),
+            (PrimitiveType::Decimal, Value::F64(f)) => from_float(*f),
+            (PrimitiveType::Decimal, Value::F32(f)) => from_float(*f as f64),
+            (PrimitiveType::Decimal, Value::Str(s)) => Decimal::from_str_exact(s.trim())
+                .map(Value::Decimal)
+                .map_err(|_| ValueError::InvalidDecimal(s.clone())),
+            (PrimitiveType::F64, Value::Decimal(d)) => Ok(Value::F64(d.to_f64()?)),
+            (PrimitiveType::F32, Value::Decimal(d)) => Ok(Value::F32(d.to_f32()?)),
+            _ => return None,
+        };
+
+        Some(result)
+    }
+
+    fn from_float(f: f64) -> Result<Value, ValueError> {
+        // Uses the shortest representation of the float, so 0.1 becomes 0.1 (and not 0.1000000000000000055...)
+        Decimal::from_f64(f)
+            .map(Value::Decimal)
+            .ok_or_else(|| ValueError::InvalidDecimal(format!("{f:?}")))
+    }
+
+    /// Convert a decimal without a fractional part
+    pub(super) fn to_integer(d: Decimal) -> Result<i128, ValueError> {
+        if !d.fract().is_zero() {
+            return Err(ValueError::NotAnInteger);
+        }
+
+        // Decimals have at most 96 bits, so this always fits
+        Ok(d.to_i128().unwrap())
+    }
+}
+
+fn round_float(float: f64, rounding: Rounding) -> Result<i128, ValueError> {
+    if !float.is_finite() {
+        return Err(ValueError::NotAnInteger);
+    }
+
+    let rounded = match rounding {
+        Rounding::Exact => {
+            if float.fract() != 0.0 {
+                return Err(ValueError::NotAnInteger);
+            }
+            float
+        }
+        Rounding::Truncate => float.trunc(),
+        Rounding::Floor => float.floor(),
+        Rounding::Ceil => float.ceil(),
+        Rounding::Round => float.round(),
+    };
+
+    // Saturates for floats outside of the i128 range, which are too large for any target
+    Ok(rounded as i128)
+}
+
+/// Convert an integer into a smaller integer type
+fn convert_integer<T>(integer: i128, mode: ConversionMode) -> Result<T, ValueError>
+where
+    T: TryFrom<i128> + Into<i128> + Bounded,
+{
+    if let Ok(result) = T::try_from(integer) {
+        return Ok(result);
+    }
+
+    match mode {
+        ConversionMode::Checked => Err(ValueError::IntegerOverflow),
+        ConversionMode::Saturating => {
+            if integer < T::MIN.into() {
+                Ok(T::MIN)
+            } else {
+                Ok(T::MAX)
+            }
+        }
+        ConversionMode::Wrapping => Ok(T::wrap(integer)),
+    }
+}
+
+/// The integer types values can be converted into
+trait Bounded: Sized {
+    const MIN: Self;
+    const MAX: Self;
+
+    fn wrap(integer: i128) -> Self;
+}
+
+macro_rules! impl_bounded {
+    ($($t:ty),*) => {
+        $(
+            impl Bounded for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn wrap(integer: i128) -> Self {
+                    integer as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_bounded!(u8, i64, u64);
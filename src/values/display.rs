@@ -0,0 +1,152 @@
+use std::fmt::{self, Write};
+
+use super::Value;
+
+/// Renders values for end users, similar to Python and JSON
+///
+/// Strings are written as they are, unless they are nested in another value.
+/// Map entries are sorted by key, so the output is the same on every run.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unfrozen() {
+            Value::Str(content) => f.write_str(content),
+            other => other.write_nested(f, None, 0),
+        }
+    }
+}
+
+impl Value {
+    /// Render the value like `Display`, but put every element of a
+    /// (non-empty) list, tuple, array, or map on a line of its own
+    ///
+    /// Nested values are indented by `indent` spaces per level.
+    pub fn pretty(&self, indent: usize) -> String {
+        let mut result = String::new();
+
+        match self.unfrozen() {
+            Value::Str(content) => result.push_str(content),
+            other => {
+                // Writing to a string cannot fail
+                other.write_nested(&mut result, Some(indent), 0).unwrap();
+            }
+        }
+
+        result
+    }
+
+    /// Write the value as it appears inside of another value
+    ///
+    /// If `indent` is set, elements go on separate lines (see `Value::pretty`).
+    fn write_nested<W: Write>(
+        &self,
+        out: &mut W,
+        indent: Option<usize>,
+        level: usize,
+    ) -> fmt::Result {
+        match self.unfrozen() {
+            Value::None => out.write_str("none"),
+            Value::Bool(b) => write!(out, "{b}"),
+            Value::Str(content) => write!(out, "{content:?}"),
+            Value::I64(i) => write!(out, "{i}"),
+            Value::U64(u) => write!(out, "{u}"),
+            Value::U8(u) => write!(out, "{u}"),
+            // Same as `to_canonical_string`, so floats always look like floats
+            Value::F64(f) => write!(out, "{f:?}"),
+            Value::F32(f) => write!(out, "{f:?}"),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => write!(out, "{d}"),
+            Value::Bytes(bytes) => {
+                out.write_str("b\"")?;
+
+                for byte in bytes.iter() {
+                    match byte {
+                        b'"' | b'\\' => write!(out, "\\{}", *byte as char)?,
+                        0x20..=0x7e => out.write_char(*byte as char)?,
+                        _ => write!(out, "\\x{byte:02x}")?,
+                    }
+                }
+
+                out.write_char('"')
+            }
+            Value::List(elements) | Value::Array(elements) => write_sequence(
+                out,
+                "[",
+                "]",
+                elements.iter(),
+                indent,
+                level,
+                |out, elem| elem.write_nested(out, indent, level + 1),
+            ),
+            // A tuple with one element needs a trailing comma, as in Python
+            Value::Tuple(elements) if elements.len() == 1 && indent.is_none() => {
+                out.write_char('(')?;
+                elements[0].write_nested(out, indent, level + 1)?;
+                out.write_str(",)")
+            }
+            Value::Tuple(elements) => write_sequence(
+                out,
+                "(",
+                ")",
+                elements.iter(),
+                indent,
+                level,
+                |out, elem| elem.write_nested(out, indent, level + 1),
+            ),
+            Value::Map(entries) => {
+                let mut entries: Vec<(&String, &Value)> = entries.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+
+                write_sequence(
+                    out,
+                    "{",
+                    "}",
+                    entries.into_iter(),
+                    indent,
+                    level,
+                    |out, (key, value)| {
+                        write!(out, "{key:?}: ")?;
+                        value.write_nested(out, indent, level + 1)
+                    },
+                )
+            }
+            Value::Frozen(_) => unreachable!(),
+        }
+    }
+}
+
+/// Write elements separated by commas (or one per line if `indent` is set)
+fn write_sequence<W, I, F>(
+    out: &mut W,
+    open: &str,
+    close: &str,
+    elements: I,
+    indent: Option<usize>,
+    level: usize,
+    mut write_element: F,
+) -> fmt::Result
+where
+    W: Write,
+    I: ExactSizeIterator,
+    F: FnMut(&mut W, I::Item) -> fmt::Result,
+{
+    out.write_str(open)?;
+
+    let Some(indent) = indent.filter(|_| elements.len() > 0) else {
+        for (pos, elem) in elements.enumerate() {
+            if pos > 0 {
+                out.write_str(", ")?;
+            }
+            write_element(out, elem)?;
+        }
+
+        return out.write_str(close);
+    };
+
+    for elem in elements {
+        write!(out, "\n{:width$}", "", width = indent * (level + 1))?;
+        write_element(out, elem)?;
+        out.write_char(',')?;
+    }
+
+    write!(out, "\n{:width$}{close}", "", width = indent * level)
+}
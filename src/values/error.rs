@@ -1,12 +1,77 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+use super::Value;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, thiserror::Error)]
 pub enum ValueError {
-    TypeMismatch,
+    #[error("Type mismatch: expected {expected}, but got {actual}")]
+    TypeMismatch { expected: String, actual: String },
+    #[error("Operation not supported for this type")]
     OperationNotSupported,
+    #[error("No such child")]
     NoSuchChild,
+    #[error("Invalid key")]
     InvalidKey,
+    #[error("Integer overflow")]
     IntegerOverflow,
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Index out of bounds")]
     IndexOutOfBounds,
+    #[error("Field already exists")]
     FieldAlreadyExists,
+    #[error("Value is frozen and cannot be modified")]
+    Frozen,
+    #[error("List is empty")]
+    EmptyList,
+    #[error("Float is not an integer")]
+    NotAnInteger,
+    #[error("Bytes are not valid UTF-8")]
+    InvalidUtf8,
+    /// Nested lists do not form a valid array, or arrays have different shapes
+    #[error("Shape mismatch")]
+    ShapeMismatch,
+    /// A path (see `Value::get_path`) does not lead to a value
+    ///
+    /// `index` is the position of the first segment that could not be resolved.
+    #[error("Path not found: no element `{segment}` at segment {index}")]
+    PathNotFound { segment: String, index: usize },
+    /// A string or float cannot be converted into a decimal
+    #[error("Not a valid decimal: {0}")]
+    InvalidDecimal(String),
+    /// The result of a decimal operation has too many digits
+    #[error("Decimal overflow")]
+    DecimalOverflow,
+}
+
+impl ValueError {
+    /// The name of the error as seen by programs (e.g., `IndexOutOfBounds`)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::TypeMismatch { .. } => "TypeMismatch",
+            Self::OperationNotSupported => "OperationNotSupported",
+            Self::NoSuchChild => "NoSuchChild",
+            Self::InvalidKey => "InvalidKey",
+            Self::IntegerOverflow => "IntegerOverflow",
+            Self::DivisionByZero => "DivisionByZero",
+            Self::IndexOutOfBounds => "IndexOutOfBounds",
+            Self::FieldAlreadyExists => "FieldAlreadyExists",
+            Self::Frozen => "Frozen",
+            Self::EmptyList => "EmptyList",
+            Self::NotAnInteger => "NotAnInteger",
+            Self::InvalidUtf8 => "InvalidUtf8",
+            Self::ShapeMismatch => "ShapeMismatch",
+            Self::PathNotFound { .. } => "PathNotFound",
+            Self::InvalidDecimal(_) => "InvalidDecimal",
+            Self::DecimalOverflow => "DecimalOverflow",
+        }
+    }
+
+    /// Create a TypeMismatch error for a value that is not of the expected type
+    pub fn type_mismatch(expected: &str, actual: &Value) -> Self {
+        Self::TypeMismatch {
+            expected: expected.to_string(),
+            actual: actual.type_name().to_string(),
+        }
+    }
 }
@@ -0,0 +1,104 @@
+use serde_json::{Map, Number};
+
+use super::{MapEntries, Value, ValueError};
+
+/// Conversions between values and JSON (requires the `json` feature)
+impl Value {
+    /// Parse a JSON document
+    pub fn from_json_str(input: &str) -> Result<Value, serde_json::Error> {
+        let json: serde_json::Value = serde_json::from_str(input)?;
+        Ok(json.into())
+    }
+
+    /// Write the value as a JSON document
+    ///
+    /// Fails for values JSON cannot represent (see `TryFrom<Value> for serde_json::Value`).
+    pub fn to_json_string(&self) -> Result<String, ValueError> {
+        let json = serde_json::Value::try_from(self.clone())?;
+        Ok(json.to_string())
+    }
+}
+
+/// Numbers become I64 if they fit, U64 if they are larger, and F64 otherwise
+impl From<serde_json::Value> for Value {
+    fn from(json: serde_json::Value) -> Self {
+        match json {
+            serde_json::Value::Null => Value::None,
+            serde_json::Value::Bool(b) => Value::Bool(b),
+            serde_json::Value::Number(number) => {
+                if let Some(i) = number.as_i64() {
+                    Value::I64(i)
+                } else if let Some(u) = number.as_u64() {
+                    Value::U64(u)
+                } else {
+                    Value::F64(number.as_f64().unwrap_or_default())
+                }
+            }
+            serde_json::Value::String(s) => Value::Str(s),
+            serde_json::Value::Array(elements) => {
+                Value::List(elements.into_iter().map(Value::from).collect())
+            }
+            serde_json::Value::Object(fields) => {
+                let entries: MapEntries = fields
+                    .into_iter()
+                    .map(|(key, value)| (key, value.into()))
+                    .collect();
+                Value::Map(Box::new(entries))
+            }
+        }
+    }
+}
+
+/// Tuples and arrays become JSON arrays, and decimals become strings (to keep all of their digits)
+///
+/// Fails for bytes and for floats that are not finite, as JSON has no representation for them.
+impl TryFrom<Value> for serde_json::Value {
+    type Error = ValueError;
+
+    fn try_from(value: Value) -> Result<Self, ValueError> {
+        let json = match value {
+            Value::None => serde_json::Value::Null,
+            Value::Bool(b) => serde_json::Value::Bool(b),
+            Value::Str(s) => serde_json::Value::String(s),
+            Value::I64(i) => i.into(),
+            Value::U64(u) => u.into(),
+            Value::U8(u) => u.into(),
+            Value::F32(f) => float_to_json(f as f64, &value)?,
+            Value::F64(f) => float_to_json(f, &value)?,
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => serde_json::Value::String(d.to_string()),
+            Value::List(elements) | Value::Tuple(elements) | Value::Array(elements) => {
+                let elements = elements
+                    .into_iter()
+                    .map(serde_json::Value::try_from)
+                    .collect::<Result<Vec<_>, _>>()?;
+                serde_json::Value::Array(elements)
+            }
+            Value::Map(entries) => {
+                let mut fields = Map::new();
+
+                for (key, value) in entries.into_iter() {
+                    fields.insert(key, value.try_into()?);
+                }
+
+                serde_json::Value::Object(fields)
+            }
+            Value::Bytes(_) => {
+                return Err(ValueError::type_mismatch(
+                    "a value with a JSON representation",
+                    &value,
+                ))
+            }
+            Value::Frozen(inner) => Value::clone(&inner).try_into()?,
+        };
+
+        Ok(json)
+    }
+}
+
+fn float_to_json(f: f64, value: &Value) -> Result<serde_json::Value, ValueError> {
+    match Number::from_f64(f) {
+        Some(number) => Ok(serde_json::Value::Number(number)),
+        None => Err(ValueError::type_mismatch("a finite number", value)),
+    }
+}
@@ -0,0 +1,128 @@
+use super::{Value, ValueError};
+
+/// How `Value::merge` combines two values
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MergeStrategy {
+    /// Entries of the other map replace entries with the same key
+    ///
+    /// Any other value is replaced as a whole.
+    Replace,
+    /// Like `Recursive`, but lists are concatenated instead of replaced
+    Append,
+    /// Maps present on both sides are merged recursively
+    ///
+    /// Any other value (including lists) is replaced as a whole.
+    Recursive,
+}
+
+impl Value {
+    /// Merge another value into this one, e.g., to layer configurations
+    ///
+    /// If either side is not a map (or, for `MergeStrategy::Append`, not a
+    /// list), this value is replaced by `other`. Fails if this value or a
+    /// nested value that needs to change is frozen.
+    pub fn merge(&mut self, other: Value, strategy: MergeStrategy) -> Result<(), ValueError> {
+        if self.is_frozen() {
+            return Err(ValueError::Frozen);
+        }
+
+        match (&mut *self, other.thaw()) {
+            (Value::Map(content), Value::Map(entries)) => {
+                for (key, value) in entries.into_iter() {
+                    match content.get_mut(&key) {
+                        Some(existing) if strategy != MergeStrategy::Replace => {
+                            existing.merge(value, strategy)?;
+                        }
+                        _ => {
+                            content.insert(key, value);
+                        }
+                    }
+                }
+            }
+            (Value::List(content), Value::List(elements)) if strategy == MergeStrategy::Append => {
+                content.extend(elements);
+            }
+            (_, other) => *self = other,
+        }
+
+        Ok(())
+    }
+
+    /// Compute the changes that turn this value into `other`
+    ///
+    /// The result is a list of maps, one for each change. Every change has an
+    /// `op` (`"add"`, `"remove"`, or `"replace"`) and a `path`, which is a list
+    /// of map keys and list indices (empty for this value itself). Removed and
+    /// replaced values are stored in `old`, added and new values in `new`.
+    ///
+    /// Maps are compared entry by entry and lists of the same length element by
+    /// element. All other values are replaced as a whole if they differ.
+    pub fn diff(&self, other: &Value) -> Value {
+        let mut changes = vec![];
+        let mut path = vec![];
+        diff_into(self.unfrozen(), other.unfrozen(), &mut path, &mut changes);
+
+        Value::List(changes)
+    }
+}
+
+fn diff_into(old: &Value, new: &Value, path: &mut Vec<Value>, changes: &mut Vec<Value>) {
+    match (old, new) {
+        (Value::Map(old_entries), Value::Map(new_entries)) => {
+            // Sort keys, so that the result is the same on every run
+            let mut keys: Vec<&String> = old_entries.keys().chain(new_entries.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            for key in keys {
+                path.push(key.as_str().into());
+
+                match (old_entries.get(key), new_entries.get(key)) {
+                    (Some(old_value), Some(new_value)) => {
+                        diff_into(old_value.unfrozen(), new_value.unfrozen(), path, changes)
+                    }
+                    (Some(old_value), None) => {
+                        changes.push(make_change("remove", path, Some(old_value), None))
+                    }
+                    (None, Some(new_value)) => {
+                        changes.push(make_change("add", path, None, Some(new_value)))
+                    }
+                    (None, None) => unreachable!(),
+                }
+
+                path.pop();
+            }
+        }
+        (Value::List(old_elements), Value::List(new_elements))
+            if old_elements.len() == new_elements.len() =>
+        {
+            for (pos, (old_value, new_value)) in old_elements.iter().zip(new_elements).enumerate() {
+                path.push(pos.into());
+                diff_into(old_value.unfrozen(), new_value.unfrozen(), path, changes);
+                path.pop();
+            }
+        }
+        (old, new) => {
+            if old != new {
+                changes.push(make_change("replace", path, Some(old), Some(new)));
+            }
+        }
+    }
+}
+
+fn make_change(op: &str, path: &[Value], old: Option<&Value>, new: Option<&Value>) -> Value {
+    let mut change = Value::make_map();
+    change.set("op".to_string(), op.into()).unwrap();
+    change
+        .set("path".to_string(), Value::List(path.to_vec()))
+        .unwrap();
+
+    if let Some(old) = old {
+        change.set("old".to_string(), old.clone()).unwrap();
+    }
+    if let Some(new) = new {
+        change.set("new".to_string(), new.clone()).unwrap();
+    }
+
+    change
+}
@@ -1,6 +1,9 @@
-use std::collections::{hash_map, HashMap};
+use std::cmp::Ordering;
+use std::collections::HashMap;
 
-use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize, Serializer};
 
 #[cfg(feature = "python-bindings")]
 use pyo3::prelude::*;
@@ -22,9 +25,38 @@ use byte_slice_cast::AsByteSlice;
 
 use serde_bytes::ByteBuf;
 
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::ToPrimitive;
+
+mod convert;
+pub use convert::{ConversionMode, Rounding};
+
+mod display;
+
 mod error;
 pub use error::ValueError;
 
+mod merge;
+pub use merge::MergeStrategy;
+
+mod numeric;
+
+mod ordering;
+pub use ordering::HashableValue;
+
+mod path;
+
+mod string;
+
+#[cfg(feature = "ndarray")]
+mod array;
+
+#[cfg(feature = "json")]
+mod json;
+
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
 pub enum PrimitiveType {
     None,
@@ -36,6 +68,7 @@ pub enum PrimitiveType {
     U8,
     F32,
     F64,
+    Decimal,
 }
 
 #[derive(Serialize, Deserialize, PartialEq, Clone, Debug)]
@@ -45,6 +78,7 @@ pub enum TypeDefinition {
     Map(Box<TypeDefinition>, Box<TypeDefinition>),
     List(Box<TypeDefinition>),
     Bytes,
+    Tuple(Vec<TypeDefinition>),
 }
 
 impl TypeDefinition {
@@ -53,10 +87,23 @@ impl TypeDefinition {
     }
 }
 
+// Values are embedded in programs, which can be shared between threads
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<TypeDefinition>();
+    assert_send_sync::<Value>();
+};
+
+/// The entries of a map value
+///
+/// With the `ordered-maps` feature, maps are serialized with their entries
+/// sorted by key, so that they result in the same bytes on every run.
+pub type MapEntries = HashMap<String, Value>;
+
 /// A variant data type used by the cowlang interpreter.
 ///
 /// *Note:* this uses heap allocation for all non-primitive types /// To keep the enum size small
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Deserialize, Clone, Debug)]
 pub enum Value {
     None,
     Bool(bool),
@@ -66,9 +113,73 @@ pub enum Value {
     U64(u64),
     F64(f64),
     U8(u8),
-    Map(Box<HashMap<String, Value>>),
+    Map(Box<MapEntries>),
     List(Vec<Value>),
     Bytes(ByteBuf),
+    /// A fixed sequence of values, e.g., to return multiple results from a function
+    ///
+    /// Unlike lists, tuples cannot be modified.
+    Tuple(Vec<Value>),
+    /// A list with a fixed length, e.g., a buffer of a binary protocol (`[u8; 16]`)
+    ///
+    /// Elements can be replaced, but only by values of the same type.
+    Array(Vec<Value>),
+    /// A decimal number with exact arithmetic, e.g., for amounts of money
+    ///
+    /// Serialized as a string, so that no digits are lost.
+    #[cfg(feature = "decimal")]
+    #[serde(with = "rust_decimal::serde::str")]
+    Decimal(Decimal),
+    /// An immutable value that can be shared without copying (see `Value::freeze`)
+    ///
+    /// Serializes like the value it contains.
+    #[serde(skip_deserializing)]
+    Frozen(Arc<Value>),
+}
+
+// Written by hand so that frozen values serialize like their mutable counterparts
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::None => serializer.serialize_unit_variant("Value", 0, "None"),
+            Value::Bool(b) => serializer.serialize_newtype_variant("Value", 1, "Bool", b),
+            Value::Str(s) => serializer.serialize_newtype_variant("Value", 2, "Str", s),
+            Value::F32(f) => serializer.serialize_newtype_variant("Value", 3, "F32", f),
+            Value::I64(i) => serializer.serialize_newtype_variant("Value", 4, "I64", i),
+            Value::U64(u) => serializer.serialize_newtype_variant("Value", 5, "U64", u),
+            Value::F64(f) => serializer.serialize_newtype_variant("Value", 6, "F64", f),
+            Value::U8(u) => serializer.serialize_newtype_variant("Value", 7, "U8", u),
+            #[cfg(not(feature = "ordered-maps"))]
+            Value::Map(m) => serializer.serialize_newtype_variant("Value", 8, "Map", m),
+            #[cfg(feature = "ordered-maps")]
+            Value::Map(m) => {
+                serializer.serialize_newtype_variant("Value", 8, "Map", &SortedEntries(m))
+            }
+            Value::List(l) => serializer.serialize_newtype_variant("Value", 9, "List", l),
+            Value::Bytes(b) => serializer.serialize_newtype_variant("Value", 10, "Bytes", b),
+            Value::Tuple(t) => serializer.serialize_newtype_variant("Value", 11, "Tuple", t),
+            Value::Array(a) => serializer.serialize_newtype_variant("Value", 12, "Array", a),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => {
+                serializer.serialize_newtype_variant("Value", 13, "Decimal", &d.to_string())
+            }
+            Value::Frozen(inner) => inner.serialize(serializer),
+        }
+    }
+}
+
+/// Serializes the entries of a map sorted by key (see `MapEntries`)
+#[cfg(feature = "ordered-maps")]
+struct SortedEntries<'a>(&'a MapEntries);
+
+#[cfg(feature = "ordered-maps")]
+impl Serialize for SortedEntries<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut entries: Vec<(&String, &Value)> = self.0.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+
+        serializer.collect_map(entries)
+    }
 }
 
 impl Value {
@@ -87,15 +198,67 @@ impl Value {
         Value::List(Vec::new())
     }
 
+    /// Create a fixed-length array (e.g., `Value::from_array(bytes.to_vec())`)
+    ///
+    /// `Value::from(Vec<T>)` creates a list instead.
+    pub fn from_array<T: Into<Value>>(elems: Vec<T>) -> Value {
+        Value::Array(elems.into_iter().map(Into::into).collect())
+    }
+
+    /// Make this value immutable
+    ///
+    /// Clones of a frozen value share the same data, so it can be handed to
+    /// many scripts without copying it. Any attempt to modify it (or any of
+    /// its children) results in `ValueError::Frozen`.
+    #[must_use]
+    pub fn freeze(self) -> Value {
+        match self {
+            Value::Frozen(_) => self,
+            other => Value::Frozen(Arc::new(other)),
+        }
+    }
+
+    /// Is this value immutable?
+    pub fn is_frozen(&self) -> bool {
+        matches!(self, Value::Frozen(_))
+    }
+
+    /// Get a mutable version of this value
+    ///
+    /// This only copies the underlying data if it is still shared with other clones.
+    #[must_use]
+    pub fn thaw(self) -> Value {
+        match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner),
+            other => other,
+        }
+    }
+
+    /// Look through a frozen value (if any)
+    pub(crate) fn unfrozen(&self) -> &Value {
+        match self {
+            Value::Frozen(inner) => inner,
+            other => other,
+        }
+    }
+
+    /// Feed the content of this value into a digest
+    ///
+    /// Map entries are hashed in the order of their keys, so that
+    /// equal maps always result in the same digest.
     #[cfg(feature = "hash")]
     pub fn hash<Hasher: Digest>(&self, hasher: &mut Hasher) {
         match &self {
-            Value::Map(hashmap) => {
-                for val in hashmap.values() {
+            Value::Map(content) => {
+                let mut entries: Vec<(&String, &Value)> = content.iter().collect();
+                entries.sort_by_key(|(key, _)| *key);
+
+                for (key, val) in entries {
+                    hasher.update(key);
                     val.hash(hasher);
                 }
             }
-            Value::List(vec) => {
+            Value::List(vec) | Value::Tuple(vec) | Value::Array(vec) => {
                 for val in vec.iter() {
                     val.hash(hasher);
                 }
@@ -134,6 +297,12 @@ impl Value {
             Value::Bytes(content) => {
                 hasher.update(&content[..]);
             }
+            // Normalize, so that equal decimals (e.g., 1.5 and 1.50) result in the same digest
+            #[cfg(feature = "decimal")]
+            Value::Decimal(content) => {
+                hasher.update(content.normalize().serialize());
+            }
+            Value::Frozen(inner) => inner.hash(hasher),
         }
     }
 
@@ -143,7 +312,7 @@ impl Value {
             return Err(ValueError::InvalidKey);
         }
 
-        match self {
+        match self.unfrozen() {
             Value::Map(content) => {
                 if let Some(val) = content.get(key) {
                     Ok(val)
@@ -151,106 +320,36 @@ impl Value {
                     Err(ValueError::NoSuchChild)
                 }
             }
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("map", self)),
         }
     }
 
-    /// Do a numeric comparison (>=) between this value and another
-    pub fn is_greater_than(&self, other: &Value) -> Result<bool, ValueError> {
-        let result = match self {
-            Value::I64(content) => content > &other.clone().try_into()?,
-            Value::U64(content) => content > &other.clone().try_into()?,
-            Value::F64(content) => content > &other.clone().try_into()?,
-            _ => return Err(ValueError::TypeMismatch),
-        };
-
-        Ok(result)
-    }
-
-    /// Do a numeric comparison (==) between this value and another
-    pub fn equals(&self, other: &Value) -> Result<bool, ValueError> {
-        // TODO borrowed try-into is not implemented yet
-        let other = other.clone();
-
-        let result = match self {
-            Value::I64(content) => content == &TryInto::<i64>::try_into(other)?,
-            Value::U64(content) => content == &TryInto::<u64>::try_into(other)?,
-            Value::Bool(content) => content == &TryInto::<bool>::try_into(other)?,
-            Value::F64(content) => content == &TryInto::<f64>::try_into(other)?,
-            _ => {
-                return Err(ValueError::TypeMismatch);
-            }
-        };
-
-        Ok(result)
-    }
-
-    /// Do a numeric comparison (<=) between this value and another
-    pub fn is_smaller_than(&self, other: &Value) -> Result<bool, ValueError> {
-        let result = match self {
-            Value::I64(content) => content < &other.clone().try_into()?,
-            Value::U64(content) => content < &other.clone().try_into()?,
-            Value::F64(content) => content < &other.clone().try_into()?,
-            _ => return Err(ValueError::TypeMismatch),
-        };
-
-        Ok(result)
-    }
-
-    /// Multiply this value with another (numerals only)
-    pub fn multiply(&self, other: &Value) -> Result<Value, ValueError> {
-        match self {
-            Value::I64(content) => {
-                let val: i64 = other.clone().try_into()?;
-                Ok((content * val).into())
-            }
-            Value::U64(content) => {
-                let val: u64 = other.clone().try_into()?;
-                Ok((content * val).into())
-            }
-            Value::F64(content) => {
-                let val: f64 = other.clone().try_into()?;
-                Ok((content * val).into())
-            }
-            _ => Err(ValueError::OperationNotSupported),
-        }
-    }
-
-    /// Sum this value with another (numerals only)
-    pub fn add(&self, other: &Value) -> Result<Value, ValueError> {
-        let result = match self {
-            Value::I64(content) => {
-                let val: i64 = other.clone().try_into()?;
-                (content + val).into()
-            }
-            Value::U64(content) => {
-                let val: u64 = other.clone().try_into()?;
-                (content + val).into()
-            }
-            Value::F64(content) => {
-                let val: f64 = other.clone().try_into()?;
-                (content + val).into()
-            }
-            Value::U8(content) => {
-                let val: u8 = other.clone().try_into()?;
-                (content + val).into()
-            }
-            _ => return Err(ValueError::OperationNotSupported),
-        };
-
-        Ok(result)
-    }
-
     /// Get the inverse of this value
     ///
     /// *Note:* This only works with booleans
     pub fn negate(&self) -> Result<Value, ValueError> {
-        match self {
+        match self.unfrozen() {
             Value::Bool(content) => Ok((!content).into()),
             _ => Err(ValueError::OperationNotSupported),
         }
     }
 
+    /// The number of elements of a list, tuple, or array, entries of a map, characters of a string, or bytes
+    pub fn length(&self) -> Result<usize, ValueError> {
+        match self.unfrozen() {
+            Value::List(content) | Value::Tuple(content) | Value::Array(content) => {
+                Ok(content.len())
+            }
+            Value::Map(content) => Ok(content.len()),
+            Value::Str(content) => Ok(content.chars().count()),
+            Value::Bytes(content) => Ok(content.len()),
+            other => Err(ValueError::type_mismatch(
+                "list, tuple, map, string, or bytes",
+                other,
+            )),
+        }
+    }
+
     /// Remove a field from this value
     pub fn remove(&mut self, key: &str) -> Result<Value, ValueError> {
         if key.is_empty() {
@@ -265,6 +364,7 @@ impl Value {
                     Err(ValueError::NoSuchChild)
                 }
             }
+            Value::Frozen(_) => Err(ValueError::Frozen),
             _ => Err(ValueError::OperationNotSupported),
         }
     }
@@ -283,6 +383,7 @@ impl Value {
                     Err(ValueError::NoSuchChild)
                 }
             }
+            Value::Frozen(_) => Err(ValueError::Frozen),
             _ => Err(ValueError::OperationNotSupported),
         }
     }
@@ -297,9 +398,10 @@ impl Value {
             return Err(ValueError::InvalidKey);
         }
 
-        match &mut *self {
+        match self {
             Value::Map(content) => Ok(content.entry(key).or_insert_with(func)),
-            _ => Err(ValueError::TypeMismatch),
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("map", self)),
         }
     }
 
@@ -313,33 +415,134 @@ impl Value {
                 content.insert(key, value);
                 Ok(())
             }
-            _ => Err(ValueError::TypeMismatch),
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("map", self)),
         }
     }
 
     pub fn num_children(&self) -> usize {
-        match self {
+        match self.unfrozen() {
             Value::Map(content) => content.len(),
-            Value::List(content) => content.len(),
+            Value::List(content) | Value::Tuple(content) | Value::Array(content) => content.len(),
             _ => 0,
         }
     }
 
+    /// How deeply lists and maps are nested inside this value
+    ///
+    /// Other values have a depth of zero, and a list of integers has a depth of one.
+    pub fn depth(&self) -> usize {
+        let children = match self.unfrozen() {
+            Value::Map(content) => content.values().map(Value::depth).max(),
+            Value::List(content) | Value::Tuple(content) | Value::Array(content) => {
+                content.iter().map(Value::depth).max()
+            }
+            _ => return 0,
+        };
+
+        children.unwrap_or(0) + 1
+    }
+
+    /// The approximate size of this value
+    ///
+    /// Every value (including the ones nested in lists and maps) counts as one,
+    /// and strings, bytes, and map keys count their length in bytes in addition.
+    pub fn size(&self) -> usize {
+        match self.unfrozen() {
+            Value::Map(content) => {
+                1 + content
+                    .iter()
+                    .map(|(key, value)| key.len() + value.size())
+                    .sum::<usize>()
+            }
+            Value::List(content) | Value::Tuple(content) | Value::Array(content) => {
+                1 + content.iter().map(Value::size).sum::<usize>()
+            }
+            Value::Str(content) => 1 + content.len(),
+            Value::Bytes(content) => 1 + content.len(),
+            _ => 1,
+        }
+    }
+
     pub fn map_insert(&mut self, key: String, value: Value) -> Result<(), ValueError> {
         match &mut *self {
-            Value::Map(content) => match content.entry(key) {
-                hash_map::Entry::Vacant(v) => {
-                    v.insert(value);
-                    Ok(())
+            Value::Map(content) => {
+                if content.contains_key(&key) {
+                    return Err(ValueError::FieldAlreadyExists);
                 }
-                hash_map::Entry::Occupied(_) => Err(ValueError::FieldAlreadyExists),
+
+                content.insert(key, value);
+                Ok(())
+            }
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("map", self)),
+        }
+    }
+
+    /// Convert a string into its UTF-8 bytes
+    pub fn encode(&self) -> Result<Value, ValueError> {
+        match self.unfrozen() {
+            Value::Str(content) => Ok(Value::Bytes(ByteBuf::from(content.as_bytes()))),
+            other => Err(ValueError::type_mismatch("str", other)),
+        }
+    }
+
+    /// Convert UTF-8 bytes into a string
+    pub fn decode(&self) -> Result<Value, ValueError> {
+        match self.unfrozen() {
+            Value::Bytes(content) => match std::str::from_utf8(content) {
+                Ok(s) => Ok(s.into()),
+                Err(_) => Err(ValueError::InvalidUtf8),
             },
-            _ => Err(ValueError::TypeMismatch),
+            other => Err(ValueError::type_mismatch("bytes", other)),
+        }
+    }
+
+    /// Insert all entries of another map, overwriting existing ones with the same key
+    pub fn map_update(&mut self, other: Value) -> Result<(), ValueError> {
+        let entries = match other.into_map() {
+            Ok(entries) => entries,
+            Err(other) => return Err(ValueError::type_mismatch("map", &other)),
+        };
+
+        match &mut *self {
+            Value::Map(content) => {
+                content.extend(entries);
+                Ok(())
+            }
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("map", self)),
+        }
+    }
+
+    /// Get all entries of the map as `[key, value]` pairs, sorted by key
+    pub fn map_items(&self) -> Result<Value, ValueError> {
+        let content = match self.unfrozen() {
+            Value::Map(content) => content,
+            _ => return Err(ValueError::type_mismatch("map", self)),
+        };
+
+        let mut entries: Vec<(&String, &Value)> = content.iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+
+        let items = entries
+            .into_iter()
+            .map(|(key, value)| Value::List(vec![key.clone().into(), value.clone()]))
+            .collect();
+
+        Ok(Value::List(items))
+    }
+
+    /// Check whether the map has an entry with the given key
+    pub fn map_contains_key(&self, key: &str) -> Result<bool, ValueError> {
+        match self.unfrozen() {
+            Value::Map(content) => Ok(content.contains_key(key)),
+            _ => Err(ValueError::type_mismatch("map", self)),
         }
     }
 
     pub fn get_child(&self, key: Value) -> Result<&Value, ValueError> {
-        match self {
+        match self.unfrozen() {
             Value::Map(content) => {
                 //FIXME map should allow other index types too
                 let kstr: String = key.try_into()?;
@@ -350,7 +553,7 @@ impl Value {
                     Err(ValueError::NoSuchChild)
                 }
             }
-            Value::List(content) => {
+            Value::List(content) | Value::Tuple(content) | Value::Array(content) => {
                 let pos: i64 = key.try_into()?;
 
                 let res = content.get(pos as usize);
@@ -361,41 +564,115 @@ impl Value {
                     Err(ValueError::IndexOutOfBounds)
                 }
             }
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("map, list, or tuple", self)),
         }
     }
 
-    /// Convert this value into a Rust HashMap
-    pub fn into_map(self) -> Result<HashMap<String, Value>, Value> {
+    /// Get a mutable reference to an element of a list or an entry of a map
+    pub fn get_child_mut(&mut self, key: Value) -> Result<&mut Value, ValueError> {
         match self {
-            Value::Map(mut content) => {
-                let mut res = HashMap::new();
-                std::mem::swap(&mut res, content.as_mut());
+            Value::Map(content) => {
+                let kstr: String = key.try_into()?;
 
-                Ok(res)
+                if let Some(val) = content.get_mut(&kstr) {
+                    Ok(val)
+                } else {
+                    Err(ValueError::NoSuchChild)
+                }
+            }
+            Value::List(content) | Value::Array(content) => {
+                let pos: i64 = key.try_into()?;
+
+                if let Some(val) = content.get_mut(pos as usize) {
+                    Ok(val)
+                } else {
+                    Err(ValueError::IndexOutOfBounds)
+                }
             }
-            _ => Err(self),
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("map or list", self)),
+        }
+    }
+
+    /// Replace an element of a list or array, or insert or replace an entry of a map
+    ///
+    /// Elements of arrays can only be replaced by values of the same type.
+    pub fn set_child(&mut self, key: Value, value: Value) -> Result<(), ValueError> {
+        match self {
+            Value::Map(content) => {
+                let kstr: String = key.try_into()?;
+                content.insert(kstr, value);
+                Ok(())
+            }
+            Value::Array(_) => {
+                let elem = self.get_child_mut(key)?;
+                if elem.type_name() != value.type_name() {
+                    return Err(ValueError::type_mismatch(elem.type_name(), &value));
+                }
+
+                *elem = value;
+                Ok(())
+            }
+            _ => {
+                *self.get_child_mut(key)? = value;
+                Ok(())
+            }
+        }
+    }
+
+    /// Convert this value into a Rust HashMap
+    pub fn into_map(self) -> Result<HashMap<String, Value>, Value> {
+        match self.thaw() {
+            Value::Map(content) => Ok(*content),
+            other => Err(other),
         }
     }
 
     /// Convert this value into a Rust Vec
+    ///
+    /// Bytes are converted into a list of u8 values, so that
+    /// `TryInto<Vec<u8>>` works for both lists and bytes.
     pub fn into_vec(self) -> Result<Vec<Value>, ValueError> {
-        match self {
-            Value::List(content) => Ok(content),
-            _ => Err(ValueError::TypeMismatch),
+        match self.thaw() {
+            Value::List(content) | Value::Tuple(content) | Value::Array(content) => Ok(content),
+            Value::Bytes(content) => Ok(content.into_iter().map(Value::from).collect()),
+            other => Err(ValueError::type_mismatch("list", &other)),
+        }
+    }
+
+    /// Create a bytes value
+    ///
+    /// `Value::from(Vec<u8>)` creates a list of u8 values instead.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Value::Bytes(ByteBuf::from(bytes))
+    }
+
+    /// Get the content of a bytes value without copying it
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self.unfrozen() {
+            Value::Bytes(content) => Some(content),
+            _ => None,
+        }
+    }
+
+    /// Convert a bytes value into a Rust Vec without copying it
+    pub fn into_bytes(self) -> Result<Vec<u8>, ValueError> {
+        match self.thaw() {
+            Value::Bytes(content) => Ok(content.into_vec()),
+            other => Err(ValueError::type_mismatch("bytes", &other)),
         }
     }
 
     pub fn list_get_at(&self, position: usize) -> Result<&Value, ValueError> {
-        match self {
-            Value::List(content) => {
+        match self.unfrozen() {
+            Value::List(content) | Value::Array(content) => {
                 if let Some(c) = content.get(position) {
                     Ok(c)
                 } else {
                     Err(ValueError::NoSuchChild)
                 }
             }
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("list", self)),
         }
     }
 
@@ -406,13 +683,220 @@ impl Value {
                 content.push(value);
                 Ok(())
             }
-            _ => Err(ValueError::TypeMismatch),
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("list", self)),
+        }
+    }
+
+    /// Append all elements of another list to this list
+    pub fn list_extend(&mut self, other: Value) -> Result<(), ValueError> {
+        match &mut *self {
+            Value::List(content) => {
+                content.extend(other.into_vec()?);
+                Ok(())
+            }
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("list", self)),
+        }
+    }
+
+    /// Remove all elements from the list
+    pub fn list_clear(&mut self) -> Result<(), ValueError> {
+        match &mut *self {
+            Value::List(content) => {
+                content.clear();
+                Ok(())
+            }
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("list", self)),
         }
     }
 
+    /// Count how many elements of the list are equal to the given value
+    pub fn list_count(&self, value: &Value) -> Result<usize, ValueError> {
+        match self.unfrozen() {
+            Value::List(content) | Value::Array(content) => {
+                Ok(content.iter().filter(|v| *v == value).count())
+            }
+            _ => Err(ValueError::type_mismatch("list", self)),
+        }
+    }
+
+    /// Remove and return the element at the given position (or the last element)
+    pub fn list_pop(&mut self, position: Option<usize>) -> Result<Value, ValueError> {
+        let content = self.as_list_mut()?;
+
+        let position = match position {
+            Some(pos) if pos >= content.len() => return Err(ValueError::IndexOutOfBounds),
+            Some(pos) => pos,
+            None if content.is_empty() => return Err(ValueError::EmptyList),
+            None => content.len() - 1,
+        };
+
+        Ok(content.remove(position))
+    }
+
+    /// Insert an element before the given position
+    ///
+    /// A position equal to the length of the list appends the element.
+    pub fn list_insert(&mut self, position: usize, value: Value) -> Result<(), ValueError> {
+        let content = self.as_list_mut()?;
+
+        if position > content.len() {
+            return Err(ValueError::IndexOutOfBounds);
+        }
+
+        content.insert(position, value);
+        Ok(())
+    }
+
+    /// Remove the first element that is equal to the given value
+    pub fn list_remove(&mut self, value: &Value) -> Result<(), ValueError> {
+        let position = self.list_index(value)?;
+        self.as_list_mut()?.remove(position);
+        Ok(())
+    }
+
+    /// Get the position of the first element that is equal to the given value
+    pub fn list_index(&self, value: &Value) -> Result<usize, ValueError> {
+        match self.unfrozen() {
+            Value::List(content) | Value::Array(content) => content
+                .iter()
+                .position(|v| v == value)
+                .ok_or(ValueError::NoSuchChild),
+            _ => Err(ValueError::type_mismatch("list", self)),
+        }
+    }
+
+    /// Sort the list in ascending order (numerals or strings only)
+    ///
+    /// The list is left unchanged if its elements cannot be compared.
+    pub fn list_sort(&mut self) -> Result<(), ValueError> {
+        let content = self.as_list_mut()?;
+
+        let mut sorted = content.clone();
+        let mut error = None;
+
+        sorted.sort_by(|a, b| match a.sort_order(b) {
+            Ok(order) => order,
+            Err(err) => {
+                error.get_or_insert(err);
+                Ordering::Equal
+            }
+        });
+
+        match error {
+            Some(err) => Err(err),
+            None => {
+                *content = sorted;
+                Ok(())
+            }
+        }
+    }
+
+    /// Reverse the order of the list's elements
+    pub fn list_reverse(&mut self) -> Result<(), ValueError> {
+        self.as_list_mut()?.reverse();
+        Ok(())
+    }
+
+    fn as_list_mut(&mut self) -> Result<&mut Vec<Value>, ValueError> {
+        match self {
+            Value::List(content) => Ok(content),
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(ValueError::type_mismatch("list", self)),
+        }
+    }
+
+    /// How two elements are ordered by `list_sort`
+    fn sort_order(&self, other: &Value) -> Result<Ordering, ValueError> {
+        if let (Value::Str(lhs), Value::Str(rhs)) = (self.unfrozen(), other.unfrozen()) {
+            return Ok(lhs.cmp(rhs));
+        }
+
+        // NaN cannot be ordered
+        self.compare_numbers(other)?
+            .ok_or(ValueError::OperationNotSupported)
+    }
+
+    /// Check whether a list contains a value, a map contains a key, or a string contains a substring
+    pub fn contains(&self, item: &Value) -> Result<bool, ValueError> {
+        match (self.unfrozen(), item.unfrozen()) {
+            (Value::List(content) | Value::Tuple(content) | Value::Array(content), item) => {
+                Ok(content.iter().any(|v| v == item))
+            }
+            (Value::Map(content), Value::Str(key)) => Ok(content.contains_key(key)),
+            (Value::Str(content), Value::Str(substring)) => {
+                Ok(content.contains(substring.as_str()))
+            }
+            (Value::Map(_), item) | (Value::Str(_), item) => {
+                Err(ValueError::type_mismatch("string", item))
+            }
+            (other, _) => Err(ValueError::type_mismatch(
+                "list, tuple, map, or string",
+                other,
+            )),
+        }
+    }
+
+    /// Get the smallest element of the list (numerals only)
+    pub fn list_min(&self) -> Result<Value, ValueError> {
+        self.list_extreme(|candidate, current| candidate.is_smaller_than(current))
+    }
+
+    /// Get the largest element of the list (numerals only)
+    pub fn list_max(&self) -> Result<Value, ValueError> {
+        self.list_extreme(|candidate, current| candidate.is_greater_than(current))
+    }
+
+    fn list_extreme<F>(&self, replaces: F) -> Result<Value, ValueError>
+    where
+        F: Fn(&Value, &Value) -> Result<bool, ValueError>,
+    {
+        let content = match self.unfrozen() {
+            Value::List(content) | Value::Array(content) => content,
+            _ => return Err(ValueError::type_mismatch("list", self)),
+        };
+
+        let mut elems = content.iter();
+        let mut result = elems.next().ok_or(ValueError::EmptyList)?;
+
+        for elem in elems {
+            if replaces(elem, result)? {
+                result = elem;
+            }
+        }
+
+        Ok(result.clone())
+    }
+
+    /// Sum up all elements of the list (numerals only)
+    ///
+    /// The sum of an empty list is 0.
+    pub fn list_sum(&self) -> Result<Value, ValueError> {
+        let content = match self.unfrozen() {
+            Value::List(content) | Value::Array(content) => content,
+            _ => return Err(ValueError::type_mismatch("list", self)),
+        };
+
+        let mut elems = content.iter();
+
+        let Some(first) = elems.next() else {
+            return Ok(Value::I64(0));
+        };
+
+        let mut result = first.clone();
+
+        for elem in elems {
+            result = result.add(elem)?;
+        }
+
+        Ok(result)
+    }
+
     /// Convert this value into a boolean (if possible)
     pub fn as_bool(&self) -> Result<bool, ValueError> {
-        match &self {
+        match self.unfrozen() {
             Value::Bool(content) => Ok(*content),
             Value::I64(content) => Ok(*content > 0),
             Value::U64(content) => Ok(*content > 0),
@@ -420,6 +904,50 @@ impl Value {
         }
     }
 
+    /// Convert this value into a string that is the same on every platform
+    ///
+    /// Integers are written in decimal without separators, and floats always
+    /// use '.' as their decimal separator and contain at least one fractional
+    /// digit (or an exponent). Use this whenever output might be hashed or compared.
+    pub fn to_canonical_string(&self) -> Result<String, ValueError> {
+        match self.unfrozen() {
+            Value::Str(content) => Ok(content.clone()),
+            Value::Bool(b) => Ok(b.to_string()),
+            Value::I64(i) => Ok(i.to_string()),
+            Value::U64(u) => Ok(u.to_string()),
+            Value::U8(u) => Ok(u.to_string()),
+            // Debug formatting is the shortest representation that round-trips
+            Value::F64(f) => Ok(format!("{:?}", f)),
+            Value::F32(f) => Ok(format!("{:?}", f)),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => Ok(d.to_string()),
+            Value::Bytes(b) => Ok(format!("{:#x?}", b)),
+            _ => Err(ValueError::type_mismatch("string", self)),
+        }
+    }
+
+    /// Human-readable name of this value's type (used in error messages)
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::None => "none",
+            Value::Bool(_) => "bool",
+            Value::Str(_) => "string",
+            Value::F32(_) => "f32",
+            Value::I64(_) => "i64",
+            Value::U64(_) => "u64",
+            Value::F64(_) => "f64",
+            Value::U8(_) => "u8",
+            Value::Map(_) => "map",
+            Value::List(_) => "list",
+            Value::Bytes(_) => "bytes",
+            Value::Tuple(_) => "tuple",
+            Value::Array(_) => "array",
+            #[cfg(feature = "decimal")]
+            Value::Decimal(_) => "decimal",
+            Value::Frozen(inner) => inner.type_name(),
+        }
+    }
+
     pub fn get_type(&self) -> TypeDefinition {
         match &self {
             Value::Bool(_content) => TypeDefinition::Primitive(PrimitiveType::Bool),
@@ -429,6 +957,8 @@ impl Value {
             Value::U8(_content) => TypeDefinition::Primitive(PrimitiveType::U64),
             Value::F64(_content) => TypeDefinition::Primitive(PrimitiveType::F64),
             Value::F32(_content) => TypeDefinition::Primitive(PrimitiveType::F32),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(_content) => TypeDefinition::Primitive(PrimitiveType::Decimal),
             Value::Map(hashmap) => {
                 let mut iterator = hashmap.values();
                 let val = Value::get_value_from_option(iterator.next());
@@ -464,7 +994,25 @@ impl Value {
                 TypeDefinition::List(Box::new(type_1))
             }
             Value::Bytes(_) => TypeDefinition::Bytes,
+            Value::Tuple(content) => {
+                TypeDefinition::Tuple(content.iter().map(Value::get_type).collect())
+            }
+            Value::Array(content) => {
+                let elem_type = match content.first() {
+                    Some(first)
+                        if content
+                            .iter()
+                            .all(|elem| elem.get_type() == first.get_type()) =>
+                    {
+                        first.get_type()
+                    }
+                    Some(_) => TypeDefinition::Primitive(PrimitiveType::Any),
+                    None => TypeDefinition::Primitive(PrimitiveType::None),
+                };
+                TypeDefinition::Array(Box::new(elem_type), content.len())
+            }
             Value::None => TypeDefinition::Primitive(PrimitiveType::None),
+            Value::Frozen(inner) => inner.get_type(),
         }
     }
 
@@ -481,6 +1029,40 @@ impl Value {
     }
 }
 
+impl PartialEq for Value {
+    /// Frozen values are equal to their mutable counterparts
+    fn eq(&self, other: &Value) -> bool {
+        match (self.unfrozen(), other.unfrozen()) {
+            (Value::None, Value::None) => true,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::F32(a), Value::F32(b)) => a == b,
+            (Value::I64(a), Value::I64(b)) => a == b,
+            (Value::U64(a), Value::U64(b)) => a == b,
+            (Value::F64(a), Value::F64(b)) => a == b,
+            (Value::U8(a), Value::U8(b)) => a == b,
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Tuple(a), Value::Tuple(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => a == b,
+            #[cfg(feature = "decimal")]
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Value
+where
+    T: Into<Value>,
+{
+    /// Creates an array (not a list) with the same length
+    fn from(array: [T; N]) -> Value {
+        Value::Array(array.into_iter().map(Into::into).collect())
+    }
+}
+
 impl From<&str> for Value {
     fn from(s: &str) -> Self {
         Self::Str(s.to_string())
@@ -519,15 +1101,16 @@ impl TryInto<ByteBuf> for Value {
 
     fn try_into(self) -> Result<ByteBuf, ValueError> {
         match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
             Value::Bytes(b) => Ok(b),
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("bytes", &self)),
         }
     }
 }
 
 impl<T> TryInto<Vec<T>> for Value
 where
-    Value: TryInto<T>,
+    Value: TryInto<T, Error = ValueError>,
 {
     type Error = ValueError;
 
@@ -536,24 +1119,34 @@ where
         let mut vec = self.into_vec()?;
 
         for val in vec.drain(..) {
-            if let Ok(val) = val.try_into() {
-                res.push(val);
-            } else {
-                return Err(ValueError::TypeMismatch);
-            }
+            res.push(val.try_into()?);
         }
 
         Ok(res)
     }
 }
 
+impl<T, const N: usize> TryInto<[T; N]> for Value
+where
+    Value: TryInto<T, Error = ValueError>,
+{
+    type Error = ValueError;
+
+    /// Works for arrays (and lists) with exactly `N` elements
+    fn try_into(self) -> Result<[T; N], ValueError> {
+        let vec: Vec<T> = TryInto::<Vec<T>>::try_into(self)?;
+        vec.try_into().map_err(|_| ValueError::ShapeMismatch)
+    }
+}
+
 impl TryInto<bool> for Value {
     type Error = ValueError;
 
     fn try_into(self) -> Result<bool, ValueError> {
         match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
             Value::Bool(content) => Ok(content),
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("bool", &self)),
         }
     }
 }
@@ -563,9 +1156,10 @@ impl TryInto<u8> for Value {
 
     fn try_into(self) -> Result<u8, ValueError> {
         match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
             Value::U8(content) => Ok(content),
             Value::I64(content) => {
-                if (0..=256).contains(&content) {
+                if (0..=255).contains(&content) {
                     Ok(content as u8)
                 } else {
                     Err(ValueError::IntegerOverflow)
@@ -578,7 +1172,7 @@ impl TryInto<u8> for Value {
                     Err(ValueError::IntegerOverflow)
                 }
             }
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("u8", &self)),
         }
     }
 }
@@ -588,10 +1182,11 @@ impl TryInto<i64> for Value {
 
     fn try_into(self) -> Result<i64, ValueError> {
         match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
             Value::I64(content) => Ok(content),
-            Value::U64(content) => Ok(content as i64),
-            Value::F64(content) => Ok(content as i64),
-            _ => Err(ValueError::TypeMismatch),
+            Value::U64(content) => i64::try_from(content).map_err(|_| ValueError::IntegerOverflow),
+            Value::F64(_) => self.to_integer(Rounding::Exact)?.try_into(),
+            _ => Err(ValueError::type_mismatch("i64", &self)),
         }
     }
 }
@@ -601,11 +1196,14 @@ impl TryInto<f64> for Value {
 
     fn try_into(self) -> Result<f64, ValueError> {
         match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
             Value::I64(content) => Ok(content as f64),
             Value::U64(content) => Ok(content as f64),
             Value::F64(content) => Ok(content),
             Value::F32(content) => Ok(content as f64),
-            _ => Err(ValueError::TypeMismatch),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(content) => Ok(content.to_f64().unwrap()),
+            _ => Err(ValueError::type_mismatch("f64", &self)),
         }
     }
 }
@@ -615,11 +1213,12 @@ impl TryInto<f32> for Value {
 
     fn try_into(self) -> Result<f32, ValueError> {
         match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
             Value::I64(content) => Ok(content as f32),
             Value::U64(content) => Ok(content as f32),
             Value::F64(content) => Ok(content as f32),
             Value::F32(content) => Ok(content),
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("f32", &self)),
         }
     }
 }
@@ -629,9 +1228,10 @@ impl TryInto<u64> for Value {
 
     fn try_into(self) -> Result<u64, ValueError> {
         match self {
-            Value::I64(content) => Ok(content as u64),
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
+            Value::I64(content) => u64::try_from(content).map_err(|_| ValueError::IntegerOverflow),
             Value::U64(content) => Ok(content),
-            _ => Err(ValueError::TypeMismatch),
+            _ => Err(ValueError::type_mismatch("u64", &self)),
         }
     }
 }
@@ -642,12 +1242,8 @@ impl TryInto<String> for Value {
     fn try_into(self) -> Result<String, ValueError> {
         match self {
             Value::Str(content) => Ok(content),
-            Value::I64(i) => Ok(format!("{}", i)),
-            Value::F64(f) => Ok(format!("{}", f)),
-            Value::F32(f) => Ok(format!("{}", f)),
-            Value::U64(u) => Ok(format!("{}", u)),
-            Value::Bytes(b) => Ok(format!("{:#x?}", b)),
-            _ => Err(ValueError::TypeMismatch),
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
+            other => other.to_canonical_string(),
         }
     }
 }
@@ -698,6 +1294,29 @@ impl From<i32> for Value {
     }
 }
 
+#[cfg(feature = "decimal")]
+impl From<Decimal> for Value {
+    fn from(d: Decimal) -> Self {
+        Self::Decimal(d)
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl TryInto<Decimal> for Value {
+    type Error = ValueError;
+
+    fn try_into(self) -> Result<Decimal, ValueError> {
+        match self {
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).try_into(),
+            Value::Decimal(content) => Ok(content),
+            Value::U8(content) => Ok(content.into()),
+            Value::I64(content) => Ok(content.into()),
+            Value::U64(content) => Ok(content.into()),
+            _ => Err(ValueError::type_mismatch("decimal", &self)),
+        }
+    }
+}
+
 impl From<f64> for Value {
     fn from(f: f64) -> Self {
         Self::F64(f)
@@ -759,6 +1378,15 @@ impl FromPyObject<'_> for Value {
             return Ok(result);
         }
 
+        if let Ok(tuple) = obj.downcast::<PyTuple>() {
+            let elems = tuple
+                .iter()
+                .map(|elem| elem.extract())
+                .collect::<PyResult<Vec<Value>>>()?;
+
+            return Ok(Value::Tuple(elems));
+        }
+
         if let Ok(pyfloat) = obj.downcast::<PyFloat>() {
             let f: f64 = pyfloat.extract()?;
             return Ok(f.into());
@@ -820,30 +1448,310 @@ impl<'a> IntoPyObject<'a> for Value {
             Value::F32(f) => Ok(f.into_pyobject(py).unwrap().into_bound().into_any()),
             Value::U64(u) => Ok(u.into_pyobject(py).unwrap().into_bound().into_any()),
             Value::U8(u) => Ok(u.into_pyobject(py).unwrap().into_bound().into_any()),
-            Value::Map(mut map) => {
-                let map = map.as_mut();
-                let mut moved = HashMap::new();
-
-                std::mem::swap(map, &mut moved);
-                moved.clone().into_pyobject(py).map(|o| o.into_any())
-            }
-            Value::List(list) => list.into_pyobject(py),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => d.into_pyobject(py).map(|o| o.into_any()),
+            Value::Map(map) => map.into_pyobject(py).map(|o| o.into_any()),
+            Value::List(list) | Value::Array(list) => list.into_pyobject(py),
             Value::Bytes(bytes) => {
                 let bytes = bytes.as_ref();
                 Ok(PyBytes::new(py, bytes).into_any())
             }
+            Value::Tuple(tuple) => PyTuple::new(py, tuple).map(|o| o.into_any()),
+            Value::Frozen(inner) => Arc::unwrap_or_clone(inner).into_pyobject(py),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::values::{Value, ValueError};
+    use crate::values::{
+        ConversionMode, HashableValue, MergeStrategy, PrimitiveType, Rounding, TypeDefinition,
+        Value, ValueError,
+    };
 
     use std::convert::TryInto;
 
     use serde_bytes::ByteBuf;
 
+    #[test]
+    fn canonical_string() {
+        assert_eq!(Value::from(1.0f64).to_canonical_string().unwrap(), "1.0");
+        assert_eq!(Value::from(0.1f32).to_canonical_string().unwrap(), "0.1");
+        assert_eq!(
+            Value::from(1e100f64).to_canonical_string().unwrap(),
+            "1e100"
+        );
+        assert_eq!(
+            Value::from(-1234567i64).to_canonical_string().unwrap(),
+            "-1234567"
+        );
+        assert_eq!(Value::from(true).to_canonical_string().unwrap(), "true");
+    }
+
+    #[test]
+    fn freeze() {
+        let mut map = Value::make_map();
+        map.map_insert(String::from("foobar"), "hi".into()).unwrap();
+
+        let mut frozen = map.clone().freeze();
+        assert!(frozen.is_frozen());
+        assert_eq!(frozen, map);
+        assert_eq!(frozen.get_child("foobar".into()).unwrap(), &"hi".into());
+
+        assert_eq!(
+            frozen.map_insert(String::from("x"), "hi".into()),
+            Err(ValueError::Frozen)
+        );
+        assert_eq!(frozen.remove("foobar"), Err(ValueError::Frozen));
+
+        // Frozen values serialize like their mutable counterpart
+        let encoded = bincode::serialize(&frozen).unwrap();
+        assert_eq!(encoded, bincode::serialize(&map).unwrap());
+
+        let decoded: Value = bincode::deserialize(&encoded).unwrap();
+        assert!(!decoded.is_frozen());
+        assert_eq!(decoded, map);
+
+        let mut thawed = frozen.thaw();
+        assert!(!thawed.is_frozen());
+        assert_eq!(thawed.map_insert(String::from("x"), "hi".into()), Ok(()));
+    }
+
+    #[test]
+    fn tuple_serialization() {
+        let tuple = Value::Tuple(vec![1i64.into(), "two".into()]);
+
+        let encoded = bincode::serialize(&tuple).unwrap();
+        let decoded: Value = bincode::deserialize(&encoded).unwrap();
+
+        assert_eq!(decoded, tuple);
+        assert_ne!(decoded, Value::from(vec![Value::from(1i64), "two".into()]));
+    }
+
+    #[test]
+    fn fixed_length_array() {
+        let mut array = Value::from([1u8, 2, 3]);
+
+        assert_eq!(array.type_name(), "array");
+        assert_eq!(
+            Value::from(["a", "b"]).get_type(),
+            TypeDefinition::Array(
+                Box::new(TypeDefinition::Primitive(PrimitiveType::String)),
+                2
+            )
+        );
+
+        assert_eq!(array.set_child(1i64.into(), 5u8.into()), Ok(()));
+        assert_eq!(
+            array.set_child(1i64.into(), 5i64.into()),
+            Err(ValueError::type_mismatch("u8", &5i64.into()))
+        );
+        assert_eq!(
+            array.set_child(3i64.into(), 5u8.into()),
+            Err(ValueError::IndexOutOfBounds)
+        );
+        assert!(array.list_append(4u8.into()).is_err());
+
+        let bytes: [u8; 3] = array.clone().try_into().unwrap();
+        assert_eq!(bytes, [1, 5, 3]);
+
+        let wrong_length: Result<[u8; 2], ValueError> = array.clone().try_into();
+        assert_eq!(wrong_length, Err(ValueError::ShapeMismatch));
+
+        assert_eq!(Value::from_array(bytes.to_vec()), array);
+        assert_ne!(Value::from(bytes.to_vec()), array);
+
+        let encoded = bincode::serialize(&array).unwrap();
+        let decoded: Value = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded, array);
+    }
+
+    #[test]
+    fn display() {
+        let mut map = Value::make_map();
+        map.map_insert("b".to_string(), vec![1i64, 2].into())
+            .unwrap();
+        map.map_insert("a".to_string(), Value::Tuple(vec!["x".into()]))
+            .unwrap();
+
+        assert_eq!(map.to_string(), r#"{"a": ("x",), "b": [1, 2]}"#);
+        assert_eq!(Value::from("text").to_string(), "text");
+        assert_eq!(Value::from(1.0f64).to_string(), "1.0");
+        assert_eq!(
+            Value::List(vec![Value::None, Value::from(true)])
+                .freeze()
+                .to_string(),
+            "[none, true]"
+        );
+        assert_eq!(
+            Value::Bytes(ByteBuf::from(vec![b'a', b'"', 0])).to_string(),
+            r#"b"a\"\x00""#
+        );
+
+        assert_eq!(
+            map.pretty(2),
+            "{\n  \"a\": (\n    \"x\",\n  ),\n  \"b\": [\n    1,\n    2,\n  ],\n}"
+        );
+        assert_eq!(Value::make_list().pretty(2), "[]");
+    }
+
+    #[test]
+    fn path() {
+        let mut user = Value::make_map();
+        user.map_insert("name".to_string(), "alice".into()).unwrap();
+
+        let mut value = Value::make_map();
+        value
+            .map_insert("users".to_string(), vec![user].into())
+            .unwrap();
+        value
+            .map_insert(
+                "point".to_string(),
+                Value::Tuple(vec![1i64.into(), 2i64.into()]),
+            )
+            .unwrap();
+
+        assert_eq!(value.get_path("users.0.name"), Ok(&"alice".into()));
+        assert_eq!(value.get_path("point.1"), Ok(&2i64.into()));
+        assert_eq!(
+            value.get_path("users.1.name"),
+            Err(ValueError::PathNotFound {
+                segment: "1".to_string(),
+                index: 1
+            })
+        );
+        assert_eq!(
+            value.get_path("users.0.name.first"),
+            Err(ValueError::PathNotFound {
+                segment: "first".to_string(),
+                index: 3
+            })
+        );
+        assert_eq!(value.get_path("users..name"), Err(ValueError::InvalidKey));
+
+        value.set_path("users.0.age", 42i64.into()).unwrap();
+        value.set_path("users.0.name", "bob".into()).unwrap();
+        assert_eq!(value.get_path("users.0.age"), Ok(&42i64.into()));
+        assert_eq!(value.get_path("users.0.name"), Ok(&"bob".into()));
+        assert_eq!(
+            value.set_path("groups.0", "admins".into()),
+            Err(ValueError::PathNotFound {
+                segment: "groups".to_string(),
+                index: 0
+            })
+        );
+        assert_eq!(
+            value.set_path("point.0", 5i64.into()),
+            Err(ValueError::OperationNotSupported)
+        );
+
+        assert_eq!(value.remove_path("users.0.age"), Ok(42i64.into()));
+        assert_eq!(
+            value.remove_path("users.0.age"),
+            Err(ValueError::PathNotFound {
+                segment: "age".to_string(),
+                index: 2
+            })
+        );
+
+        let mut frozen = value.freeze();
+        assert_eq!(frozen.get_path("users.0.name"), Ok(&"bob".into()));
+        assert_eq!(
+            frozen.set_path("users.0.name", "eve".into()),
+            Err(ValueError::Frozen)
+        );
+    }
+
+    fn config(port: i64, hosts: Vec<&str>) -> Value {
+        let mut server = Value::make_map();
+        server.set("port".to_string(), port.into()).unwrap();
+        server.set("hosts".to_string(), hosts.into()).unwrap();
+
+        let mut config = Value::make_map();
+        config.set("server".to_string(), server).unwrap();
+        config
+    }
+
+    #[test]
+    fn merge() {
+        let base = config(80, vec!["a"]);
+        let mut server = Value::make_map();
+        server.set("port".to_string(), 8080i64.into()).unwrap();
+
+        let mut layer = Value::make_map();
+        layer.set("server".to_string(), server).unwrap();
+
+        let mut replaced = base.clone();
+        replaced
+            .merge(layer.clone(), MergeStrategy::Replace)
+            .unwrap();
+        assert_eq!(
+            replaced.get_path("server.hosts"),
+            Err(ValueError::PathNotFound {
+                segment: "hosts".to_string(),
+                index: 1
+            })
+        );
+        assert_eq!(replaced.get_path("server.port"), Ok(&8080i64.into()));
+
+        let mut recursive = base.clone();
+        recursive
+            .merge(config(8080, vec!["b"]), MergeStrategy::Recursive)
+            .unwrap();
+        assert_eq!(recursive, config(8080, vec!["b"]));
+
+        let mut appended = base.clone();
+        appended
+            .merge(config(8080, vec!["b"]), MergeStrategy::Append)
+            .unwrap();
+        assert_eq!(appended, config(8080, vec!["a", "b"]));
+
+        let mut scalar = Value::from(1i64);
+        scalar.merge(base.clone(), MergeStrategy::Append).unwrap();
+        assert_eq!(scalar, base);
+
+        let mut frozen = base.clone().freeze();
+        assert_eq!(
+            frozen.merge(layer, MergeStrategy::Recursive),
+            Err(ValueError::Frozen)
+        );
+    }
+
+    #[test]
+    fn diff() {
+        let old = config(80, vec!["a", "b"]);
+        let mut new = config(8080, vec!["a", "c"]);
+        new.set("debug".to_string(), true.into()).unwrap();
+
+        assert_eq!(old.diff(&old.clone().freeze()), Value::make_list());
+
+        let changes = old.diff(&new).into_vec().unwrap();
+        assert_eq!(changes.len(), 3);
+
+        assert_eq!(changes[0].get("op"), Ok(&"add".into()));
+        assert_eq!(changes[0].get("path"), Ok(&vec!["debug"].into()));
+        assert_eq!(changes[0].get("new"), Ok(&true.into()));
+
+        assert_eq!(changes[1].get("op"), Ok(&"replace".into()));
+        assert_eq!(
+            changes[1].get("path"),
+            Ok(&Value::List(vec![
+                "server".into(),
+                "hosts".into(),
+                1usize.into()
+            ]))
+        );
+        assert_eq!(changes[1].get("old"), Ok(&"b".into()));
+        assert_eq!(changes[1].get("new"), Ok(&"c".into()));
+
+        assert_eq!(changes[2].get("path"), Ok(&vec!["server", "port"].into()));
+        assert_eq!(changes[2].get("old"), Ok(&80i64.into()));
+
+        let removed = new.diff(&Value::make_map()).into_vec().unwrap();
+        assert_eq!(removed[0].get("op"), Ok(&"remove".into()));
+        assert_eq!(removed[1].get("old"), Ok(new.get("server").unwrap()));
+    }
+
     #[test]
     fn list_append() {
         let mut list = Value::make_list();
@@ -854,6 +1762,336 @@ mod tests {
         assert_eq!(list.list_get_at(0).unwrap(), &"hi".into());
     }
 
+    #[test]
+    fn encode_decode() {
+        let s: Value = "grüße".into();
+        let bytes = s.encode().unwrap();
+
+        assert_eq!(bytes.type_name(), "bytes");
+        assert_eq!(bytes.decode(), Ok(s));
+
+        let invalid = Value::Bytes(ByteBuf::from(vec![0xff, 0xfe]));
+        assert_eq!(invalid.decode(), Err(ValueError::InvalidUtf8));
+    }
+
+    #[test]
+    fn bytes_conversions() {
+        let value = Value::from_bytes(vec![1, 2, 3]);
+        assert_eq!(value.as_bytes(), Some(&[1u8, 2, 3][..]));
+
+        let vec: Vec<u8> = value.clone().try_into().unwrap();
+        assert_eq!(vec, vec![1, 2, 3]);
+        assert_eq!(value.into_bytes(), Ok(vec![1, 2, 3]));
+
+        let list = Value::from(vec![1u8, 2, 3]);
+        assert_eq!(list.as_bytes(), None);
+        assert!(list.into_bytes().is_err());
+    }
+
+    #[test]
+    fn u8_range() {
+        let max: Result<u8, ValueError> = Value::I64(255).try_into();
+        let overflow: Result<u8, ValueError> = Value::I64(256).try_into();
+
+        assert_eq!(max, Ok(255));
+        assert_eq!(overflow, Err(ValueError::IntegerOverflow));
+    }
+
+    #[test]
+    fn convert_to() {
+        let value = Value::I64(300);
+
+        assert_eq!(
+            value.convert_to(PrimitiveType::U8, ConversionMode::Checked),
+            Err(ValueError::IntegerOverflow)
+        );
+        assert_eq!(
+            value.convert_to(PrimitiveType::U8, ConversionMode::Saturating),
+            Ok(Value::U8(255))
+        );
+        assert_eq!(
+            value.convert_to(PrimitiveType::U8, ConversionMode::Wrapping),
+            Ok(Value::U8(44))
+        );
+
+        let negative = Value::I64(-1);
+
+        assert_eq!(
+            negative.convert_to(PrimitiveType::U64, ConversionMode::Saturating),
+            Ok(Value::U64(0))
+        );
+        assert_eq!(
+            negative.convert_to(PrimitiveType::U64, ConversionMode::Wrapping),
+            Ok(Value::U64(u64::MAX))
+        );
+        assert_eq!(
+            Value::U64(u64::MAX).convert_to(PrimitiveType::I64, ConversionMode::Saturating),
+            Ok(Value::I64(i64::MAX))
+        );
+        assert_eq!(
+            Value::U8(7).convert_to(PrimitiveType::F64, ConversionMode::Checked),
+            Ok(Value::F64(7.0))
+        );
+    }
+
+    #[test]
+    fn float_to_integer() {
+        let value = Value::F64(-2.5);
+
+        assert_eq!(value.to_integer(Rounding::Truncate), Ok(Value::I64(-2)));
+        assert_eq!(value.to_integer(Rounding::Floor), Ok(Value::I64(-3)));
+        assert_eq!(value.to_integer(Rounding::Ceil), Ok(Value::I64(-2)));
+        assert_eq!(value.to_integer(Rounding::Round), Ok(Value::I64(-3)));
+        assert_eq!(
+            value.to_integer(Rounding::Exact),
+            Err(ValueError::NotAnInteger)
+        );
+
+        let truncated: Result<i64, ValueError> = value.try_into();
+        assert_eq!(truncated, Err(ValueError::NotAnInteger));
+
+        let exact: Result<i64, ValueError> = Value::F64(4.0).try_into();
+        assert_eq!(exact, Ok(4));
+
+        assert_eq!(
+            Value::F64(f64::NAN).to_integer(Rounding::Round),
+            Err(ValueError::NotAnInteger)
+        );
+        assert_eq!(
+            Value::F64(1e30).to_integer(Rounding::Round),
+            Err(ValueError::IntegerOverflow)
+        );
+    }
+
+    #[test]
+    fn compare_numbers() {
+        assert_eq!(Value::U8(5).is_greater_than(&Value::U8(3)), Ok(true));
+        assert_eq!(Value::U8(5).equals(&Value::I64(5)), Ok(true));
+        assert_eq!(
+            Value::I64(-1).is_smaller_than(&Value::U64(u64::MAX)),
+            Ok(true)
+        );
+        assert_eq!(Value::U64(u64::MAX).equals(&Value::I64(-1)), Ok(false));
+        assert_eq!(Value::F32(2.5).is_greater_than(&Value::U8(2)), Ok(true));
+        assert_eq!(
+            Value::F64(f64::NAN).equals(&Value::F64(f64::NAN)),
+            Ok(false)
+        );
+        assert_eq!(
+            Value::U8(1).is_smaller_than(&"foo".into()),
+            Err(ValueError::type_mismatch("a number", &"foo".into()))
+        );
+    }
+
+    #[test]
+    fn equals() {
+        assert_eq!(Value::from("a").equals(&"a".into()), Ok(true));
+        assert_eq!(Value::None.equals(&5i64.into()), Ok(false));
+        assert_eq!(
+            Value::from(vec![1i64, 2]).equals(&vec![Value::U8(1), Value::U64(2)].into()),
+            Ok(true)
+        );
+        assert_eq!(
+            Value::from("a").equals(&1i64.into()),
+            Err(ValueError::type_mismatch("string", &1i64.into()))
+        );
+    }
+
+    #[test]
+    fn total_cmp() {
+        use std::cmp::Ordering;
+
+        let mut values = vec![
+            Value::make_map(),
+            Value::from(vec![2i64]),
+            Value::Tuple(vec![]),
+            "b".into(),
+            "a".into(),
+            Value::F64(f64::NAN),
+            Value::F64(1.5),
+            Value::U64(u64::MAX),
+            Value::I64(-1),
+            Value::U8(1),
+            Value::I64(1),
+            Value::F64(1.0),
+            true.into(),
+            Value::None,
+        ];
+        values.sort_by(|a, b| a.total_cmp(b));
+
+        // NaN is only equal to itself when wrapped
+        let sorted: Vec<HashableValue> = values.into_iter().map(HashableValue).collect();
+        let expected: Vec<HashableValue> = vec![
+            Value::None,
+            true.into(),
+            Value::I64(-1),
+            Value::U8(1),
+            Value::I64(1),
+            Value::F64(1.0),
+            Value::F64(1.5),
+            Value::U64(u64::MAX),
+            Value::F64(f64::NAN),
+            "a".into(),
+            "b".into(),
+            Value::Tuple(vec![]),
+            Value::from(vec![2i64]),
+            Value::make_map(),
+        ]
+        .into_iter()
+        .map(HashableValue)
+        .collect();
+        assert_eq!(sorted, expected);
+
+        // Floats are compared without rounding large integers
+        let large = 1i64 << 60;
+        assert_eq!(
+            Value::I64(large + 1).total_cmp(&Value::F64(large as f64)),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Value::F64(0.0).total_cmp(&Value::F64(-0.0)),
+            Ordering::Equal
+        );
+        assert_eq!(
+            Value::from(vec![1i64, 2]).total_cmp(&Value::from(vec![1i64, 2]).freeze()),
+            Ordering::Equal
+        );
+    }
+
+    #[test]
+    fn hashable_value() {
+        use std::collections::HashMap;
+
+        let mut counts = HashMap::new();
+        for value in [
+            Value::F64(f64::NAN),
+            Value::F64(f64::NAN),
+            Value::F64(0.0),
+            Value::F64(-0.0),
+            Value::I64(0),
+            Value::from(vec!["x"]),
+            Value::from(vec!["x"]).freeze(),
+        ] {
+            *counts.entry(HashableValue::from(value)).or_insert(0) += 1;
+        }
+
+        assert_eq!(counts.len(), 4);
+        assert_eq!(counts[&HashableValue(Value::F64(f64::NAN))], 2);
+        assert_eq!(counts[&HashableValue(Value::F64(0.0))], 2);
+        assert_eq!(counts[&HashableValue(Value::I64(0))], 1);
+        assert_eq!(counts[&HashableValue(Value::from(vec!["x"]))], 2);
+    }
+
+    #[test]
+    fn contains() {
+        let list = Value::from(vec![1i64, 2]);
+        assert_eq!(list.contains(&2i64.into()), Ok(true));
+        assert_eq!(list.contains(&3i64.into()), Ok(false));
+
+        let mut map = Value::make_map();
+        map.map_insert(String::from("a"), 1i64.into()).unwrap();
+        assert_eq!(map.contains(&"a".into()), Ok(true));
+        assert_eq!(map.contains(&"b".into()), Ok(false));
+
+        let string: Value = "hello".into();
+        assert_eq!(string.contains(&"ell".into()), Ok(true));
+        assert!(string.contains(&1i64.into()).is_err());
+    }
+
+    #[test]
+    fn set_child() {
+        let mut list = Value::from(vec![1i64, 2]);
+        assert_eq!(list.set_child(1i64.into(), 5i64.into()), Ok(()));
+        assert_eq!(list, Value::from(vec![1i64, 5]));
+        assert_eq!(
+            list.set_child(2i64.into(), 5i64.into()),
+            Err(ValueError::IndexOutOfBounds)
+        );
+
+        let mut map = Value::make_map();
+        assert_eq!(map.set_child("a".into(), 1i64.into()), Ok(()));
+        assert_eq!(map.get("a"), Ok(&1i64.into()));
+
+        let mut frozen = Value::from(vec![1i64]).freeze();
+        assert_eq!(
+            frozen.set_child(0i64.into(), 2i64.into()),
+            Err(ValueError::Frozen)
+        );
+    }
+
+    #[test]
+    fn depth_and_size() {
+        let scalar = Value::I64(1);
+        assert_eq!(scalar.depth(), 0);
+        assert_eq!(scalar.size(), 1);
+
+        let nested = Value::List(vec![Value::from(vec![1i64, 2]), "abc".into()]);
+        assert_eq!(nested.depth(), 2);
+        assert_eq!(nested.size(), 1 + 3 + 4);
+
+        let mut map = Value::make_map();
+        map.map_insert(String::from("key"), nested).unwrap();
+        assert_eq!(map.depth(), 3);
+        assert_eq!(map.size(), 1 + 3 + 8);
+    }
+
+    #[test]
+    fn map_update() {
+        let mut map = Value::make_map();
+        map.map_insert(String::from("a"), 1i64.into()).unwrap();
+        map.map_insert(String::from("b"), 2i64.into()).unwrap();
+
+        let mut other = Value::make_map();
+        other.map_insert(String::from("b"), 3i64.into()).unwrap();
+        other.map_insert(String::from("c"), 4i64.into()).unwrap();
+
+        assert_eq!(map.map_update(other), Ok(()));
+        assert_eq!(map.num_children(), 3);
+        assert_eq!(map.get("b"), Ok(&3i64.into()));
+    }
+
+    #[test]
+    fn list_extend() {
+        let mut list: Value = vec![1i64, 2].into();
+
+        assert_eq!(list.list_extend(vec![3i64].into()), Ok(()));
+        assert_eq!(list, vec![1i64, 2, 3].into());
+
+        assert_eq!(
+            list.list_extend(1i64.into()),
+            Err(ValueError::type_mismatch("list", &1i64.into()))
+        );
+
+        assert_eq!(list.list_clear(), Ok(()));
+        assert_eq!(list.num_children(), 0);
+    }
+
+    #[test]
+    fn list_aggregates() {
+        let list: Value = vec![3i64, -1, 7, 3].into();
+
+        assert_eq!(list.list_count(&3i64.into()), Ok(2));
+        assert_eq!(list.list_min(), Ok((-1i64).into()));
+        assert_eq!(list.list_max(), Ok(7i64.into()));
+        assert_eq!(list.list_sum(), Ok(12i64.into()));
+
+        let empty = Value::make_list();
+        assert_eq!(empty.list_max(), Err(ValueError::EmptyList));
+        assert_eq!(empty.list_sum(), Ok(0i64.into()));
+    }
+
+    #[test]
+    fn list_sort() {
+        let mut numbers: Value = vec![2.5, 1.0, 3.0].into();
+        numbers.list_sort().unwrap();
+        assert_eq!(numbers, vec![1.0, 2.5, 3.0].into());
+
+        // Lists that cannot be sorted stay the same
+        let mut nan: Value = vec![2.0, f64::NAN, 1.0].into();
+        assert_eq!(nan.list_sort(), Err(ValueError::OperationNotSupported));
+        assert_eq!(nan.list_get_at(2).unwrap(), &Value::F64(1.0));
+    }
+
     #[test]
     fn map_insert() {
         let mut map = Value::make_map();
@@ -867,6 +2105,124 @@ mod tests {
         assert_eq!(res2, Err(ValueError::FieldAlreadyExists));
     }
 
+    #[test]
+    fn floor_divide() {
+        let lhs: Value = (-7i64).into();
+        let rhs: Value = 2i64.into();
+
+        assert_eq!(lhs.floor_divide(&rhs), Ok((-4i64).into()));
+        assert_eq!(lhs.divmod(&rhs), Ok(((-4i64).into(), 1i64.into())));
+        assert_eq!(
+            lhs.floor_divide(&0i64.into()),
+            Err(ValueError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn numeric_promotion() {
+        assert_eq!(Value::U8(2).add(&Value::U8(3)), Ok(Value::U8(5)));
+        assert_eq!(Value::U8(2).add(&Value::I64(-3)), Ok(Value::I64(-1)));
+        assert_eq!(Value::I64(2).add(&Value::U64(3)), Ok(Value::U64(5)));
+        assert_eq!(Value::U8(2).multiply(&Value::U64(3)), Ok(Value::U64(6)));
+        assert_eq!(Value::I64(1).add(&Value::F64(0.5)), Ok(Value::F64(1.5)));
+        assert_eq!(Value::F32(1.0).add(&Value::F32(0.5)), Ok(Value::F32(1.5)));
+        assert_eq!(Value::F32(1.0).add(&Value::F64(0.5)), Ok(Value::F64(1.5)));
+        assert_eq!(
+            Value::U8(7).floor_divide(&Value::F32(2.0)),
+            Ok(Value::F64(3.0))
+        );
+
+        assert_eq!(
+            Value::I64(1).add(&"foo".into()),
+            Err(ValueError::type_mismatch("i64", &"foo".into()))
+        );
+        assert_eq!(
+            Value::from("foo").multiply(&Value::I64(1)),
+            Err(ValueError::OperationNotSupported)
+        );
+    }
+
+    #[test]
+    fn numeric_properties() {
+        let samples = [
+            Value::U8(0),
+            Value::U8(7),
+            Value::I64(-13),
+            Value::I64(5),
+            Value::U64(11),
+            Value::F32(-2.5),
+            Value::F64(3.25),
+        ];
+
+        for a in samples.iter() {
+            for b in samples.iter() {
+                // Both operands are converted the same way, so order does not matter
+                assert_eq!(a.add(b), b.add(a), "{a:?} + {b:?}");
+                assert_eq!(a.multiply(b), b.multiply(a), "{a:?} * {b:?}");
+
+                let Ok((quotient, remainder)) = a.divmod(b) else {
+                    continue;
+                };
+
+                let product = quotient.multiply(b).unwrap();
+                assert_eq!(
+                    product.add(&remainder).unwrap().equals(a),
+                    Ok(true),
+                    "divmod({a:?}, {b:?})"
+                );
+                assert!(
+                    remainder.equals(&Value::U8(0)).unwrap()
+                        || remainder.is_smaller_than(&Value::U8(0)).unwrap()
+                            == b.is_smaller_than(&Value::U8(0)).unwrap(),
+                    "divmod({a:?}, {b:?})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn numeric_overflow() {
+        assert_eq!(
+            Value::I64(i64::MAX).add(&Value::I64(1)),
+            Err(ValueError::IntegerOverflow)
+        );
+        assert_eq!(
+            Value::I64(i64::MIN).floor_divide(&Value::I64(-1)),
+            Err(ValueError::IntegerOverflow)
+        );
+        assert_eq!(
+            Value::I64(i64::MIN).divmod(&Value::I64(-1)),
+            Err(ValueError::IntegerOverflow)
+        );
+        assert_eq!(
+            Value::U64(0).subtract(&Value::U64(1)),
+            Err(ValueError::IntegerOverflow)
+        );
+        assert_eq!(
+            Value::U64(1).add(&Value::I64(-1)),
+            Err(ValueError::IntegerOverflow)
+        );
+        assert_eq!(
+            Value::U8(255).multiply(&Value::U8(2)),
+            Err(ValueError::IntegerOverflow)
+        );
+        assert_eq!(
+            Value::U8(1).floor_divide(&Value::U8(0)),
+            Err(ValueError::DivisionByZero)
+        );
+        assert_eq!(
+            Value::F64(1.0).divmod(&Value::F64(0.0)),
+            Err(ValueError::DivisionByZero)
+        );
+
+        // Conversions do not wrap around either
+        let negative: Result<u64, ValueError> = Value::I64(-1).try_into();
+        assert_eq!(negative, Err(ValueError::IntegerOverflow));
+
+        let large: Result<i64, ValueError> = Value::U64(u64::MAX).try_into();
+        assert_eq!(large, Err(ValueError::IntegerOverflow));
+    }
+
     #[test]
     fn bytes_to_str() {
         let mut bytes1 = ByteBuf::new();
@@ -887,7 +2243,7 @@ mod tests {
     #[test]
     fn vec_convert() {
         let vector = vec![5.0, 6.5, 1.524];
-        let value: Value = vector.clone().try_into().unwrap();
+        let value: Value = vector.clone().into();
 
         let field = value.get_child(1.into()).unwrap().clone();
         let field_res: f64 = field.try_into().unwrap();
@@ -896,4 +2252,104 @@ mod tests {
         let result: Vec<f64> = value.try_into().unwrap();
         assert_eq!(vector, result);
     }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn decimal() {
+        use crate::values::Decimal;
+        use std::cmp::Ordering;
+
+        let price = Value::from("0.10")
+            .convert_to(PrimitiveType::Decimal, ConversionMode::Checked)
+            .unwrap();
+        assert_eq!(price.type_name(), "decimal");
+
+        // Unlike floats, ten times 0.1 is exactly 1
+        let mut total: Value = Decimal::ZERO.into();
+        for _ in 0..10 {
+            total = total.add(&price).unwrap();
+        }
+        assert!(total.equals(&1i64.into()).unwrap());
+        assert_eq!(total.to_string(), "1.00");
+
+        assert_eq!(
+            price.multiply(&3i64.into()).unwrap().to_canonical_string(),
+            Ok("0.30".to_string())
+        );
+        assert_eq!(
+            Value::from(Decimal::new(-75, 1)).divmod(&2i64.into()),
+            Ok((Decimal::from(-4).into(), Decimal::new(5, 1).into()))
+        );
+        assert_eq!(
+            price.floor_divide(&Decimal::ZERO.into()),
+            Err(ValueError::DivisionByZero)
+        );
+        assert_eq!(
+            Value::from(Decimal::MAX).add(&1i64.into()),
+            Err(ValueError::DecimalOverflow)
+        );
+
+        // Mixing decimals and floats requires an explicit conversion
+        assert!(matches!(
+            price.add(&0.5.into()),
+            Err(ValueError::TypeMismatch { .. })
+        ));
+        assert_eq!(
+            Value::F64(0.1).convert_to(PrimitiveType::Decimal, ConversionMode::Checked),
+            Ok(Decimal::new(1, 1).into())
+        );
+        assert_eq!(
+            price.convert_to(PrimitiveType::F64, ConversionMode::Checked),
+            Ok(0.1.into())
+        );
+        assert_eq!(
+            Value::from("ten").convert_to(PrimitiveType::Decimal, ConversionMode::Checked),
+            Err(ValueError::InvalidDecimal("ten".to_string()))
+        );
+        assert_eq!(
+            Value::F64(f64::NAN)
+                .convert_to(PrimitiveType::Decimal, ConversionMode::Checked)
+                .unwrap_err()
+                .name(),
+            "InvalidDecimal"
+        );
+        assert_eq!(
+            total.convert_to(PrimitiveType::U8, ConversionMode::Checked),
+            Ok(Value::U8(1))
+        );
+        assert_eq!(
+            price.convert_to(PrimitiveType::I64, ConversionMode::Checked),
+            Err(ValueError::NotAnInteger)
+        );
+
+        // Scale does not matter for comparisons and hashing
+        let one = Value::from(Decimal::ONE);
+        assert!(one.equals(&total).unwrap());
+        assert!(price.is_smaller_than(&one).unwrap());
+        assert!(one.is_greater_than(&0.5.into()).unwrap());
+        assert_eq!(one.total_cmp(&total), Ordering::Equal);
+        assert_eq!(one.total_cmp(&1i64.into()), Ordering::Greater);
+        assert_eq!(HashableValue(one.clone()), HashableValue(total.clone()));
+
+        let encoded = bincode::serialize(&total).unwrap();
+        let decoded: Value = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded.to_string(), "1.00");
+    }
+
+    #[cfg(feature = "ordered-maps")]
+    #[test]
+    fn ordered_map_serialization() {
+        // The feature does not change the type of the entries
+        let entries: std::collections::HashMap<String, Value> =
+            [("b".to_string(), 2.into()), ("a".to_string(), 1.into())].into();
+        let first = Value::Map(Box::new(entries));
+
+        let mut second = Value::make_map();
+        second.map_insert("a".to_string(), 1.into()).unwrap();
+        second.map_insert("b".to_string(), 2.into()).unwrap();
+
+        let first = bincode::serialize(&first).unwrap();
+        let second = bincode::serialize(&second).unwrap();
+        assert_eq!(first, second);
+    }
 }
@@ -0,0 +1,577 @@
+//! Arithmetic on and comparisons of numbers
+//!
+//! Before an arithmetic operation, both operands are converted to a common type:
+//!
+//! | operands                        | result type |
+//! |---------------------------------|-------------|
+//! | both of the same type           | that type   |
+//! | u8 and i64                      | i64         |
+//! | u8 or i64, and u64              | u64         |
+//! | a float and any other number    | f64 (or f32 if both are f32) |
+//! | a decimal and an integer        | decimal     |
+//!
+//! Decimals and floats cannot be mixed, as converting between them might
+//! silently round (convert them explicitly with `Value::convert_to`).
+//!
+//! `/` always divides exactly, so integers are divided as f64 values. Other
+//! integer operations never produce floats: `//` rounds towards negative
+//! infinity and `%` has the sign of the divisor, as in Python. Results that do
+//! not fit into the result type (including negative i64 values that are
+//! converted to u64) fail with `ValueError::IntegerOverflow` instead of wrapping
+//! around. Comparisons do not convert their operands and work across all
+//! numeric types.
+
+use std::cmp::Ordering;
+
+#[cfg(feature = "decimal")]
+use rust_decimal::prelude::ToPrimitive;
+
+#[cfg(feature = "decimal")]
+use rust_decimal::Decimal;
+
+use super::{Value, ValueError};
+
+/// Common representation of all numeric values, used to compare them
+#[derive(Debug, Copy, Clone)]
+pub(super) enum Number {
+    Integer(i128),
+    Float(f64),
+    #[cfg(feature = "decimal")]
+    Decimal(Decimal),
+}
+
+impl Number {
+    pub(super) fn from_value(value: &Value) -> Option<Self> {
+        let number = match value.unfrozen() {
+            Value::U8(i) => Number::Integer(*i as i128),
+            Value::I64(i) => Number::Integer(*i as i128),
+            Value::U64(i) => Number::Integer(*i as i128),
+            Value::F32(f) => Number::Float(*f as f64),
+            Value::F64(f) => Number::Float(*f),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => Number::Decimal(*d),
+            _ => return None,
+        };
+
+        Some(number)
+    }
+
+    /// Compare two numbers (returns None if one of them is NaN)
+    ///
+    /// Decimals are compared exactly with integers, but as floats with floats.
+    pub(super) fn compare(self, other: Self) -> Option<Ordering> {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b)) => Some(a.cmp(&b)),
+            #[cfg(feature = "decimal")]
+            (Number::Decimal(_) | Number::Integer(_), Number::Decimal(_) | Number::Integer(_)) => {
+                Some(self.as_decimal()?.cmp(&other.as_decimal()?))
+            }
+            (a, b) => a.as_float().partial_cmp(&b.as_float()),
+        }
+    }
+
+    pub(super) fn as_float(self) -> f64 {
+        match self {
+            Number::Integer(i) => i as f64,
+            Number::Float(f) => f,
+            #[cfg(feature = "decimal")]
+            Number::Decimal(d) => d.to_f64().unwrap(),
+        }
+    }
+
+    /// Convert an integer or decimal (None for floats)
+    #[cfg(feature = "decimal")]
+    pub(super) fn as_decimal(self) -> Option<Decimal> {
+        match self {
+            // All integer types fit into a decimal
+            Number::Integer(i) => Some(Decimal::from_i128_with_scale(i, 0)),
+            Number::Decimal(d) => Some(d),
+            Number::Float(_) => None,
+        }
+    }
+}
+
+/// The types arithmetic operations can be performed on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum NumericType {
+    U8,
+    I64,
+    U64,
+    F32,
+    F64,
+    #[cfg(feature = "decimal")]
+    Decimal,
+}
+
+impl NumericType {
+    fn of(value: &Value) -> Option<Self> {
+        let numeric_type = match value.unfrozen() {
+            Value::U8(_) => Self::U8,
+            Value::I64(_) => Self::I64,
+            Value::U64(_) => Self::U64,
+            Value::F32(_) => Self::F32,
+            Value::F64(_) => Self::F64,
+            #[cfg(feature = "decimal")]
+            Value::Decimal(_) => Self::Decimal,
+            _ => return None,
+        };
+
+        Some(numeric_type)
+    }
+
+    /// The type both operands are converted to (see the table at the top of this module)
+    ///
+    /// Returns None for decimals and floats.
+    fn promote(self, other: Self) -> Option<Self> {
+        let result = match (self, other) {
+            (a, b) if a == b => a,
+            #[cfg(feature = "decimal")]
+            (Self::Decimal, Self::F32 | Self::F64) | (Self::F32 | Self::F64, Self::Decimal) => {
+                return None;
+            }
+            #[cfg(feature = "decimal")]
+            (Self::Decimal, _) | (_, Self::Decimal) => Self::Decimal,
+            (Self::F32 | Self::F64, _) | (_, Self::F32 | Self::F64) => Self::F64,
+            (Self::U64, _) | (_, Self::U64) => Self::U64,
+            _ => Self::I64,
+        };
+
+        Some(result)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Operation {
+    Add,
+    Subtract,
+    Multiply,
+    FloorDivide,
+    Remainder,
+    Divide,
+}
+
+/// The operations every numeric type supports
+trait Numeric: Copy + Into<Value> {
+    /// Returned if the result of `checked_add`, `checked_sub`, or `checked_mul` does not fit
+    const OVERFLOW: ValueError = ValueError::IntegerOverflow;
+
+    /// Convert a number whose type promotes to this type
+    fn from_value(value: &Value) -> Result<Self, ValueError>;
+
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+    fn checked_mul(self, other: Self) -> Option<Self>;
+
+    /// Divide and round towards negative infinity
+    fn floor_div(self, other: Self) -> Result<Self, ValueError>;
+
+    /// The remainder of `floor_div` (has the same sign as the divisor)
+    fn floor_rem(self, other: Self) -> Result<Self, ValueError>;
+
+    /// Divide without rounding
+    ///
+    /// Integers are converted to f64 before dividing (see `Value::arithmetic`).
+    fn exact_div(self, _other: Self) -> Result<Self, ValueError> {
+        Err(ValueError::OperationNotSupported)
+    }
+
+    fn apply(self, op: Operation, other: Self) -> Result<Value, ValueError> {
+        let result = match op {
+            Operation::Add => self.checked_add(other).ok_or(Self::OVERFLOW)?,
+            Operation::Subtract => self.checked_sub(other).ok_or(Self::OVERFLOW)?,
+            Operation::Multiply => self.checked_mul(other).ok_or(Self::OVERFLOW)?,
+            Operation::FloorDivide => self.floor_div(other)?,
+            Operation::Remainder => self.floor_rem(other)?,
+            Operation::Divide => self.exact_div(other)?,
+        };
+
+        Ok(result.into())
+    }
+}
+
+macro_rules! impl_unsigned {
+    ($type:ty) => {
+        fn checked_add(self, other: Self) -> Option<Self> {
+            <$type>::checked_add(self, other)
+        }
+
+        fn checked_sub(self, other: Self) -> Option<Self> {
+            <$type>::checked_sub(self, other)
+        }
+
+        fn checked_mul(self, other: Self) -> Option<Self> {
+            <$type>::checked_mul(self, other)
+        }
+
+        fn floor_div(self, other: Self) -> Result<Self, ValueError> {
+            self.checked_div(other).ok_or(ValueError::DivisionByZero)
+        }
+
+        fn floor_rem(self, other: Self) -> Result<Self, ValueError> {
+            self.checked_rem(other).ok_or(ValueError::DivisionByZero)
+        }
+    };
+}
+
+impl Numeric for u8 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        match value.unfrozen() {
+            Value::U8(u) => Ok(*u),
+            other => Err(ValueError::type_mismatch("u8", other)),
+        }
+    }
+
+    impl_unsigned!(u8);
+}
+
+impl Numeric for u64 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        match value.unfrozen() {
+            Value::U8(u) => Ok(*u as u64),
+            Value::U64(u) => Ok(*u),
+            Value::I64(i) => u64::try_from(*i).map_err(|_| ValueError::IntegerOverflow),
+            other => Err(ValueError::type_mismatch("u64", other)),
+        }
+    }
+
+    impl_unsigned!(u64);
+}
+
+impl Numeric for i64 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        match value.unfrozen() {
+            Value::U8(u) => Ok(*u as i64),
+            Value::I64(i) => Ok(*i),
+            other => Err(ValueError::type_mismatch("i64", other)),
+        }
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        i64::checked_add(self, other)
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        i64::checked_sub(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        i64::checked_mul(self, other)
+    }
+
+    fn floor_div(self, other: Self) -> Result<Self, ValueError> {
+        if other == 0 {
+            return Err(ValueError::DivisionByZero);
+        }
+
+        // Only fails for i64::MIN // -1
+        let quotient = self.checked_div(other).ok_or(ValueError::IntegerOverflow)?;
+
+        // Rust rounds towards zero, so fix up negative results
+        if self % other != 0 && ((self < 0) != (other < 0)) {
+            Ok(quotient - 1)
+        } else {
+            Ok(quotient)
+        }
+    }
+
+    fn floor_rem(self, other: Self) -> Result<Self, ValueError> {
+        if other == 0 {
+            return Err(ValueError::DivisionByZero);
+        }
+
+        // Cannot overflow, as the remainder of i64::MIN / -1 is zero
+        let remainder = self.wrapping_rem(other);
+
+        if remainder != 0 && ((remainder < 0) != (other < 0)) {
+            Ok(remainder + other)
+        } else {
+            Ok(remainder)
+        }
+    }
+}
+
+macro_rules! impl_float {
+    () => {
+        fn checked_add(self, other: Self) -> Option<Self> {
+            Some(self + other)
+        }
+
+        fn checked_sub(self, other: Self) -> Option<Self> {
+            Some(self - other)
+        }
+
+        fn checked_mul(self, other: Self) -> Option<Self> {
+            Some(self * other)
+        }
+
+        fn floor_div(self, other: Self) -> Result<Self, ValueError> {
+            if other == 0.0 {
+                return Err(ValueError::DivisionByZero);
+            }
+
+            Ok((self / other).floor())
+        }
+
+        fn floor_rem(self, other: Self) -> Result<Self, ValueError> {
+            Ok(self - self.floor_div(other)? * other)
+        }
+
+        fn exact_div(self, other: Self) -> Result<Self, ValueError> {
+            if other == 0.0 {
+                return Err(ValueError::DivisionByZero);
+            }
+
+            Ok(self / other)
+        }
+    };
+}
+
+impl Numeric for f32 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        match value.unfrozen() {
+            Value::F32(f) => Ok(*f),
+            other => Err(ValueError::type_mismatch("f32", other)),
+        }
+    }
+
+    impl_float!();
+}
+
+impl Numeric for f64 {
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        match Number::from_value(value) {
+            Some(number) => Ok(number.as_float()),
+            None => Err(ValueError::type_mismatch("f64", value)),
+        }
+    }
+
+    impl_float!();
+}
+
+#[cfg(feature = "decimal")]
+impl Numeric for Decimal {
+    const OVERFLOW: ValueError = ValueError::DecimalOverflow;
+
+    fn from_value(value: &Value) -> Result<Self, ValueError> {
+        match Number::from_value(value).and_then(Number::as_decimal) {
+            Some(d) => Ok(d),
+            None => Err(ValueError::type_mismatch("decimal", value)),
+        }
+    }
+
+    fn checked_add(self, other: Self) -> Option<Self> {
+        Decimal::checked_add(self, other)
+    }
+
+    fn checked_sub(self, other: Self) -> Option<Self> {
+        Decimal::checked_sub(self, other)
+    }
+
+    fn checked_mul(self, other: Self) -> Option<Self> {
+        Decimal::checked_mul(self, other)
+    }
+
+    fn floor_div(self, other: Self) -> Result<Self, ValueError> {
+        if other.is_zero() {
+            return Err(ValueError::DivisionByZero);
+        }
+
+        let quotient = self.checked_div(other).ok_or(ValueError::DecimalOverflow)?;
+        Ok(quotient.floor())
+    }
+
+    fn floor_rem(self, other: Self) -> Result<Self, ValueError> {
+        let quotient = self.floor_div(other)?;
+        let product = quotient
+            .checked_mul(other)
+            .ok_or(ValueError::DecimalOverflow)?;
+
+        self.checked_sub(product).ok_or(ValueError::DecimalOverflow)
+    }
+
+    fn exact_div(self, other: Self) -> Result<Self, ValueError> {
+        if other.is_zero() {
+            return Err(ValueError::DivisionByZero);
+        }
+
+        self.checked_div(other).ok_or(ValueError::DecimalOverflow)
+    }
+}
+
+/// Convert both operands to their common type and apply the operation
+fn apply<T: Numeric>(lhs: &Value, op: Operation, rhs: &Value) -> Result<Value, ValueError> {
+    T::from_value(lhs)?.apply(op, T::from_value(rhs)?)
+}
+
+impl Value {
+    fn arithmetic(&self, op: Operation, other: &Value) -> Result<Value, ValueError> {
+        let Some(lhs_type) = NumericType::of(self) else {
+            return Err(ValueError::OperationNotSupported);
+        };
+        let Some(rhs_type) = NumericType::of(other) else {
+            return Err(ValueError::type_mismatch(self.type_name(), other));
+        };
+        let Some(mut result_type) = lhs_type.promote(rhs_type) else {
+            return Err(ValueError::type_mismatch(self.type_name(), other));
+        };
+
+        if let (Operation::Divide, NumericType::U8 | NumericType::I64 | NumericType::U64) =
+            (op, result_type)
+        {
+            result_type = NumericType::F64;
+        }
+
+        match result_type {
+            NumericType::U8 => apply::<u8>(self, op, other),
+            NumericType::I64 => apply::<i64>(self, op, other),
+            NumericType::U64 => apply::<u64>(self, op, other),
+            NumericType::F32 => apply::<f32>(self, op, other),
+            NumericType::F64 => apply::<f64>(self, op, other),
+            #[cfg(feature = "decimal")]
+            NumericType::Decimal => apply::<Decimal>(self, op, other),
+        }
+    }
+
+    /// Sum this value with another (numerals only)
+    pub fn add(&self, other: &Value) -> Result<Value, ValueError> {
+        self.arithmetic(Operation::Add, other)
+    }
+
+    /// Subtract another value from this value (numerals only)
+    pub fn subtract(&self, other: &Value) -> Result<Value, ValueError> {
+        self.arithmetic(Operation::Subtract, other)
+    }
+
+    /// Multiply this value with another (numerals only)
+    pub fn multiply(&self, other: &Value) -> Result<Value, ValueError> {
+        self.arithmetic(Operation::Multiply, other)
+    }
+
+    /// Divide this value by another and round towards negative infinity (numerals only)
+    pub fn floor_divide(&self, other: &Value) -> Result<Value, ValueError> {
+        self.arithmetic(Operation::FloorDivide, other)
+    }
+
+    /// Divide this value by another without rounding (numerals only)
+    ///
+    /// Integers are divided as f64 values.
+    pub fn divide(&self, other: &Value) -> Result<Value, ValueError> {
+        self.arithmetic(Operation::Divide, other)
+    }
+
+    /// The remainder of `floor_divide` (has the same sign as the divisor)
+    pub fn remainder(&self, other: &Value) -> Result<Value, ValueError> {
+        self.arithmetic(Operation::Remainder, other)
+    }
+
+    /// Compute the floored quotient and the remainder of a division
+    ///
+    /// The remainder has the same sign as the divisor, which matches the semantics of `//`
+    pub fn divmod(&self, other: &Value) -> Result<(Value, Value), ValueError> {
+        let quotient = self.arithmetic(Operation::FloorDivide, other)?;
+        let remainder = self.arithmetic(Operation::Remainder, other)?;
+
+        Ok((quotient, remainder))
+    }
+
+    /// Get the absolute value of a number
+    pub fn abs(&self) -> Result<Value, ValueError> {
+        match self.unfrozen() {
+            Value::I64(i) => Ok(i.checked_abs().ok_or(ValueError::IntegerOverflow)?.into()),
+            Value::U64(_) | Value::U8(_) => Ok(self.unfrozen().clone()),
+            Value::F64(f) => Ok(f.abs().into()),
+            Value::F32(f) => Ok(f.abs().into()),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => Ok(d.abs().into()),
+            other => Err(ValueError::type_mismatch("number", other)),
+        }
+    }
+
+    /// Get the number with the opposite sign (`-x`)
+    ///
+    /// Unsigned integers other than zero fail with `ValueError::IntegerOverflow`.
+    pub fn negative(&self) -> Result<Value, ValueError> {
+        match self.unfrozen() {
+            Value::I64(i) => Ok(i.checked_neg().ok_or(ValueError::IntegerOverflow)?.into()),
+            Value::U64(0) | Value::U8(0) => Ok(self.unfrozen().clone()),
+            Value::U64(_) | Value::U8(_) => Err(ValueError::IntegerOverflow),
+            Value::F64(f) => Ok((-f).into()),
+            Value::F32(f) => Ok((-f).into()),
+            #[cfg(feature = "decimal")]
+            Value::Decimal(d) => Ok((-d).into()),
+            other => Err(ValueError::type_mismatch("number", other)),
+        }
+    }
+
+    /// Compare two numbers of any type (None if one of them is NaN)
+    pub(super) fn compare_numbers(&self, other: &Value) -> Result<Option<Ordering>, ValueError> {
+        let Some(lhs) = Number::from_value(self) else {
+            return Err(ValueError::type_mismatch("a number", self));
+        };
+        let Some(rhs) = Number::from_value(other) else {
+            return Err(ValueError::type_mismatch("a number", other));
+        };
+
+        Ok(lhs.compare(rhs))
+    }
+
+    /// Do a numeric comparison (>) between this value and another
+    pub fn is_greater_than(&self, other: &Value) -> Result<bool, ValueError> {
+        Ok(self.compare_numbers(other)? == Some(Ordering::Greater))
+    }
+
+    /// Compare this value with another (==)
+    ///
+    /// Numbers are compared across numeric types, lists, tuples, arrays, and maps
+    /// element by element. `none` is only equal to itself, but can be compared
+    /// with anything. Other values must be of the same type.
+    pub fn equals(&self, other: &Value) -> Result<bool, ValueError> {
+        match (self.unfrozen(), other.unfrozen()) {
+            (Value::None, other) | (other, Value::None) => Ok(matches!(other, Value::None)),
+            (Value::Bool(content), other) => {
+                let other: bool = other.clone().try_into()?;
+                Ok(*content == other)
+            }
+            (Value::Str(lhs), Value::Str(rhs)) => Ok(lhs == rhs),
+            (Value::Bytes(lhs), Value::Bytes(rhs)) => Ok(lhs == rhs),
+            (Value::List(lhs), Value::List(rhs))
+            | (Value::Tuple(lhs), Value::Tuple(rhs))
+            | (Value::Array(lhs), Value::Array(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Ok(false);
+                }
+
+                for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+                    if !lhs.equals(rhs)? {
+                        return Ok(false);
+                    }
+                }
+
+                Ok(true)
+            }
+            (Value::Map(lhs), Value::Map(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Ok(false);
+                }
+
+                for (key, lhs) in lhs.iter() {
+                    match rhs.get(key) {
+                        Some(rhs) if lhs.equals(rhs)? => {}
+                        _ => return Ok(false),
+                    }
+                }
+
+                Ok(true)
+            }
+            (lhs, rhs) if Number::from_value(lhs).is_some() => {
+                Ok(lhs.compare_numbers(rhs)? == Some(Ordering::Equal))
+            }
+            (lhs, rhs) => Err(ValueError::type_mismatch(lhs.type_name(), rhs)),
+        }
+    }
+
+    /// Do a numeric comparison (<) between this value and another
+    pub fn is_smaller_than(&self, other: &Value) -> Result<bool, ValueError> {
+        Ok(self.compare_numbers(other)? == Some(Ordering::Less))
+    }
+}
@@ -0,0 +1,250 @@
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+
+use super::numeric::Number;
+use super::{MapEntries, Value};
+
+impl Value {
+    /// Compare two values of any type
+    ///
+    /// Values of different kinds are ordered as follows: none, booleans,
+    /// numbers, strings, bytes, tuples, lists, arrays, and maps. Numbers are
+    /// compared by their value, with NaN larger than any other number. Numbers
+    /// with the same value are ordered by their type (u8, i64, u64, f32, f64, decimal),
+    /// so that only values that are equal (`==`) compare as equal. The only
+    /// exception is NaN, which is equal to itself here.
+    ///
+    /// Sequences are compared element by element and maps entry by entry
+    /// (sorted by key). Frozen values compare like their mutable counterparts.
+    pub fn total_cmp(&self, other: &Value) -> Ordering {
+        let (lhs, rhs) = (self.unfrozen(), other.unfrozen());
+
+        let order = kind_rank(lhs).cmp(&kind_rank(rhs));
+        if order != Ordering::Equal {
+            return order;
+        }
+
+        match (lhs, rhs) {
+            (Value::None, Value::None) => Ordering::Equal,
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Str(a), Value::Str(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::Tuple(a), Value::Tuple(b))
+            | (Value::List(a), Value::List(b))
+            | (Value::Array(a), Value::Array(b)) => cmp_sequences(a, b),
+            (Value::Map(a), Value::Map(b)) => {
+                let a = sorted_entries(a);
+                let b = sorted_entries(b);
+
+                for ((key_a, value_a), (key_b, value_b)) in a.iter().zip(b.iter()) {
+                    let order = key_a.cmp(key_b).then_with(|| value_a.total_cmp(value_b));
+                    if order != Ordering::Equal {
+                        return order;
+                    }
+                }
+
+                a.len().cmp(&b.len())
+            }
+            (a, b) => {
+                // Both are numbers
+                let a_num = Number::from_value(a).unwrap();
+                let b_num = Number::from_value(b).unwrap();
+
+                cmp_numbers(a_num, b_num).then_with(|| number_rank(a).cmp(&number_rank(b)))
+            }
+        }
+    }
+}
+
+/// A value that can be used as the key of a `HashMap` or `BTreeMap`
+///
+/// Equality and ordering follow `Value::total_cmp`.
+#[derive(Debug, Clone)]
+pub struct HashableValue(pub Value);
+
+impl HashableValue {
+    pub fn into_inner(self) -> Value {
+        self.0
+    }
+}
+
+impl From<Value> for HashableValue {
+    fn from(value: Value) -> Self {
+        Self(value)
+    }
+}
+
+impl PartialEq for HashableValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.total_cmp(&other.0) == Ordering::Equal
+    }
+}
+
+impl Eq for HashableValue {}
+
+impl PartialOrd for HashableValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HashableValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl Hash for HashableValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_value(&self.0, state);
+    }
+}
+
+fn hash_value<H: Hasher>(value: &Value, state: &mut H) {
+    let value = value.unfrozen();
+    kind_rank(value).hash(state);
+
+    match value {
+        Value::None => {}
+        Value::Bool(b) => b.hash(state),
+        Value::Str(s) => s.hash(state),
+        Value::Bytes(bytes) => bytes.hash(state),
+        Value::U8(u) => u.hash(state),
+        Value::I64(i) => i.hash(state),
+        Value::U64(u) => u.hash(state),
+        // Values that compare as equal must have the same hash
+        Value::F32(f) => canonical_float(*f as f64).to_bits().hash(state),
+        Value::F64(f) => canonical_float(*f).to_bits().hash(state),
+        // Equal decimals can have different scales (e.g., 1.5 and 1.50)
+        #[cfg(feature = "decimal")]
+        Value::Decimal(d) => d.normalize().hash(state),
+        Value::Tuple(elements) | Value::List(elements) | Value::Array(elements) => {
+            elements.len().hash(state);
+            for elem in elements {
+                hash_value(elem, state);
+            }
+        }
+        Value::Map(entries) => {
+            entries.len().hash(state);
+            for (key, value) in sorted_entries(entries) {
+                key.hash(state);
+                hash_value(value, state);
+            }
+        }
+        Value::Frozen(_) => unreachable!(),
+    }
+
+    number_rank(value).hash(state);
+}
+
+/// Treat -0.0 like 0.0 and all NaNs alike
+fn canonical_float(f: f64) -> f64 {
+    if f == 0.0 {
+        0.0
+    } else if f.is_nan() {
+        f64::NAN
+    } else {
+        f
+    }
+}
+
+fn kind_rank(value: &Value) -> u8 {
+    match value {
+        Value::None => 0,
+        Value::Bool(_) => 1,
+        Value::U8(_) | Value::I64(_) | Value::U64(_) | Value::F32(_) | Value::F64(_) => 2,
+        #[cfg(feature = "decimal")]
+        Value::Decimal(_) => 2,
+        Value::Str(_) => 3,
+        Value::Bytes(_) => 4,
+        Value::Tuple(_) => 5,
+        Value::List(_) => 6,
+        Value::Array(_) => 7,
+        Value::Map(_) => 8,
+        Value::Frozen(inner) => kind_rank(inner),
+    }
+}
+
+/// Orders numbers with the same value (zero for all other values)
+fn number_rank(value: &Value) -> u8 {
+    match value {
+        Value::U8(_) => 1,
+        Value::I64(_) => 2,
+        Value::U64(_) => 3,
+        Value::F32(_) => 4,
+        Value::F64(_) => 5,
+        #[cfg(feature = "decimal")]
+        Value::Decimal(_) => 6,
+        _ => 0,
+    }
+}
+
+fn cmp_sequences(a: &[Value], b: &[Value]) -> Ordering {
+    for (elem_a, elem_b) in a.iter().zip(b.iter()) {
+        let order = elem_a.total_cmp(elem_b);
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+
+    a.len().cmp(&b.len())
+}
+
+fn sorted_entries(entries: &MapEntries) -> Vec<(&String, &Value)> {
+    let mut entries: Vec<(&String, &Value)> = entries.iter().collect();
+    entries.sort_by_key(|(key, _)| *key);
+    entries
+}
+
+fn cmp_numbers(a: Number, b: Number) -> Ordering {
+    match (a, b) {
+        (Number::Integer(a), Number::Integer(b)) => a.cmp(&b),
+        (Number::Integer(i), Number::Float(f)) => cmp_integer_float(i, f),
+        (Number::Float(f), Number::Integer(i)) => cmp_integer_float(i, f).reverse(),
+        (Number::Float(a), Number::Float(b)) => match (a.is_nan(), b.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Greater,
+            (false, true) => Ordering::Less,
+            // -0.0 and 0.0 are equal
+            (false, false) => a.partial_cmp(&b).unwrap(),
+        },
+        #[cfg(feature = "decimal")]
+        (Number::Decimal(_), Number::Float(f)) => cmp_decimal_float(a, f),
+        #[cfg(feature = "decimal")]
+        (Number::Float(f), Number::Decimal(_)) => cmp_decimal_float(b, f).reverse(),
+        #[cfg(feature = "decimal")]
+        (a, b) => a.compare(b).unwrap(),
+    }
+}
+
+#[cfg(feature = "decimal")]
+fn cmp_decimal_float(d: Number, f: f64) -> Ordering {
+    if f.is_nan() {
+        Ordering::Less
+    } else {
+        d.compare(Number::Float(f)).unwrap()
+    }
+}
+
+/// Compare without converting the integer to a float, which might round it
+fn cmp_integer_float(i: i128, f: f64) -> Ordering {
+    if f.is_nan() {
+        return Ordering::Less;
+    }
+
+    // Integers are at most 64 bits wide, so this is out of their range
+    const LIMIT: f64 = 1e20;
+
+    if f >= LIMIT {
+        return Ordering::Less;
+    } else if f <= -LIMIT {
+        return Ordering::Greater;
+    }
+
+    let floor = f.floor();
+
+    match i.cmp(&(floor as i128)) {
+        Ordering::Equal if f > floor => Ordering::Less,
+        order => order,
+    }
+}
@@ -0,0 +1,128 @@
+use super::{Value, ValueError};
+
+/// Access to nested values by path, e.g., `"users.0.name"`
+///
+/// A path consists of segments separated by dots. Segments select entries of
+/// maps, or, if they are numbers, elements of lists, tuples, and arrays.
+impl Value {
+    /// Get the value at the given path
+    pub fn get_path(&self, path: &str) -> Result<&Value, ValueError> {
+        let mut current = self;
+
+        for (index, segment) in split_path(path)?.into_iter().enumerate() {
+            current = current
+                .path_child(segment)
+                .ok_or_else(|| not_found(segment, index))?;
+        }
+
+        Ok(current)
+    }
+
+    /// Replace the value at the given path, or add a new entry if the path ends in a map
+    ///
+    /// Values on the way to the last segment are not created if they are missing.
+    pub fn set_path(&mut self, path: &str, value: Value) -> Result<(), ValueError> {
+        let (parent, segment, index) = self.path_parent_mut(path)?;
+
+        match parent {
+            Value::Map(content) => {
+                content.insert(segment.to_string(), value);
+                Ok(())
+            }
+            Value::List(content) | Value::Array(content) => {
+                let Some(position) = parse_index(segment).filter(|pos| *pos < content.len()) else {
+                    return Err(not_found(segment, index));
+                };
+
+                // Checks the type of array elements
+                parent.set_child(position.into(), value)
+            }
+            Value::Tuple(_) => Err(ValueError::OperationNotSupported),
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(not_found(segment, index)),
+        }
+    }
+
+    /// Remove the map entry or list element at the given path and return it
+    pub fn remove_path(&mut self, path: &str) -> Result<Value, ValueError> {
+        let (parent, segment, index) = self.path_parent_mut(path)?;
+
+        match parent {
+            Value::Map(content) => content
+                .remove(segment)
+                .ok_or_else(|| not_found(segment, index)),
+            Value::List(content) => match parse_index(segment) {
+                Some(position) if position < content.len() => Ok(content.remove(position)),
+                _ => Err(not_found(segment, index)),
+            },
+            // Arrays have a fixed length and tuples are immutable
+            Value::Array(_) | Value::Tuple(_) => Err(ValueError::OperationNotSupported),
+            Value::Frozen(_) => Err(ValueError::Frozen),
+            _ => Err(not_found(segment, index)),
+        }
+    }
+
+    fn path_child(&self, segment: &str) -> Option<&Value> {
+        match self.unfrozen() {
+            Value::Map(content) => content.get(segment),
+            Value::List(content) | Value::Tuple(content) | Value::Array(content) => {
+                content.get(parse_index(segment)?)
+            }
+            _ => None,
+        }
+    }
+
+    /// Walk to the value holding the last segment of the path
+    ///
+    /// Returns that value, the last segment, and its position in the path.
+    fn path_parent_mut<'a>(
+        &mut self,
+        path: &'a str,
+    ) -> Result<(&mut Value, &'a str, usize), ValueError> {
+        let mut segments = split_path(path)?;
+        // Paths have at least one segment
+        let last = segments.pop().unwrap();
+
+        let mut current = self;
+
+        for (index, segment) in segments.iter().enumerate() {
+            current = match current {
+                Value::Map(content) => content.get_mut(*segment),
+                Value::List(content) | Value::Array(content) => {
+                    parse_index(segment).and_then(|pos| content.get_mut(pos))
+                }
+                Value::Tuple(_) => return Err(ValueError::OperationNotSupported),
+                Value::Frozen(_) => return Err(ValueError::Frozen),
+                _ => None,
+            }
+            .ok_or_else(|| not_found(segment, index))?;
+        }
+
+        Ok((current, last, segments.len()))
+    }
+}
+
+fn split_path(path: &str) -> Result<Vec<&str>, ValueError> {
+    let segments: Vec<&str> = path.split('.').collect();
+
+    if segments.iter().any(|segment| segment.is_empty()) {
+        Err(ValueError::InvalidKey)
+    } else {
+        Ok(segments)
+    }
+}
+
+fn parse_index(segment: &str) -> Option<usize> {
+    if segment.bytes().all(|c| c.is_ascii_digit()) {
+        segment.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn not_found(segment: &str, index: usize) -> ValueError {
+    ValueError::PathNotFound {
+        segment: segment.to_string(),
+        index,
+    }
+}
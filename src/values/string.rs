@@ -0,0 +1,86 @@
+use super::{Value, ValueError};
+
+/// Operations on strings (see `grammar::STR_BUILTINS`)
+impl Value {
+    fn as_str(&self) -> Result<&str, ValueError> {
+        match self.unfrozen() {
+            Value::Str(content) => Ok(content),
+            other => Err(ValueError::type_mismatch("string", other)),
+        }
+    }
+
+    /// Split the string at every occurrence of the separator
+    ///
+    /// Without a separator, the string is split at runs of whitespace
+    /// and empty parts are dropped.
+    pub fn str_split(&self, separator: Option<&str>) -> Result<Value, ValueError> {
+        let content = self.as_str()?;
+
+        let parts: Vec<Value> = match separator {
+            Some("") => return Err(ValueError::OperationNotSupported),
+            Some(separator) => content.split(separator).map(Value::from).collect(),
+            None => content.split_whitespace().map(Value::from).collect(),
+        };
+
+        Ok(Value::List(parts))
+    }
+
+    /// Concatenate a list of strings, using this string as the separator
+    pub fn str_join(&self, parts: &Value) -> Result<Value, ValueError> {
+        let separator = self.as_str()?;
+
+        let parts = match parts.unfrozen() {
+            Value::List(parts) => parts,
+            other => return Err(ValueError::type_mismatch("list", other)),
+        };
+
+        let parts = parts
+            .iter()
+            .map(Value::as_str)
+            .collect::<Result<Vec<&str>, ValueError>>()?;
+
+        Ok(parts.join(separator).into())
+    }
+
+    pub fn str_upper(&self) -> Result<Value, ValueError> {
+        Ok(self.as_str()?.to_uppercase().into())
+    }
+
+    pub fn str_lower(&self) -> Result<Value, ValueError> {
+        Ok(self.as_str()?.to_lowercase().into())
+    }
+
+    /// Remove leading and trailing whitespace
+    pub fn str_strip(&self) -> Result<Value, ValueError> {
+        Ok(self.as_str()?.trim().into())
+    }
+
+    pub fn str_starts_with(&self, prefix: &str) -> Result<bool, ValueError> {
+        Ok(self.as_str()?.starts_with(prefix))
+    }
+
+    pub fn str_ends_with(&self, suffix: &str) -> Result<bool, ValueError> {
+        Ok(self.as_str()?.ends_with(suffix))
+    }
+
+    /// Replace all occurrences of `from` with `to`
+    pub fn str_replace(&self, from: &str, to: &str) -> Result<Value, ValueError> {
+        if from.is_empty() {
+            return Err(ValueError::OperationNotSupported);
+        }
+
+        Ok(self.as_str()?.replace(from, to).into())
+    }
+
+    /// The position (in characters) of the first occurrence of `needle`, or -1 if there is none
+    pub fn str_find(&self, needle: &str) -> Result<Value, ValueError> {
+        let content = self.as_str()?;
+
+        let position = match content.find(needle) {
+            Some(pos) => content[..pos].chars().count() as i64,
+            None => -1,
+        };
+
+        Ok(Value::I64(position))
+    }
+}
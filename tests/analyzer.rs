@@ -0,0 +1,92 @@
+use cowlang::{analyze, compile_named, compile_with_warnings};
+
+#[test]
+fn discarded_expression() {
+    let (_, warnings) = compile_with_warnings(
+        "\
+        let x = 5\n\
+        x + 1\n\
+        return x\n\
+    ",
+    )
+    .unwrap();
+
+    assert_eq!(warnings.len(), 1);
+}
+
+#[test]
+fn no_warnings() {
+    let (_, warnings) = compile_with_warnings(
+        "\
+        let l = [1, 2]\n\
+        l.append(3)\n\
+        return l\n\
+    ",
+    )
+    .unwrap();
+
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn named_warnings() {
+    let program = compile_named("main.cow", "let x = 5\nx + 1\n").unwrap();
+    let warnings = analyze(&program);
+
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].source_name.as_deref(), Some("main.cow"));
+}
+
+#[test]
+fn format_argument_count() {
+    let (_, warnings) = compile_with_warnings("return format('%s scored %d', 'bob')\n").unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].message,
+        "Format string expects 2 arguments, but got 1"
+    );
+
+    // Programs can define their own format()
+    let (_, warnings) = compile_with_warnings(
+        "\
+        def format(s, x):\n\
+        \x20   return s\n\
+        return format('%s scored %d', 'bob')\n\
+    ",
+    )
+    .unwrap();
+
+    assert!(warnings.is_empty());
+}
+
+#[test]
+fn assignment_count() {
+    let (_, warnings) = compile_with_warnings("let x = 1\nlet y = 2\nx, y = 1, 2, 3\n").unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].message, "Cannot assign 3 values to 2 variables");
+}
+
+#[test]
+fn enormous_loop() {
+    let (_, warnings) = compile_with_warnings(
+        "\
+        let x = 0\n\
+        for i in range(0, 10000000000):\
+      \n    x += i\n\
+        \n\
+        for i in range(0, 10000000000, 1000):\
+      \n    x += i\n\
+        \n\
+        return x\n\
+    ",
+    )
+    .unwrap();
+
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        warnings[0].message,
+        "Loop runs 10000000000 times and will probably not finish"
+    );
+}
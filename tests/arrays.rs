@@ -0,0 +1,84 @@
+use cowlang::{
+    compile, compile_string, typecheck, Error, Interpreter, RuntimeErrorKind, Value, ValueError,
+};
+
+fn run(source: &str) -> Result<Value, Error> {
+    let program = compile(source)?;
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.try_run(&program)?;
+
+    Ok(result)
+}
+
+#[test]
+fn fill_header() {
+    let program = compile_string(
+        "\
+        let header = [u8; 4]\n\
+        header[0] = 255u8\n\
+        for i in range(1, 4):\
+  \n    header[i] = i as u8\n\
+        \n\
+        return header\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, Value::from([255u8, 1, 2, 3]));
+
+    let bytes: [u8; 4] = result.try_into().unwrap();
+    assert_eq!(bytes, [255, 1, 2, 3]);
+}
+
+#[test]
+fn repeated_value() {
+    let result = run("let flags = [false; 3]\nreturn [flags.len(), flags.count(false)]\n").unwrap();
+
+    assert_eq!(result, Value::from(vec![3u64, 3]));
+}
+
+#[test]
+fn annotated_array() {
+    let program = compile("let array: [u8; 5] = [u8; 5]\nreturn array\n").unwrap();
+
+    assert!(typecheck(&program).is_ok());
+
+    let mut interpreter = Interpreter::default();
+    let result: Vec<u8> = interpreter.run(&program).try_into().unwrap();
+    assert_eq!(result, vec![0; 5]);
+}
+
+#[test]
+fn element_type_is_enforced() {
+    let err = run("let buffer = [0u8; 2]\nbuffer[0] = 300\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => assert_eq!(
+            err.kind,
+            RuntimeErrorKind::Value(ValueError::TypeMismatch {
+                expected: "u8".to_string(),
+                actual: "i64".to_string()
+            })
+        ),
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn length_is_fixed() {
+    let err = run("let buffer = [0u8; 2]\nbuffer.append(1u8)\n").unwrap_err();
+    assert!(matches!(err, Error::Runtime(_)));
+
+    let err = run("let buffer = [0u8; 2]\nbuffer[2] = 1u8\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => assert_eq!(
+            err.kind,
+            RuntimeErrorKind::Value(ValueError::IndexOutOfBounds)
+        ),
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
@@ -0,0 +1,181 @@
+#![cfg(feature = "async")]
+
+use cowlang::interpreter::{CancelToken, Handle};
+use cowlang::tokio::{run_blocking, AsyncCallable, AsyncFunction, AsyncResult};
+use cowlang::{compile_string, Interpreter, Module, RuntimeErrorKind, Value};
+
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct TimerModule {
+    fired: Arc<AtomicBool>,
+}
+
+/// Waits for the given number of milliseconds and returns it
+struct Wait {}
+
+/// Returns whether the timer task of the test finished
+struct HasFired {
+    fired: Arc<AtomicBool>,
+}
+
+impl AsyncCallable for Wait {
+    fn call(&self, args: Vec<Value>) -> AsyncResult {
+        Box::pin(async move {
+            let millis: i64 = args[0].clone().try_into()?;
+            tokio::time::sleep(Duration::from_millis(millis as u64)).await;
+            Ok(millis.into())
+        })
+    }
+}
+
+impl AsyncCallable for HasFired {
+    fn call(&self, _args: Vec<Value>) -> AsyncResult {
+        let fired = self.fired.load(Ordering::SeqCst);
+        Box::pin(async move { Ok(fired.into()) })
+    }
+}
+
+impl Module for TimerModule {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match name {
+            "wait" => Handle::Callable(Rc::new(AsyncFunction(Wait {}))),
+            "has_fired" => Handle::Callable(Rc::new(AsyncFunction(HasFired {
+                fired: self.fired.clone(),
+            }))),
+            _ => panic!("Unexpected function call: {}", name),
+        }
+    }
+}
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_time()
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn await_module_function() {
+    let program = compile_string("return timer.wait(20) + 1\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("timer".to_string(), Rc::new(TimerModule::default()));
+
+    let result = runtime()
+        .block_on(async { run_blocking(&program, &mut interpreter) })
+        .unwrap();
+
+    let expected: i64 = 21;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn other_tasks_keep_running() {
+    let program = compile_string("timer.wait(100)\nreturn timer.has_fired()\n");
+
+    let module = TimerModule::default();
+    let fired = module.fired.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("timer".to_string(), Rc::new(module));
+
+    let result = runtime().block_on(async {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            fired.store(true, Ordering::SeqCst);
+        });
+
+        run_blocking(&program, &mut interpreter)
+    });
+
+    assert_eq!(result.unwrap(), true.into());
+}
+
+#[test]
+fn sleep_does_not_block_other_tasks() {
+    let program = compile_string("sleep(100)\nreturn timer.has_fired()\n");
+
+    let module = TimerModule::default();
+    let fired = module.fired.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("timer".to_string(), Rc::new(module));
+    // Only sleeps outside of the runtime are limited
+    interpreter.set_max_sleep(Duration::ZERO);
+
+    let result = runtime().block_on(async {
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            fired.store(true, Ordering::SeqCst);
+        });
+
+        run_blocking(&program, &mut interpreter)
+    });
+
+    assert_eq!(result.unwrap(), true.into());
+}
+
+#[test]
+fn cancel_pending_call() {
+    let program = compile_string("timer.wait(10000)\nreturn 1\n");
+
+    let token = CancelToken::default();
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("timer".to_string(), Rc::new(TimerModule::default()));
+    interpreter.set_cancel_token(token.clone());
+
+    let start = Instant::now();
+    let err = runtime()
+        .block_on(async {
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                token.cancel();
+            });
+
+            run_blocking(&program, &mut interpreter)
+        })
+        .unwrap_err();
+
+    assert!(matches!(err.kind, RuntimeErrorKind::Cancelled));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn async_function_outside_of_runtime() {
+    let program = compile_string("return timer.wait(1)\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("timer".to_string(), Rc::new(TimerModule::default()));
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert!(matches!(err.kind, RuntimeErrorKind::NotAsync));
+}
+
+#[test]
+fn current_thread_runtime() {
+    let program = compile_string("return 1\n");
+    let mut interpreter = Interpreter::default();
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let err = runtime
+        .block_on(async { run_blocking(&program, &mut interpreter) })
+        .unwrap_err();
+
+    assert!(matches!(err.kind, RuntimeErrorKind::NotAsync));
+}
+
+#[test]
+fn outside_of_runtime() {
+    let program = compile_string("return 1\n");
+    let mut interpreter = Interpreter::default();
+
+    let err = run_blocking(&program, &mut interpreter).unwrap_err();
+    assert!(matches!(err.kind, RuntimeErrorKind::NotAsync));
+}
@@ -1,4 +1,15 @@
-use cowlang::{compile_string, Interpreter, Value};
+use cowlang::ast::Expr;
+use cowlang::interpreter::StdoutSink;
+use cowlang::{
+    compile, compile_string, compile_with_options, CompilerOptions, ConversionMode,
+    IntegerLiteralType, Interpreter, Rounding, RuntimeErrorKind, Value, ValueError,
+};
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[test]
 fn compile_comment() {
@@ -27,7 +38,7 @@ fn cast_integer() {
     let mut interpreter = Interpreter::default();
     let result = interpreter.run(&program);
 
-    assert_eq!(result, (15 as i64).into());
+    assert_eq!(result, 15i64.into());
 }
 
 #[test]
@@ -41,7 +52,7 @@ fn max() {
     let mut interpreter = Interpreter::default();
     let result = interpreter.run(&program);
 
-    assert_eq!(result, (101 as i64).into());
+    assert_eq!(result, 101i64.into());
 }
 
 #[test]
@@ -55,7 +66,7 @@ fn min() {
     let mut interpreter = Interpreter::default();
     let result = interpreter.run(&program);
 
-    assert_eq!(result, (5 as i64).into());
+    assert_eq!(result, 5i64.into());
 }
 
 #[test]
@@ -134,17 +145,11 @@ fn scoped_variables() {
     assert_eq!(result, expected.into());
 }
 
-/* FIXME don't panic if compile fails
 #[test]
-fn error() {
-    // make sure everything works well without a new line at the end
-    let program = compile_string("=");
-
-    let mut interpreter = Interpreter::default();
-    let result = interpreter.run(&program);
-
-    assert_eq!(result, Value::None);
-}*/
+fn compile_error() {
+    let result = compile("=");
+    assert!(result.is_err());
+}
 
 #[test]
 fn compile_empty() {
@@ -173,6 +178,26 @@ fn to_string() {
     assert_eq!(result, expected.into());
 }
 
+#[test]
+fn list_and_map_to_string() {
+    let program = compile_string(
+        "\
+        let l = [1, 'two', (3, true)]\n\
+        let m = {'b': l, 'a': 'x'}\n\
+        return [str(l), str(m)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: Vec<Value> = vec![
+        r#"[1, "two", (3, true)]"#.into(),
+        r#"{"a": "x", "b": [1, "two", (3, true)]}"#.into(),
+    ];
+    assert_eq!(result, Value::List(expected));
+}
+
 #[test]
 fn braces() {
     let program = compile_string(
@@ -347,3 +372,692 @@ fn else_if_statement() {
     let expected: i64 = 1;
     assert_eq!(result, expected.into());
 }
+
+#[test]
+fn floor_division() {
+    let program = compile_string(
+        "\
+        let x = 17\n\
+        return x // 5\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 3;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn division_and_remainder() {
+    let program = compile_string(
+        "\
+        let x = 17\n\
+        return [x / 4, x % 5, 17u8 % 5u8, x / 0]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::Value(ValueError::DivisionByZero)
+    );
+
+    let program = compile_string("return [17 / 4, 17 % 5, 17u8 % 5u8]\n");
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![4.25f64.into(), 2i64.into(), 2u8.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn divmod() {
+    let program = compile_string(
+        "\
+        return divmod(17, 5)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: Vec<i64> = vec![3, 2];
+    assert_eq!(result, expected.into());
+
+    // divmod is not reserved
+    let program = compile_string(
+        "\
+        let divmod = 5\n\
+        return divmod\n\
+    ",
+    );
+
+    let expected: i64 = 5;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn operator_precedence() {
+    let program = compile_string(
+        "\
+        return 1 + 2 * 3 // 2 > 3\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, true.into());
+}
+
+#[test]
+fn cast_binds_tighter_than_arithmetic() {
+    // `a + (b as u8)`, not `(a + b) as u8`
+    let program = compile_string("return a + b as u8\n");
+
+    let Expr::Return(value) = &program.stmts[0].1 else {
+        panic!("Expected a return statement");
+    };
+    let Expr::Add { lhs, rhs } = &value.1 else {
+        panic!("Expected an addition");
+    };
+
+    assert!(matches!(lhs.1, Expr::Var(_)));
+    assert!(matches!(rhs.1, Expr::Cast { .. }));
+}
+
+#[test]
+fn not_binds_at_unary_level() {
+    // `(not a) == b`, and `not (x.y)`
+    let program = compile_string("let c = not a == b\nreturn not x.y\n");
+
+    let Expr::AssignNew(_, value) = &program.stmts[0].1 else {
+        panic!("Expected an assignment");
+    };
+    let Expr::Compare { lhs, .. } = &value.1 else {
+        panic!("Expected a comparison");
+    };
+    assert!(matches!(lhs.1, Expr::Not(_)));
+
+    let Expr::Return(value) = &program.stmts[1].1 else {
+        panic!("Expected a return statement");
+    };
+    let Expr::Not(inner) = &value.1 else {
+        panic!("Expected a negation");
+    };
+    assert!(matches!(inner.1, Expr::GetMember(..)));
+}
+
+#[test]
+fn deeply_nested_sum() {
+    let depth = 10_000;
+    let source = format!("return {}1\n", "1 + ".repeat(depth));
+
+    let program = compile_string(&source);
+    let mut interpreter = Interpreter::default();
+
+    let expected: i64 = depth as i64 + 1;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn deeply_nested_brackets() {
+    let depth = 10_000;
+    let source = format!("return {}5{}\n", "(".repeat(depth), ")".repeat(depth));
+
+    let program = compile_string(&source);
+    let mut interpreter = Interpreter::default();
+
+    let expected: i64 = 5;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn restore_seed() {
+    let seed = Interpreter::default().seed();
+
+    let program = compile_string("let x = 2\nreturn x + 3\n");
+    let mut interpreter = Interpreter::with_seed(seed);
+
+    assert_eq!(interpreter.seed(), seed);
+
+    let expected: i64 = 5;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn format_string() {
+    let program = compile_string(
+        "\
+        let name = 'alice'\n\
+        return format('%s scored %d (100%%)', name, 42)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: Value = "alice scored 42 (100%)".to_string().into();
+    assert_eq!(result, expected);
+
+    // format is not reserved
+    let program = compile_string(
+        "\
+        let format = '%s!'\n\
+        return format.replace('%s', 'hi')\n\
+    ",
+    );
+
+    let expected: Value = "hi!".into();
+    assert_eq!(interpreter.run(&program), expected);
+}
+
+#[test]
+fn share_program_between_threads() {
+    let program = Arc::new(compile_string("let x = 2\nreturn x * 21\n"));
+
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            let program = program.clone();
+
+            thread::spawn(move || {
+                let mut interpreter = Interpreter::default();
+                interpreter.run(&program)
+            })
+        })
+        .collect();
+
+    for thread in threads {
+        let expected: i64 = 42;
+        assert_eq!(thread.join().unwrap(), expected.into());
+    }
+}
+
+#[test]
+fn chained_assignment() {
+    let program = compile_string(
+        "\
+        let a = 1\n\
+        let b = 2\n\
+        let c = a = b = 5\n\
+        return a + b + c\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 15;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn swap_assignment() {
+    let program = compile_string(
+        "\
+        let x = 1\n\
+        let y = 2\n\
+        x, y = y, x\n\
+        return [x, y]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: Value = vec![2i64, 1].into();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn unpack_assignment() {
+    let program = compile_string(
+        "\
+        let q = 0\n\
+        let r = 0\n\
+        q, r = divmod(7, 2)\n\
+        return q * 10 + r\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 31;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn logical_operators() {
+    let program = compile_string(
+        "\
+        let x = 5\n\
+        return [x > 1 and x < 10, x > 6 or x == 5, not (x == 5) || false && true]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value(String::from("f"), (-1.5f64).into());
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![true.into(), true.into(), false.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn short_circuit() {
+    let program = compile_string(
+        "\
+        let l = [1]\n\
+        if false and l[5] == 1:\
+      \n    return 1\n\
+        if true or l[5] == 1:\
+      \n    return 2\n\
+        \n\
+        return 3",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 2;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn encode_decode() {
+    let program = compile_string(
+        "\
+        let b = \"héllo\".encode()\n\
+        return b.decode()\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, "héllo".into());
+}
+
+#[test]
+fn saturating_cast() {
+    let program = compile_string("return 300 as u8\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_cast_mode(ConversionMode::Saturating);
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, Value::U8(255));
+}
+
+#[test]
+fn round_floats() {
+    let program = compile_string("return [x.trunc(), x.floor(), x.ceil(), x.round(), x as i64]\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("x".to_string(), 2.5f64.into());
+    interpreter.set_cast_rounding(Rounding::Floor);
+    let result = interpreter.run(&program);
+
+    let expected: Vec<i64> = vec![2, 2, 3, 3, 2];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn compare_integer_widths() {
+    let program = compile_string("return [5u8 > 3u8, 5u8 == 5, 2u < 3u8, 1 == 1u]\n");
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = vec![true, true, true, true];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn membership() {
+    let program = compile_string(
+        "\
+        let l = [1, 2, 3]\n\
+        let m = {'a': 1}\n\
+        return [2 in l, 5 in l, 'a' in m, 'b' in m, 'ell' in 'hello', not ('x' in 'hello')]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = vec![true, false, true, false, true, true];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn compound_assignment() {
+    let program = compile_string(
+        "\
+        let x = 20\n\
+        x -= 3\n\
+        x *= 2\n\
+        x //= 5\n\
+        let l = [x, 17, 3]\n\
+        l[1] %= 5\n\
+        l[2] /= 2\n\
+        return l\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![6i64.into(), 2i64.into(), 1.5f64.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn global_functions() {
+    let program = compile_string(
+        "\
+        let l = [1, 2, 3]\n\
+        l[1] -= 4\n\
+        return [len(l), len('hello'), type(l), type(f), abs(l[1]), abs(f), sum(l)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value(String::from("f"), (-1.5f64).into());
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        3u64.into(),
+        5u64.into(),
+        "list".into(),
+        "f64".into(),
+        2i64.into(),
+        1.5f64.into(),
+        2i64.into(),
+    ]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn shadow_global_function() {
+    let program = compile_string(
+        "\
+        let len = 5\n\
+        return len\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 5;
+    assert_eq!(result, expected.into());
+}
+
+#[derive(Default)]
+struct Output {
+    lines: RefCell<Vec<String>>,
+}
+
+impl StdoutSink for Output {
+    fn print(&self, line: &str) {
+        self.lines.borrow_mut().push(line.to_string());
+    }
+}
+
+#[test]
+fn capture_print() {
+    let program = compile_string(
+        "\
+        print('hello', 42)\n\
+        print()\n\
+    ",
+    );
+
+    let output = Rc::new(Output::default());
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_stdout(output.clone());
+    interpreter.run(&program);
+
+    assert_eq!(
+        *output.lines.borrow(),
+        vec!["hello 42".to_string(), String::new()]
+    );
+}
+
+#[test]
+fn unsigned_integer_literals() {
+    let options = CompilerOptions {
+        integer_literals: IntegerLiteralType::U64,
+        ..Default::default()
+    };
+
+    let program = compile_with_options("let x = 5\nreturn x + num\n", &options).unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("num".to_string(), 10u64.into());
+
+    assert_eq!(interpreter.run(&program), Value::U64(15));
+}
+
+#[test]
+fn function_values() {
+    let program = compile_string(
+        "\
+    def twice(f, x):\
+  \n    return f(f(x))\n\
+    \n\
+    def increment(x):\
+  \n    return x + 1\n\
+    \n\
+    return twice(increment, 5)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, Value::I64(7));
+}
+
+#[test]
+fn higher_order_functions() {
+    let program = compile_string(
+        "\
+    def double(x):\
+  \n    return x * 2\n\
+    \n\
+    def is_large(x):\
+  \n    return x > 2\n\
+    \n\
+    def add(total, x):\
+  \n    return total + x\n\
+    \n\
+    let l = map(double, [1, 2, 3])\n\
+    return [l, filter(is_large, l), reduce(add, l, 0), map(bind(add, 10), l)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        vec![2i64, 4, 6].into(),
+        vec![4i64, 6].into(),
+        12i64.into(),
+        vec![12i64, 14, 16].into(),
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn enumerate_and_zip() {
+    let program = compile_string(
+        "\
+    let indexed = []\n\
+    for pair in enumerate(['a', 'b']):\
+  \n    indexed.append(pair)\n\
+    \n\
+    let zipped = []\n\
+    for pair in zip([1, 2, 3], 'xy'):\
+  \n    zipped.append(pair)\n\
+    \n\
+    return [indexed, zipped]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        Value::List(vec![
+            Value::List(vec![0i64.into(), "a".into()]),
+            Value::List(vec![1i64.into(), "b".into()]),
+        ]),
+        Value::List(vec![
+            Value::List(vec![1i64.into(), "x".into()]),
+            Value::List(vec![2i64.into(), "y".into()]),
+        ]),
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn stored_iterator() {
+    let program = compile_string(
+        "\
+    let pairs = enumerate([1, 2])\n\
+    for pair in pairs:\
+  \n    print(pair)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::StoredIterator("pairs".to_string())
+    );
+
+    let program = compile_string("let pairs = zip([1], [2])\nreturn pairs\n");
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::StoredIterator("pairs".to_string())
+    );
+}
+
+#[test]
+fn stored_and_descending_ranges() {
+    let program = compile_string(
+        "\
+    let r = range(0, 3)\n\
+    let total = 0\n\
+    for i in r:\
+  \n    total += i\n\
+    \n\
+    for i in r:\
+  \n    total += i\n\
+    \n\
+    let down = []\n\
+    for i in range(3, 0, -1):\
+  \n    down.append(i)\n\
+    \n\
+    return [total, down, range(5, 3), r]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        6i64.into(),
+        vec![3i64, 2, 1].into(),
+        Value::List(vec![]),
+        vec![0i64, 1, 2].into(),
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn unary_minus() {
+    let program = compile_string(
+        "\
+    let x = 5\n\
+    let y = -x\n\
+    return [y, -(x / 2), --3, -(x + 1), -(0 as u8)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        (-5i64).into(),
+        (-2.5f64).into(),
+        3i64.into(),
+        (-6i64).into(),
+        0u8.into(),
+    ]);
+
+    assert_eq!(result, expected);
+
+    let program = compile_string("return -(1 as u64)");
+    let err = Interpreter::default().try_run(&program).unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::Value(ValueError::IntegerOverflow)
+    );
+}
+
+#[test]
+fn tuples() {
+    let program = compile_string(
+        "\
+    def bounds(l):\
+  \n    return (l.min(), l.max())\n\
+    \n\
+    let low, high = bounds([3, 1, 4])\n\
+    let pair = (low, high)\n\
+    return [low, high, pair, pair[1], len(pair), type(pair)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        1i64.into(),
+        4i64.into(),
+        Value::Tuple(vec![1i64.into(), 4i64.into()]),
+        4i64.into(),
+        2u64.into(),
+        "tuple".into(),
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn sleep_is_capped() {
+    let program = compile_string("sleep(10000)\nreturn 1\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_max_sleep(Duration::from_millis(10));
+
+    let start = Instant::now();
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, Value::I64(1));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
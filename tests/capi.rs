@@ -0,0 +1,233 @@
+#![cfg(feature = "capi")]
+
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use cowlang::capi::*;
+
+#[test]
+fn header_declares_all_functions() {
+    let header = include_str!("../include/cowlang.h");
+    let source = include_str!("../src/capi.rs");
+
+    let functions = source
+        .lines()
+        .filter_map(|line| line.split("extern \"C\" fn ").nth(1))
+        .map(|rest| rest.split('(').next().unwrap());
+
+    let mut count = 0;
+    for name in functions {
+        assert!(
+            header.contains(&format!(" {name}(")) || header.contains(&format!("*{name}(")),
+            "{name} is missing from the header"
+        );
+        count += 1;
+    }
+
+    assert!(count > 20);
+}
+
+#[test]
+fn compile_and_run() {
+    let source =
+        CString::new("let total = 0\nfor x in xs:\n    total += x\nreturn [name, total]\n")
+            .unwrap();
+    let mut error = ptr::null_mut();
+
+    unsafe {
+        let program = cow_compile(source.as_ptr(), &mut error);
+        assert!(!program.is_null());
+
+        let xs = cow_value_list();
+        for i in 1..=3 {
+            assert!(cow_value_list_append(xs, cow_value_i64(i), &mut error));
+        }
+
+        let interpreter = cow_interpreter_new();
+        let name = CString::new("xs").unwrap();
+        assert!(cow_interpreter_set_value(
+            interpreter,
+            name.as_ptr(),
+            xs,
+            &mut error
+        ));
+
+        let value = CString::new("cow").unwrap();
+        let name = CString::new("name").unwrap();
+        let value = cow_value_string(value.as_ptr());
+        assert!(cow_interpreter_set_value(
+            interpreter,
+            name.as_ptr(),
+            value,
+            &mut error
+        ));
+
+        // The interpreter can run the same program again
+        for _ in 0..2 {
+            let result = cow_interpreter_run(interpreter, program, &mut error);
+            assert!(error.is_null());
+            assert_eq!(cow_value_type(result), CowValueType::List);
+            assert_eq!(cow_value_length(result), 2);
+
+            let first = cow_value_get_index(result, 0);
+            let s = cow_value_as_string(first);
+            assert_eq!(CStr::from_ptr(s).to_str().unwrap(), "cow");
+            cow_string_free(s);
+
+            let second = cow_value_get_index(result, 1);
+            let mut total = 0;
+            assert!(cow_value_as_i64(second, &mut total));
+            assert_eq!(total, 6);
+
+            assert!(cow_value_get_index(result, 2).is_null());
+
+            cow_value_free(first);
+            cow_value_free(second);
+            cow_value_free(result);
+        }
+
+        cow_interpreter_free(interpreter);
+        cow_program_free(program);
+    }
+}
+
+#[test]
+fn values() {
+    let key = CString::new("pi").unwrap();
+    let mut error = ptr::null_mut();
+
+    unsafe {
+        let map = cow_value_map();
+        assert!(cow_value_map_insert(
+            map,
+            key.as_ptr(),
+            cow_value_f64(3.5),
+            &mut error
+        ));
+
+        let entry = cow_value_get_key(map, key.as_ptr());
+        let mut f = 0.0;
+        assert!(cow_value_as_f64(entry, &mut f));
+        assert_eq!(f, 3.5);
+
+        let mut b = false;
+        assert!(!cow_value_as_bool(entry, &mut b));
+        assert!(cow_value_as_string(entry).is_null());
+
+        let s = cow_value_to_string(map);
+        assert_eq!(CStr::from_ptr(s).to_str().unwrap(), "{\"pi\": 3.5}");
+        cow_string_free(s);
+
+        let missing = CString::new("tau").unwrap();
+        assert!(cow_value_get_key(map, missing.as_ptr()).is_null());
+
+        // Maps are not lists
+        assert!(!cow_value_list_append(map, cow_value_none(), &mut error));
+        assert!(!error.is_null());
+        cow_error_free(error);
+
+        let flag = cow_value_bool(true);
+        assert!(cow_value_as_bool(flag, &mut b));
+        assert!(b);
+        assert_eq!(cow_value_length(flag), -1);
+
+        let big = cow_value_u64(u64::MAX);
+        let mut i = 0;
+        assert!(!cow_value_as_i64(big, &mut i));
+
+        cow_value_free(big);
+        cow_value_free(flag);
+        cow_value_free(entry);
+        cow_value_free(map);
+    }
+}
+
+#[test]
+fn errors() {
+    let mut error = ptr::null_mut();
+
+    unsafe {
+        let source = CString::new("return (1").unwrap();
+        assert!(cow_compile(source.as_ptr(), &mut error).is_null());
+        assert!(!error.is_null());
+        cow_error_free(error);
+        error = ptr::null_mut();
+
+        let source = CString::new("return missing\n").unwrap();
+        let program = cow_compile(source.as_ptr(), &mut error);
+        assert!(!program.is_null());
+
+        let interpreter = cow_interpreter_new();
+        assert!(cow_interpreter_run(interpreter, program, &mut error).is_null());
+
+        let message = CStr::from_ptr(cow_error_message(error)).to_str().unwrap();
+        assert!(message.contains("missing"), "{message}");
+
+        cow_error_free(error);
+        cow_interpreter_free(interpreter);
+        cow_program_free(program);
+
+        // Errors are optional
+        let source = CString::new("return (1").unwrap();
+        assert!(cow_compile(source.as_ptr(), ptr::null_mut()).is_null());
+    }
+}
+
+#[test]
+fn compiler_panics() {
+    for source in ["return 99999999999999999999\n", "return 300u8\n"] {
+        let source = CString::new(source).unwrap();
+        let mut error = ptr::null_mut();
+
+        unsafe {
+            assert!(cow_compile(source.as_ptr(), &mut error).is_null());
+            assert!(!error.is_null());
+
+            let message = CStr::from_ptr(cow_error_message(error)).to_str().unwrap();
+            assert!(message.starts_with("Internal error"), "{message}");
+            cow_error_free(error);
+        }
+    }
+}
+
+#[test]
+fn null_values() {
+    let name = CString::new("x").unwrap();
+    let mut error = ptr::null_mut();
+
+    unsafe {
+        let interpreter = cow_interpreter_new();
+        assert!(!cow_interpreter_set_value(
+            interpreter,
+            name.as_ptr(),
+            ptr::null_mut(),
+            &mut error
+        ));
+        assert!(!error.is_null());
+        cow_error_free(error);
+        error = ptr::null_mut();
+
+        let list = cow_value_list();
+        assert!(!cow_value_list_append(list, ptr::null_mut(), &mut error));
+        assert!(!error.is_null());
+        cow_error_free(error);
+        error = ptr::null_mut();
+
+        let map = cow_value_map();
+        assert!(!cow_value_map_insert(
+            map,
+            name.as_ptr(),
+            ptr::null_mut(),
+            &mut error
+        ));
+        assert!(!error.is_null());
+        cow_error_free(error);
+
+        assert_eq!(cow_value_length(list), 0);
+        assert_eq!(cow_value_length(map), 0);
+
+        cow_value_free(map);
+        cow_value_free(list);
+        cow_interpreter_free(interpreter);
+    }
+}
@@ -0,0 +1,54 @@
+use cowlang::{compile_string, Interpreter, NamedSource};
+
+const PROGRAM: &str = "\
+let x = 0\n\
+for i in range(0, 3):\
+\n    x += i\n\
+if x > 100:\
+\n    x = 100\n\
+return x\n\
+";
+
+#[test]
+fn line_coverage() {
+    let program = compile_string(PROGRAM);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.enable_coverage();
+    interpreter.run(&program);
+
+    let source = NamedSource::new("coverage.cow", PROGRAM);
+    let report = interpreter.coverage().unwrap().report(&program, &source);
+
+    assert_eq!(
+        report.lines,
+        vec![Some(1), Some(1), Some(3), Some(1), Some(0), Some(1)]
+    );
+    assert_eq!(report.num_coverable(), 6);
+    assert_eq!(report.num_covered(), 5);
+}
+
+#[test]
+fn accumulate_coverage() {
+    let program = compile_string(PROGRAM);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.enable_coverage();
+    interpreter.run(&program);
+    interpreter.run(&program);
+
+    let source = NamedSource::new("coverage.cow", PROGRAM);
+    let report = interpreter.coverage().unwrap().report(&program, &source);
+
+    assert_eq!(report.lines[2], Some(6));
+}
+
+#[test]
+fn coverage_disabled() {
+    let program = compile_string(PROGRAM);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.run(&program);
+
+    assert!(interpreter.coverage().is_none());
+}
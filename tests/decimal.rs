@@ -0,0 +1,61 @@
+#![cfg(feature = "decimal")]
+
+use cowlang::{compile, compile_string, Decimal, Interpreter, RuntimeErrorKind, Value, ValueError};
+
+use std::collections::HashMap;
+
+#[test]
+fn decimal_arithmetic() {
+    let program = compile_string(
+        "\
+        let total = 19.99d * 3\n\
+        total += 0.01d\n\
+        return [total, 0.1d + 0.2d == 0.3d, 1.5d > 1, str(2.50d)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: Value = vec![
+        Decimal::new(5998, 2).into(),
+        true.into(),
+        true.into(),
+        Value::from("2.50"),
+    ]
+    .into();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn decimals_and_floats_do_not_mix() {
+    let program = compile_string("return 2d * rate\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("rate".to_string(), 0.5.into());
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert!(matches!(
+        err.kind,
+        RuntimeErrorKind::Value(ValueError::TypeMismatch { .. })
+    ));
+}
+
+#[test]
+fn invalid_decimal_literal() {
+    let err = compile("return 123456789012345678901234567890d\n").unwrap_err();
+    assert!(err.message.contains("Invalid decimal literal"), "{err}");
+}
+
+#[test]
+fn specialize_decimal() {
+    let program = compile_string("return price * 2\n");
+
+    let mut vars = HashMap::new();
+    vars.insert("price".to_string(), Decimal::new(125, 2).into());
+
+    let specialized = program.specialize(vars);
+
+    let mut interpreter = Interpreter::default();
+    assert_eq!(interpreter.run(&specialized), Decimal::new(250, 2).into());
+}
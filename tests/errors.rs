@@ -0,0 +1,484 @@
+use cowlang::ast::Span;
+use cowlang::interpreter::OutputLimits;
+use cowlang::{
+    compile, compile_named, Error, Interpreter, NamedSource, RuntimeErrorKind, Value, ValueError,
+};
+
+fn run(source: &str) -> Result<Value, Error> {
+    let program = compile(source)?;
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.try_run(&program)?;
+
+    Ok(result)
+}
+
+#[test]
+fn unknown_variable() {
+    let err = run("let x = 1\nreturn y + x\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(err.kind, RuntimeErrorKind::NoSuchVariable("y".to_string()));
+            assert_eq!(err.span, Some(Span { lo: 17, hi: 18 }));
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn type_mismatch() {
+    let err = run("return 1 + \"foo\"\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::Value(ValueError::TypeMismatch {
+                    expected: "i64".to_string(),
+                    actual: "string".to_string()
+                })
+            );
+            assert_eq!(
+                err.to_string(),
+                "Type mismatch: expected i64, but got string at [7 to 16]"
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn syntax_error() {
+    let err = run("let = 5\n").unwrap_err();
+    assert!(matches!(err, Error::Compile(_)));
+}
+
+#[test]
+fn variable_already_exists() {
+    let err = run("let x = 1\nlet x = 2\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::VariableAlreadyExists("x".to_string())
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn error_message() {
+    let err = run("return x\n").unwrap_err();
+
+    assert_eq!(
+        err.to_string(),
+        "Runtime error: No such value or module 'x' at [7 to 8]"
+    );
+}
+
+#[test]
+fn source_name() {
+    let err = run("return x\n").unwrap_err().with_source_name("main.cow");
+
+    assert_eq!(err.source_name(), Some("main.cow"));
+    assert_eq!(
+        err.to_string(),
+        "Runtime error: No such value or module 'x' at [7 to 8] in 'main.cow'"
+    );
+}
+
+#[test]
+fn render_named_source() {
+    let code = "let a = 1\nreturn a + x\n";
+    let source = NamedSource::new("main.cow", code);
+    let err = run(code).unwrap_err();
+
+    assert_eq!(
+        err.render(&source),
+        "main.cow:2:12: No such value or module 'x'"
+    );
+}
+
+#[test]
+fn with_span() {
+    let span = Span { lo: 3, hi: 4 };
+    let err = Error::from(ValueError::DivisionByZero).with_span(span);
+
+    assert_eq!(err.span(), Some(span));
+    assert!(matches!(err, Error::Runtime(_)));
+}
+
+#[test]
+fn send_and_sync() {
+    fn assert_error<E: std::error::Error + Send + Sync + 'static>() {}
+    assert_error::<Error>();
+}
+
+#[test]
+fn named_compile_error() {
+    let err = compile_named("main.cow", "let = 5\n").unwrap_err();
+    assert_eq!(err.source_name.as_deref(), Some("main.cow"));
+}
+
+#[test]
+fn unknown_character() {
+    let err = compile("let x = 1 😀 2\nreturn x\n").unwrap_err();
+
+    assert_eq!(err.message, "Unexpected character `😀`");
+    assert_eq!(err.span, Some(Span { lo: 10, hi: 14 }));
+}
+
+#[test]
+fn unterminated_string() {
+    let err = compile("let x = \"abc\nreturn \"x\"\n").unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "Unterminated string literal starting at line 1"
+    );
+    assert_eq!(err.span, Some(Span { lo: 8, hi: 12 }));
+
+    let err = compile("let a = 'fine'\nlet b = 'oops\nlet c = 'also fine'\n").unwrap_err();
+    assert_eq!(
+        err.message,
+        "Unterminated string literal starting at line 2"
+    );
+}
+
+#[test]
+fn integer_literal_out_of_range() {
+    let err = compile("let x = 99999999999999999999\n").unwrap_err();
+
+    assert_eq!(
+        err.message,
+        "Integer literal `99999999999999999999` is out of range"
+    );
+    assert_eq!(err.span, Some(Span { lo: 8, hi: 28 }));
+
+    let err = compile("let x = 300u8\n").unwrap_err();
+    assert_eq!(err.message, "Integer literal `300u8` is out of range");
+
+    let err = compile("let x = 99999999999999999999u\n").unwrap_err();
+    assert_eq!(
+        err.message,
+        "Integer literal `99999999999999999999u` is out of range"
+    );
+
+    assert!(compile("return [9223372036854775807, 255u8]\n").is_ok());
+}
+
+#[test]
+fn render_non_ascii_source() {
+    let source = "let name = '日本語'\nlet x = name 😀\n";
+    let err = compile(source).unwrap_err();
+
+    let lo = source.find('😀').unwrap();
+    assert_eq!(err.span, Some(Span { lo, hi: lo + 4 }));
+
+    let rendered = err.render(source);
+    assert!(rendered.contains("|    let x = name 😀\n"));
+    assert!(rendered.contains("|                 ^--- Unexpected character `😀`"));
+}
+
+#[test]
+fn non_ascii_runtime_error() {
+    let code = "let greeting = '你好'\nreturn greeting + x\n";
+    let err = run(code).unwrap_err();
+
+    let lo = code.find('x').unwrap();
+    assert_eq!(err.span(), Some(Span { lo, hi: lo + 1 }));
+
+    let source = NamedSource::new("main.cow", code);
+    assert_eq!(
+        err.render(&source),
+        "main.cow:2:19: No such value or module 'x'"
+    );
+}
+
+#[test]
+fn named_runtime_error() {
+    let program = compile_named("main.cow", "return x\n").unwrap();
+    assert_eq!(program.name.as_deref(), Some("main.cow"));
+
+    let mut interpreter = Interpreter::default();
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(err.source_name.as_deref(), Some("main.cow"));
+}
+
+#[test]
+fn non_finite_float() {
+    let program = compile("return x * x\n").unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("x".to_string(), f64::MAX.into());
+    interpreter.forbid_non_finite_floats();
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NonFiniteFloat("inf".to_string())
+    );
+}
+
+#[test]
+fn allow_non_finite_float() {
+    let program = compile("return x * x\n").unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("x".to_string(), f64::MAX.into());
+
+    let result = interpreter.try_run(&program).unwrap();
+    assert_eq!(result, f64::INFINITY.into());
+}
+
+#[test]
+fn format_type_mismatch() {
+    let err = run("return format('%d', 'bob')\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::Value(ValueError::TypeMismatch {
+                    expected: "integer".to_string(),
+                    actual: "string".to_string()
+                })
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn append_to_frozen_list() {
+    let program = compile("config.append(4)\nreturn len(config)\n").unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("config".to_string(), Value::from(vec![1, 2, 3]).freeze());
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::Value(ValueError::Frozen));
+}
+
+#[test]
+fn assignment_mismatch() {
+    let err = run("let x = 1\nlet y = 2\nx, y = [1, 2, 3]\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::AssignmentMismatch {
+                    targets: 2,
+                    values: 3
+                }
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn decode_invalid_utf8() {
+    let program = compile("return data.decode()\n").unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("data".to_string(), Value::from_bytes(vec![0xc3, 0x28]));
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::Value(ValueError::InvalidUtf8));
+}
+
+#[test]
+fn cast_overflow() {
+    let err = run("return 256 as u8\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::Value(ValueError::IntegerOverflow)
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn integer_overflow() {
+    let err = run("\
+        let counter = 9223372036854775807\n\
+        counter += 1\n\
+        return counter\n\
+    ")
+    .unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::Value(ValueError::IntegerOverflow)
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn cast_fractional_float() {
+    let program = compile("return x as i64\n").unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("x".to_string(), 2.5f64.into());
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::Value(ValueError::NotAnInteger));
+}
+
+#[test]
+fn result_too_deep() {
+    let program = compile("return [[[1]]]\n").unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_output_limits(OutputLimits {
+        max_depth: Some(2),
+        max_size: None,
+    });
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::ValueTooDeep {
+            depth: 3,
+            max_depth: 2
+        }
+    );
+}
+
+#[test]
+fn catch_cast_error() {
+    let result = run("\
+        try:\
+      \n    return 256 as u8\n\
+        except as err:\
+      \n    return err[\"kind\"]\n\
+    ")
+    .unwrap();
+
+    assert_eq!(result, Value::from("IntegerOverflow"));
+}
+
+#[test]
+fn catch_without_name() {
+    let result = run("\
+        let data = {\"a\": 1}\n\
+        let found = true\n\
+        try:\
+      \n    let value = data[\"b\"]\n\
+        except:\
+      \n    found = false\n\
+        \n\
+        return found\n\
+    ")
+    .unwrap();
+
+    assert_eq!(result, Value::Bool(false));
+}
+
+#[test]
+fn raise_error() {
+    let err = run("raise \"something is wrong\"\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::Raised("something is wrong".to_string())
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn catch_raised_error() {
+    let result = run("\
+        try:\
+      \n    raise \"custom\"\n\
+        except as err:\
+      \n    return err[\"message\"]\n\
+    ")
+    .unwrap();
+
+    assert_eq!(result, Value::from("custom"));
+}
+
+#[test]
+fn zero_range_step() {
+    let err = run("let x = 0\nfor i in range(0, 3, x):\n    x += i\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::InvalidArgument("invalid step size: 0".to_string())
+            );
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+}
+
+#[test]
+fn endless_recursion() {
+    let err = run("def f(x):\n    return f(x)\n\nreturn f(1)\n").unwrap_err();
+
+    match err {
+        Error::Runtime(err) => {
+            assert_eq!(err.kind, RuntimeErrorKind::CallTooDeep { max_depth: 1000 });
+        }
+        _ => panic!("Unexpected error: {}", err),
+    }
+
+    let result = run("\
+        def f(x):\
+      \n    return f(x)\n\
+        \n\
+        try:\
+      \n    f(1)\n\
+        except as err:\
+      \n    return err[\"kind\"]\n\
+    ")
+    .unwrap();
+
+    assert_eq!(result, Value::from("CallTooDeep"));
+}
+
+#[test]
+fn max_call_depth() {
+    let program = compile(
+        "\
+        def count(n):\
+      \n    if n == 0:\
+      \n        return 0\n\
+      \n    let rest = n\
+      \n    rest -= 1\
+      \n    return count(rest) + 1\n\
+        \n\
+        return [count(4), count(5)]\n\
+    ",
+    )
+    .unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_max_call_depth(5);
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::CallTooDeep { max_depth: 5 });
+
+    interpreter.set_max_call_depth(6);
+    let result = interpreter.try_run(&program).unwrap();
+    assert_eq!(result, vec![4i64, 5].into());
+}
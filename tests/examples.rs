@@ -0,0 +1,31 @@
+use cowlang::ast::Program;
+use cowlang::{compile, example, examples, Interpreter};
+
+#[test]
+fn run_examples() {
+    for example in examples() {
+        let program = compile(example.source).unwrap();
+        let result = Interpreter::default().try_run(&program);
+
+        assert_eq!(result, Ok(example.expected), "{}", example.name);
+    }
+}
+
+#[test]
+fn serialized_examples() {
+    for example in examples() {
+        let program = compile(example.source).unwrap();
+
+        let stored = bincode::serialize(&program).unwrap();
+        let loaded: Program = bincode::deserialize(&stored).unwrap();
+
+        let result = Interpreter::default().try_run(&loaded);
+        assert_eq!(result, Ok(example.expected), "{}", example.name);
+    }
+}
+
+#[test]
+fn find_example() {
+    assert_eq!(example("loops").unwrap().name, "loops");
+    assert!(example("does not exist").is_none());
+}
@@ -0,0 +1,66 @@
+use cowlang::grammar::{describe, BuiltinKind};
+use cowlang::{compile, compile_string, Interpreter};
+
+#[test]
+fn reserved_words_are_not_identifiers() {
+    let grammar = describe();
+
+    for word in grammar.reserved_words() {
+        let source = format!("let {} = 1\n", word);
+        assert!(compile(&source).is_err(), "'{}' is not reserved", word);
+    }
+}
+
+#[test]
+fn list_builtins() {
+    let grammar = describe();
+
+    let signatures: Vec<&str> = grammar
+        .builtins
+        .iter()
+        .filter(|b| b.kind == BuiltinKind::ListMethod)
+        .map(|b| b.signature)
+        .collect();
+
+    assert_eq!(
+        signatures,
+        vec![
+            "len()",
+            "append(value)",
+            "extend(other)",
+            "clear()",
+            "get(index, default)",
+            "count(value)",
+            "min()",
+            "max()",
+            "sum()",
+            "pop(index)",
+            "insert(index, value)",
+            "remove(value)",
+            "index(value)",
+            "sort()",
+            "reverse()"
+        ]
+    );
+
+    // Methods are not reserved
+    assert!(!grammar.reserved_words().contains(&"append"));
+    assert!(compile("let append = 1\n").is_ok());
+}
+
+#[test]
+fn escaped_keyword() {
+    let program = compile_string(
+        "\
+        let \\max = 5\n\
+        let \\in = [1, 2]\n\
+        return max(\\max, \\in[1])\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 5;
+    assert_eq!(result, expected.into());
+}
@@ -0,0 +1,73 @@
+use cowlang::interpreter::IdModule;
+use cowlang::{compile_string, Interpreter, RuntimeErrorKind, Value};
+
+use std::rc::Rc;
+
+fn run(source: &str, ids: IdModule) -> Value {
+    let program = compile_string(source);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("id".to_string(), Rc::new(ids));
+    interpreter.run(&program)
+}
+
+#[test]
+fn generate_ids() {
+    let result = run(
+        "\
+        let uuid = id.uuid4()\n\
+        let ulid = id.ulid()\n\
+        return [id.is_uuid(uuid), id.is_ulid(ulid), id.is_uuid(ulid), id.parse_uuid(uuid) == uuid]\n\
+        ",
+        IdModule::default(),
+    );
+
+    assert_eq!(result, vec![true, true, false, true].into());
+}
+
+#[test]
+fn deterministic_ids() {
+    let source = "return [id.uuid4(), id.ulid(), id.ulid()]\n";
+
+    let first = run(source, IdModule::with_seed(42));
+    let second = run(source, IdModule::with_seed(42));
+    assert_eq!(first, second);
+
+    let ids: Vec<String> = first.try_into().unwrap();
+    assert_eq!(ids[0].len(), 36);
+    assert!(ids[1] < ids[2]);
+}
+
+#[test]
+fn parse_ids() {
+    let result = run(
+        "\
+        return [id.parse_uuid('123E4567E89B12D3A456426614174000'), id.ulid_timestamp('01ARZ3NDEKTSV4RRFFQ69G5FAV')]\n\
+        ",
+        IdModule::default(),
+    );
+
+    let expected = Value::List(vec![
+        "123e4567-e89b-12d3-a456-426614174000".into(),
+        1469922850259u64.into(),
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn unknown_function() {
+    let program = compile_string("return id.uuid7()\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("id".to_string(), Rc::new(IdModule::default()));
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NoSuchMember {
+            module: "id".to_string(),
+            member: "uuid7".to_string(),
+        }
+    );
+}
@@ -0,0 +1,213 @@
+use cowlang::ast::Span;
+use cowlang::{compile, compile_string, compile_with_options, CompilerOptions, Interpreter, Value};
+
+fn run(source: &str) -> Value {
+    let program = compile_string(source);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.run(&program)
+}
+
+#[test]
+fn dedent_to_statement() {
+    let result = run("let x = 0\n\
+                      if true:\n    x = 1\n\
+                      return x\n");
+
+    let expected: i64 = 1;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn dedent_without_trailing_newline() {
+    let result = run("let x = 0\n\
+                      if true:\n    x = 1");
+
+    assert_eq!(result, Value::None);
+}
+
+#[test]
+fn dedent_two_levels_to_statement() {
+    let result = run("let x = 0\n\
+                      for i in range(0, 3):\n    if i > 0:\n        x += i\n\
+                      return x\n");
+
+    let expected: i64 = 3;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn dedent_two_levels_to_block() {
+    let result = run("let x = 0\n\
+                      for i in range(0, 3):\n    for j in range(0, 2):\n        x += j\n\
+                      for i in range(0, 2):\n    x += 10\n\
+                      return x\n");
+
+    let expected: i64 = 23;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn dedent_one_of_two_levels() {
+    let result = run("let x = 0\n\
+                      for i in range(0, 2):\n    for j in range(0, 2):\n        x += 1\n    x += 10\n\
+                      return x\n");
+
+    let expected: i64 = 24;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn dedent_many_levels() {
+    let result = run("let x = 0\n\
+                      if true:\n  if true:\n    if true:\n      if true:\n        x = 4\n\
+                      return x\n");
+
+    let expected: i64 = 4;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn nested_block_before_else() {
+    let result = run("let x = 0\n\
+                      if false:\n    if true:\n        x = 1\n\
+                      else:\n    x = 2\n\
+                      return x\n");
+
+    let expected: i64 = 2;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn else_if_chain_in_loop() {
+    let result = run("let x = 0\n\
+                      for i in range(0, 3):\n    if i == 0:\n        x += 1\n    else if i == 1:\n        x += 10\n    else:\n        x += 100\n\
+                      return x\n");
+
+    let expected: i64 = 111;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn blank_lines_and_comments_in_block() {
+    let result = run("let x = 0\n\
+                      if true:\n    x += 1\n\n   \n# a comment\n        # another comment\n    x += 2\n\n\
+                      return x\n");
+
+    let expected: i64 = 3;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn indented_first_line() {
+    let result = run("   let x = 1\n   return x\n");
+
+    let expected: i64 = 1;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn tab_indentation() {
+    let result = run("let x = 0\n\
+                      if true:\n\tx = 5\n\
+                      return x\n");
+
+    let expected: i64 = 5;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn return_from_nested_block() {
+    let result = run(
+        "for i in range(0, 5):\n  for j in range(0, 5):\n    if i + j > 6:\n      return i\n\
+                      return 0\n",
+    );
+
+    let expected: i64 = 3;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn inconsistent_dedent() {
+    let result = compile("if true:\n    let x = 1\n  return x\n");
+    let err = result.unwrap_err();
+
+    assert_eq!(err.span, Some(Span { lo: 25, hi: 25 }));
+}
+
+#[test]
+fn windows_line_endings() {
+    let result = run("let x = 0\r\n\
+                      if false:\r\n    x = 1\r\n\
+                      else:\r\n    x = 2\r\n\
+                      return x\r\n");
+
+    let expected: i64 = 2;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn byte_order_mark() {
+    let result = run("\u{feff}let x = 0\n\
+                      if true:\n    x = 1\n\
+                      return x\n");
+
+    let expected: i64 = 1;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn normalize_source() {
+    let options = CompilerOptions {
+        normalize_source: true,
+        ..Default::default()
+    };
+
+    let program = compile_with_options(
+        "\u{feff}let x = 0\r\n\
+         if true:\r\n    x = 1\r\n\
+         return x\r\n",
+        &options,
+    )
+    .unwrap();
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 1;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn keep_comments() {
+    let options = CompilerOptions {
+        keep_comments: true,
+        ..Default::default()
+    };
+
+    let program = compile_with_options(
+        "# the result\n\
+         let x = 0\n\
+         if true:\n    x = 1 # always\n\
+         return x\n",
+        &options,
+    )
+    .unwrap();
+
+    let texts: Vec<&str> = program.comments.iter().map(|c| c.text.as_str()).collect();
+    assert_eq!(texts, vec!["the result", "always"]);
+
+    let (let_span, _) = &program.stmts[0];
+    assert_eq!(program.comments[0].target, Some(*let_span));
+    assert_eq!(program.comments_of(*let_span).count(), 1);
+
+    let (if_span, _) = &program.stmts[1];
+    let target = program.comments[1].target.unwrap();
+    assert!(if_span.lo < target.lo && target.hi <= if_span.hi);
+
+    // Comments are dropped by default
+    assert!(compile("# nothing\nreturn 1\n")
+        .unwrap()
+        .comments
+        .is_empty());
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "json")]
+
+use cowlang::{Value, ValueError};
+
+#[test]
+fn from_json() {
+    let value = Value::from_json_str(
+        r#"{"name": "bob", "scores": [1, 18446744073709551615, 2.5], "active": true, "parent": null}"#,
+    )
+    .unwrap();
+
+    let scores: Vec<Value> = vec![Value::I64(1), Value::U64(u64::MAX), Value::F64(2.5)];
+
+    assert_eq!(
+        value.get_child("name".into()).unwrap(),
+        &Value::Str("bob".to_string())
+    );
+    assert_eq!(
+        value.get_child("scores".into()).unwrap(),
+        &Value::List(scores)
+    );
+    assert_eq!(
+        value.get_child("active".into()).unwrap(),
+        &Value::Bool(true)
+    );
+    assert_eq!(value.get_child("parent".into()).unwrap(), &Value::None);
+}
+
+#[test]
+fn to_json() {
+    let value = Value::List(vec![
+        Value::Tuple(vec![Value::U8(1), Value::Str("a".to_string())]),
+        Value::None,
+        Value::F32(0.5),
+    ]);
+
+    assert_eq!(value.to_json_string().unwrap(), r#"[[1,"a"],null,0.5]"#);
+}
+
+#[test]
+fn round_trip() {
+    let input = r#"{"a":{"b":[1,2,3]},"c":"text"}"#;
+    let value = Value::from_json_str(input).unwrap();
+
+    let json = serde_json::Value::try_from(value.clone()).unwrap();
+    assert_eq!(Value::from(json), value);
+}
+
+#[test]
+fn not_representable() {
+    let bytes = Value::Bytes(vec![1u8, 2].into());
+    assert!(matches!(
+        bytes.to_json_string(),
+        Err(ValueError::TypeMismatch { .. })
+    ));
+
+    assert!(Value::F64(f64::NAN).to_json_string().is_err());
+}
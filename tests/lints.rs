@@ -0,0 +1,168 @@
+use cowlang::{
+    compile_named, compile_with_diagnostics, compile_with_options, lint, CompilerOptions, Lint,
+    LintLevel, LintOptions,
+};
+
+#[test]
+fn unused_and_shadowed_variables() {
+    let (_, diagnostics) = compile_with_diagnostics(
+        "\
+        let total = 0\n\
+        def add(x):\
+  \n    let unused = 1\
+  \n    let total = x\
+  \n    return x\n\
+        \n\
+        return add(1)\n\
+    ",
+        &CompilerOptions::default(),
+    )
+    .unwrap();
+
+    let found: Vec<(Lint, &str)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.lint, diagnostic.message.as_str()))
+        .collect();
+
+    assert_eq!(
+        found,
+        [
+            (Lint::UnusedVariable, "Variable `unused` is never used"),
+            (
+                Lint::Shadowing,
+                "`total` shadows a variable of an outer scope"
+            ),
+            (Lint::UnusedVariable, "Variable `total` is never used"),
+        ]
+    );
+}
+
+#[test]
+fn unused_functions() {
+    let (_, diagnostics) = compile_with_diagnostics(
+        "\
+        def outer(x):\
+  \n    def unused(y):\
+  \n        return unused(y)\
+  \n    def double(y):\
+  \n        return y * 2\
+  \n    return double(x)\n\
+        \n\
+        return outer(1)\n\
+    ",
+        &CompilerOptions::default(),
+    )
+    .unwrap();
+
+    let found: Vec<(Lint, &str)> = diagnostics
+        .iter()
+        .map(|diagnostic| (diagnostic.lint, diagnostic.message.as_str()))
+        .collect();
+
+    assert_eq!(
+        found,
+        [(Lint::UnusedVariable, "Variable `unused` is never used")]
+    );
+}
+
+#[test]
+fn underscore_and_loop_variables() {
+    let (_, diagnostics) = compile_with_diagnostics(
+        "\
+        let count = 0\n\
+        for _i in range(0, 3):\
+  \n    let _ignored = 1\
+  \n    count += 1\n\
+        \n\
+        for i in range(0, 3):\
+  \n    count += i\n\
+        \n\
+        return count\n\
+    ",
+        &CompilerOptions::default(),
+    )
+    .unwrap();
+
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn constant_condition() {
+    let program = compile_named(
+        "main.cow",
+        "\
+        let x = 1\n\
+        if 1 == 2:\
+  \n    x = 2\n\
+        \n\
+        return x\n\
+    ",
+    )
+    .unwrap();
+
+    let diagnostics = lint(&program, &LintOptions::default());
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].lint, Lint::ConstantCondition);
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "main.cow: warning[constant_condition]: \
+         Condition is constant, so only one of the branches can ever run"
+    );
+}
+
+#[test]
+fn long_function() {
+    let mut lints = LintOptions::default();
+    lints.max_function_length = 2;
+
+    let program = compile_named(
+        "main.cow",
+        "\
+        def f(x):\
+  \n    let y = x + 1\
+  \n    let z = y + 1\
+  \n    return z\n\
+        \n\
+        return f(1)\n\
+    ",
+    )
+    .unwrap();
+
+    let diagnostics = lint(&program, &lints);
+
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(
+        diagnostics[0].message,
+        "Function `f` has 3 statements (at most 2 are allowed)"
+    );
+}
+
+#[test]
+fn allow_and_deny() {
+    let source = "\
+        def f(x):\
+  \n    let y = 1\
+  \n    return x\n\
+        \n\
+        return f(1)\n\
+    ";
+
+    let mut options = CompilerOptions::default();
+    options
+        .lints
+        .set_level(Lint::UnusedVariable, LintLevel::Allow);
+
+    let (_, diagnostics) = compile_with_diagnostics(source, &options).unwrap();
+    assert!(diagnostics.is_empty());
+
+    options
+        .lints
+        .set_level(Lint::UnusedVariable, LintLevel::Deny);
+
+    let err = compile_with_options(source, &options).unwrap_err();
+    assert_eq!(
+        err.message,
+        "Variable `y` is never used (denied by `unused_variable`)"
+    );
+}
@@ -1,4 +1,4 @@
-use cowlang::{compile_string, Interpreter, PrimitiveType, TypeDefinition, Value};
+use cowlang::{compile_string, Interpreter, PrimitiveType, TypeDefinition, Value, ValueError};
 
 #[test]
 fn return_list() {
@@ -11,7 +11,7 @@ fn return_list() {
     let mut interpreter = Interpreter::default();
     let result = interpreter.run(&program);
 
-    let expected = vec![42 as i64];
+    let expected = vec![42i64];
     assert_eq!(result, expected.into());
 }
 
@@ -101,6 +101,26 @@ fn iterate_list() {
     assert_eq!(result, expected.into());
 }
 
+#[test]
+fn iterate_frozen_list() {
+    let program = compile_string(
+        "\
+        let result = 0\
+      \n\n\
+        for num in l:\
+      \n   result += num\n\
+        \n\
+        return result + l[0]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("l".to_string(), Value::from(vec![5, 5, 10]).freeze());
+
+    let result = interpreter.run(&program);
+    assert_eq!(result, 25i64.into());
+}
+
 #[test]
 fn list_append() {
     let program = compile_string(
@@ -126,7 +146,7 @@ fn type_check_str_list() {
     let list = Value::List(vec!["foo".into()]);
 
     let result = Value::type_check(&meta_list, &list);
-    assert_eq!(result, true);
+    assert!(result);
 }
 
 #[test]
@@ -137,7 +157,7 @@ fn type_check_u64_list() {
     let list = Value::List(vec![num.into()]);
 
     let result = Value::type_check(&meta_list, &list);
-    assert_eq!(result, true);
+    assert!(result);
 }
 
 #[test]
@@ -148,5 +168,241 @@ fn type_check_any_list() {
     let list = Value::List(vec![num.into(), "foo".into()]);
 
     let result = Value::type_check(&meta_list, &list);
-    assert_eq!(result, true);
+    assert!(result);
+}
+
+#[test]
+fn bind_append() {
+    let program = compile_string(
+        "\
+        let l = [1]\n\
+        let push = bind(l.append)\n\
+        push(2)\n\
+        push(3)\n\
+        return l\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: Value = vec![1i64, 2, 3].into();
+    assert_eq!(result, expected);
+
+    // bind is not reserved
+    let program = compile_string(
+        "\
+        let bind = [1]\n\
+        bind.append(2)\n\
+        return bind\n\
+    ",
+    );
+
+    let expected: Value = vec![1i64, 2].into();
+    assert_eq!(interpreter.run(&program), expected);
+}
+
+#[test]
+fn help_list() {
+    let program = compile_string("return help([1, 2])\n");
+
+    let mut interpreter = Interpreter::default();
+    let result: String = interpreter.run(&program).try_into().unwrap();
+
+    assert!(result.starts_with("value of type list with builtins:"));
+    assert!(result.contains("append(value)"));
+
+    // help is not reserved
+    let program = compile_string("let help = [1]\nreturn help.len()\n");
+    assert_eq!(interpreter.run(&program), 1u64.into());
+}
+
+#[test]
+fn list_get_default() {
+    let program = compile_string(
+        "\
+        let l = [1, 2, 3]\n\
+        return [l.get(1, 0), l.get(3, 0), l.get(5)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![2i64.into(), 0i64.into(), Value::None]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn list_aggregates() {
+    let program = compile_string(
+        "\
+        let l = [4, 1, 9, 4]\n\
+        return [l.count(4), l.min(), l.max(), l.sum()]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![2u64.into(), 1i64.into(), 9i64.into(), 18i64.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn list_extend_and_clear() {
+    let program = compile_string(
+        "\
+        let l = [1, 2]\n\
+        let other = [3, 4]\n\
+        l.extend(other)\n\
+        let total = l.sum()\n\
+        other.clear()\n\
+        return [total, other.len()]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![10i64.into(), 0u64.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn add_to_element() {
+    let program = compile_string(
+        "\
+        let l = [1, 2, 3]\n\
+        l[1] += 40\n\
+        return l\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = vec![1i64, 42, 3];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn set_element() {
+    let program = compile_string(
+        "\
+        let l = [1, 2, 3]\n\
+        l[0] = 'first'\n\
+        return l\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec!["first".into(), 2i64.into(), 3i64.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn set_nested_element() {
+    let program = compile_string(
+        "\
+        let l = [[1, 2], [3, 4]]\n\
+        l[0][1] += 40\n\
+        l[1][0] = 'x'\n\
+        return l\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        vec![1i64, 42].into(),
+        Value::List(vec!["x".into(), 4i64.into()]),
+    ]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn pop_and_insert() {
+    let program = compile_string(
+        "\
+        let l = [1, 2, 3, 4]\n\
+        let last = l.pop()\n\
+        let first = l.pop(0)\n\
+        l.insert(1, last)\n\
+        l.insert(3, first)\n\
+        return l\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = vec![2i64, 4, 3, 1];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn remove_and_index() {
+    let program = compile_string(
+        "\
+        let l = ['a', 'b', 'c', 'b']\n\
+        l.remove('b')\n\
+        return [l.index('b'), l.index('a'), len(l)]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = vec![2u64, 0, 3];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn index_of_missing_element() {
+    let program = compile_string("return [1, 2].index(3)\n");
+
+    let mut interpreter = Interpreter::default();
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(err.kind, ValueError::NoSuchChild.into());
+}
+
+#[test]
+fn sort_and_reverse() {
+    let program = compile_string(
+        "\
+        let numbers = [3, 1, 2]\n\
+        numbers.sort()\n\
+        let words = ['pear', 'apple', 'fig']\n\
+        words.sort()\n\
+        words.reverse()\n\
+        return [numbers, words]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![
+        vec![1i64, 2, 3].into(),
+        vec!["pear", "fig", "apple"].into(),
+    ]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn sort_mixed_types() {
+    let program = compile_string(
+        "\
+        let l = [2, 'one']\n\
+        l.sort()\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    assert!(interpreter.try_run(&program).is_err());
 }
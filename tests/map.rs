@@ -15,7 +15,7 @@ fn return_map() {
     expected
         .map_insert(String::from("foo"), "bar".into())
         .unwrap();
-    assert_eq!(result, expected.into());
+    assert_eq!(result, expected);
 }
 
 #[test]
@@ -33,7 +33,7 @@ fn nested_map() {
     expected
         .map_insert(String::from("foo"), Value::make_map())
         .unwrap();
-    assert_eq!(result, expected.into());
+    assert_eq!(result, expected);
 }
 
 #[test]
@@ -84,7 +84,7 @@ fn type_check_str_map() {
     map.map_insert(String::from("foo"), "bar".into()).unwrap();
 
     let result = Value::type_check(&meta_map, &map);
-    assert_eq!(result, true);
+    assert!(result);
 }
 
 #[test]
@@ -99,7 +99,7 @@ fn type_check_u64_map() {
     map.map_insert(String::from("foo"), num.into()).unwrap();
 
     let result = Value::type_check(&meta_map, &map);
-    assert_eq!(result, true);
+    assert!(result);
 }
 
 #[test]
@@ -114,5 +114,209 @@ fn type_check_any_map() {
     map.map_insert(String::from("cat"), 2.into()).unwrap();
 
     let result = Value::type_check(&meta_map, &map);
-    assert_eq!(result, true);
+    assert!(result);
+}
+
+#[test]
+fn map_chain_values() {
+    let program = compile_string(
+        "\
+        let m = {'foo': [1, 2], 'faz': [3]}\n\
+        return m.values().len() + m['foo'][1 + 0]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: u64 = 4;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn map_iter_keys() {
+    let program = compile_string(
+        "\
+        let m = {'foo': 6, 'faz': 4, 'bar': 11}\n\
+        let result = 0\n\
+        \n\
+        for k in m:\
+      \n     result += m[k]\n\
+        \n\
+        return result\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 21;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn map_values_sorted() {
+    let program = compile_string(
+        "\
+        let m = {'foo': 6, 'faz': 4, 'bar': 11}\n\
+        let result = []\n\
+        \n\
+        for v in m.values():\
+      \n     result.append(v)\n\
+        \n\
+        return result\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: Value = vec![11i64, 4, 6].into();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn map_keys_sorted() {
+    let program = compile_string(
+        "\
+        let m = {'foo': 6, 'faz': 4, 'bar': 11}\n\
+        let result = []\n\
+        \n\
+        for k in m:\
+      \n     result.append(k)\n\
+        \n\
+        return [result, m.keys()]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let keys: Value = vec!["bar".to_string(), "faz".to_string(), "foo".to_string()].into();
+    let expected: Value = vec![keys.clone(), keys].into();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn map_get_default() {
+    let program = compile_string(
+        "\
+        let m = {'a': 1}\n\
+        return m.get('a', 5) + m.get('b', 5)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 6;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn map_update_and_pop() {
+    let program = compile_string(
+        "\
+        let m = {'a': 1, 'b': 2}\n\
+        m.update({'b': 20, 'c': 30})\n\
+        let b = m.pop('b')\n\
+        let d = m.pop('d', 0)\n\
+        return [b, d, m.len()]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec![20i64.into(), 0i64.into(), 2u64.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn add_to_entry() {
+    let program = compile_string(
+        "\
+        let m = {'a': 1}\n\
+        m['a'] += 1\n\
+        return m['a']\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, 2i64.into());
+}
+
+#[test]
+fn set_entry() {
+    let program = compile_string(
+        "\
+        let m = {'a': 1}\n\
+        m['a'] = 2\n\
+        m['b'] = 3\n\
+        return [m['a'], m['b']]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = vec![2i64, 3];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn set_nested_entry() {
+    let program = compile_string(
+        "\
+        let m = {'a': {'b': 1}, 'l': [1, 2]}\n\
+        m['a']['b'] = 2\n\
+        m['a']['c'] = 3\n\
+        m['l'][1] += 1\n\
+        return [m['a']['b'], m['a']['c'], m['l'][1]]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = vec![2i64, 3, 3];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn map_items() {
+    let program = compile_string(
+        "\
+        let m = {'b': 2, 'a': 1}\n\
+        let result = []\n\
+        for item in m.items():\
+      \n    result.append(item[0])\n\
+      \n    result.append(item[1])\n\
+        \n\
+        return result\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    let expected = Value::List(vec!["a".into(), 1i64.into(), "b".into(), 2i64.into()]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn map_contains_key() {
+    let program = compile_string(
+        "\
+        let m = {'a': 1}\n\
+        return [m.contains_key('a'), m.contains_key('b')]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, vec![true, false].into());
 }
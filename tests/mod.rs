@@ -1,4 +1,29 @@
+pub mod analyzer;
+pub mod arrays;
+pub mod asynchronous;
 pub mod basic;
+pub mod capi;
+pub mod coverage;
+pub mod decimal;
+pub mod errors;
+pub mod examples;
+pub mod grammar;
+pub mod id;
+pub mod indentation;
+pub mod json;
+pub mod lints;
 pub mod list;
 pub mod map;
 pub mod modules;
+pub mod msgpack;
+pub mod parallel;
+pub mod specialize;
+pub mod stats;
+pub mod string;
+pub mod syntax;
+pub mod tensor;
+pub mod testing;
+pub mod trace;
+pub mod typecheck;
+pub mod validate;
+pub mod versioning;
@@ -1,8 +1,14 @@
-use cowlang::interpreter::{Callable, Handle};
-use cowlang::{compile_string, Interpreter, Module, Value};
+use cowlang::ast::{Policy, Violation};
+use cowlang::interpreter::{
+    CallInfo, Callable, CancelToken, Handle, Iterable, MemberInfo, MemberKind, OutputLimits,
+    SharedInterpreter,
+};
+use cowlang::{compile_string, Interpreter, Module, RuntimeError, RuntimeErrorKind, Value};
 
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::convert::TryInto;
-use std::rc::Rc;
+use std::rc::{Rc, Weak};
 
 #[derive(Default, Debug)]
 struct TestModule {}
@@ -13,21 +19,60 @@ struct GetAnswer {}
 struct PassString {}
 #[derive(Debug)]
 struct AddTwo {}
+#[derive(Debug)]
+struct GetList {}
+#[derive(Debug)]
+struct CountTo {}
+#[derive(Debug)]
+struct MapList {}
+
+struct Counter {
+    pos: i64,
+    end: i64,
+}
 
 impl Module for TestModule {
     fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
         if name == "get_answer" {
-            Handle::Callable(Box::new(GetAnswer {}))
+            Handle::Callable(Rc::new(GetAnswer {}))
         } else if name == "pass_string" {
-            Handle::Callable(Box::new(PassString {}))
+            Handle::Callable(Rc::new(PassString {}))
         } else if name == "add_two" {
-            Handle::Callable(Box::new(AddTwo {}))
+            Handle::Callable(Rc::new(AddTwo {}))
+        } else if name == "get_list" {
+            Handle::Callable(Rc::new(GetList {}))
+        } else if name == "count_to" {
+            Handle::Callable(Rc::new(CountTo {}))
+        } else if name == "map" {
+            Handle::Callable(Rc::new(MapList {}))
         } else if name == "MY_CONSTANT" {
             Handle::wrap_value("this is a test".to_string().into())
         } else {
             panic!("Unexpected function call: {}", name);
         }
     }
+
+    fn has_member(&self, name: &str) -> bool {
+        [
+            "get_answer",
+            "pass_string",
+            "add_two",
+            "get_list",
+            "count_to",
+            "map",
+            "MY_CONSTANT",
+        ]
+        .contains(&name)
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        vec![MemberInfo {
+            name: "add_two".to_string(),
+            kind: MemberKind::Function,
+            signature: Some("add_two(num)".to_string()),
+            doc: Some("Adds two to the given number".to_string()),
+        }]
+    }
 }
 
 impl Callable for GetAnswer {
@@ -52,6 +97,54 @@ impl Callable for AddTwo {
     }
 }
 
+impl Callable for GetList {
+    fn call(&self, mut _argv: Vec<Value>) -> Handle {
+        let result: Vec<Value> = vec![vec![1, 2, 3].into(), vec![4].into()];
+
+        Handle::wrap_value(result.into())
+    }
+}
+
+impl Callable for CountTo {
+    fn call(&self, mut argv: Vec<Value>) -> Handle {
+        let end: i64 = argv.drain(..).next().unwrap().try_into().unwrap();
+
+        Handle::Iter(Box::new(Counter { pos: 0, end }))
+    }
+}
+
+impl Callable for MapList {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        panic!("map() expects a function as its second argument");
+    }
+
+    fn call_with_handles(&self, argv: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let mut args = argv.into_iter();
+
+        let list = args.next().unwrap().into_value()?.into_vec()?;
+        let func = args.next().unwrap();
+
+        let mut result = Vec::new();
+
+        for elem in list {
+            result.push(func.call(vec![Handle::wrap_value(elem)])?.into_value()?);
+        }
+
+        Ok(Handle::wrap_value(result.into()))
+    }
+}
+
+impl Iterable for Counter {
+    fn next(&mut self) -> Option<Value> {
+        if self.pos < self.end {
+            self.pos += 1;
+            Some(self.pos.into())
+        } else {
+            None
+        }
+    }
+}
+
 impl Callable for PassString {
     fn call(&self, mut argv: Vec<Value>) -> Handle {
         let mut args = argv.drain(..);
@@ -62,6 +155,83 @@ impl Callable for PassString {
     }
 }
 
+/// Counts how often its members are looked up
+#[derive(Default)]
+struct LookupCounter {
+    lookups: Cell<usize>,
+}
+
+impl Module for LookupCounter {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        self.lookups.set(self.lookups.get() + 1);
+
+        if name == "add_two" {
+            Handle::Callable(Rc::new(AddTwo {}))
+        } else {
+            panic!("Unexpected function call: {}", name);
+        }
+    }
+}
+
+/// Keeps all callbacks that are registered with it
+struct Callbacks {
+    stored: Rc<RefCell<Vec<Handle>>>,
+    /// Let callables hold a strong reference to the module (creates a cycle)
+    strong_refs: bool,
+}
+
+struct Register {
+    stored: Rc<RefCell<Vec<Handle>>>,
+}
+
+struct Noop {
+    _strong_ref: Option<Rc<dyn Module>>,
+    _weak_ref: Weak<dyn Module>,
+}
+
+impl Callbacks {
+    fn new(strong_refs: bool) -> Self {
+        Self {
+            stored: Default::default(),
+            strong_refs,
+        }
+    }
+}
+
+impl Module for Callbacks {
+    fn get_member(&self, self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        if name == "register" {
+            Handle::Callable(Rc::new(Register {
+                stored: self.stored.clone(),
+            }))
+        } else if name == "noop" {
+            Handle::Callable(Rc::new(Noop {
+                _strong_ref: self.strong_refs.then(|| self_ptr.clone()),
+                _weak_ref: Rc::downgrade(self_ptr),
+            }))
+        } else {
+            panic!("Unexpected function call: {}", name);
+        }
+    }
+}
+
+impl Callable for Register {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        panic!("register() expects a function");
+    }
+
+    fn call_with_handles(&self, argv: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        self.stored.borrow_mut().extend(argv);
+        Ok(Handle::wrap_value(Value::None))
+    }
+}
+
+impl Callable for Noop {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        Handle::wrap_value(Value::None)
+    }
+}
+
 #[test]
 fn constant_function() {
     let module = Rc::new(TestModule::default());
@@ -190,10 +360,1044 @@ fn set_value() {
     );
 
     let mut interpreter = Interpreter::default();
-    interpreter.set_value(String::from("my_value"), (42 as i64).into());
+    interpreter.set_value(String::from("my_value"), 42i64.into());
 
-    let expected: Value = (42 as i64).into();
+    let expected: Value = 42i64.into();
     let result = interpreter.run(&program);
 
     assert_eq!(expected, result);
 }
+
+#[test]
+fn chain_call_results() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+    return mymodule.get_list()[0].len() + mymodule.get_list()[1][0]\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    let result = interpreter.run(&program);
+
+    let expected: u64 = 7;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn iterate_call_results() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+    let result = 0\n\
+    for l in mymodule.get_list():\
+  \n    for x in l:\
+  \n        result += x\n\
+    \n\
+    for i in mymodule.count_to(4):\
+  \n    result += i\n\
+    \n\
+    return result\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 20;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn cache_member_lookups() {
+    let module = Rc::new(LookupCounter::default());
+
+    let program = compile_string(
+        "\
+    let result = 0\n\
+    for i in range(0, 10):\
+  \n    result = mymodule.add_two(result)\n\
+    \n\
+    return result\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module.clone());
+
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 20;
+    assert_eq!(result, expected.into());
+    assert_eq!(module.lookups.get(), 1);
+}
+
+#[test]
+fn link_modules() {
+    let module = Rc::new(TestModule::default());
+
+    let mut program = compile_string(
+        "\
+    let mymodule_result = mymodule.add_two(1)\n\
+    return mymodule.add_two(mymodule_result)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    program.link(&interpreter).unwrap();
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 5;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn link_unknown_module() {
+    let mut program = compile_string("return othermodule.get_answer()\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), Rc::new(TestModule::default()));
+
+    let err = program.link(&interpreter).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NoSuchVariable("othermodule".to_string())
+    );
+}
+
+#[test]
+fn link_unknown_member() {
+    let mut program = compile_string("return mymodule.get_question()\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), Rc::new(TestModule::default()));
+
+    let err = program.link(&interpreter).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NoSuchMember {
+            module: "mymodule".to_string(),
+            member: "get_question".to_string(),
+        }
+    );
+}
+
+#[test]
+fn restrict_members() {
+    let mut program = compile_string(
+        "\
+    let x = mymodule.add_two(1)\n\
+    return mymodule.get_answer() + x\n\
+    ",
+    );
+
+    let mut policy = Policy::default();
+    policy.allow_member("mymodule", "add_two");
+
+    let err = program.clone().restrict(&policy).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NotAllowed("mymodule.get_answer".to_string())
+    );
+
+    policy.allow_member("mymodule", "get_answer");
+    program.restrict(&policy).unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), Rc::new(TestModule::default()));
+
+    let expected: i64 = 45;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn restrict_shadowed_modules() {
+    let mut policy = Policy::default();
+    policy.allow_member("mymodule", "add_two");
+    policy.register_module("os");
+
+    // Declarations never hide modules from the policy
+    for (source, access) in [
+        ("let os = 1\nreturn os.system()\n", "os.system"),
+        ("let os = 1\nreturn [os]\n", "'os'"),
+        (
+            "if false:\n    let mymodule = 1\n\nreturn mymodule.get_answer()\n",
+            "mymodule.get_answer",
+        ),
+        (
+            "def f(mymodule):\n    return mymodule.get_answer()\n\nreturn f(1)\n",
+            "mymodule.get_answer",
+        ),
+    ] {
+        let err = compile_string(source).restrict(&policy).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::NotAllowed(access.to_string()));
+    }
+
+    // Variables are only visible in the block that declares them
+    for source in [
+        "if false:\n    let x = 1\n\nreturn x\n",
+        "def f(x):\n    return x\n\nreturn x\n",
+        "return x\nlet x = 1\n",
+    ] {
+        let err = compile_string(source).restrict(&policy).unwrap_err();
+        assert_eq!(err.kind, RuntimeErrorKind::NotAllowed("'x'".to_string()));
+    }
+}
+
+#[test]
+fn restrict_with_stubs() {
+    let mut program = compile_string(
+        "\
+    if flag:\
+  \n    return mymodule.get_answer()\n\
+    \n\
+    return 1\n\
+    ",
+    );
+
+    let mut policy = Policy::default();
+    policy.allow_variable("flag");
+    policy.on_violation = Violation::Stub;
+    program.restrict(&policy).unwrap();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), Rc::new(TestModule::default()));
+
+    interpreter.set_value(String::from("flag"), false.into());
+    let expected: i64 = 1;
+    assert_eq!(interpreter.run(&program), expected.into());
+
+    interpreter.set_value(String::from("flag"), true.into());
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NotAllowed("mymodule.get_answer".to_string())
+    );
+}
+
+#[test]
+fn store_module_function() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+    let f = mymodule.add_two\n\
+    return f(1) + f(2)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 7;
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn pass_module_function() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+    return mymodule.map([1, 2, 3], mymodule.add_two)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    let result = interpreter.run(&program);
+
+    let expected: Value = vec![3i64, 4, 5].into();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn pass_script_function() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+    let offset = 10\n\
+    def shift(x):\
+  \n    return x + offset\n\
+    \n\
+    return mymodule.map([1, 2, 3], shift)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    let result = interpreter.run(&program);
+
+    let expected: Value = vec![11i64, 12, 13].into();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn bind_module_function() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+    let f = bind(mymodule.add_two, 5)\n\
+    return mymodule.map([f(), f()], bind(mymodule.add_two))\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    let result = interpreter.run(&program);
+
+    let expected: Value = vec![9i64, 9].into();
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn help_module() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string("return help(mymodule)\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), module);
+
+    let result = interpreter.run(&program);
+
+    let expected = "module with members:\n  add_two(num)\n      Adds two to the given number";
+    assert_eq!(result, expected.to_string().into());
+}
+
+#[test]
+fn failing_bound_callable() {
+    let program = compile_string("callbacks.register(bind(len, [1, 2]))\n");
+
+    let module = Rc::new(Callbacks::new(false));
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("callbacks"), module.clone());
+    interpreter.run(&program);
+
+    let Handle::Callable(callable) = module.stored.borrow_mut().remove(0) else {
+        panic!("Not a callable");
+    };
+
+    // Errors of the script are returned instead of panicking
+    assert_eq!(callable.call(vec![]).into_value(), Ok(2u64.into()));
+    assert!(matches!(callable.call(vec![1i64.into()]), Handle::Error(_)));
+
+    let result = Handle::Callable(callable).call(vec![Handle::wrap_value(1i64.into())]);
+    assert!(matches!(
+        result,
+        Err(RuntimeError {
+            kind: RuntimeErrorKind::InvalidArgument(_),
+            ..
+        })
+    ));
+}
+
+#[test]
+fn leak_check() {
+    let program = compile_string("callbacks.register(callbacks.noop)\n");
+
+    let module = Rc::new(Callbacks::new(false));
+
+    let mut interpreter = Interpreter::default();
+    interpreter.enable_leak_detection();
+    interpreter.register_module(String::from("callbacks"), module.clone());
+    interpreter.run(&program);
+
+    assert_eq!(module.stored.borrow().len(), 1);
+    assert_eq!(interpreter.leak_check(), Ok(()));
+}
+
+#[test]
+fn detect_reference_cycle() {
+    let program = compile_string("callbacks.register(callbacks.noop)\n");
+
+    let module = Rc::new(Callbacks::new(true));
+
+    let mut interpreter = Interpreter::default();
+    interpreter.enable_leak_detection();
+    interpreter.register_module(String::from("callbacks"), module.clone());
+    interpreter.run(&program);
+
+    let err = interpreter.leak_check().unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::ModuleLeaked("callbacks".to_string())
+    );
+
+    // Break the cycle
+    module.stored.borrow_mut().clear();
+    assert_eq!(interpreter.leak_check(), Ok(()));
+}
+
+/// Opens fake files and remembers which ones were closed
+#[derive(Default)]
+struct Files {
+    closed: Rc<RefCell<Vec<String>>>,
+}
+
+struct Open {
+    closed: Rc<RefCell<Vec<String>>>,
+}
+
+struct File {
+    name: String,
+    closed: Rc<RefCell<Vec<String>>>,
+}
+
+struct Read {
+    name: String,
+}
+
+struct Close {
+    name: String,
+    closed: Rc<RefCell<Vec<String>>>,
+}
+
+impl Module for Files {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        if name == "open" {
+            Handle::Callable(Rc::new(Open {
+                closed: self.closed.clone(),
+            }))
+        } else {
+            panic!("Unexpected function call: {}", name);
+        }
+    }
+}
+
+impl Module for File {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        if name == "read" {
+            Handle::Callable(Rc::new(Read {
+                name: self.name.clone(),
+            }))
+        } else if name == "close" {
+            Handle::Callable(Rc::new(Close {
+                name: self.name.clone(),
+                closed: self.closed.clone(),
+            }))
+        } else {
+            panic!("Unexpected function call: {}", name);
+        }
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        ["read", "close"]
+            .iter()
+            .map(|name| MemberInfo {
+                name: name.to_string(),
+                kind: MemberKind::Function,
+                signature: Some(format!("{name}()")),
+                doc: None,
+            })
+            .collect()
+    }
+}
+
+impl Callable for Open {
+    fn call(&self, argv: Vec<Value>) -> Handle {
+        let name: String = argv.into_iter().next().unwrap().try_into().unwrap();
+
+        Handle::Object(Rc::new(File {
+            name,
+            closed: self.closed.clone(),
+        }))
+    }
+}
+
+impl Callable for Read {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        Handle::wrap_value(format!("contents of {}", self.name).into())
+    }
+}
+
+impl Callable for Close {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        self.closed.borrow_mut().push(self.name.clone());
+        Handle::None
+    }
+}
+
+#[test]
+fn with_block() {
+    let program = compile_string(
+        "\
+    let result = ''\n\
+    with files.open('a.txt') as f:\
+  \n    result = f.read()\n\
+    return result\n\
+    ",
+    );
+
+    let module = Rc::new(Files::default());
+    let closed = module.closed.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("files"), module);
+
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, "contents of a.txt".to_string().into());
+    assert_eq!(*closed.borrow(), vec!["a.txt".to_string()]);
+}
+
+#[test]
+fn with_block_early_return() {
+    let program = compile_string(
+        "\
+    with files.open('a.txt') as f:\
+  \n    return f.read()\n\
+    ",
+    );
+
+    let module = Rc::new(Files::default());
+    let closed = module.closed.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("files"), module);
+
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, "contents of a.txt".to_string().into());
+    assert_eq!(*closed.borrow(), vec!["a.txt".to_string()]);
+}
+
+#[test]
+fn with_block_error() {
+    let program = compile_string(
+        "\
+    with files.open('a.txt') as f:\
+  \n    assert(false)\n\
+    ",
+    );
+
+    let module = Rc::new(Files::default());
+    let closed = module.closed.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("files"), module);
+
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert!(matches!(err.kind, RuntimeErrorKind::AssertionFailed(_)));
+    assert_eq!(*closed.borrow(), vec!["a.txt".to_string()]);
+}
+
+#[test]
+fn with_block_not_a_resource() {
+    let program = compile_string(
+        "\
+    with [1, 2] as l:\
+  \n    return l\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(err.kind, RuntimeErrorKind::NotAResource("l".to_string()));
+}
+
+#[test]
+fn with_block_needs_close_member() {
+    // Files does not list a close() member, even though it accepts any name
+    let program = compile_string(
+        "\
+    with files as f:\
+  \n    return 1\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("files"), Rc::new(Files::default()));
+
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(err.kind, RuntimeErrorKind::NotAResource("f".to_string()));
+}
+
+#[test]
+fn with_block_close_uses_middleware() {
+    let program = compile_string(
+        "\
+    with files.open('a.txt') as f:\
+  \n    f.read()\n\
+    ",
+    );
+
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let calls_cpy = calls.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("files"), Rc::new(Files::default()));
+    interpreter.wrap_all_callables(move |info, args, inner| {
+        calls_cpy.borrow_mut().push(info.member.clone());
+        inner.call_with_handles(args)
+    });
+
+    interpreter.run(&program);
+
+    assert_eq!(*calls.borrow(), ["open", "read", "close"]);
+    assert_eq!(interpreter.stats().module_calls, 3);
+}
+
+#[test]
+fn middleware_records_calls() {
+    let program = compile_string(
+        "\
+    let f = mymodule.add_two\n\
+    return f(1) + mymodule.get_answer()\n\
+    ",
+    );
+
+    let calls = Rc::new(RefCell::new(Vec::new()));
+    let calls_cpy = calls.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), Rc::new(TestModule::default()));
+    interpreter.wrap_all_callables(move |info, args, inner| {
+        calls_cpy.borrow_mut().push(info.clone());
+        inner.call_with_handles(args)
+    });
+
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 45;
+    assert_eq!(result, expected.into());
+
+    let expected_calls: Vec<CallInfo> = ["add_two", "get_answer"]
+        .iter()
+        .map(|member| CallInfo {
+            module: Some("mymodule".to_string()),
+            member: member.to_string(),
+        })
+        .collect();
+    assert_eq!(*calls.borrow(), expected_calls);
+}
+
+#[test]
+fn middleware_rewrites_arguments() {
+    let program = compile_string("return mymodule.add_two(1)\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("mymodule"), Rc::new(TestModule::default()));
+
+    // Runs second and doubles the argument
+    interpreter.wrap_all_callables(|_, args, inner| {
+        let args = Handle::into_values(args)?
+            .into_iter()
+            .map(|arg| Handle::wrap_value(arg.multiply(&2i64.into()).unwrap()))
+            .collect();
+        inner.call_with_handles(args)
+    });
+
+    // Runs first and increments the argument
+    interpreter.wrap_all_callables(|_, args, inner| {
+        let args = Handle::into_values(args)?
+            .into_iter()
+            .map(|arg| Handle::wrap_value(arg.add(&1i64.into()).unwrap()))
+            .collect();
+        inner.call_with_handles(args)
+    });
+
+    let result = interpreter.run(&program);
+
+    let expected: i64 = 6;
+    assert_eq!(result, expected.into());
+}
+
+/// Keeps its value in a shared handle, so scripts can modify it
+struct Tally {
+    count: Rc<Cell<Value>>,
+}
+
+impl Module for Tally {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match name {
+            "count" => Handle::Value(self.count.clone()),
+            "copy" => Handle::wrap_value(0i64.into()),
+            _ => panic!("Unexpected member: {}", name),
+        }
+    }
+}
+
+#[test]
+fn add_to_member() {
+    let module = Rc::new(Tally {
+        count: Rc::new(Cell::new(0i64.into())),
+    });
+
+    let program = compile_string(
+        "\
+        tally.count += 2\n\
+        tally.count += 3\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("tally"), module.clone());
+    interpreter.run(&program);
+
+    assert_eq!(module.count.replace(Value::None), 5i64.into());
+}
+
+#[test]
+fn add_to_read_only_member() {
+    let module = Rc::new(Tally {
+        count: Rc::new(Cell::new(0i64.into())),
+    });
+    let program = compile_string("tally.copy += 1\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("tally"), module);
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NotAssignable("a temporary value".to_string())
+    );
+}
+
+#[test]
+fn count_module_calls() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+        let a = test_module.get_answer()\n\
+        print(len([a]))\n\
+        return test_module.add_two(a)\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("test_module"), module);
+    interpreter.run(&program);
+
+    assert_eq!(interpreter.stats().module_calls, 2);
+}
+
+#[test]
+fn run_isolated() {
+    let module = Rc::new(TestModule::default());
+
+    let program = compile_string(
+        "\
+        base += test_module.add_two(x)\n\
+        return base\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("test_module"), module);
+    interpreter.set_value(String::from("base"), 10i64.into());
+
+    for x in [1i64, 2] {
+        let inputs = HashMap::from([(String::from("x"), x.into())]);
+        let result = interpreter.run_isolated(&program, inputs).unwrap();
+
+        assert_eq!(result, (12 + x).into());
+    }
+}
+
+/// Counts how often its pure function was actually invoked
+struct Config {
+    lookups: Rc<Cell<u32>>,
+}
+
+struct Lookup {
+    lookups: Rc<Cell<u32>>,
+}
+
+impl Module for Config {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match name {
+            "lookup" => Handle::Callable(Rc::new(Lookup {
+                lookups: self.lookups.clone(),
+            })),
+            _ => panic!("Unexpected member: {}", name),
+        }
+    }
+}
+
+impl Callable for Lookup {
+    fn call(&self, argv: Vec<Value>) -> Handle {
+        self.lookups.set(self.lookups.get() + 1);
+
+        let key: i64 = argv.into_iter().next().unwrap().try_into().unwrap();
+        Handle::wrap_value((key * 2).into())
+    }
+
+    fn is_pure(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn memoize_pure_functions() {
+    let lookups = Rc::new(Cell::new(0));
+    let module = Rc::new(Config {
+        lookups: lookups.clone(),
+    });
+
+    let program = compile_string(
+        "\
+        let total = 0\n\
+        for i in range(0, 3):\
+      \n    total += config.lookup(5) + config.lookup(6)\n\
+        \n\
+        return total",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("config"), module);
+    interpreter.enable_memoization();
+
+    let result = interpreter.run(&program);
+
+    assert_eq!(result, 66i64.into());
+    assert_eq!(lookups.get(), 2);
+}
+
+#[test]
+fn argument_too_large() {
+    let module = Rc::new(TestModule::default());
+    let program = compile_string("return test_module.pass_string('a very long string')\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("test_module"), module);
+    interpreter.set_output_limits(OutputLimits {
+        max_depth: None,
+        max_size: Some(10),
+    });
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::ValueTooLarge {
+            size: 19,
+            max_size: 10
+        }
+    );
+}
+
+struct Buggy {}
+
+struct Crash {}
+
+impl Module for Buggy {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, _name: &str) -> Handle {
+        Handle::Callable(Rc::new(Crash {}))
+    }
+}
+
+impl Callable for Crash {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        panic!("something went wrong");
+    }
+}
+
+#[test]
+fn catch_module_panic() {
+    let program = compile_string("return buggy.crash()\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("buggy"), Rc::new(Buggy {}));
+    interpreter.catch_module_panics();
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::ModulePanicked("something went wrong".to_string())
+    );
+}
+
+struct Legacy {}
+
+impl Module for Legacy {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match name {
+            "old_answer" => Handle::deprecated(
+                Handle::Callable(Rc::new(GetAnswer {})),
+                "use legacy.answer() instead",
+            ),
+            "answer" => Handle::Callable(Rc::new(GetAnswer {})),
+            _ => panic!("Unexpected function call: {}", name),
+        }
+    }
+}
+
+#[test]
+fn report_deprecated_members() {
+    let program = compile_string(
+        "let a = legacy.old_answer()\n\
+         let b = legacy.old_answer()\n\
+         return a + b + legacy.answer()\n",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module(String::from("legacy"), Rc::new(Legacy {}));
+
+    let result: i64 = interpreter.run(&program).try_into().unwrap();
+    assert_eq!(result, 126);
+
+    let deprecations = interpreter.deprecations();
+    assert_eq!(deprecations.len(), 1);
+    assert_eq!(deprecations[0].module, Some("legacy".to_string()));
+    assert_eq!(deprecations[0].member, "old_answer");
+    assert_eq!(deprecations[0].message, "use legacy.answer() instead");
+}
+
+#[test]
+fn register_constants() {
+    let mut colors = HashMap::new();
+    colors.insert("RED".to_string(), Value::from("red"));
+    colors.insert("GREEN".to_string(), Value::from("green"));
+
+    let program = compile_string(
+        "\
+        let color = Color.GREEN\n\
+        if color == Color.RED:\
+        \n    return 1\n\
+        \n\
+        if color == Color.GREEN:\
+        \n    return 2\n\
+        \n\
+        return 3\n\
+    ",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_constants("Color", colors);
+
+    let result: i64 = interpreter.run(&program).try_into().unwrap();
+    assert_eq!(result, 2);
+}
+
+/// Runs a program on the interpreter that called it
+struct Reenter {
+    interpreter: RefCell<Option<SharedInterpreter>>,
+}
+
+impl Module for Reenter {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        assert_eq!(name, "run");
+        Handle::Callable(Rc::new(ReenterRun {
+            interpreter: self.interpreter.borrow().clone().unwrap(),
+        }))
+    }
+}
+
+struct ReenterRun {
+    interpreter: SharedInterpreter,
+}
+
+impl Callable for ReenterRun {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        panic!("run() must be called with handles");
+    }
+
+    fn call_with_handles(&self, _argv: Vec<Handle>) -> Result<Handle, RuntimeError> {
+        let value = self.interpreter.try_run(&compile_string("return 1\n"))?;
+        Ok(Handle::wrap_value(value))
+    }
+}
+
+#[test]
+fn reentrant_run() {
+    let module = Rc::new(Reenter {
+        interpreter: RefCell::new(None),
+    });
+
+    let shared = Interpreter::default().into_shared();
+    shared
+        .get()
+        .unwrap()
+        .register_module(String::from("host"), module.clone());
+    *module.interpreter.borrow_mut() = Some(shared.clone());
+
+    // Cannot be caught by the program
+    let program = compile_string(
+        "\
+        try:\
+        \n    return host.run()\n\
+        except:\
+        \n    return 0\n\
+    ",
+    );
+
+    let err = shared.try_run(&program).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::ReentrantRun);
+
+    // The interpreter can be used again once the run finished
+    assert!(shared.get().is_ok());
+    module.interpreter.borrow_mut().take();
+}
+
+/// Cancels the program that calls it
+struct CancelNow {
+    token: CancelToken,
+}
+
+impl Callable for CancelNow {
+    fn call(&self, _argv: Vec<Value>) -> Handle {
+        self.token.cancel();
+        Handle::wrap_value(Value::None)
+    }
+}
+
+struct Canceller {
+    token: CancelToken,
+}
+
+impl Module for Canceller {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        assert_eq!(name, "now");
+        Handle::Callable(Rc::new(CancelNow {
+            token: self.token.clone(),
+        }))
+    }
+}
+
+#[test]
+fn cancel_program() {
+    let token = CancelToken::default();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_cancel_token(token.clone());
+    interpreter.register_module(
+        String::from("cancel"),
+        Rc::new(Canceller {
+            token: token.clone(),
+        }),
+    );
+
+    let program = compile_string(
+        "\
+        let count = 1\n\
+        cancel.now()\n\
+        count += 1\n\
+        return count\n\
+    ",
+    );
+
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert_eq!(err.kind, RuntimeErrorKind::Cancelled);
+    assert!(token.is_cancelled());
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "msgpack")]
+
+use cowlang::ast::{Program, PROGRAM_VERSION};
+use cowlang::{compile_string, DecodeError, Interpreter, Value};
+
+#[test]
+fn program_round_trip() {
+    let program = compile_string("let x = [1, 2]\nreturn x[1] + 3\n");
+
+    let encoded = program.to_bytes();
+    assert_eq!(&encoded[..4], b"COWP");
+
+    let program = Program::from_bytes(&encoded).unwrap();
+
+    let mut interpreter = Interpreter::default();
+    let expected: i64 = 5;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn program_from_newer_version() {
+    let mut encoded = compile_string("return 1\n").to_bytes();
+    encoded[4..8].copy_from_slice(&(PROGRAM_VERSION + 1).to_le_bytes());
+
+    assert_eq!(
+        Program::from_bytes(&encoded).unwrap_err(),
+        DecodeError::UnknownVersion(PROGRAM_VERSION + 1)
+    );
+}
+
+#[test]
+fn value_round_trip() {
+    let value = Value::List(vec![
+        Value::Str("a".to_string()),
+        Value::Tuple(vec![Value::U8(1), Value::F64(0.5)]),
+        Value::Array(vec![Value::None; 2]),
+        Value::Bytes(vec![1u8, 2].into()),
+    ]);
+
+    let encoded = value.to_msgpack();
+    assert_eq!(Value::from_msgpack(&encoded).unwrap(), value);
+
+    // Frozen values are stored like their contents
+    assert_eq!(
+        Value::from_msgpack(&value.clone().freeze().to_msgpack()).unwrap(),
+        value
+    );
+}
+
+#[test]
+fn invalid_header() {
+    let encoded = Value::I64(1).to_msgpack();
+
+    assert_eq!(
+        Program::from_bytes(&encoded).unwrap_err(),
+        DecodeError::InvalidHeader
+    );
+    assert_eq!(
+        Value::from_msgpack(&[1, 2]).unwrap_err(),
+        DecodeError::InvalidHeader
+    );
+}
@@ -0,0 +1,131 @@
+use cowlang::interpreter::{
+    Executor, Handle, Job, MemberInfo, ParallelModule, ThreadExecutor, ThreadSafeFunction,
+};
+use cowlang::{compile_string, Interpreter, Module, RuntimeError, RuntimeErrorKind, Value};
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[derive(Default)]
+struct MathModule {}
+
+impl Module for MathModule {
+    fn get_member(&self, _self_ptr: &Rc<dyn Module>, name: &str) -> Handle {
+        match name {
+            "square" => Handle::Callable(Rc::new(ThreadSafeFunction::new(|args| {
+                let value: i64 = args[0].clone().try_into()?;
+                Ok(Value::I64(value * value))
+            }))),
+            "explode" => Handle::Callable(Rc::new(ThreadSafeFunction::new(|_args| {
+                panic!("boom");
+            }))),
+            _ => panic!("Unexpected member: {name}"),
+        }
+    }
+
+    fn has_member(&self, name: &str) -> bool {
+        name == "square" || name == "explode"
+    }
+
+    fn members(&self) -> Vec<MemberInfo> {
+        vec![]
+    }
+}
+
+/// Runs all jobs on the current thread and counts the batches
+#[derive(Default)]
+struct CountingExecutor {
+    batches: Cell<usize>,
+}
+
+impl Executor for CountingExecutor {
+    fn execute(&self, jobs: Vec<Job>) -> Vec<Result<Value, RuntimeError>> {
+        self.batches.set(self.batches.get() + 1);
+        jobs.into_iter().map(|job| job()).collect()
+    }
+}
+
+fn interpreter(parallel: ParallelModule) -> Interpreter {
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("math".to_string(), Rc::new(MathModule::default()));
+    interpreter.register_module("parallel".to_string(), Rc::new(parallel));
+    interpreter
+}
+
+#[test]
+fn map_on_threads() {
+    let program = compile_string("return parallel.map(range(0, 100), math.square)\n");
+
+    let parallel = ParallelModule::new(Rc::new(ThreadExecutor::new(4)));
+    let result = interpreter(parallel).run(&program);
+
+    let expected: Vec<i64> = (0..100).map(|i| i * i).collect();
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn custom_executor() {
+    let program = compile_string(
+        "\
+        let a = parallel.map([1, 2], math.square)\n\
+        let b = parallel.map([3], math.square)\n\
+        return [a, b]\n\
+    ",
+    );
+
+    let executor = Rc::new(CountingExecutor::default());
+    let parallel = ParallelModule::new(executor.clone());
+    let result = interpreter(parallel).run(&program);
+
+    let expected: Vec<Value> = vec![vec![1i64, 4].into(), vec![9i64].into()];
+    assert_eq!(result, Value::List(expected));
+    assert_eq!(executor.batches.get(), 2);
+}
+
+#[test]
+fn script_functions_are_rejected() {
+    let program = compile_string(
+        "\
+        def double(x):\
+  \n    return x * 2\n\
+        \n\
+        return parallel.map([1, 2], double)\n\
+    ",
+    );
+
+    let err = interpreter(ParallelModule::default())
+        .try_run(&program)
+        .unwrap_err();
+
+    assert!(matches!(err.kind, RuntimeErrorKind::InvalidArgument(_)));
+}
+
+#[test]
+fn unknown_function() {
+    let program = compile_string("return parallel.mpa([1], math.square)\n");
+
+    let err = interpreter(ParallelModule::default())
+        .try_run(&program)
+        .unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NoSuchMember {
+            module: "parallel".to_string(),
+            member: "mpa".to_string(),
+        }
+    );
+}
+
+#[test]
+fn job_panics() {
+    let program = compile_string("return parallel.map([1, 2, 3], math.explode)\n");
+
+    let parallel = ParallelModule::new(Rc::new(ThreadExecutor::new(2)));
+    let err = interpreter(parallel).try_run(&program).unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::ModulePanicked("boom".to_string())
+    );
+}
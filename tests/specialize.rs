@@ -0,0 +1,85 @@
+use cowlang::ast::Expr;
+use cowlang::{compile_string, Interpreter, Value, ValueError};
+
+use std::collections::HashMap;
+
+fn inputs(debug: bool, scale: i64) -> HashMap<String, Value> {
+    let mut vars = HashMap::new();
+    vars.insert("debug".to_string(), debug.into());
+    vars.insert("scale".to_string(), scale.into());
+    vars
+}
+
+#[test]
+fn fold_known_inputs() {
+    let program = compile_string(
+        "\
+        let result = []\n\
+        if debug:\
+      \n    result.append('debug')\n\
+        else:\
+      \n    result.append(scale * (2 + 3))\n\
+        \n\
+        result.append(value * scale)\n\
+        return result\n\
+    ",
+    );
+
+    let specialized = program.specialize(inputs(false, 4));
+
+    // The condition is gone and only the else-branch is left
+    assert_eq!(specialized.stmts.len(), 4);
+    assert!(!specialized
+        .stmts
+        .iter()
+        .any(|(_, expr)| matches!(expr, Expr::IfElse { .. })));
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("value".to_string(), 3i64.into());
+    let result = interpreter.run(&specialized);
+
+    assert_eq!(result, vec![20i64, 12].into());
+}
+
+#[test]
+fn keep_written_variables() {
+    let program = compile_string(
+        "\
+        scale += 1\n\
+        return scale\n\
+    ",
+    );
+
+    let specialized = program.specialize(inputs(true, 4));
+
+    let mut interpreter = Interpreter::default();
+    interpreter.set_value("scale".to_string(), 1i64.into());
+    assert_eq!(interpreter.run(&specialized), Value::I64(2));
+}
+
+#[test]
+fn keep_failing_expressions() {
+    let program = compile_string("return scale // 0\n");
+    let specialized = program.specialize(inputs(true, 4));
+
+    let err = Interpreter::default().try_run(&specialized).unwrap_err();
+    assert_eq!(err.kind, ValueError::DivisionByZero.into());
+}
+
+#[test]
+fn keep_blocks_with_declarations() {
+    let program = compile_string(
+        "\
+        let x = 1\n\
+        if debug:\
+      \n    let x = 2\n\
+        \n\
+        return x\n\
+    ",
+    );
+
+    let specialized = program.specialize(inputs(true, 4));
+
+    let mut interpreter = Interpreter::default();
+    assert_eq!(interpreter.run(&specialized), Value::I64(1));
+}
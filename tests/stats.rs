@@ -0,0 +1,22 @@
+use cowlang::{compile_string, Interpreter};
+
+#[test]
+fn count_statements() {
+    let program = compile_string(
+        "\
+        let x = 0\n\
+        for i in range(0, 3):\
+      \n    x += i\n\
+        \n\
+        return x",
+    );
+
+    let mut interpreter = Interpreter::default();
+    interpreter.run(&program);
+
+    let stats = interpreter.stats();
+    assert_eq!(stats.statements, 6);
+    assert_eq!(stats.peak_scope_depth, 2);
+    assert_eq!(stats.module_calls, 0);
+    assert!(stats.allocations > 0);
+}
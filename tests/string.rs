@@ -0,0 +1,85 @@
+use cowlang::{compile_string, Interpreter, RuntimeErrorKind, Value};
+
+fn run(source: &str) -> Value {
+    let program = compile_string(source);
+    let mut interpreter = Interpreter::default();
+    interpreter.run(&program)
+}
+
+#[test]
+fn split() {
+    let result = run("return ['a,b,,c'.split(','), '  one two\tthree '.split()]\n");
+
+    let expected = Value::List(vec![
+        vec!["a", "b", "", "c"].into(),
+        vec!["one", "two", "three"].into(),
+    ]);
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn join() {
+    let result = run("\
+        let words = 'x y z'.split()\n\
+        return ', '.join(words)\n\
+    ");
+
+    assert_eq!(result, "x, y, z".into());
+}
+
+#[test]
+fn join_non_strings() {
+    let program = compile_string("return '-'.join([1, 2])\n");
+
+    let mut interpreter = Interpreter::default();
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert!(matches!(err.kind, RuntimeErrorKind::Value(_)));
+}
+
+#[test]
+fn change_case() {
+    let result = run("return ['MiXeD'.upper(), 'MiXeD'.lower()]\n");
+
+    assert_eq!(result, vec!["MIXED", "mixed"].into());
+}
+
+#[test]
+fn strip() {
+    let result = run("return ' \t padded \t '.strip()\n");
+
+    assert_eq!(result, "padded".into());
+}
+
+#[test]
+fn prefix_and_suffix() {
+    let result = run("\
+        let name = 'report.csv'\n\
+        return [name.startswith('rep'), name.startswith('csv'), name.endswith('.csv'), name.endswith('rep')]\n\
+    ");
+
+    assert_eq!(result, vec![true, false, true, false].into());
+}
+
+#[test]
+fn replace() {
+    let result = run("return 'a-b-c'.replace('-', '+')\n");
+
+    assert_eq!(result, "a+b+c".into());
+}
+
+#[test]
+fn find() {
+    let result = run("return ['héllo'.find('l'), 'hello'.find('x')]\n");
+
+    let expected: Vec<i64> = vec![2, -1];
+    assert_eq!(result, expected.into());
+}
+
+#[test]
+fn wrong_argument_count() {
+    let program = compile_string("return 'abc'.replace('a')\n");
+
+    let mut interpreter = Interpreter::default();
+    let err = interpreter.try_run(&program).unwrap_err();
+    assert!(matches!(err.kind, RuntimeErrorKind::InvalidArgument(_)));
+}
@@ -0,0 +1,87 @@
+use cowlang::{find_references, parse_lossless, rename_variable, TokenKind};
+
+const SOURCE: &str = "\
+# count things\n\
+let count = 0   # start at zero\n\
+for i in range(0, 3):\
+\n    count += i\n\
+\n\
+return  count\n\
+";
+
+#[test]
+fn round_trip() {
+    let (program, tree) = parse_lossless(SOURCE).unwrap();
+
+    assert_eq!(tree.to_source(), SOURCE);
+    assert_eq!(tree.statements.len(), program.stmts.len());
+
+    let comments = tree
+        .tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Comment)
+        .count();
+    assert_eq!(comments, 2);
+}
+
+#[test]
+fn statement_tokens() {
+    let (_, tree) = parse_lossless(SOURCE).unwrap();
+
+    let first = &tree.statements[0];
+    let text: String = tree.tokens[first.tokens.clone()]
+        .iter()
+        .map(|token| token.text.as_str())
+        .collect();
+
+    assert_eq!(text, "let count = 0");
+}
+
+#[test]
+fn rename_tokens() {
+    let (_, mut tree) = parse_lossless(SOURCE).unwrap();
+
+    assert_eq!(tree.rename("count", "total"), 3);
+    // Comments and formatting are not touched
+    assert_eq!(
+        tree.to_source(),
+        "\
+# count things\n\
+let total = 0   # start at zero\n\
+for i in range(0, 3):\
+\n    total += i\n\
+\n\
+return  total\n\
+"
+    );
+}
+
+#[test]
+fn rename_ignores_members() {
+    let (_, mut tree) = parse_lossless("let l = [1]\nl.append(2)\nreturn l\n").unwrap();
+
+    assert_eq!(tree.rename("append", "push"), 0);
+    assert_eq!(tree.rename("l", "list"), 3);
+    assert_eq!(
+        tree.to_source(),
+        "let list = [1]\nlist.append(2)\nreturn list\n"
+    );
+}
+
+#[test]
+fn find_variable_references() {
+    let spans = find_references(SOURCE, "count").unwrap();
+
+    let texts: Vec<&str> = spans.iter().map(|span| &SOURCE[span.lo..span.hi]).collect();
+    assert_eq!(texts, vec!["count"; 3]);
+    assert!(find_references(SOURCE, "total").unwrap().is_empty());
+}
+
+#[test]
+fn rename_in_source() {
+    let output = rename_variable("let x = 1\nreturn x # the x\n", "x", "y").unwrap();
+    assert_eq!(output, "let y = 1\nreturn y # the x\n");
+
+    // Keywords cannot be used as names
+    assert!(rename_variable("let x = 1\nreturn x\n", "x", "return").is_err());
+}
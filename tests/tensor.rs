@@ -0,0 +1,64 @@
+#![cfg(feature = "ndarray")]
+
+use cowlang::interpreter::TensorModule;
+use cowlang::{compile_string, Interpreter, RuntimeErrorKind, Value, ValueError};
+
+use ndarray::array;
+use std::rc::Rc;
+
+fn run(source: &str) -> Value {
+    let program = compile_string(source);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("tensor".to_string(), Rc::new(TensorModule::default()));
+    interpreter.run(&program)
+}
+
+#[test]
+fn matrix_product() {
+    let result = run("\
+        let m = [[1, 2], [3, 4]]\n\
+        return tensor.dot(m, tensor.identity(2))\n\
+    ");
+
+    assert_eq!(
+        result.to_array().unwrap(),
+        array![[1.0, 2.0], [3.0, 4.0]].into_dyn()
+    );
+}
+
+#[test]
+fn elementwise_operations() {
+    let result = run("\
+        let v = tensor.add([1, 2, 3], tensor.ones(3))\n\
+        return tensor.scale(v, 2)\n\
+    ");
+
+    assert_eq!(result, Value::from(vec![4.0, 6.0, 8.0]));
+}
+
+#[test]
+fn array_conversion() {
+    let value = Value::from(array![[1.0, 2.0], [3.0, 4.0]]);
+    assert_eq!(value, Value::from(vec![vec![1.0, 2.0], vec![3.0, 4.0]]));
+
+    let ragged = Value::from(vec![Value::from(vec![1]), Value::from(vec![1, 2])]);
+    assert_eq!(ragged.to_array(), Err(ValueError::ShapeMismatch));
+}
+
+#[test]
+fn unknown_function() {
+    let program = compile_string("return tensor.inverse([[1, 0], [0, 1]])\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("tensor".to_string(), Rc::new(TensorModule::default()));
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NoSuchMember {
+            module: "tensor".to_string(),
+            member: "inverse".to_string(),
+        }
+    );
+}
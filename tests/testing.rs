@@ -0,0 +1,50 @@
+use cowlang::testing::{run_test, run_test_file};
+use cowlang::{Error, Interpreter, RuntimeErrorKind};
+
+#[test]
+fn passing_file() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scripts/passing.cow");
+    let report = run_test_file(path).unwrap();
+
+    assert!(report.all_passed());
+    assert_eq!(report.num_passed(), 1);
+}
+
+#[test]
+fn failing_file() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scripts/failing.cow");
+    let report = run_test_file(path).unwrap();
+
+    assert_eq!(report.num_failed(), 1);
+
+    match &report.results[0].error {
+        Some(Error::Runtime(err)) => {
+            assert_eq!(
+                err.kind,
+                RuntimeErrorKind::AssertionFailed("x is 2".to_string())
+            );
+        }
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn missing_file() {
+    assert!(run_test_file("does/not/exist.cow").is_err());
+}
+
+#[test]
+fn compile_error() {
+    let result = run_test("broken", "assert(\n", Interpreter::default());
+    assert!(matches!(result.error, Some(Error::Compile(_))));
+}
+
+#[test]
+fn assert_is_not_reserved() {
+    let result = run_test(
+        "shadowed",
+        "def assert(cond):\n    return cond\n\nassert(false)\n",
+        Interpreter::default(),
+    );
+    assert!(result.passed());
+}
@@ -0,0 +1,110 @@
+use cowlang::{compile_string, Interpreter, Value};
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+const PROGRAM: &str = "\
+let x = 1\n\
+let l = []\n\
+for i in range(0, 3):\
+\n    x += i\
+\n    l.append(i)\n\
+\n\
+return x\n\
+";
+
+#[test]
+fn record_trace() {
+    let program = compile_string(PROGRAM);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.enable_tracing();
+
+    let result = interpreter.run(&program);
+    assert_eq!(result, 4i64.into());
+
+    let trace = interpreter.take_trace().unwrap();
+    assert_eq!(trace.num_statements(), 10);
+}
+
+#[test]
+fn replay_trace() {
+    let program = compile_string(PROGRAM);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.enable_tracing();
+    interpreter.run(&program);
+
+    let trace = interpreter.take_trace().unwrap();
+
+    assert!(trace.replay(0).is_empty());
+
+    let mut expected = HashMap::new();
+    expected.insert("x".to_string(), Value::from(1i64));
+    expected.insert("l".to_string(), Value::make_list());
+    assert_eq!(trace.replay(2), expected);
+
+    // Inside the second iteration of the loop
+    expected.insert("i".to_string(), Value::from(1i64));
+    expected.insert("l".to_string(), vec![0i64].into());
+    assert_eq!(trace.replay(5), expected);
+
+    // The loop variable is gone after the loop
+    expected.remove("i");
+    expected.insert("x".to_string(), Value::from(4i64));
+    expected.insert("l".to_string(), vec![0i64, 1, 2].into());
+    assert_eq!(trace.replay(9), expected);
+}
+
+#[test]
+fn trace_disabled() {
+    let program = compile_string(PROGRAM);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.run(&program);
+
+    assert!(interpreter.take_trace().is_none());
+}
+
+#[test]
+fn watch_variables() {
+    let program = compile_string(PROGRAM);
+
+    let changes = Rc::new(RefCell::new(vec![]));
+    let recorded = changes.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.watch_variables(&["x"], move |name, old, new| {
+        recorded
+            .borrow_mut()
+            .push((name.to_string(), old.cloned(), new.clone()));
+    });
+    interpreter.run(&program);
+
+    // Adding zero in the first iteration does not change `x`
+    assert_eq!(
+        *changes.borrow(),
+        vec![
+            ("x".to_string(), None, Value::from(1i64)),
+            ("x".to_string(), Some(Value::from(1i64)), Value::from(2i64)),
+            ("x".to_string(), Some(Value::from(2i64)), Value::from(4i64)),
+        ]
+    );
+}
+
+#[test]
+fn watch_modified_list() {
+    let program = compile_string(PROGRAM);
+
+    let lengths = Rc::new(RefCell::new(vec![]));
+    let recorded = lengths.clone();
+
+    let mut interpreter = Interpreter::default();
+    interpreter.watch_variables(&["l"], move |_, _, new| {
+        recorded.borrow_mut().push(new.length().unwrap());
+    });
+    interpreter.run(&program);
+
+    assert_eq!(*lengths.borrow(), vec![0, 1, 2, 3]);
+}
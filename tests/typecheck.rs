@@ -0,0 +1,150 @@
+use cowlang::{compile, compile_named, typecheck, Interpreter, Value};
+
+#[test]
+fn annotated_array() {
+    let program = compile(
+        "\
+        let array: [u8; 5] = [1u8, 2u8, 3u8, 4u8, 5u8]\n\
+        return array[2]\n\
+    ",
+    )
+    .unwrap();
+
+    assert!(typecheck(&program).is_ok());
+
+    let mut interpreter = Interpreter::default();
+    let result: u8 = interpreter.run(&program).try_into().unwrap();
+    assert_eq!(result, 3);
+}
+
+#[test]
+fn wrong_array_length() {
+    let program = compile("let array: [u8; 5] = [1u8, 2u8]\n").unwrap();
+    let errors = typecheck(&program).unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].message,
+        "Cannot assign a value of type `[u8; 2]` to `array` of type `[u8; 5]`"
+    );
+}
+
+#[test]
+fn reassign_annotated_variable() {
+    let program = compile(
+        "\
+        let name: str = 'bob'\n\
+        name = 5\n\
+    ",
+    )
+    .unwrap();
+
+    let errors = typecheck(&program).unwrap_err();
+    assert_eq!(
+        errors[0].message,
+        "Cannot assign a value of type `i64` to `name` of type `str`"
+    );
+}
+
+#[test]
+fn annotated_function() {
+    let program = compile(
+        "\
+        def add(a: i64, b: i64) -> i64:\
+  \n    return a + b\n\
+        \n\
+        let total: i64 = add(1, 2)\n\
+        let counts: {str: [i64]} = {'a': [1, 2], 'b': [3]}\n\
+        let pair: (str, bool) = ('x', true)\n\
+        return total\n\
+    ",
+    )
+    .unwrap();
+
+    assert!(typecheck(&program).is_ok());
+
+    let mut interpreter = Interpreter::default();
+    assert_eq!(interpreter.run(&program), Value::I64(3));
+}
+
+#[test]
+fn wrong_argument_and_result() {
+    let program = compile_named(
+        "main.cow",
+        "\
+        def greet(name: str) -> str:\
+  \n    return 5\n\
+        \n\
+        greet(1)\n\
+        greet('a', 'b')\n\
+    ",
+    )
+    .unwrap();
+
+    let errors = typecheck(&program).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|err| err.message.as_str()).collect();
+
+    assert_eq!(
+        messages,
+        [
+            "Expected a return value of type `str`, but got `i64`",
+            "Argument `name` of greet() must be of type `str`, but got `i64`",
+            "greet() expects 1 argument(s), but got 2",
+        ]
+    );
+    assert_eq!(errors[0].source_name.as_deref(), Some("main.cow"));
+}
+
+#[test]
+fn unknown_type() {
+    let program = compile("let x: integer = 5\n").unwrap();
+    let errors = typecheck(&program).unwrap_err();
+
+    assert_eq!(errors[0].message, "Unknown type `integer`");
+}
+
+#[test]
+fn unannotated_values_are_accepted() {
+    let program = compile(
+        "\
+        def identity(x):\
+  \n    return x\n\
+        \n\
+        let value: [str] = identity(5)\n\
+    ",
+    )
+    .unwrap();
+
+    assert!(typecheck(&program).is_ok());
+}
+
+#[test]
+fn annotated_integer_literals() {
+    let program = compile(
+        "\
+        let small: u8 = 5\n\
+        let large: u64 = 5\n\
+        return [small, large]\n\
+    ",
+    )
+    .unwrap();
+
+    assert!(typecheck(&program).is_ok());
+
+    let mut interpreter = Interpreter::default();
+    assert_eq!(
+        interpreter.run(&program),
+        Value::List(vec![Value::U8(5), Value::U64(5)])
+    );
+}
+
+#[test]
+fn integer_literal_out_of_range() {
+    let program = compile("let x: u8 = 300\n").unwrap();
+    let errors = typecheck(&program).unwrap_err();
+
+    assert_eq!(
+        errors[0].message,
+        "Cannot assign a value of type `i64` to `x` of type `u8`"
+    );
+}
@@ -0,0 +1,62 @@
+use cowlang::interpreter::ValidateModule;
+use cowlang::{compile_string, Interpreter, RuntimeErrorKind, Value};
+
+use std::rc::Rc;
+
+fn run(source: &str) -> Value {
+    let program = compile_string(source);
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("validate".to_string(), Rc::new(ValidateModule::default()));
+    interpreter.run(&program)
+}
+
+#[test]
+fn validate_input() {
+    let result = run("\
+        return [validate.email('jane.doe@example.com'), validate.email('jane@localhost'), \
+        validate.url('https://example.com/path'), validate.url('example.com'), \
+        validate.ip('::1'), validate.ip('256.0.0.1')]\n\
+    ");
+
+    assert_eq!(result, vec![true, false, true, false, true, false].into());
+}
+
+#[test]
+fn parse_input() {
+    let result = run("\
+        let email = validate.parse_email('jane.doe@example.com')\n\
+        let url = validate.parse_url('http://localhost:8080/search?q=cow#results')\n\
+        let ip = validate.parse_ip('2001:DB8::1')\n\
+        return [email['domain'], url['host'], url['port'], url['path'], url['query'], ip['version'], ip['address']]\n\
+    ");
+
+    let expected = Value::List(vec![
+        "example.com".into(),
+        "localhost".into(),
+        8080i64.into(),
+        "/search".into(),
+        "q=cow".into(),
+        6i64.into(),
+        "2001:db8::1".into(),
+    ]);
+
+    assert_eq!(result, expected);
+}
+
+#[test]
+fn unknown_function() {
+    let program = compile_string("return validate.emial('a@b.c')\n");
+
+    let mut interpreter = Interpreter::default();
+    interpreter.register_module("validate".to_string(), Rc::new(ValidateModule::default()));
+    let err = interpreter.try_run(&program).unwrap_err();
+
+    assert_eq!(
+        err.kind,
+        RuntimeErrorKind::NoSuchMember {
+            module: "validate".to_string(),
+            member: "emial".to_string(),
+        }
+    );
+}
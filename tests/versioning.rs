@@ -0,0 +1,91 @@
+use cowlang::ast::{ArithmeticOp, Expr, LegacyProgram, Span, VersionedProgram, PROGRAM_VERSION};
+use cowlang::{compile_string, Interpreter, MigrationError, Value};
+
+#[test]
+fn store_and_load_program() {
+    let program = compile_string("let x = 2\nreturn x + 3\n");
+
+    let stored = bincode::serialize(&VersionedProgram::from(program)).unwrap();
+
+    let loaded: VersionedProgram = bincode::deserialize(&stored).unwrap();
+    assert_eq!(loaded.version(), PROGRAM_VERSION);
+
+    let program = loaded.into_program().unwrap();
+
+    let mut interpreter = Interpreter::default();
+    let expected: i64 = 5;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn migrate_unknown_version() {
+    let mut program = compile_string("return 1\n");
+
+    assert_eq!(
+        program.migrate(PROGRAM_VERSION + 1),
+        Err(MigrationError::UnknownVersion(PROGRAM_VERSION + 1))
+    );
+    assert_eq!(program.migrate(PROGRAM_VERSION), Ok(()));
+}
+
+#[test]
+fn migrate_add_equals() {
+    let span = Span { lo: 0, hi: 0 };
+    let stmts = vec![
+        (
+            span,
+            Expr::AssignNew("x".to_string(), Box::new((span, Expr::I64(1)))),
+        ),
+        (
+            span,
+            Expr::AddEquals {
+                lhs: "x".to_string(),
+                rhs: Box::new((span, Expr::I64(2))),
+            },
+        ),
+        (
+            span,
+            Expr::Return(Box::new((span, Expr::Var("x".to_string())))),
+        ),
+    ];
+
+    let stored = bincode::serialize(&LegacyProgram { stmts }).unwrap();
+
+    let loaded: LegacyProgram = bincode::deserialize(&stored).unwrap();
+    let program = loaded.into_program().unwrap();
+
+    assert!(matches!(
+        program.stmts[1].1,
+        Expr::OpAssign {
+            op: ArithmeticOp::Add,
+            ..
+        }
+    ));
+
+    let mut interpreter = Interpreter::default();
+    let expected: i64 = 3;
+    assert_eq!(interpreter.run(&program), expected.into());
+}
+
+#[test]
+fn load_legacy_program() {
+    // Stored by a release without versioned programs; compiled from
+    //
+    //   let total = max(1, 0)
+    //   for i in range(0, 4):
+    //       if i > 1:
+    //           total += i
+    //
+    //   return [total, str(total) == '6', 'x']
+    let stored = include_bytes!("data/legacy_program.bin");
+
+    let loaded: LegacyProgram = bincode::deserialize(stored).unwrap();
+    let program = loaded.into_program().unwrap();
+
+    assert!(program.name.is_none());
+    assert!(matches!(program.stmts[1].1, Expr::ForIn { .. }));
+
+    let mut interpreter = Interpreter::default();
+    let expected: Value = vec![Value::from(6i64), true.into(), "x".into()].into();
+    assert_eq!(interpreter.run(&program), expected);
+}